@@ -0,0 +1,349 @@
+//! Compact binary encoding of [`Jmap`]: a magic and version header followed by MessagePack.
+//!
+//! Structs are encoded as maps with field names and in human readable mode (flags and addresses
+//! as strings, same as JSON) so flattened and tagged types round trip exactly like the JSON form.
+//!
+//! Since version 2 the object paths referenced throughout the dump (outers, classes, super
+//! structs, property types, ...) are written once in a table ahead of the dump and stored as
+//! their index everywhere else. Fields holding paths opt in with the [`path`] serde helpers,
+//! which only intern while a binary jmap is being encoded or decoded on the current thread. This
+//! only shrinks the file, a decoded [`Jmap`] holds its own copy of every path as usual.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Jmap;
+
+pub const MAGIC: &[u8; 4] = b"JMPB";
+/// Bumped whenever the encoding changes incompatibly
+pub const VERSION: u32 = 2;
+/// Oldest version that can still be read (before the path table)
+pub const MIN_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Magic,
+    Version(u32),
+    Encode(rmp_serde::encode::Error),
+    Decode(rmp_serde::decode::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Magic => write!(f, "not a binary jmap (bad magic)"),
+            Error::Version(version) => write!(
+                f,
+                "unsupported binary jmap version {version} (expected {MIN_VERSION} to {VERSION})"
+            ),
+            Error::Encode(err) => write!(f, "failed to encode binary jmap: {err}"),
+            Error::Decode(err) => write!(f, "failed to decode binary jmap: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl Jmap {
+    pub fn to_binary<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        // the table is only complete once the dump has been encoded
+        let mut body = vec![];
+        let paths = {
+            let _guard = PathTable::enter(PathTable::Encode {
+                indices: HashMap::new(),
+                paths: vec![],
+            });
+            let mut serializer = rmp_serde::Serializer::new(&mut body)
+                .with_struct_map()
+                .with_human_readable();
+            self.serialize(&mut serializer).map_err(Error::Encode)?;
+            match PathTable::leave() {
+                Some(PathTable::Encode { paths, .. }) => paths,
+                _ => unreachable!(),
+            }
+        };
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        let mut serializer = rmp_serde::Serializer::new(&mut writer).with_human_readable();
+        paths.serialize(&mut serializer).map_err(Error::Encode)?;
+        writer.write_all(&body)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn from_binary<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::Magic);
+        }
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if !(MIN_VERSION..=VERSION).contains(&version) {
+            return Err(Error::Version(version));
+        }
+        let mut deserializer = rmp_serde::Deserializer::new(reader).with_human_readable();
+        if version < 2 {
+            return Jmap::deserialize(&mut deserializer).map_err(Error::Decode);
+        }
+        let paths = Vec::<String>::deserialize(&mut deserializer).map_err(Error::Decode)?;
+        let _guard = PathTable::enter(PathTable::Decode(paths));
+        Jmap::deserialize(&mut deserializer).map_err(Error::Decode)
+    }
+}
+
+thread_local! {
+    static PATHS: RefCell<Option<PathTable>> = const { RefCell::new(None) };
+}
+
+/// Paths of the binary jmap being encoded or decoded on this thread
+enum PathTable {
+    Encode {
+        indices: HashMap<String, u32>,
+        paths: Vec<String>,
+    },
+    Decode(Vec<String>),
+}
+
+/// Clears the table of the current thread when dropped, even if encoding bailed early
+struct PathTableGuard;
+impl Drop for PathTableGuard {
+    fn drop(&mut self) {
+        PathTable::leave();
+    }
+}
+
+impl PathTable {
+    fn enter(table: PathTable) -> PathTableGuard {
+        PATHS.with_borrow_mut(|current| *current = Some(table));
+        PathTableGuard
+    }
+    fn leave() -> Option<PathTable> {
+        PATHS.with_borrow_mut(Option::take)
+    }
+}
+
+/// Serde helpers for fields holding object paths, e.g. `#[serde(with = "binary::path")]`. They
+/// read and write plain strings unless a binary jmap is being encoded or decoded.
+pub mod path {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use serde::de::{Deserializer, Error as _};
+    use serde::ser::Serializer;
+    use serde::{Deserialize, Serialize};
+
+    use super::{PATHS, PathTable};
+
+    struct Interned<'a>(&'a str);
+    impl Serialize for Interned<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let index = PATHS.with_borrow_mut(|table| match table {
+                Some(PathTable::Encode { indices, paths }) => Some(match indices.get(self.0) {
+                    Some(index) => *index,
+                    None => {
+                        let index = paths.len() as u32;
+                        indices.insert(self.0.to_string(), index);
+                        paths.push(self.0.to_string());
+                        index
+                    }
+                }),
+                _ => None,
+            });
+            match index {
+                Some(index) => serializer.serialize_u32(index),
+                None => serializer.serialize_str(self.0),
+            }
+        }
+    }
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Resolved(String);
+    impl<'de> Deserialize<'de> for Resolved {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let decoding = PATHS.with_borrow(|table| matches!(table, Some(PathTable::Decode(_))));
+            if !decoding {
+                return String::deserialize(deserializer).map(Resolved);
+            }
+            let index = u32::deserialize(deserializer)?;
+            PATHS
+                .with_borrow(|table| match table {
+                    Some(PathTable::Decode(paths)) => paths.get(index as usize).cloned(),
+                    _ => None,
+                })
+                .map(Resolved)
+                .ok_or_else(|| D::Error::custom(format!("path index {index} out of range")))
+        }
+    }
+
+    pub fn serialize<S: Serializer>(path: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        Interned(path).serialize(serializer)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        Resolved::deserialize(deserializer).map(|p| p.0)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            path: &Option<String>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            path.as_deref().map(Interned).serialize(serializer)
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<String>, D::Error> {
+            Ok(Option::<Resolved>::deserialize(deserializer)?.map(|p| p.0))
+        }
+    }
+
+    pub mod set {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            paths: &BTreeSet<String>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(paths.iter().map(|p| Interned(p)))
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<BTreeSet<String>, D::Error> {
+            let paths = Vec::<Resolved>::deserialize(deserializer)?;
+            Ok(paths.into_iter().map(|p| p.0).collect())
+        }
+    }
+
+    /// Maps keyed by path
+    pub mod keys {
+        use super::*;
+
+        pub fn serialize<S: Serializer, V: Serialize>(
+            map: &BTreeMap<String, V>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(map.iter().map(|(k, v)| (Interned(k), v)))
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+            deserializer: D,
+        ) -> Result<BTreeMap<String, V>, D::Error> {
+            let map = BTreeMap::<Resolved, V>::deserialize(deserializer)?;
+            Ok(map.into_iter().map(|(k, v)| (k.0, v)).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn object(class: &str, outer: &str) -> serde_json::Value {
+        json!({
+            "address": "0x1000",
+            "vtable": "0x2000",
+            "object_flags": "RF_Public",
+            "outer": outer,
+            "class": class,
+            "children": [],
+            "property_values": {},
+        })
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), Error> {
+        let mut actor = object("/Script/CoreUObject.Class", "/Script/Engine");
+        actor.as_object_mut().unwrap().extend(
+            json!({
+                "type": "Class",
+                "super_struct": "/Script/CoreUObject.Object",
+                "properties": [{
+                    "name": "Owner",
+                    "offset": 0x28,
+                    "array_dim": 1,
+                    "size": 8,
+                    "type": "ObjectProperty",
+                    "property_class": "/Script/Engine.Actor",
+                    "flags": "CPF_Net",
+                }],
+                "properties_size": 0x30,
+                "min_alignment": 8,
+                "class_flags": "CLASS_Native",
+                "class_cast_flags": "CASTCLASS_AActor",
+                "class_default_object": "/Script/Engine.Default__Actor",
+                "interfaces": [],
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        );
+        let mut cdo = object("/Script/Engine.Actor", "/Script/Engine");
+        cdo["type"] = "Object".into();
+        cdo["property_values"] = json!({ "Owner": null });
+        let mut package = object("/Script/CoreUObject.Package", "/Script/Engine");
+        package["type"] = "Package".into();
+        package["outer"] = serde_json::Value::Null;
+        package["children"] = json!(["/Script/Engine.Actor", "/Script/Engine.Default__Actor"]);
+        let jmap: Jmap = serde_json::from_value(json!({
+            "image_base_address": "0x140000000",
+            "objects": {
+                "/Script/Engine": package,
+                "/Script/Engine.Actor": actor,
+                "/Script/Engine.Default__Actor": cdo,
+            },
+            "vtables": { "0x2000": ["0x3000", "0x3010"] },
+            "resolved_addresses": { "GUObjectArray": "0x5000" },
+        }))
+        .unwrap();
+
+        let mut binary = vec![];
+        jmap.to_binary(&mut binary)?;
+        let read = Jmap::from_binary(binary.as_slice())?;
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&jmap).unwrap()
+        );
+
+        // every path is in the table once however often the dump references it
+        let count = |needle: &[u8]| {
+            binary
+                .windows(needle.len())
+                .filter(|w| w == &needle)
+                .count()
+        };
+        assert_eq!(count(b"/Script/Engine.Actor"), 1);
+        // the package and the two paths prefixed with it
+        assert_eq!(count(b"/Script/Engine"), 3);
+        // and nothing is left behind for plain serialization on this thread
+        assert!(PATHS.with_borrow(Option::is_none));
+        Ok(())
+    }
+
+    #[test]
+    fn test_header() {
+        assert!(matches!(
+            Jmap::from_binary(&b"JMAP\x02\0\0\0"[..]),
+            Err(Error::Magic)
+        ));
+        let version = (VERSION + 1).to_le_bytes();
+        let header = [MAGIC.as_slice(), &version].concat();
+        assert!(matches!(
+            Jmap::from_binary(header.as_slice()),
+            Err(Error::Version(v)) if v == VERSION + 1
+        ));
+    }
+}
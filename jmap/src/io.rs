@@ -0,0 +1,141 @@
+//! Reading and writing dumps from disk in any of the supported encodings.
+//!
+//! Output encoding is picked from the file extension. Input is detected from the leading bytes
+//! so compressed and binary dumps load regardless of how they are named.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::Jmap;
+use crate::split::SplitIndex;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// File extensions [`write`] accepts
+pub const EXTENSIONS: &[&str] = &[".jmap", ".jmap.gz", ".jmap.zst", ".json.zst", ".jmapb"];
+
+/// Whether `path` has one of the [`EXTENSIONS`]
+pub fn is_dump_path(path: impl AsRef<Path>) -> bool {
+    let name = path
+        .as_ref()
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Compression wrapper chosen by [`create`]. Must be [`finish`](Self::finish)ed to write the
+/// compressed stream trailer.
+pub enum Writer<W: Write> {
+    Plain(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Writer<W> {
+    pub fn finish(self) -> io::Result<W> {
+        let mut inner = match self {
+            Writer::Plain(w) => w,
+            Writer::Gzip(e) => e.finish()?,
+            Writer::Zstd(e) => e.finish()?,
+        };
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Writer::Plain(w) => w.write(buf),
+            Writer::Gzip(e) => e.write(buf),
+            Writer::Zstd(e) => e.write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Writer::Plain(w) => w.flush(),
+            Writer::Gzip(e) => e.flush(),
+            Writer::Zstd(e) => e.flush(),
+        }
+    }
+}
+
+/// Create `path`, compressing anything written according to its `.gz` or `.zst` extension
+pub fn create(path: impl AsRef<Path>) -> io::Result<Writer<BufWriter<File>>> {
+    let path = path.as_ref();
+    let file = BufWriter::new(File::create(path)?);
+    Ok(match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => Writer::Gzip(flate2::write::GzEncoder::new(
+            file,
+            flate2::Compression::default(),
+        )),
+        Some("zst") => Writer::Zstd(zstd::Encoder::new(file, 0)?),
+        _ => Writer::Plain(file),
+    })
+}
+
+/// Write a dump as JSON, gzip or zstd compressed JSON, or binary `.jmapb` depending on the
+/// extension of `path`
+pub fn write(path: impl AsRef<Path>, jmap: &Jmap) -> io::Result<()> {
+    let path = path.as_ref();
+    if !is_dump_path(path) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("expected one of {} output types", EXTENSIONS.join(", ")),
+        ));
+    }
+    let mut writer = create(path)?;
+    if path.extension().is_some_and(|e| e == "jmapb") {
+        jmap.to_binary(&mut writer).map_err(io::Error::other)?;
+    } else {
+        serde_json::to_writer_pretty(&mut writer, jmap)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Read a dump written by [`write`] or a directory written by [`Jmap::split_by_package`] with its
+/// `index.json`
+pub fn read(path: impl AsRef<Path>) -> io::Result<Jmap> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        let index: SplitIndex =
+            serde_json::from_reader(BufReader::new(File::open(path.join("index.json"))?))?;
+        let packages = index
+            .packages
+            .values()
+            .map(|file| read(path.join(file)))
+            .collect::<io::Result<Vec<_>>>()?;
+        return Ok(Jmap::join_packages(index.header, packages));
+    }
+    from_reader(File::open(path)?)
+}
+
+/// Read a dump from any of the supported encodings, detected from the leading bytes
+pub fn from_reader(reader: impl Read) -> io::Result<Jmap> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(GZIP_MAGIC) {
+        decode(flate2::read::GzDecoder::new(reader))
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        decode(zstd::Decoder::with_buffer(reader)?)
+    } else {
+        decode(reader)
+    }
+}
+
+/// Decode uncompressed JSON or binary
+fn decode(reader: impl Read) -> io::Result<Jmap> {
+    let mut reader = BufReader::new(reader);
+    if reader.fill_buf()?.starts_with(crate::binary::MAGIC) {
+        Jmap::from_binary(reader).map_err(io::Error::other)
+    } else {
+        // parsing from a slice is considerably faster than from a reader
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf)?;
+        Ok(serde_json::from_slice(&buf)?)
+    }
+}
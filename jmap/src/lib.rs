@@ -1,3 +1,7 @@
+pub mod binary;
+pub mod io;
+pub mod split;
+
 use std::collections::{BTreeMap, BTreeSet};
 
 use bytemuck::{Pod, Zeroable};
@@ -74,6 +78,55 @@ impl<'de> Deserialize<'de> for Address {
     }
 }
 
+/// Raw byte blob that serializes as a hex string.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use std::fmt::Write;
+
+        let mut hex = String::with_capacity(self.0.len() * 2);
+        for b in &self.0 {
+            write!(hex, "{b:02x}").unwrap();
+        }
+        serializer.serialize_str(&hex)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let hex = String::deserialize(deserializer)?;
+        if !hex.len().is_multiple_of(2) {
+            return Err(D::Error::custom("odd length hex string"));
+        }
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| D::Error::custom("invalid hex string"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Bytes)
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Debug, Clone, Copy, Serialize, Deserialize, Pod, Zeroable)]
     #[repr(C)]
@@ -384,14 +437,195 @@ bitflags::bitflags! {
         const Flags = 0x00000001;
         const NewerVersionExists = 0x00000002;
     }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, Pod, Zeroable)]
+    #[repr(C)]
+    pub struct EPackageFlags : u32 {
+        const _ = !0;
+
+        const PKG_None = 0x00000000;
+        const PKG_NewlyCreated = 0x00000001;
+        const PKG_ClientOptional = 0x00000002;
+        const PKG_ServerSideOnly = 0x00000004;
+        const PKG_CompiledIn = 0x00000010;
+        const PKG_ForDiffing = 0x00000020;
+        const PKG_EditorOnly = 0x00000040;
+        const PKG_Developer = 0x00000080;
+        const PKG_UncookedOnly = 0x00000100;
+        const PKG_Cooked = 0x00000200;
+        const PKG_ContainsNoAsset = 0x00000400;
+        const PKG_NotExternallyReferenceable = 0x00000800;
+        const PKG_UnversionedProperties = 0x00002000;
+        const PKG_ContainsMapData = 0x00004000;
+        const PKG_IsSaving = 0x00008000;
+        const PKG_Compiling = 0x00010000;
+        const PKG_ContainsMap = 0x00020000;
+        const PKG_RequiresLocalizationGather = 0x00040000;
+        const PKG_PlayInEditor = 0x00100000;
+        const PKG_ContainsScript = 0x00200000;
+        const PKG_DisallowExport = 0x00400000;
+        const PKG_CookGenerated = 0x08000000;
+        const PKG_DynamicImports = 0x10000000;
+        const PKG_RuntimeGenerated = 0x20000000;
+        const PKG_ReloadingForCooker = 0x40000000;
+        const PKG_FilterEditorOnly = 0x80000000;
+    }
+}
+
+impl Default for EPackageFlags {
+    fn default() -> Self {
+        Self::PKG_None
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Jmap {
     pub metadata: Option<Metadata>,
     pub image_base_address: Address,
+    #[serde(with = "binary::path::keys")]
     pub objects: BTreeMap<String, ObjectType>,
     pub vtables: BTreeMap<Address, Vec<Address>>,
+    /// Symbols referenced by dumped script bytecode (only dumped on request)
+    pub script_references: Option<ScriptReferences>,
+    /// PE image the dump was taken from (absent in older dumps)
+    pub image: Option<ImageInfo>,
+    /// Engine globals and functions resolved from the image, e.g. GUObjectArray, GWorld, GNatives
+    /// and UObject::ProcessEvent (resolvers that failed are left out)
+    #[serde(default)]
+    pub resolved_addresses: BTreeMap<String, Address>,
+    /// Objects skipped because they failed to read (only with `--keep-going`)
+    #[serde(default)]
+    pub errors: Vec<ObjectError>,
+    /// Bytecode handlers of GNatives by opcode (empty if GNatives wasn't resolved)
+    #[serde(default)]
+    pub natives: Vec<Native>,
+    /// Byte signatures of engine globals and UFunction natives (only dumped on request)
+    #[serde(default)]
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signature {
+    /// Name of the engine global or path of the UFunction
+    pub name: String,
+    pub rva: Address,
+    /// Space separated hex bytes, `??` for wildcards
+    pub pattern: String,
+    /// Set if the pattern is at an instruction referencing a global rather than the function
+    #[serde(default)]
+    pub reference: Option<SignatureReference>,
+}
+
+/// Where to resolve a global from a match: it is at `match + end + disp32`, the displacement being
+/// at `match + displacement`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureReference {
+    pub displacement: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Native {
+    pub opcode: u8,
+    /// `EExprToken` of the opcode, e.g. `EX_Let` (absent for opcodes without one)
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub function: NativeFunction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectError {
+    /// Index of the object in GUObjectArray
+    pub index: i32,
+    /// Absent if the GUObjectArray item itself couldn't be read
+    pub address: Option<Address>,
+    /// Absent if the path couldn't be read
+    #[serde(default, with = "binary::path::option")]
+    pub path: Option<String>,
+    pub error: String,
+}
+
+impl Jmap {
+    /// Module containing `address` and the address relative to its base. Dumps without a module
+    /// list only know the main image.
+    pub fn module_of(&self, address: u64) -> Option<(&str, u64)> {
+        match self.image.as_ref().filter(|i| !i.modules.is_empty()) {
+            Some(image) => image
+                .modules
+                .iter()
+                .find(|m| (m.base.0..m.base.0 + m.size).contains(&address))
+                .map(|m| (m.name.as_str(), address - m.base.0)),
+            None => {
+                let name = self.metadata.as_ref().map_or("", |m| m.source.as_str());
+                address
+                    .checked_sub(self.image_base_address.0)
+                    .map(|rva| (name, rva))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageInfo {
+    /// Executable format, which tells the C++ ABI of the vtables
+    #[serde(default)]
+    pub format: ImageFormat,
+    /// Resolved engine globals, e.g. GUObjectArray and FNamePool
+    pub globals: BTreeMap<String, Address>,
+    /// PE section headers
+    pub sections: Vec<ImageSection>,
+    /// CodeView record identifying the matching PDB
+    pub codeview: Option<CodeView>,
+    /// Every module loaded in the process, main executable first (empty if unknown). Games
+    /// split across DLLs have functions and vtables in several of them.
+    #[serde(default)]
+    pub modules: Vec<Module>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageFormat {
+    #[default]
+    Pe,
+    /// Linux builds, whose "sections" are the loadable segments
+    Elf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Module {
+    /// File name, e.g. `UnrealEditor-CoreUObject.dll`
+    pub name: String,
+    pub base: Address,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageSection {
+    pub name: String,
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub size_of_raw_data: u32,
+    pub pointer_to_raw_data: u32,
+    pub characteristics: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeView {
+    /// PDB GUID in registry format without braces
+    pub guid: String,
+    pub age: u32,
+    pub pdb_path: String,
+}
+
+/// Operands of script bytecode which can't be resolved from the rest of the dump
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptReferences {
+    /// FName comparison index -> name entry
+    pub names: BTreeMap<u32, String>,
+    /// Object address -> path
+    pub objects: BTreeMap<Address, String>,
+    /// Property address -> name
+    pub properties: BTreeMap<Address, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -404,12 +638,30 @@ pub struct Metadata {
     pub source: String,
     pub engine_version: EngineVersion,
     pub build_change_list: Option<String>,
+    /// Dumping was interrupted, objects and vtables past that point are missing
+    #[serde(default)]
+    pub partial: bool,
+    /// Size of pointers on the target in bytes (8 in older dumps, which were all of PC builds)
+    #[serde(default = "default_pointer_size")]
+    pub pointer_size: u8,
+}
+fn default_pointer_size() -> u8 {
+    8
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EngineVersion {
     pub major: u16,
     pub minor: u16,
+    /// Read from `FEngineVersion::Current` when it could be found
+    #[serde(default)]
+    pub patch: Option<u16>,
+    /// Branch the engine was built from, e.g. `++UE5+Release-5.3`
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Major and minor version were given by the user instead of detected
+    #[serde(default)]
+    pub overridden: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -417,26 +669,49 @@ pub struct Object {
     pub address: Address,
     pub vtable: Address,
     pub object_flags: EObjectFlags,
+    #[serde(default, with = "binary::path::option")]
     pub outer: Option<String>,
+    #[serde(with = "binary::path")]
     pub class: String,
+    #[serde(with = "binary::path::set")]
     pub children: BTreeSet<String>,
     pub property_values: ValuesWrapper,
+    /// Index of the object in GUObjectArray
+    #[serde(default)]
+    pub internal_index: i32,
+    /// FUObjectItem::Flags (UE >= 4.11)
+    pub internal_flags: Option<EInternalObjectFlags>,
+    /// FUObjectItem::ClusterRootIndex (UE >= 4.11)
+    pub cluster_root_index: Option<i32>,
+    /// FUObjectItem::SerialNumber (UE >= 4.11)
+    pub serial_number: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Package {
     #[serde(flatten)]
     pub object: Object,
+    #[serde(default)]
+    pub package_flags: EPackageFlags,
+    /// File name the package was loaded from (UE < 5.5)
+    pub file_name: Option<String>,
+    /// Package name of the path the package was loaded from (UE >= 5.1)
+    pub loaded_path: Option<String>,
+    /// Persistent package GUID (UE < 5.4)
+    pub guid: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Struct {
     #[serde(flatten)]
     pub object: Object,
+    #[serde(default, with = "binary::path::option")]
     pub super_struct: Option<String>,
     pub properties: Vec<Property>,
     pub properties_size: usize,
     pub min_alignment: usize,
+    /// Serialized script bytecode (only dumped on request)
+    pub script: Option<Bytes>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -452,16 +727,88 @@ pub struct Class {
     pub r#struct: Struct,
     pub class_flags: EClassFlags,
     pub class_cast_flags: EClassCastFlags,
+    #[serde(default, with = "binary::path::option")]
     pub class_default_object: Option<String>,
+    /// Class that instances of this class must be contained within
+    #[serde(default, with = "binary::path::option")]
+    pub class_within: Option<String>,
+    /// Config file name (e.g. "Engine", "Game") for config classes
+    pub config_name: Option<String>,
     /// VTable ptr of any instance of this UClass if found
     pub instance_vtable: Option<Address>,
+    /// Function of every slot of the instance vtable, bounded by the next vtable, the first
+    /// pointer outside code (e.g. the RTTI locator of the next vtable) and the vtable of any
+    /// derived class
+    #[serde(default)]
+    pub virtual_functions: Vec<VirtualFunction>,
+    /// `InternalConstructor<T>` constructing instances in place
+    #[serde(default)]
+    pub class_constructor: Option<NativeFunction>,
+    /// `InternalVTableHelperCtorCaller<T>` constructing a dummy instance to take its vtable from
+    /// (UE >= 4.8)
+    #[serde(default)]
+    pub vtable_helper_ctor_caller: Option<NativeFunction>,
+    /// `GetPrivateStaticClass` registering the class, if it was looked for and found
+    #[serde(default)]
+    pub static_class: Option<NativeFunction>,
+    /// UScriptStruct describing the layout of the sparse class data
+    #[serde(default, with = "binary::path::option")]
+    pub sparse_class_data_struct: Option<String>,
+    /// Values of the sparse class data (if allocated)
+    pub sparse_class_data: Option<ValuesWrapper>,
+    #[serde(default)]
+    pub interfaces: Vec<ImplementedInterface>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualFunction {
+    /// Address of the function relative to the base of its module
+    pub rva: Address,
+    /// Module the function is in if it isn't the main image
+    #[serde(default)]
+    pub module: Option<String>,
+    /// UObject virtual the slot holds (or an override of) if it is a well known one, e.g.
+    /// `ProcessEvent`
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Name of the function in the PDB of the image, if one was found
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+/// Native function a class points to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NativeFunction {
+    /// Address of the function relative to the base of its module
+    pub rva: Address,
+    /// Module the function is in if it isn't the main image
+    #[serde(default)]
+    pub module: Option<String>,
+    /// Name of the function in the PDB of the image, if one was found
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImplementedInterface {
+    #[serde(with = "binary::path")]
+    pub class: String,
+    /// Offset of the interface vtable pointer from the start of the implementing object
+    pub pointer_offset: i32,
+    /// Interface is implemented by blueprint rather than native code
+    pub implemented_by_k2: bool,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Function {
     #[serde(flatten)]
     pub r#struct: Struct,
     pub function_flags: EFunctionFlags,
+    /// Native of the function, for C++ functions the generated `execFoo` thunk
     pub func: Address,
+    /// Name of the native in the PDB of the image, if one was found
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// C++ function the `execFoo` thunk calls, if it could be told apart from the helpers the
+    /// thunk calls to read parameters
+    #[serde(default)]
+    pub implementation: Option<NativeFunction>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Enum {
@@ -471,6 +818,16 @@ pub struct Enum {
     pub enum_flags: Option<EEnumFlags>,
     pub cpp_form: ECppForm,
     pub names: Vec<(String, i64)>,
+    /// Native function used to resolve display names (FText(*)(int32)) if set
+    pub display_name_fn: Option<Address>,
+    /// Display name of every entry as `UEnum::GetDisplayNameTextByIndex` returns it in cooked
+    /// builds. Empty when the names come from `display_name_fn`, which can't be evaluated from a
+    /// dump.
+    #[serde(default)]
+    pub display_names: Vec<String>,
+    /// Package the enum was declared in (UE >= 5.1)
+    #[serde(default, with = "binary::path::option")]
+    pub enum_package: Option<String>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, strum::FromRepr)]
 #[repr(u8)]
@@ -479,9 +836,23 @@ pub enum ECppForm {
     Namespaced,
     EnumClass,
 }
+impl ECppForm {
+    /// Name of an entry without the `Enum::` prefix of namespaced and enum class entries, as
+    /// `UEnum::GetNameStringByIndex` returns it
+    pub fn short_name<'a>(&self, name: &'a str) -> &'a str {
+        match self {
+            ECppForm::Regular => name,
+            ECppForm::Namespaced | ECppForm::EnumClass => {
+                name.split_once("::").map_or(name, |(_, short)| short)
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
+// classes dominate every dump anyway, boxing them would only add an allocation per class
+#[allow(clippy::large_enum_variant)]
 pub enum ObjectType {
     Object(Object),
     Package(Package),
@@ -501,6 +872,16 @@ impl ObjectType {
             ObjectType::Function(obj) => &obj.r#struct.object,
         }
     }
+    pub fn get_object_mut(&mut self) -> &mut Object {
+        match self {
+            ObjectType::Object(obj) => obj,
+            ObjectType::Package(obj) => &mut obj.object,
+            ObjectType::Enum(obj) => &mut obj.object,
+            ObjectType::ScriptStruct(obj) => &mut obj.r#struct.object,
+            ObjectType::Class(obj) => &mut obj.r#struct.object,
+            ObjectType::Function(obj) => &mut obj.r#struct.object,
+        }
+    }
     pub fn get_struct(&self) -> Option<&Struct> {
         match self {
             ObjectType::Object(_) => None,
@@ -547,7 +928,10 @@ pub struct Property {
 #[serde(tag = "type")]
 pub enum PropertyType {
     #[serde(rename = "StructProperty")]
-    Struct { r#struct: String },
+    Struct {
+        #[serde(with = "binary::path")]
+        r#struct: String,
+    },
     #[serde(rename = "StrProperty")]
     Str,
     #[serde(rename = "NameProperty")]
@@ -555,13 +939,25 @@ pub enum PropertyType {
     #[serde(rename = "TextProperty")]
     Text,
     #[serde(rename = "MulticastInlineDelegateProperty")]
-    MulticastInlineDelegate { signature_function: Option<String> },
+    MulticastInlineDelegate {
+        #[serde(default, with = "binary::path::option")]
+        signature_function: Option<String>,
+    },
     #[serde(rename = "MulticastSparseDelegateProperty")]
-    MulticastSparseDelegate { signature_function: Option<String> },
+    MulticastSparseDelegate {
+        #[serde(default, with = "binary::path::option")]
+        signature_function: Option<String>,
+    },
     #[serde(rename = "MulticastDelegateProperty")]
-    MulticastDelegate { signature_function: Option<String> },
+    MulticastDelegate {
+        #[serde(default, with = "binary::path::option")]
+        signature_function: Option<String>,
+    },
     #[serde(rename = "DelegateProperty")]
-    Delegate { signature_function: Option<String> },
+    Delegate {
+        #[serde(default, with = "binary::path::option")]
+        signature_function: Option<String>,
+    },
     #[serde(rename = "BoolProperty")]
     Bool {
         field_size: u8,
@@ -574,6 +970,7 @@ pub enum PropertyType {
     #[serde(rename = "EnumProperty")]
     Enum {
         container: Box<Property>,
+        #[serde(default, with = "binary::path::option")]
         r#enum: Option<String>,
     },
     #[serde(rename = "MapProperty")]
@@ -588,7 +985,10 @@ pub enum PropertyType {
     #[serde(rename = "DoubleProperty")]
     Double,
     #[serde(rename = "ByteProperty")]
-    Byte { r#enum: Option<String> },
+    Byte {
+        #[serde(default, with = "binary::path::option")]
+        r#enum: Option<String>,
+    },
     #[serde(rename = "UInt16Property")]
     UInt16,
     #[serde(rename = "UInt32Property")]
@@ -604,25 +1004,44 @@ pub enum PropertyType {
     #[serde(rename = "Int64Property")]
     Int64,
     #[serde(rename = "ObjectProperty")]
-    Object { property_class: String },
+    Object {
+        #[serde(with = "binary::path")]
+        property_class: String,
+    },
     #[serde(rename = "ClassProperty")]
     Class {
+        #[serde(with = "binary::path")]
         property_class: String,
+        #[serde(with = "binary::path")]
         meta_class: String,
     },
     #[serde(rename = "WeakObjectProperty")]
-    WeakObject { property_class: String },
+    WeakObject {
+        #[serde(with = "binary::path")]
+        property_class: String,
+    },
     #[serde(rename = "SoftObjectProperty")]
-    SoftObject { property_class: String },
+    SoftObject {
+        #[serde(with = "binary::path")]
+        property_class: String,
+    },
     #[serde(rename = "SoftClassProperty")]
     SoftClass {
+        #[serde(with = "binary::path")]
         property_class: String,
+        #[serde(with = "binary::path")]
         meta_class: String,
     },
     #[serde(rename = "LazyObjectProperty")]
-    LazyObject { property_class: String },
+    LazyObject {
+        #[serde(with = "binary::path")]
+        property_class: String,
+    },
     #[serde(rename = "InterfaceProperty")]
-    Interface { interface_class: String },
+    Interface {
+        #[serde(with = "binary::path")]
+        interface_class: String,
+    },
     #[serde(rename = "FieldPathProperty")]
     FieldPath,
     #[serde(rename = "OptionalProperty")]
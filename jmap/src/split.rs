@@ -0,0 +1,88 @@
+//! Splitting a [`Jmap`] into one dump per package and joining it back together.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Jmap;
+
+/// Index of a dump split by package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitIndex {
+    /// Everything that doesn't belong to a single package (metadata, image info, unreferenced
+    /// vtables, script references)
+    pub header: Jmap,
+    /// Package path -> file name relative to the index
+    pub packages: BTreeMap<String, String>,
+}
+
+/// Package an object belongs to, e.g. `/Script/Engine` for `/Script/Engine.Actor:K2_DestroyActor`
+pub fn package_of(path: &str) -> &str {
+    path.split_once('.').map_or(path, |(package, _)| package)
+}
+
+impl Jmap {
+    /// Split into a header holding everything but objects plus one dump per package. Vtables
+    /// move into the package of the class using them.
+    pub fn split_by_package(self) -> (Jmap, BTreeMap<String, Jmap>) {
+        let Jmap {
+            metadata,
+            image_base_address,
+            objects,
+            mut vtables,
+            script_references,
+            image,
+            resolved_addresses,
+            errors,
+            natives,
+            signatures,
+        } = self;
+
+        let mut packages = BTreeMap::<String, Jmap>::new();
+        for (path, obj) in objects {
+            let package = packages
+                .entry(package_of(&path).to_string())
+                .or_insert_with(|| Jmap {
+                    metadata: None,
+                    image_base_address,
+                    objects: Default::default(),
+                    vtables: Default::default(),
+                    script_references: None,
+                    image: None,
+                    resolved_addresses: Default::default(),
+                    errors: vec![],
+                    natives: vec![],
+                    signatures: vec![],
+                });
+            if let Some(vtable) = obj.get_class().and_then(|c| c.instance_vtable)
+                && let Some(entries) = vtables.remove(&vtable)
+            {
+                package.vtables.insert(vtable, entries);
+            }
+            package.objects.insert(path, obj);
+        }
+
+        let header = Jmap {
+            metadata,
+            image_base_address,
+            objects: Default::default(),
+            vtables,
+            script_references,
+            image,
+            resolved_addresses,
+            errors,
+            natives,
+            signatures,
+        };
+        (header, packages)
+    }
+
+    /// Inverse of [`Jmap::split_by_package`]. Any subset of packages may be joined.
+    pub fn join_packages(mut header: Jmap, packages: impl IntoIterator<Item = Jmap>) -> Jmap {
+        for package in packages {
+            header.objects.extend(package.objects);
+            header.vtables.extend(package.vtables);
+        }
+        header
+    }
+}
@@ -0,0 +1,206 @@
+//! Runtime registry of memory read backends selected with `--backend <name>:<argument>`, so
+//! readers the dumper doesn't know about (e.g. a private kernel driver) can be plugged in with a
+//! small adapter implementing [`ReadMemory`] and registered with [`register`] before dumping.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Result, bail};
+
+use crate::mem::Mem;
+
+/// Memory reader behind a registered backend
+pub trait ReadMemory: Send + Sync {
+    /// Fill `buf` with the target's memory at `address`, failing if any of it can't be read
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()>;
+
+    /// Address the game executable is mapped at, if the backend can tell. Otherwise it is looked
+    /// up from the process' module list.
+    fn image_base(&self) -> Result<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// Opens a reader for `argument` (the part of the spec after `:`) targeting process `pid`
+pub type BackendFactory = fn(argument: &str, pid: i32) -> Result<Box<dyn ReadMemory>>;
+
+struct Backend {
+    name: String,
+    description: String,
+    open: BackendFactory,
+}
+
+static REGISTRY: Mutex<Vec<Backend>> = Mutex::new(vec![]);
+
+fn with_registry<R>(f: impl FnOnce(&mut Vec<Backend>) -> R) -> R {
+    let mut registry = REGISTRY.lock().unwrap();
+    if registry.is_empty() {
+        registry.push(Backend {
+            name: "driver".into(),
+            description: r"kernel read driver device, e.g. driver:\\.\MyReader".into(),
+            open: driver::open,
+        });
+    }
+    f(&mut registry)
+}
+
+/// Register a backend, replacing any existing one with the same name
+pub fn register(name: &str, description: &str, open: BackendFactory) {
+    with_registry(|registry| {
+        registry.retain(|b| b.name != name);
+        registry.push(Backend {
+            name: name.into(),
+            description: description.into(),
+            open,
+        });
+    });
+}
+
+/// Names and descriptions of all registered backends
+pub fn backends() -> Vec<(String, String)> {
+    with_registry(|registry| {
+        registry
+            .iter()
+            .map(|b| (b.name.clone(), b.description.clone()))
+            .collect()
+    })
+}
+
+/// Open the backend named by `spec` (`name:argument` or just `name`) for process `pid`
+pub fn open(spec: &str, pid: i32) -> Result<DynMem> {
+    let (name, argument) = spec.split_once(':').unwrap_or((spec, ""));
+    let Some(open) =
+        with_registry(|registry| registry.iter().find(|b| b.name == name).map(|b| b.open))
+    else {
+        let list = backends()
+            .into_iter()
+            .map(|(name, description)| format!("  {name}: {description}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        bail!("Unknown backend {name:?}, available backends:\n{list}");
+    };
+    Ok(DynMem(open(argument, pid)?.into()))
+}
+
+/// Reader returned by [`open`], usable wherever the dumper reads process memory
+#[derive(Clone)]
+pub struct DynMem(Arc<dyn ReadMemory>);
+
+impl DynMem {
+    pub fn image_base(&self) -> Result<Option<u64>> {
+        self.0.image_base()
+    }
+}
+
+impl Mem for DynMem {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        self.0.read_buf(address, buf)
+    }
+}
+
+/// Generic adapter for kernel read drivers. The device at the given path is opened and every read
+/// is a single `DeviceIoControl` with [`IOCTL_READ`], a [`ReadRequest`] as input and the
+/// destination buffer as output. Drivers speaking a different protocol can copy this adapter and
+/// [`register`] their own.
+pub mod driver {
+    use anyhow::Result;
+
+    use super::ReadMemory;
+
+    /// `CTL_CODE(FILE_DEVICE_UNKNOWN, 0x800, METHOD_BUFFERED, FILE_ANY_ACCESS)`
+    pub const IOCTL_READ: u32 = 0x0022_2000;
+
+    /// Input buffer of [`IOCTL_READ`]
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct ReadRequest {
+        pub pid: u64,
+        pub address: u64,
+        pub size: u64,
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn open(device: &str, pid: i32) -> Result<Box<dyn ReadMemory>> {
+        use anyhow::Context as _;
+        use windows::Win32::Foundation::{GENERIC_READ, GENERIC_WRITE};
+        use windows::Win32::Storage::FileSystem::{
+            CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+        };
+        use windows::core::HSTRING;
+
+        let handle = unsafe {
+            CreateFileW(
+                &HSTRING::from(device),
+                GENERIC_READ.0 | GENERIC_WRITE.0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                FILE_ATTRIBUTE_NORMAL,
+                None,
+            )
+        }
+        .with_context(|| format!("Failed to open driver device {device:?}"))?;
+        Ok(Box::new(Driver {
+            handle: handle.0 as isize,
+            pid: pid as u64,
+        }))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn open(_device: &str, _pid: i32) -> Result<Box<dyn ReadMemory>> {
+        anyhow::bail!("The driver backend is only available on Windows")
+    }
+
+    #[cfg(target_os = "windows")]
+    struct Driver {
+        /// Raw `HANDLE` to the device, kept as an integer so the reader is `Send + Sync`
+        handle: isize,
+        pid: u64,
+    }
+
+    #[cfg(target_os = "windows")]
+    impl Drop for Driver {
+        fn drop(&mut self) {
+            use windows::Win32::Foundation::{CloseHandle, HANDLE};
+            unsafe {
+                let _ = CloseHandle(HANDLE(self.handle as _));
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    impl ReadMemory for Driver {
+        fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+            use anyhow::{Context as _, bail};
+            use windows::Win32::Foundation::HANDLE;
+            use windows::Win32::System::IO::DeviceIoControl;
+
+            let request = ReadRequest {
+                pid: self.pid,
+                address,
+                size: buf.len() as u64,
+            };
+            let mut returned = 0;
+            unsafe {
+                DeviceIoControl(
+                    HANDLE(self.handle as _),
+                    IOCTL_READ,
+                    Some(&request as *const ReadRequest as *const _),
+                    size_of::<ReadRequest>() as u32,
+                    Some(buf.as_mut_ptr() as *mut _),
+                    buf.len() as u32,
+                    Some(&mut returned),
+                    None,
+                )
+            }
+            .with_context(|| format!("reading {} bytes at 0x{:x}", buf.len(), address))?;
+            if returned as usize != buf.len() {
+                bail!(
+                    "reading {} bytes at 0x{:x}: driver returned {returned} bytes",
+                    buf.len(),
+                    address
+                );
+            }
+            Ok(())
+        }
+    }
+}
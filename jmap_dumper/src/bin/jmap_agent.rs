@@ -0,0 +1,31 @@
+//! Runs next to the game and serves its memory to `jmap_dumper --remote <host>`
+
+use anyhow::Result;
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(group = clap::ArgGroup::new("target").required(true))]
+struct Cli {
+    /// Process ID to serve
+    #[arg(long, short, group = "target")]
+    pid: Option<i32>,
+
+    /// Process to serve by executable name or window title
+    #[arg(long, group = "target")]
+    process: Option<String>,
+
+    /// Address to listen on
+    #[arg(long, default_value_t = format!("0.0.0.0:{}", jmap_dumper::remote::DEFAULT_PORT))]
+    listen: String,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let pid = match (cli.pid, cli.process) {
+        (Some(pid), _) => pid,
+        (None, Some(query)) => jmap_dumper::find_process(&query)?.pid,
+        (None, None) => unreachable!("clap requires a target"),
+    };
+    jmap_dumper::remote::serve_process(pid, cli.listen.as_str())
+}
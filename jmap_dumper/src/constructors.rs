@@ -0,0 +1,197 @@
+//! Natives behind every class: `ClassConstructor` (`InternalConstructor<T>`) and
+//! `ClassVTableHelperCtorCaller`, and with [`DumpOptions::static_class`] the
+//! `GetPrivateStaticClass` registering the class. The latter passes the constructor to
+//! `GetPrivateStaticClassBody`, so it is found as the one function loading the constructor's
+//! address with a RIP relative `lea`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+use jmap::{Class, Jmap, ObjectType};
+use rayon::prelude::*;
+use tracing::{info, info_span, warn};
+
+use crate::mem::{Ctx, Mem, Ptr};
+use crate::objects::UClass;
+use crate::vtable::{SECTION_EXECUTE, native_function};
+use crate::{Cancel, DumpOptions, pe};
+
+/// Bytes of code scanned per read
+const CHUNK: u64 = 0x100000;
+/// `lea r64, [rip + disp32]`: REX.W (optionally REX.R), 8D, ModRM with mod 00 and r/m 101
+const LEA_LEN: usize = 7;
+
+/// Addresses `ClassConstructor` and `ClassVTableHelperCtorCaller` of a class point to
+pub struct Constructors {
+    pub class_constructor: u64,
+    /// only present for >= 4.8
+    pub vtable_helper_ctor_caller: Option<u64>,
+}
+
+impl Constructors {
+    pub fn read<C: Ctx>(class: &Ptr<UClass, C>) -> Result<Self> {
+        let vtable_helper_ctor_caller = if class.ctx().ue_version() >= (4, 8) {
+            Some(class.class_vtable_helper_ctor_caller().read()? as u64)
+        } else {
+            None
+        };
+        Ok(Self {
+            class_constructor: class.class_constructor().read()? as u64,
+            vtable_helper_ctor_caller,
+        })
+    }
+}
+
+/// Fill in the natives of `class`, `static_classes` being what [`find_static_classes`] found
+pub fn apply(
+    header: &Jmap,
+    class: &mut Class,
+    constructors: &Constructors,
+    static_classes: &HashMap<u64, u64>,
+) {
+    let function = |address: u64| (address != 0).then(|| native_function(header, address));
+    class.class_constructor = function(constructors.class_constructor);
+    class.vtable_helper_ctor_caller = constructors.vtable_helper_ctor_caller.and_then(function);
+    class.static_class = static_classes
+        .get(&constructors.class_constructor)
+        .and_then(|f| function(*f));
+}
+
+/// Read the natives of the dumped classes, looking for their `GetPrivateStaticClass` as well if
+/// [`DumpOptions::static_class`] is set
+pub fn analyze_constructors<C: Ctx>(
+    ctx: &C,
+    header: &Jmap,
+    objects: &mut BTreeMap<String, ObjectType>,
+    options: &DumpOptions,
+) {
+    let constructors = objects
+        .iter()
+        .filter(|(_, obj)| matches!(obj, ObjectType::Class(_)))
+        .filter_map(|(path, obj)| {
+            let class = Ptr::<UClass, _>::new(obj.get_object().address.0, ctx.clone());
+            Constructors::read(&class)
+                .inspect_err(|err| warn!("failed to read the constructors of {path}: {err:#}"))
+                .ok()
+                .map(|c| (path.clone(), c))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let static_classes = if options.static_class {
+        find_static_classes(
+            ctx,
+            header,
+            constructors.values().map(|c| c.class_constructor),
+            &options.cancel,
+        )
+    } else {
+        Default::default()
+    };
+
+    for (path, constructors) in &constructors {
+        if let Some(ObjectType::Class(class)) = objects.get_mut(path) {
+            apply(header, class, constructors, &static_classes);
+        }
+    }
+}
+
+/// Start of the only function referencing each of `constructors`. Constructors shared by several
+/// classes (identical code folded by the linker) or referenced from several functions are left
+/// out, as is everything if the image has no exception directory to find function starts in.
+pub fn find_static_classes<M: Mem>(
+    mem: &M,
+    header: &Jmap,
+    constructors: impl IntoIterator<Item = u64>,
+    cancel: &Cancel,
+) -> HashMap<u64, u64> {
+    let Some(image) = &header.image else {
+        return Default::default();
+    };
+    let base = header.image_base_address.0;
+
+    let mut classes = HashMap::<u64, usize>::new();
+    for constructor in constructors.into_iter().filter(|c| *c != 0) {
+        *classes.entry(constructor).or_default() += 1;
+    }
+    let targets = classes
+        .into_iter()
+        .filter(|(_, n)| *n == 1)
+        .map(|(c, _)| c)
+        .collect::<HashSet<_>>();
+    if targets.is_empty() {
+        return Default::default();
+    }
+
+    let functions = match pe::read_runtime_functions(mem, base) {
+        Ok(functions) => functions,
+        Err(err) => {
+            warn!("failed to read the exception directory, not looking for StaticClass: {err:#}");
+            return Default::default();
+        }
+    };
+
+    let chunks = image
+        .sections
+        .iter()
+        .filter(|s| s.characteristics & SECTION_EXECUTE != 0)
+        .flat_map(|s| {
+            let start = base + s.virtual_address as u64;
+            let end = start + s.virtual_size as u64;
+            (start..end)
+                .step_by(CHUNK as usize)
+                .map(move |chunk| (chunk, end))
+        })
+        .collect::<Vec<_>>();
+    let _span = info_span!("static_class", chunks = chunks.len()).entered();
+    let xrefs = chunks
+        .par_iter()
+        .flat_map_iter(|(chunk, end)| {
+            if cancel.is_cancelled() {
+                return vec![];
+            }
+            scan_chunk(mem, *chunk, *end, &targets)
+        })
+        .collect::<Vec<_>>();
+
+    let mut referencing = HashMap::<u64, HashSet<u32>>::new();
+    for (target, xref) in xrefs {
+        let Ok(rva) = u32::try_from(xref - base) else {
+            continue;
+        };
+        if let Some(function) = pe::function_start(mem, base, &functions, rva) {
+            referencing.entry(target).or_default().insert(function);
+        }
+    }
+    let static_classes = referencing
+        .into_iter()
+        .filter(|(_, functions)| functions.len() == 1)
+        .map(|(target, functions)| (target, base + *functions.iter().next().unwrap() as u64))
+        .collect::<HashMap<_, _>>();
+    info!(
+        "found StaticClass of {} of {} classes",
+        static_classes.len(),
+        targets.len()
+    );
+    static_classes
+}
+
+/// `lea`s of the `CHUNK` bytes from `chunk` loading any of `targets`, as (target, instruction)
+fn scan_chunk<M: Mem>(mem: &M, chunk: u64, end: u64, targets: &HashSet<u64>) -> Vec<(u64, u64)> {
+    // overlapping the next chunk by an instruction so none are missed on the boundary
+    let len = (end - chunk).min(CHUNK + LEA_LEN as u64 - 1) as usize;
+    let mut buf = vec![0; len];
+    if mem.read_buf(chunk, &mut buf).is_err() {
+        return vec![];
+    }
+    buf.windows(LEA_LEN)
+        .enumerate()
+        .take(CHUNK as usize)
+        .filter(|(_, i)| i[0] & 0xfb == 0x48 && i[1] == 0x8d && i[2] & 0xc7 == 0x05)
+        .filter_map(|(offset, i)| {
+            let address = chunk + offset as u64;
+            let disp = i32::from_le_bytes(i[3..7].try_into().unwrap());
+            let target = (address + LEA_LEN as u64).wrapping_add_signed(disp as i64);
+            targets.contains(&target).then_some((target, address))
+        })
+        .collect()
+}
@@ -1,6 +1,8 @@
 use crate::mem::{Ctx, VirtSize};
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use derive_where::derive_where;
+use std::collections::HashMap;
+use std::sync::RwLock;
 
 use alloc::*;
 
@@ -22,6 +24,15 @@ impl<C: Mem> Ptr<FString, C> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct FGuid;
+impl<C: Mem> Ptr<FGuid, C> {
+    pub fn read(&self) -> Result<String> {
+        let parts = self.cast::<u32>().read_vec(4)?;
+        Ok(parts.iter().map(|p| format!("{p:08X}")).collect())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
 pub struct FUtf8String(pub TArray<u16>);
@@ -61,10 +72,9 @@ impl<C: Mem, T, A: TAlloc> Ptr<TArray<T, A>, C> {
 }
 impl<C: Mem, T, A: TAlloc> Ptr<TArray<T, A>, C> {
     pub fn len(&self) -> Result<usize> {
-        Ok(self
-            .byte_offset(std::mem::offset_of!(TArray<T, A>, num))
-            .cast::<u32>()
-            .read()? as usize)
+        // ArrayNum directly follows the data pointer
+        let offset = self.ctx().target().pointer_size as usize;
+        Ok(self.byte_offset(offset).cast::<u32>().read()? as usize)
     }
 }
 
@@ -129,60 +139,59 @@ impl<C: Ctx> Ptr<FName, C> {
     pub fn read(&self) -> Result<String> {
         let number = self.number().read()?;
         let value = self.comparison_index().value().read()?;
-        let mem = self.ctx();
-
-        let case_preserving = mem.case_preserving();
-
-        if mem.ue_version() < (4, 22) {
-            // wtf :skull_emoji:
-            let chunks = self
-                .map(|_| mem.fnamepool().0)
-                .cast::<Ptr<Ptr<Ptr<(), C>, C>, C>>()
-                .read()?;
-
-            let per_chunk = 0x4000;
-
-            let chunk = value / per_chunk;
-            let offset = value % per_chunk;
-
-            let chunk = chunks.offset(chunk as usize).read()?;
-            let entry = chunk.offset(offset as usize).read()?;
-
-            let index = entry.cast::<u32>().read()?;
-            let is_wide = (index & 1) == 1;
-            let char_data = entry.byte_offset(0x10);
-
-            let base = if is_wide {
-                let mut data = vec![];
-                let char_data = char_data.cast::<u16>();
-                for i in 0.. {
-                    let next = char_data.offset(i).read()?;
-                    if next == 0 {
-                        break;
-                    }
-                    data.push(next);
-                }
-                String::from_utf16(&data)?
-            } else {
-                let mut data = vec![];
-                let char_data = char_data.cast::<u8>();
-                for i in 0.. {
-                    let next = char_data.offset(i).read()?;
-                    if next == 0 {
-                        break;
-                    }
-                    data.push(next);
-                }
-                String::from_utf8(data)?
-            };
-            return Ok(if number == 0 {
-                base
-            } else {
-                format!("{base}_{}", number - 1)
-            });
-        }
+        Ok(name_with_number(
+            read_name_entry(self.ctx(), value)?,
+            number,
+        ))
+    }
+}
+
+/// Append the FName instance number suffix (if any) to the base name
+pub fn name_with_number(base: String, number: u32) -> String {
+    if number == 0 {
+        base
+    } else {
+        format!("{base}_{}", number - 1)
+    }
+}
+
+/// Read the base string of a name entry by its comparison index
+pub fn read_name_entry<C: Ctx>(mem: &C, value: u32) -> Result<String> {
+    let cache = mem.name_cache();
+    if let Some(name) = cache.0.read().unwrap().get(&value) {
+        return Ok(name.clone());
+    }
+    let entry = mem.names().read_entry(mem, value)?;
+    let name = mem
+        .name_resolver()
+        .and_then(|resolver| resolver.resolve(&entry))
+        .unwrap_or(entry);
+    cache.0.write().unwrap().insert(value, name.clone());
+    Ok(name)
+}
 
-        let blocks = self.map(|_| mem.fnamepool().0 + 0x10).cast::<Ptr<u8, C>>();
+/// Resolved base strings by comparison index, shared by all copies of a context so each name
+/// entry is read and decoded once per dump. Entries never change once allocated, so failed reads
+/// are the only thing not kept.
+#[derive(Debug, Default)]
+pub struct NameCache(RwLock<HashMap<u32, String>>);
+
+/// Storage of FName strings, looked up by comparison index
+pub trait NameTrait {
+    fn read_entry<C: Ctx>(&self, ctx: &C, index: u32) -> Result<String>;
+}
+
+/// `FNamePool` (UE 4.23+): blocks of `FNameEntry` with a packed length/wide header
+#[derive(Debug, Clone, Copy)]
+pub struct PtrFNamePool(pub u64);
+
+impl NameTrait for PtrFNamePool {
+    fn read_entry<C: Ctx>(&self, ctx: &C, value: u32) -> Result<String> {
+        let case_preserving = ctx.case_preserving();
+
+        // FNameEntryAllocator: pointer sized FRWLock, CurrentBlock, CurrentByteCursor, Blocks
+        let blocks_offset = ctx.target().pointer_size as u64 + 8;
+        let blocks = Ptr::<Ptr<u8, C>, C>::new(self.0 + blocks_offset, ctx.clone());
 
         let block_index = (value >> 16) as usize;
         let offset = if case_preserving {
@@ -201,25 +210,177 @@ impl<C: Ctx> Ptr<FName, C> {
         };
         let is_wide = header & 1 != 0;
 
-        let base = if is_wide {
-            String::from_utf16(
-                &block
-                    .offset(offset + 2)
-                    .read_vec(len * 2)?
-                    .chunks(2)
-                    .map(|chunk| u16::from_le_bytes(chunk.try_into().unwrap()))
-                    .collect::<Vec<_>>(),
-            )?
+        Ok(if is_wide {
+            String::from_utf16(&block.offset(offset + 2).cast::<u16>().read_vec(len)?)?
         } else {
             String::from_utf8(block.offset(offset + 2).read_vec(len)?)?
-        };
-        Ok(if number == 0 {
-            base
+        })
+    }
+}
+
+/// `GNames` (UE 4.8 - 4.22): pointer to a `TNameEntryArray`, an inline table of chunks of
+/// `FNameEntry*`. Each entry is `Index` (wide flag in bit 0), `HashNext` and then the null
+/// terminated characters.
+#[derive(Debug, Clone, Copy)]
+pub struct PtrTNameEntryArray(pub u64);
+
+impl PtrTNameEntryArray {
+    const ELEMENTS_PER_CHUNK: u32 = 0x4000;
+}
+
+impl NameTrait for PtrTNameEntryArray {
+    fn read_entry<C: Ctx>(&self, ctx: &C, value: u32) -> Result<String> {
+        let chunks = Ptr::<Ptr<Option<Ptr<Option<Ptr<(), C>>, C>>, C>, C>::new(self.0, ctx.clone())
+            .read()?;
+
+        let chunk = chunks
+            .offset((value / Self::ELEMENTS_PER_CHUNK) as usize)
+            .read()?
+            .with_context(|| format!("name chunk of index {value} is not allocated"))?;
+        let entry = chunk
+            .offset((value % Self::ELEMENTS_PER_CHUNK) as usize)
+            .read()?
+            .with_context(|| format!("no name entry at index {value}"))?;
+
+        let index = entry.cast::<u32>().read()?;
+        let is_wide = (index & 1) == 1;
+        let pointer_size = ctx.target().pointer_size as usize;
+        let char_data = entry.byte_offset(pointer_size * 2);
+
+        Ok(if is_wide {
+            let mut data = vec![];
+            let char_data = char_data.cast::<u16>();
+            for i in 0.. {
+                let next = char_data.offset(i).read()?;
+                if next == 0 {
+                    break;
+                }
+                data.push(next);
+            }
+            String::from_utf16(&data)?
         } else {
-            format!("{base}_{}", number - 1)
+            let mut data = vec![];
+            let char_data = char_data.cast::<u8>();
+            for i in 0.. {
+                let next = char_data.offset(i).read()?;
+                if next == 0 {
+                    break;
+                }
+                data.push(next);
+            }
+            String::from_utf8(data)?
         })
     }
 }
 
+/// Name storage of the dumped engine version
 #[derive(Debug, Clone, Copy)]
-pub struct PtrFNamePool(pub u64);
+pub enum Names {
+    Pool(PtrFNamePool),
+    EntryArray(PtrTNameEntryArray),
+}
+
+impl Names {
+    /// `address` is the resolved `FNamePool` or, before 4.23, `GNames`
+    pub fn for_version(address: u64, version: (u16, u16)) -> Self {
+        if version < (4, 23) {
+            Self::EntryArray(PtrTNameEntryArray(address))
+        } else {
+            Self::Pool(PtrFNamePool(address))
+        }
+    }
+}
+
+impl NameTrait for Names {
+    fn read_entry<C: Ctx>(&self, ctx: &C, index: u32) -> Result<String> {
+        match self {
+            Self::Pool(pool) => pool.read_entry(ctx, index),
+            Self::EntryArray(array) => array.read_entry(ctx, index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem::{CtxPtr, Target};
+    use crate::objects::PathCache;
+    use std::sync::Arc;
+
+    /// Memory of `data` mapped at `base`
+    #[derive(Clone)]
+    struct Bytes {
+        base: u64,
+        data: Arc<Vec<u8>>,
+    }
+
+    impl Mem for Bytes {
+        fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+            let offset = address
+                .checked_sub(self.base)
+                .with_context(|| format!("0x{address:x} is not mapped"))?
+                as usize;
+            let data = self
+                .data
+                .get(offset..offset + buf.len())
+                .with_context(|| format!("0x{address:x} is not mapped"))?;
+            buf.copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    fn ctx(names: Names, base: u64, data: Vec<u8>) -> CtxPtr<Bytes> {
+        CtxPtr {
+            mem: Bytes {
+                base,
+                data: Arc::new(data),
+            },
+            names,
+            name_cache: Default::default(),
+            path_cache: Arc::new(PathCache::default()),
+            guobjectarray: 0,
+            structs: Default::default(),
+            version: (4, 22),
+            case_preserving: false,
+            target: Target::default(),
+            decoders: Default::default(),
+            name_resolver: None,
+        }
+    }
+
+    #[test]
+    fn test_name_entry_array() {
+        const BASE: u64 = 0x1000;
+        let mut data = vec![0u8; 0x600];
+        let mut put = |address: u64, bytes: &[u8]| {
+            let offset = (address - BASE) as usize;
+            data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        // GNames pointing at the chunk table, the third chunk isn't allocated
+        put(0x1000, &0x1100u64.to_le_bytes());
+        put(0x1100, &0x1200u64.to_le_bytes());
+        put(0x1108, &0x1300u64.to_le_bytes());
+        // entry 2 of the first chunk and entry 1 of the second
+        put(0x1210, &0x1400u64.to_le_bytes());
+        put(0x1308, &0x1500u64.to_le_bytes());
+        put(0x1400, &(2u32 << 1).to_le_bytes());
+        put(0x1410, b"None\0");
+        let wide = 0x4001u32;
+        put(0x1500, &(wide << 1 | 1).to_le_bytes());
+        let chars: Vec<u8> = "Wide\0".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        put(0x1510, &chars);
+
+        let names = Names::for_version(0x1000, (4, 22));
+        let ctx = ctx(names, BASE, data);
+        assert_eq!(names.read_entry(&ctx, 2).unwrap(), "None");
+        assert_eq!(names.read_entry(&ctx, wide).unwrap(), "Wide");
+
+        let err = names.read_entry(&ctx, 1).unwrap_err();
+        assert_eq!(err.to_string(), "no name entry at index 1");
+        let err = names.read_entry(&ctx, 0x8000).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "name chunk of index 32768 is not allocated"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Decoding of obfuscated GUObjectArray pointers. Some shipped games store `ObjObjects`, the
+//! chunk table or `FUObjectItem::Object` XORed or rotated and only decode them inline, so the
+//! dumper runs every such pointer through a [`Decode`] hook before following it.
+//!
+//! Hooks are either implemented in Rust or written as expressions over the encoded value `x`,
+//! e.g. `rol(x ^ 0x5a3c9e1f00000000, 17) - 0x1000`. Supported are integer literals (decimal or
+//! `0x` hex), `+ - * & | ^ << >> ~` with C precedence, parentheses and the functions `rol(v, n)`,
+//! `ror(v, n)` and `bswap(v)`. All arithmetic wraps at 64 bits.
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, bail};
+use serde::Deserialize;
+
+/// Turns a pointer as stored in memory into the real pointer
+pub trait Decode: Send + Sync {
+    fn decode(&self, value: u64) -> u64;
+}
+
+impl<F: Fn(u64) -> u64 + Send + Sync> Decode for F {
+    fn decode(&self, value: u64) -> u64 {
+        self(value)
+    }
+}
+
+#[derive(Clone)]
+pub struct Hook(Arc<dyn Decode>);
+
+impl Hook {
+    pub fn new(decode: impl Decode + 'static) -> Self {
+        Self(Arc::new(decode))
+    }
+}
+
+impl<'de> Deserialize<'de> for Hook {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Expr::parse(&source)
+            .map(Hook::new)
+            .map_err(|e| serde::de::Error::custom(format!("{e:#}")))
+    }
+}
+
+/// Hooks for every obfuscated pointer GUObjectArray reads go through, loaded from a JSON profile
+/// such as `{"objects": "x ^ 0x1234", "object": "ror(x, 8)"}`. Unset hooks leave values as is.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Decoders {
+    /// `FFixedUObjectArray::Objects` / `FChunkedFixedUObjectArray::Objects`
+    pub objects: Option<Hook>,
+    /// Entries of the `FChunkedFixedUObjectArray` chunk table
+    pub chunks: Option<Hook>,
+    /// `FUObjectItem::Object`
+    pub object: Option<Hook>,
+}
+
+/// Run `value` through `hook` if there is one
+pub fn apply(hook: &Option<Hook>, value: u64) -> u64 {
+    match hook {
+        Some(hook) => hook.0.decode(value),
+        None => value,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+    Rol,
+    Ror,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Value,
+    Literal(u64),
+    Not(Box<Expr>),
+    Bswap(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Decode for Expr {
+    fn decode(&self, value: u64) -> u64 {
+        self.eval(value)
+    }
+}
+
+impl Expr {
+    fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.binary(0)?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("unexpected {token:?} in decoder expression {source:?}");
+        }
+        Ok(expr)
+    }
+
+    fn eval(&self, x: u64) -> u64 {
+        match self {
+            Self::Value => x,
+            Self::Literal(v) => *v,
+            Self::Not(e) => !e.eval(x),
+            Self::Bswap(e) => e.eval(x).swap_bytes(),
+            Self::Binary(op, a, b) => {
+                let (a, b) = (a.eval(x), b.eval(x));
+                match op {
+                    Op::Add => a.wrapping_add(b),
+                    Op::Sub => a.wrapping_sub(b),
+                    Op::Mul => a.wrapping_mul(b),
+                    Op::And => a & b,
+                    Op::Or => a | b,
+                    Op::Xor => a ^ b,
+                    Op::Shl => a.checked_shl(b as u32).unwrap_or(0),
+                    Op::Shr => a.checked_shr(b as u32).unwrap_or(0),
+                    Op::Rol => a.rotate_left(b as u32),
+                    Op::Ror => a.rotate_right(b as u32),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    const SYMBOLS: &[&str] = &["<<", ">>", "+", "-", "*", "&", "|", "^", "~", "(", ")", ","];
+
+    let mut tokens = vec![];
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            if len == 0 {
+                bail!("unexpected character in decoder expression {source:?}");
+            }
+            let word = &rest[..len];
+            tokens.push(if word.starts_with(|c: char| c.is_ascii_digit()) {
+                let value = match word.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => word.parse(),
+                };
+                Token::Number(value.with_context(|| format!("bad number {word:?}"))?)
+            } else {
+                Token::Ident(word.to_string())
+            });
+            rest = &rest[len..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+/// Binary operators by increasing precedence
+const PRECEDENCE: &[&[(&str, Op)]] = &[
+    &[("|", Op::Or)],
+    &[("^", Op::Xor)],
+    &[("&", Op::And)],
+    &[("<<", Op::Shl), (">>", Op::Shr)],
+    &[("+", Op::Add), ("-", Op::Sub)],
+    &[("*", Op::Mul)],
+];
+
+impl Parser {
+    fn peek_symbol(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Symbol(s)) => Some(*s),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<()> {
+        if self.peek_symbol() != Some(symbol) {
+            bail!("expected {symbol:?} in decoder expression");
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        let Some(ops) = PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(&(_, op)) = ops.iter().find(|(s, _)| self.peek_symbol() == Some(*s)) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(op, lhs.into(), rhs.into());
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .context("decoder expression ends early")?;
+        self.pos += 1;
+        Ok(match token {
+            Token::Number(v) => Expr::Literal(v),
+            Token::Symbol("~") => Expr::Not(self.unary()?.into()),
+            Token::Symbol("(") => {
+                let expr = self.binary(0)?;
+                self.expect(")")?;
+                expr
+            }
+            Token::Ident(name) if name == "x" => Expr::Value,
+            Token::Ident(name) => {
+                self.expect("(")?;
+                let value = self.binary(0)?;
+                let expr = match name.as_str() {
+                    "bswap" => Expr::Bswap(value.into()),
+                    "rol" | "ror" => {
+                        self.expect(",")?;
+                        let amount = self.binary(0)?;
+                        let op = if name == "rol" { Op::Rol } else { Op::Ror };
+                        Expr::Binary(op, value.into(), amount.into())
+                    }
+                    _ => bail!("unknown decoder function {name:?}"),
+                };
+                self.expect(")")?;
+                expr
+            }
+            Token::Symbol(s) => bail!("unexpected {s:?} in decoder expression"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(source: &str, x: u64) -> u64 {
+        Expr::parse(source).unwrap().eval(x)
+    }
+
+    fn error(source: &str) -> String {
+        format!("{:#}", Expr::parse(source).unwrap_err())
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 << 3", 0), 24);
+        assert_eq!(eval("1 << 2 + 3", 0), 32);
+        assert_eq!(eval("x ^ 1 & 2", 5), 5);
+        assert_eq!(eval("x | 1 ^ 3", 8), 10);
+        assert_eq!(eval("2 + 3 * 4", 0), 14);
+        assert_eq!(eval("(2 + 3) * 4", 0), 20);
+        assert_eq!(eval("10 - 3 - 2", 0), 5);
+        assert_eq!(eval("~x & 0xff", 0x0f), 0xf0);
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            eval("rol(x, 8)", 0xff00_0000_0000_0001),
+            0x0000_0000_0000_01ff
+        );
+        assert_eq!(
+            eval("ror(x, 8)", 0x0000_0000_0000_01ff),
+            0xff00_0000_0000_0001
+        );
+        assert_eq!(
+            eval("ror(rol(x, 17), 17)", 0x1234_5678_9abc_def0),
+            0x1234_5678_9abc_def0
+        );
+        assert_eq!(
+            eval("bswap(x)", 0x0102_0304_0506_0708),
+            0x0807_0605_0403_0201
+        );
+        assert_eq!(
+            eval(
+                "rol(x ^ 0x5a3c9e1f00000000, 17) - 0x1000",
+                0x5a3c_9e1f_0000_0000
+            ),
+            0u64.wrapping_sub(0x1000)
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(eval("0x10", 0), 16);
+        assert_eq!(eval("0xFFFFffffFFFFffff", 0), u64::MAX);
+        assert_eq!(eval("1234", 0), 1234);
+        assert_eq!(eval("x + 1", u64::MAX), 0);
+        assert_eq!(eval("1 << 64", 0), 0);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(error("x 1").contains("unexpected Number(1)"));
+        assert!(error("(x))").contains("unexpected Symbol(\")\")"));
+        assert!(error("foo(x)").contains("unknown decoder function \"foo\""));
+        assert!(error("-x").contains("unexpected \"-\""));
+        assert!(error("x +").contains("ends early"));
+        assert!(error("rol(x)").contains("expected \",\""));
+        assert!(error("0x").contains("bad number"));
+        assert!(error("x $ 1").contains("unexpected character"));
+    }
+}
@@ -0,0 +1,462 @@
+//! Changes between two dumps of a game, for tracking what an update touched: added and removed
+//! types, changed struct sizes, super structs and properties, and changed enum values.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use jmap::{Jmap, ObjectType, Property, Struct};
+use serde::Serialize;
+
+use crate::sdkgen::property_type_name;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Class,
+    Struct,
+    Enum,
+}
+
+impl Kind {
+    pub(crate) fn of(obj: &ObjectType) -> Option<Self> {
+        match obj {
+            ObjectType::Class(_) => Some(Kind::Class),
+            ObjectType::ScriptStruct(_) => Some(Kind::Struct),
+            ObjectType::Enum(_) => Some(Kind::Enum),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Class => "class",
+            Kind::Struct => "struct",
+            Kind::Enum => "enum",
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeRef {
+    pub path: String,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeDiff {
+    pub path: String,
+    pub kind: Kind,
+    pub changes: Vec<Change>,
+}
+
+/// Property as compared between dumps, with its type as written in headers (e.g.
+/// `TArray<FVector>`)
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PropertyInfo {
+    pub r#type: String,
+    pub offset: usize,
+    pub size: usize,
+    pub array_dim: usize,
+}
+
+impl PropertyInfo {
+    fn new(objects: &Objects, prop: &Property) -> Self {
+        Self {
+            r#type: property_type_name(objects, prop),
+            offset: prop.offset,
+            size: prop.size,
+            array_dim: prop.array_dim,
+        }
+    }
+}
+
+impl fmt::Display for PropertyInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.r#type)?;
+        if self.array_dim != 1 {
+            write!(f, "[{}]", self.array_dim)?;
+        }
+        write!(f, " at 0x{:x} (size 0x{:x})", self.offset, self.size)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Size {
+        old: usize,
+        new: usize,
+    },
+    SuperStruct {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    PropertyAdded {
+        name: String,
+        property: PropertyInfo,
+    },
+    PropertyRemoved {
+        name: String,
+        property: PropertyInfo,
+    },
+    PropertyChanged {
+        name: String,
+        old: PropertyInfo,
+        new: PropertyInfo,
+    },
+    EnumValueAdded {
+        name: String,
+        value: i64,
+    },
+    EnumValueRemoved {
+        name: String,
+        value: i64,
+    },
+    EnumValueChanged {
+        name: String,
+        old: i64,
+        new: i64,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Size { old, new } => write!(f, "size 0x{old:x} -> 0x{new:x}"),
+            Change::SuperStruct { old, new } => write!(
+                f,
+                "super {} -> {}",
+                old.as_deref().unwrap_or("none"),
+                new.as_deref().unwrap_or("none")
+            ),
+            Change::PropertyAdded { name, property } => write!(f, "+ {name}: {property}"),
+            Change::PropertyRemoved { name, property } => write!(f, "- {name}: {property}"),
+            Change::PropertyChanged { name, old, new } => {
+                write!(f, "~ {name}:")?;
+                if old.r#type != new.r#type || old.array_dim != new.array_dim {
+                    write!(f, " type {} -> {}", old.r#type, new.r#type)?;
+                    if old.array_dim != new.array_dim {
+                        write!(f, ", array_dim {} -> {}", old.array_dim, new.array_dim)?;
+                    }
+                }
+                if old.offset != new.offset {
+                    write!(f, " offset 0x{:x} -> 0x{:x}", old.offset, new.offset)?;
+                }
+                if old.size != new.size {
+                    write!(f, " size 0x{:x} -> 0x{:x}", old.size, new.size)?;
+                }
+                Ok(())
+            }
+            Change::EnumValueAdded { name, value } => write!(f, "+ {name} = {value}"),
+            Change::EnumValueRemoved { name, value } => write!(f, "- {name} = {value}"),
+            Change::EnumValueChanged { name, old, new } => write!(f, "~ {name} = {old} -> {new}"),
+        }
+    }
+}
+
+/// Classes, structs and enums which differ between two dumps. Types whose kind changed are
+/// reported as removed and added.
+#[derive(Debug, Default, Serialize)]
+pub struct Diff {
+    pub added: Vec<TypeRef>,
+    pub removed: Vec<TypeRef>,
+    pub changed: Vec<TypeDiff>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for t in &self.added {
+            writeln!(f, "+ {} {}", t.kind, t.path)?;
+        }
+        for t in &self.removed {
+            writeln!(f, "- {} {}", t.kind, t.path)?;
+        }
+        for t in &self.changed {
+            writeln!(f, "~ {} {}", t.kind, t.path)?;
+            for change in &t.changes {
+                writeln!(f, "    {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn diff(old: &Jmap, new: &Jmap) -> Diff {
+    let mut diff = Diff::default();
+    for (path, old_obj) in &old.objects {
+        let Some(kind) = Kind::of(old_obj) else {
+            continue;
+        };
+        match new.objects.get(path) {
+            Some(new_obj) if Kind::of(new_obj) == Some(kind) => {
+                let changes = diff_type(&old.objects, old_obj, &new.objects, new_obj);
+                if !changes.is_empty() {
+                    diff.changed.push(TypeDiff {
+                        path: path.clone(),
+                        kind,
+                        changes,
+                    });
+                }
+            }
+            _ => diff.removed.push(TypeRef {
+                path: path.clone(),
+                kind,
+            }),
+        }
+    }
+    for (path, new_obj) in &new.objects {
+        let Some(kind) = Kind::of(new_obj) else {
+            continue;
+        };
+        if old.objects.get(path).and_then(Kind::of) != Some(kind) {
+            diff.added.push(TypeRef {
+                path: path.clone(),
+                kind,
+            });
+        }
+    }
+    diff
+}
+
+type Objects = BTreeMap<String, ObjectType>;
+
+/// Changes between two versions of a type, `old` and `new` being the objects of the dumps
+/// containing them
+pub(crate) fn diff_type(
+    old: &Objects,
+    old_obj: &ObjectType,
+    new: &Objects,
+    new_obj: &ObjectType,
+) -> Vec<Change> {
+    let mut changes = vec![];
+    if let (ObjectType::Enum(old_enum), ObjectType::Enum(new_enum)) = (old_obj, new_obj) {
+        let old_values = old_enum.names.iter().cloned().collect::<BTreeMap<_, _>>();
+        let new_values = new_enum.names.iter().cloned().collect::<BTreeMap<_, _>>();
+        for (name, value) in &new_enum.names {
+            match old_values.get(name) {
+                None => changes.push(Change::EnumValueAdded {
+                    name: name.clone(),
+                    value: *value,
+                }),
+                Some(old) if old != value => changes.push(Change::EnumValueChanged {
+                    name: name.clone(),
+                    old: *old,
+                    new: *value,
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, value) in &old_enum.names {
+            if !new_values.contains_key(name) {
+                changes.push(Change::EnumValueRemoved {
+                    name: name.clone(),
+                    value: *value,
+                });
+            }
+        }
+    } else if let (Some(old_struct), Some(new_struct)) =
+        (old_obj.get_struct(), new_obj.get_struct())
+    {
+        diff_struct(old, old_struct, new, new_struct, &mut changes);
+    }
+    changes
+}
+
+fn diff_struct(
+    old: &Objects,
+    old_struct: &Struct,
+    new: &Objects,
+    new_struct: &Struct,
+    changes: &mut Vec<Change>,
+) {
+    if old_struct.properties_size != new_struct.properties_size {
+        changes.push(Change::Size {
+            old: old_struct.properties_size,
+            new: new_struct.properties_size,
+        });
+    }
+    if old_struct.super_struct != new_struct.super_struct {
+        changes.push(Change::SuperStruct {
+            old: old_struct.super_struct.clone(),
+            new: new_struct.super_struct.clone(),
+        });
+    }
+    let mut old_props = old_struct
+        .properties
+        .iter()
+        .map(|p| (p.name.as_str(), PropertyInfo::new(old, p)))
+        .collect::<BTreeMap<_, _>>();
+    for prop in &new_struct.properties {
+        let info = PropertyInfo::new(new, prop);
+        match old_props.remove(prop.name.as_str()) {
+            None => changes.push(Change::PropertyAdded {
+                name: prop.name.clone(),
+                property: info,
+            }),
+            Some(old_info) if old_info != info => changes.push(Change::PropertyChanged {
+                name: prop.name.clone(),
+                old: old_info,
+                new: info,
+            }),
+            Some(_) => {}
+        }
+    }
+    // whatever wasn't matched by name is gone, reported in the old declaration order
+    for prop in &old_struct.properties {
+        if let Some(info) = old_props.remove(prop.name.as_str()) {
+            changes.push(Change::PropertyRemoved {
+                name: prop.name.clone(),
+                property: info,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{dump, property};
+    use serde_json::json;
+
+    fn old() -> Jmap {
+        dump(json!({
+            "/Script/Game.Removed": { "type": "Class" },
+            "/Script/Game.Unchanged": {
+                "properties": [property("Value", 0, 4, json!({ "type": "IntProperty" }))],
+                "properties_size": 4,
+            },
+            "/Script/Game.Player": {
+                "type": "Class",
+                "super_struct": "/Script/Engine.Actor",
+                "properties": [
+                    property("Health", 0x10, 4, json!({ "type": "FloatProperty" })),
+                    property("Score", 0x14, 4, json!({ "type": "IntProperty" })),
+                    property("Name", 0x18, 0x10, json!({ "type": "StrProperty" })),
+                    property("Gone", 0x28, 4, json!({ "type": "IntProperty" })),
+                ],
+                "properties_size": 0x30,
+            },
+            "/Script/Game.EMode": {
+                "type": "Enum",
+                "names": [["EMode::Normal", 0], ["EMode::Hard", 1], ["EMode::Old", 2]],
+            },
+        }))
+    }
+
+    fn new() -> Jmap {
+        dump(json!({
+            "/Script/Game.Added": { "type": "Class" },
+            "/Script/Game.Unchanged": {
+                "properties": [property("Value", 0, 4, json!({ "type": "IntProperty" }))],
+                "properties_size": 4,
+            },
+            "/Script/Game.Player": {
+                "type": "Class",
+                "super_struct": "/Script/Engine.Pawn",
+                "properties": [
+                    property("Health", 0x18, 4, json!({ "type": "FloatProperty" })),
+                    property("Score", 0x1c, 8, json!({ "type": "Int64Property" })),
+                    property("Name", 0x28, 0x10, json!({ "type": "StrProperty" })),
+                    property("Ammo", 0x38, 4, json!({ "type": "IntProperty" })),
+                ],
+                "properties_size": 0x40,
+            },
+            "/Script/Game.EMode": {
+                "type": "Enum",
+                "names": [["EMode::Normal", 0], ["EMode::Hard", 2], ["EMode::Nightmare", 3]],
+            },
+        }))
+    }
+
+    #[test]
+    fn test_types() {
+        fn paths(types: &[TypeRef]) -> Vec<(&str, Kind)> {
+            types.iter().map(|t| (t.path.as_str(), t.kind)).collect()
+        }
+        let diff = diff(&old(), &new());
+        assert_eq!(paths(&diff.added), [("/Script/Game.Added", Kind::Class)]);
+        assert_eq!(
+            paths(&diff.removed),
+            [("/Script/Game.Removed", Kind::Class)]
+        );
+        assert_eq!(
+            diff.changed
+                .iter()
+                .map(|t| (t.path.as_str(), t.kind))
+                .collect::<Vec<_>>(),
+            [
+                ("/Script/Game.EMode", Kind::Enum),
+                ("/Script/Game.Player", Kind::Class)
+            ]
+        );
+        assert!(super::diff(&old(), &old()).is_empty());
+    }
+
+    #[test]
+    fn test_kind_change() {
+        let old = dump(json!({ "/Script/Game.Thing": { "type": "Class" } }));
+        let new = dump(json!({ "/Script/Game.Thing": {} }));
+        let diff = diff(&old, &new);
+        assert_eq!(diff.added[0].kind, Kind::Struct);
+        assert_eq!(diff.removed[0].kind, Kind::Class);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_struct_changes() {
+        let diff = diff(&old(), &new());
+        let player = diff
+            .changed
+            .iter()
+            .find(|t| t.path == "/Script/Game.Player")
+            .unwrap();
+        let changes = player
+            .changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "size 0x30 -> 0x40",
+                "super /Script/Engine.Actor -> /Script/Engine.Pawn",
+                "~ Health: offset 0x10 -> 0x18",
+                "~ Score: type int32_t -> int64_t offset 0x14 -> 0x1c size 0x4 -> 0x8",
+                "~ Name: offset 0x18 -> 0x28",
+                "+ Ammo: int32_t at 0x38 (size 0x4)",
+                "- Gone: int32_t at 0x28 (size 0x4)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enum_changes() {
+        let diff = diff(&old(), &new());
+        let mode = diff
+            .changed
+            .iter()
+            .find(|t| t.path == "/Script/Game.EMode")
+            .unwrap();
+        let changes = mode
+            .changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "~ EMode::Hard = 1 -> 2",
+                "+ EMode::Nightmare = 3",
+                "- EMode::Old = 2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,942 @@
+//! Disassembler for Kismet (blueprint VM) bytecode as stored in memory in `UStruct::Script`
+
+use std::cell::RefCell;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use anyhow::{Context as _, Result, bail};
+use jmap::{Jmap, Native, ObjectType, ScriptReferences};
+
+use crate::containers::{name_with_number, read_name_entry};
+use crate::mem::{Ctx, Mem, Ptr};
+use crate::objects::{UObject, ZField};
+use crate::vtable::native_function;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::FromRepr, strum::Display)]
+#[repr(u8)]
+pub enum EExprToken {
+    EX_LocalVariable = 0x00,
+    EX_InstanceVariable = 0x01,
+    EX_DefaultVariable = 0x02,
+    EX_Return = 0x04,
+    EX_Jump = 0x06,
+    EX_JumpIfNot = 0x07,
+    EX_Assert = 0x09,
+    EX_Nothing = 0x0B,
+    EX_NothingInt32 = 0x0C,
+    EX_Let = 0x0F,
+    EX_BitFieldConst = 0x11,
+    EX_ClassContext = 0x12,
+    EX_MetaCast = 0x13,
+    EX_LetBool = 0x14,
+    EX_EndParmValue = 0x15,
+    EX_EndFunctionParms = 0x16,
+    EX_Self = 0x17,
+    EX_Skip = 0x18,
+    EX_Context = 0x19,
+    EX_Context_FailSilent = 0x1A,
+    EX_VirtualFunction = 0x1B,
+    EX_FinalFunction = 0x1C,
+    EX_IntConst = 0x1D,
+    EX_FloatConst = 0x1E,
+    EX_StringConst = 0x1F,
+    EX_ObjectConst = 0x20,
+    EX_NameConst = 0x21,
+    EX_RotationConst = 0x22,
+    EX_VectorConst = 0x23,
+    EX_ByteConst = 0x24,
+    EX_IntZero = 0x25,
+    EX_IntOne = 0x26,
+    EX_True = 0x27,
+    EX_False = 0x28,
+    EX_TextConst = 0x29,
+    EX_NoObject = 0x2A,
+    EX_TransformConst = 0x2B,
+    EX_IntConstByte = 0x2C,
+    EX_NoInterface = 0x2D,
+    EX_DynamicCast = 0x2E,
+    EX_StructConst = 0x2F,
+    EX_EndStructConst = 0x30,
+    EX_SetArray = 0x31,
+    EX_EndArray = 0x32,
+    EX_PropertyConst = 0x33,
+    EX_UnicodeStringConst = 0x34,
+    EX_Int64Const = 0x35,
+    EX_UInt64Const = 0x36,
+    EX_DoubleConst = 0x37,
+    EX_Cast = 0x38,
+    EX_SetSet = 0x39,
+    EX_EndSet = 0x3A,
+    EX_SetMap = 0x3B,
+    EX_EndMap = 0x3C,
+    EX_SetConst = 0x3D,
+    EX_EndSetConst = 0x3E,
+    EX_MapConst = 0x3F,
+    EX_EndMapConst = 0x40,
+    EX_Vector3fConst = 0x41,
+    EX_StructMemberContext = 0x42,
+    EX_LetMulticastDelegate = 0x43,
+    EX_LetDelegate = 0x44,
+    EX_LocalVirtualFunction = 0x45,
+    EX_LocalFinalFunction = 0x46,
+    EX_LocalOutVariable = 0x48,
+    EX_DeprecatedOp4A = 0x4A,
+    EX_InstanceDelegate = 0x4B,
+    EX_PushExecutionFlow = 0x4C,
+    EX_PopExecutionFlow = 0x4D,
+    EX_ComputedJump = 0x4E,
+    EX_PopExecutionFlowIfNot = 0x4F,
+    EX_Breakpoint = 0x50,
+    EX_InterfaceContext = 0x51,
+    EX_ObjToInterfaceCast = 0x52,
+    EX_EndOfScript = 0x53,
+    EX_CrossInterfaceCast = 0x54,
+    EX_InterfaceToObjCast = 0x55,
+    EX_WireTracepoint = 0x5A,
+    EX_SkipOffsetConst = 0x5B,
+    EX_AddMulticastDelegate = 0x5C,
+    EX_ClearMulticastDelegate = 0x5D,
+    EX_Tracepoint = 0x5E,
+    EX_LetObj = 0x5F,
+    EX_LetWeakObjPtr = 0x60,
+    EX_BindDelegate = 0x61,
+    EX_RemoveMulticastDelegate = 0x62,
+    EX_CallMulticastDelegate = 0x63,
+    EX_LetValueOnPersistentFrame = 0x64,
+    EX_ArrayConst = 0x65,
+    EX_EndArrayConst = 0x66,
+    EX_SoftObjectConst = 0x67,
+    EX_CallMath = 0x68,
+    EX_SwitchValue = 0x69,
+    EX_InstrumentationEvent = 0x6A,
+    EX_ArrayGetByRef = 0x6B,
+    EX_ClassSparseDataVariable = 0x6C,
+    EX_FieldPathConst = 0x6D,
+}
+
+/// In-memory FScriptName operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptName {
+    pub comparison_index: u32,
+    pub number: u32,
+}
+
+/// Resolves raw pointer and name operands to something readable
+pub trait Resolver {
+    fn name(&self, name: ScriptName) -> Option<String>;
+    fn object(&self, address: u64) -> Option<String>;
+    fn property(&self, address: u64) -> Option<String>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    /// Offset of the token in the script
+    pub offset: usize,
+    pub token: EExprToken,
+    pub operands: Vec<Operand>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Operand {
+    Expr(Expr),
+    /// Variable length list of expressions (function parameters, container elements)
+    Exprs(Vec<Expr>),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Floats(Vec<f64>),
+    String(String),
+    /// Offset into the script
+    CodeOffset(u32),
+    Name(ScriptName, Option<String>),
+    Object(u64, Option<String>),
+    Property(u64, Option<String>),
+    Cases(Vec<SwitchCase>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SwitchCase {
+    pub value: Expr,
+    pub next: u32,
+    pub result: Expr,
+}
+
+/// Deepest expression nesting decoded before giving up, so corrupt scripts can't overflow the
+/// stack. What the blueprint compiler emits stays far below it.
+const MAX_DEPTH: usize = 128;
+
+/// Decode a script into a list of top level statements. Object and property operands are
+/// `pointer_size` bytes wide.
+pub fn disassemble<R: Resolver>(
+    script: &[u8],
+    ue_version: (u16, u16),
+    pointer_size: usize,
+    resolver: &R,
+) -> Result<Vec<Expr>> {
+    let mut reader = Reader {
+        script,
+        pos: 0,
+        depth: 0,
+        ue_version,
+        pointer_size,
+        resolver,
+    };
+    let mut statements = vec![];
+    while reader.pos < script.len() {
+        statements.push(reader.expr()?);
+    }
+    Ok(statements)
+}
+
+struct Reader<'a, R> {
+    script: &'a [u8],
+    pos: usize,
+    /// Expressions being decoded
+    depth: usize,
+    ue_version: (u16, u16),
+    pointer_size: usize,
+    resolver: &'a R,
+}
+
+impl<R: Resolver> Reader<'_, R> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let Some(bytes) = self.script.get(self.pos..self.pos + N) else {
+            bail!("unexpected end of script at 0x{:x}", self.pos);
+        };
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+    fn u8(&mut self) -> Result<u8> {
+        Ok(u8::from_le_bytes(self.bytes()?))
+    }
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes()?))
+    }
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+    /// `ScriptPointerType`, a `UPTRINT`
+    fn pointer(&mut self) -> Result<u64> {
+        if self.pointer_size == 4 {
+            Ok(self.u32()? as u64)
+        } else {
+            self.u64()
+        }
+    }
+    fn real(&mut self, lwc: bool) -> Result<f64> {
+        Ok(if lwc {
+            f64::from_le_bytes(self.bytes()?)
+        } else {
+            f32::from_le_bytes(self.bytes()?) as f64
+        })
+    }
+    fn reals(&mut self, count: usize, lwc: bool) -> Result<Operand> {
+        Ok(Operand::Floats(
+            (0..count).map(|_| self.real(lwc)).collect::<Result<_>>()?,
+        ))
+    }
+    fn lwc(&self) -> bool {
+        self.ue_version >= (5, 0)
+    }
+
+    fn int(&mut self) -> Result<Operand> {
+        Ok(Operand::Int(self.i32()? as i64))
+    }
+    fn offset(&mut self) -> Result<Operand> {
+        Ok(Operand::CodeOffset(self.u32()?))
+    }
+    fn name(&mut self) -> Result<Operand> {
+        let comparison_index = self.u32()?;
+        if self.ue_version >= (4, 22) {
+            // display index
+            self.u32()?;
+        }
+        let name = ScriptName {
+            comparison_index,
+            number: self.u32()?,
+        };
+        Ok(Operand::Name(name, self.resolver.name(name)))
+    }
+    fn object(&mut self) -> Result<Operand> {
+        let address = self.pointer()?;
+        Ok(Operand::Object(address, self.resolver.object(address)))
+    }
+    fn property(&mut self) -> Result<Operand> {
+        let address = self.pointer()?;
+        Ok(Operand::Property(address, self.resolver.property(address)))
+    }
+    fn sub_expr(&mut self) -> Result<Operand> {
+        Ok(Operand::Expr(self.expr()?))
+    }
+    /// Read expressions up to and including the terminating token
+    fn exprs_until(&mut self, end: EExprToken) -> Result<Operand> {
+        let mut exprs = vec![];
+        loop {
+            let expr = self.expr()?;
+            if expr.token == end {
+                break;
+            }
+            exprs.push(expr);
+        }
+        Ok(Operand::Exprs(exprs))
+    }
+    fn ansi_string(&mut self) -> Result<Operand> {
+        let mut data = vec![];
+        loop {
+            match self.u8()? {
+                0 => break,
+                c => data.push(c),
+            }
+        }
+        Ok(Operand::String(data.into_iter().map(char::from).collect()))
+    }
+    fn wide_string(&mut self) -> Result<Operand> {
+        let mut data = vec![];
+        loop {
+            match self.u16()? {
+                0 => break,
+                c => data.push(c),
+            }
+        }
+        Ok(Operand::String(String::from_utf16_lossy(&data)))
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        if self.depth == MAX_DEPTH {
+            bail!(
+                "expressions nested deeper than {MAX_DEPTH} at 0x{:x}",
+                self.pos
+            );
+        }
+        self.depth += 1;
+        let expr = self.read_expr();
+        self.depth -= 1;
+        expr
+    }
+
+    fn read_expr(&mut self) -> Result<Expr> {
+        use EExprToken::*;
+
+        let offset = self.pos;
+        let byte = self.u8()?;
+        let token = EExprToken::from_repr(byte)
+            .with_context(|| format!("unknown token 0x{byte:02x} at 0x{offset:x}"))?;
+
+        // the few tokens with operands that depend on each other are read by hand, everything
+        // else from its fixed list of operands so this frame stays small when nesting is deep
+        let operands = match token {
+            EX_Assert => vec![
+                Operand::Int(self.u16()? as i64),
+                Operand::Int(self.u8()? as i64),
+                self.sub_expr()?,
+            ],
+            EX_TextConst => self.text_const(offset)?,
+            EX_SwitchValue => self.switch_value()?,
+            EX_InstrumentationEvent => self.instrumentation_event()?,
+            _ => {
+                let reads = operand_reads(token);
+                let mut operands = Vec::with_capacity(reads.len());
+                for read in reads {
+                    operands.push(self.operand(*read)?);
+                }
+                operands
+            }
+        };
+
+        Ok(Expr {
+            offset,
+            token,
+            operands,
+        })
+    }
+    fn operand(&mut self, read: Read) -> Result<Operand> {
+        Ok(match read {
+            Read::Expr => self.sub_expr()?,
+            Read::Exprs(end) => self.exprs_until(end)?,
+            Read::Property => self.property()?,
+            Read::Object => self.object()?,
+            Read::Name => self.name()?,
+            Read::Offset => self.offset()?,
+            Read::Int => self.int()?,
+            Read::Byte => Operand::UInt(self.u8()? as u64),
+            Read::Int64 => Operand::Int(self.u64()? as i64),
+            Read::UInt64 => Operand::UInt(self.u64()?),
+            Read::Float => Operand::Float(self.real(false)?),
+            Read::Double => Operand::Float(self.real(true)?),
+            Read::Reals(count) => self.reals(count, self.lwc())?,
+            Read::Floats(count) => self.reals(count, false)?,
+            Read::AnsiString => self.ansi_string()?,
+            Read::WideString => self.wide_string()?,
+        })
+    }
+    fn text_const(&mut self, offset: usize) -> Result<Vec<Operand>> {
+        let literal_type = self.u8()?;
+        let mut operands = vec![Operand::UInt(literal_type as u64)];
+        match literal_type {
+            // empty
+            0 => {}
+            // localized text: source, key, namespace
+            1 => {
+                for _ in 0..3 {
+                    operands.push(self.sub_expr()?);
+                }
+            }
+            // invariant text, literal string
+            2 | 3 => operands.push(self.sub_expr()?),
+            // string table entry: table asset, table id, key
+            4 => {
+                operands.push(self.object()?);
+                operands.push(self.sub_expr()?);
+                operands.push(self.sub_expr()?);
+            }
+            other => bail!("unknown text literal type {other} at 0x{offset:x}"),
+        }
+        Ok(operands)
+    }
+    fn switch_value(&mut self) -> Result<Vec<Operand>> {
+        let num_cases = self.u16()?;
+        let end = self.offset()?;
+        let index = self.sub_expr()?;
+        let mut cases = Vec::with_capacity(num_cases as usize);
+        for _ in 0..num_cases {
+            cases.push(SwitchCase {
+                value: self.expr()?,
+                next: self.u32()?,
+                result: self.expr()?,
+            });
+        }
+        Ok(vec![end, index, Operand::Cases(cases), self.sub_expr()?])
+    }
+    fn instrumentation_event(&mut self) -> Result<Vec<Operand>> {
+        let event_type = self.u8()?;
+        let mut operands = vec![Operand::UInt(event_type as u64)];
+        // EScriptInstrumentation::InlineEvent
+        if event_type == 4 {
+            operands.push(self.name()?);
+        }
+        Ok(operands)
+    }
+}
+
+/// How an operand is read
+#[derive(Debug, Clone, Copy)]
+enum Read {
+    Expr,
+    /// Expressions up to and including the token
+    Exprs(EExprToken),
+    Property,
+    Object,
+    Name,
+    Offset,
+    Int,
+    Byte,
+    Int64,
+    UInt64,
+    Float,
+    Double,
+    /// Doubles from UE 5.0 (large world coordinates), floats before
+    Reals(usize),
+    Floats(usize),
+    AnsiString,
+    WideString,
+}
+
+/// Operands of `token`, for all but the tokens [`Reader::read_expr`] reads by hand
+fn operand_reads(token: EExprToken) -> &'static [Read] {
+    use EExprToken::*;
+    use Read::*;
+
+    match token {
+        EX_LocalVariable
+        | EX_InstanceVariable
+        | EX_DefaultVariable
+        | EX_LocalOutVariable
+        | EX_ClassSparseDataVariable
+        | EX_PropertyConst => &[Property],
+        EX_Return
+        | EX_SoftObjectConst
+        | EX_FieldPathConst
+        | EX_ComputedJump
+        | EX_PopExecutionFlowIfNot
+        | EX_InterfaceContext
+        | EX_ClearMulticastDelegate => &[Expr],
+        EX_Jump | EX_PushExecutionFlow | EX_SkipOffsetConst => &[Offset],
+        EX_JumpIfNot | EX_Skip => &[Offset, Expr],
+        EX_Nothing | EX_EndParmValue | EX_EndFunctionParms | EX_Self | EX_IntZero | EX_IntOne
+        | EX_True | EX_False | EX_NoObject | EX_NoInterface | EX_EndStructConst | EX_EndArray
+        | EX_EndSet | EX_EndMap | EX_EndSetConst | EX_EndMapConst | EX_EndArrayConst
+        | EX_DeprecatedOp4A | EX_PopExecutionFlow | EX_Breakpoint | EX_EndOfScript
+        | EX_WireTracepoint | EX_Tracepoint => &[],
+        EX_NothingInt32 | EX_IntConst => &[Int],
+        EX_Let => &[Property, Expr, Expr],
+        EX_LetValueOnPersistentFrame | EX_StructMemberContext => &[Property, Expr],
+        EX_LetBool
+        | EX_LetObj
+        | EX_LetWeakObjPtr
+        | EX_LetDelegate
+        | EX_LetMulticastDelegate
+        | EX_AddMulticastDelegate
+        | EX_RemoveMulticastDelegate
+        | EX_ArrayGetByRef => &[Expr, Expr],
+        EX_BitFieldConst => &[Property, Byte],
+        EX_MetaCast
+        | EX_DynamicCast
+        | EX_ObjToInterfaceCast
+        | EX_CrossInterfaceCast
+        | EX_InterfaceToObjCast => &[Object, Expr],
+        EX_Cast => &[Byte, Expr],
+        EX_ClassContext | EX_Context | EX_Context_FailSilent => &[Expr, Offset, Property, Expr],
+        EX_VirtualFunction | EX_LocalVirtualFunction => &[Name, Exprs(EX_EndFunctionParms)],
+        EX_FinalFunction | EX_LocalFinalFunction | EX_CallMath => {
+            &[Object, Exprs(EX_EndFunctionParms)]
+        }
+        EX_CallMulticastDelegate => &[Object, Expr, Exprs(EX_EndFunctionParms)],
+        EX_FloatConst => &[Float],
+        EX_DoubleConst => &[Double],
+        EX_Int64Const => &[Int64],
+        EX_UInt64Const => &[UInt64],
+        EX_ByteConst | EX_IntConstByte => &[Byte],
+        EX_StringConst => &[AnsiString],
+        EX_UnicodeStringConst => &[WideString],
+        EX_ObjectConst => &[Object],
+        EX_NameConst | EX_InstanceDelegate => &[Name],
+        EX_RotationConst | EX_VectorConst => &[Reals(3)],
+        EX_Vector3fConst => &[Floats(3)],
+        EX_TransformConst => &[Reals(4), Reals(3), Reals(3)],
+        EX_StructConst => &[Object, Int, Exprs(EX_EndStructConst)],
+        EX_SetArray => &[Expr, Exprs(EX_EndArray)],
+        EX_SetSet => &[Expr, Int, Exprs(EX_EndSet)],
+        EX_SetMap => &[Expr, Int, Exprs(EX_EndMap)],
+        EX_SetConst => &[Property, Int, Exprs(EX_EndSetConst)],
+        EX_ArrayConst => &[Property, Int, Exprs(EX_EndArrayConst)],
+        EX_MapConst => &[Property, Property, Int, Exprs(EX_EndMapConst)],
+        EX_BindDelegate => &[Name, Expr, Expr],
+        EX_Assert | EX_TextConst | EX_SwitchValue | EX_InstrumentationEvent => {
+            unreachable!("{token} is read by hand")
+        }
+    }
+}
+
+/// Write an indented text listing of statements
+pub fn write_listing(buffer: &mut String, statements: &[Expr]) {
+    for statement in statements {
+        write_expr(buffer, statement, 0);
+    }
+}
+
+fn write_expr(buffer: &mut String, expr: &Expr, depth: usize) {
+    let indent = "    ".repeat(depth);
+    write!(buffer, "{:04X}: {indent}{}", expr.offset, expr.token).unwrap();
+
+    let mut inline = vec![];
+    for operand in &expr.operands {
+        match operand {
+            Operand::Expr(_) | Operand::Exprs(_) | Operand::Cases(_) => {}
+            Operand::Int(v) => inline.push(v.to_string()),
+            Operand::UInt(v) => inline.push(v.to_string()),
+            Operand::Float(v) => inline.push(v.to_string()),
+            Operand::Floats(v) => inline.push(format!("{v:?}")),
+            Operand::String(v) => inline.push(format!("{v:?}")),
+            Operand::CodeOffset(v) => inline.push(format!("0x{v:04X}")),
+            Operand::Name(name, resolved) => inline.push(resolved.clone().unwrap_or_else(|| {
+                format!("FName(0x{:x}, {})", name.comparison_index, name.number)
+            })),
+            Operand::Object(address, resolved) | Operand::Property(address, resolved) => {
+                inline.push(resolved.clone().unwrap_or_else(|| format!("0x{address:x}")))
+            }
+        }
+    }
+    if !inline.is_empty() {
+        write!(buffer, " {}", inline.join(", ")).unwrap();
+    }
+    buffer.push('\n');
+
+    for operand in &expr.operands {
+        match operand {
+            Operand::Expr(expr) => write_expr(buffer, expr, depth + 1),
+            Operand::Exprs(exprs) => {
+                for expr in exprs {
+                    write_expr(buffer, expr, depth + 1);
+                }
+            }
+            Operand::Cases(cases) => {
+                for case in cases {
+                    writeln!(buffer, "      {indent}case (next 0x{:04X}):", case.next).unwrap();
+                    write_expr(buffer, &case.value, depth + 2);
+                    write_expr(buffer, &case.result, depth + 2);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Resolves operands offline from a dump
+pub struct JmapResolver<'a> {
+    objects: HashMap<u64, &'a str>,
+    references: Option<&'a ScriptReferences>,
+}
+
+impl<'a> JmapResolver<'a> {
+    pub fn new(jmap: &'a Jmap) -> Self {
+        Self {
+            objects: jmap
+                .objects
+                .iter()
+                .map(|(path, obj)| (obj.get_object().address.0, path.as_str()))
+                .collect(),
+            references: jmap.script_references.as_ref(),
+        }
+    }
+}
+
+impl Resolver for JmapResolver<'_> {
+    fn name(&self, name: ScriptName) -> Option<String> {
+        let base = self.references?.names.get(&name.comparison_index)?;
+        Some(name_with_number(base.clone(), name.number))
+    }
+    fn object(&self, address: u64) -> Option<String> {
+        if let Some(path) = self.objects.get(&address) {
+            return Some(path.to_string());
+        }
+        self.references?.objects.get(&address.into()).cloned()
+    }
+    fn property(&self, address: u64) -> Option<String> {
+        self.references?.properties.get(&address.into()).cloned()
+    }
+}
+
+/// Text listing of every struct and function in the dump that has script bytecode
+pub fn into_listing(jmap: &Jmap) -> Result<String> {
+    let metadata = jmap
+        .metadata
+        .as_ref()
+        .context("dump is missing engine version metadata")?;
+    let version = (metadata.engine_version.major, metadata.engine_version.minor);
+    let pointer_size = metadata.pointer_size as usize;
+    let resolver = JmapResolver::new(jmap);
+
+    let mut buffer = String::new();
+    for (path, obj) in &jmap.objects {
+        let Some(script) = obj.get_struct().and_then(|s| s.script.as_ref()) else {
+            continue;
+        };
+        writeln!(buffer, "{path}:").unwrap();
+        match disassemble(&script.0, version, pointer_size, &resolver) {
+            Ok(statements) => write_listing(&mut buffer, &statements),
+            Err(err) => writeln!(buffer, "failed to disassemble: {err}").unwrap(),
+        }
+        buffer.push('\n');
+    }
+    Ok(buffer)
+}
+
+/// Resolves operands from live memory, recording everything it resolves
+struct LiveResolver<C> {
+    ctx: C,
+    references: RefCell<ScriptReferences>,
+}
+
+impl<C: Ctx> Resolver for LiveResolver<C> {
+    fn name(&self, name: ScriptName) -> Option<String> {
+        let mut references = self.references.borrow_mut();
+        let base = match references.names.entry(name.comparison_index) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => entry
+                .insert(read_name_entry(&self.ctx, name.comparison_index).ok()?)
+                .clone(),
+        };
+        Some(name_with_number(base, name.number))
+    }
+    fn object(&self, address: u64) -> Option<String> {
+        if address == 0 {
+            return None;
+        }
+        let mut references = self.references.borrow_mut();
+        Some(match references.objects.entry(address.into()) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => entry
+                .insert(
+                    Ptr::<UObject, C>::new(address, self.ctx.clone())
+                        .path()
+                        .ok()?,
+                )
+                .clone(),
+        })
+    }
+    fn property(&self, address: u64) -> Option<String> {
+        if address == 0 {
+            return None;
+        }
+        let mut references = self.references.borrow_mut();
+        Some(match references.properties.entry(address.into()) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => entry
+                .insert(
+                    Ptr::<ZField, C>::new(address, self.ctx.clone())
+                        .name_private()
+                        .read()
+                        .ok()?,
+                )
+                .clone(),
+        })
+    }
+}
+
+/// Disassemble all dumped scripts against live memory to collect the names, objects and
+/// properties needed to disassemble them again offline
+pub(crate) fn collect_references<C: Ctx>(
+    ctx: &C,
+    objects: &BTreeMap<String, ObjectType>,
+) -> ScriptReferences {
+    let collector = ReferenceCollector::new(ctx);
+    for (path, obj) in objects {
+        collector.add(path, obj);
+    }
+    collector.finish()
+}
+
+/// Accumulates script references one object at a time
+pub(crate) struct ReferenceCollector<C> {
+    resolver: LiveResolver<C>,
+}
+
+impl<C: Ctx> ReferenceCollector<C> {
+    pub(crate) fn new(ctx: &C) -> Self {
+        Self {
+            resolver: LiveResolver {
+                ctx: ctx.clone(),
+                references: Default::default(),
+            },
+        }
+    }
+
+    pub(crate) fn add(&self, path: &str, obj: &ObjectType) {
+        let Some(script) = obj.get_struct().and_then(|s| s.script.as_ref()) else {
+            return;
+        };
+        let ctx = &self.resolver.ctx;
+        let pointer_size = ctx.target().pointer_size as usize;
+        if let Err(err) = disassemble(&script.0, ctx.ue_version(), pointer_size, &self.resolver) {
+            tracing::warn!("{path}: failed to disassemble script: {err}");
+        }
+    }
+
+    pub(crate) fn finish(self) -> ScriptReferences {
+        self.resolver.references.into_inner()
+    }
+}
+
+/// Opcodes GNatives has handlers for
+const NATIVES: usize = 256;
+
+/// Handler of every opcode in GNatives, if it was resolved. Entries left null are skipped.
+pub(crate) fn read_natives<M: Mem>(mem: &M, header: &Jmap) -> Vec<Native> {
+    let Some(gnatives) = header.resolved_addresses.get("GNatives") else {
+        return vec![];
+    };
+    let table = match mem.read_vec::<usize>(gnatives.0, NATIVES) {
+        Ok(table) => table,
+        Err(err) => {
+            tracing::warn!("failed to read GNatives: {err:#}");
+            return vec![];
+        }
+    };
+    table
+        .into_iter()
+        .enumerate()
+        .filter_map(|(opcode, address)| {
+            let address = address as u64;
+            (address != 0).then(|| Native {
+                opcode: opcode as u8,
+                token: EExprToken::from_repr(opcode as u8).map(|t| t.to_string()),
+                function: native_function(header, address),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct TestResolver;
+    impl Resolver for TestResolver {
+        fn name(&self, name: ScriptName) -> Option<String> {
+            Some(name_with_number(
+                format!("Name{}", name.comparison_index),
+                name.number,
+            ))
+        }
+        fn object(&self, address: u64) -> Option<String> {
+            (address == 0x1000).then(|| "/Script/Engine.KismetSystemLibrary:Delay".to_string())
+        }
+        fn property(&self, address: u64) -> Option<String> {
+            Some(format!("Prop{address:x}"))
+        }
+    }
+
+    fn listing(script: &[u8], ue_version: (u16, u16), pointer_size: usize) -> Result<String> {
+        let statements = disassemble(script, ue_version, pointer_size, &TestResolver)?;
+        let mut buffer = String::new();
+        write_listing(&mut buffer, &statements);
+        Ok(buffer)
+    }
+
+    #[test]
+    fn test_constants() -> Result<()> {
+        let script = [
+            0x1D, 0x2A, 0x00, 0x00, 0x00, // EX_IntConst 42
+            0x24, 0x07, // EX_ByteConst 7
+            0x1F, b'h', b'i', 0x00, // EX_StringConst "hi"
+            0x34, b'o', 0x00, b'k', 0x00, 0x00, 0x00, // EX_UnicodeStringConst "ok"
+            0x1E, 0x00, 0x00, 0x80, 0x3F, // EX_FloatConst 1.0
+            0x53, // EX_EndOfScript
+        ];
+        assert_eq!(
+            listing(&script, (4, 27), 8)?,
+            "0000: EX_IntConst 42\n\
+             0005: EX_ByteConst 7\n\
+             0007: EX_StringConst \"hi\"\n\
+             000B: EX_UnicodeStringConst \"ok\"\n\
+             0012: EX_FloatConst 1\n\
+             0017: EX_EndOfScript\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pointer_operands() -> Result<()> {
+        // EX_Let Prop10 = EX_LocalVariable Prop20, EX_IntOne
+        let mut script = vec![0x0F];
+        script.extend(0x10u64.to_le_bytes());
+        script.push(0x00);
+        script.extend(0x20u64.to_le_bytes());
+        script.push(0x26);
+        assert_eq!(
+            listing(&script, (4, 27), 8)?,
+            "0000: EX_Let Prop10\n\
+             0009:     EX_LocalVariable Prop20\n\
+             0012:     EX_IntOne\n"
+        );
+
+        let mut script = vec![0x0F];
+        script.extend(0x10u32.to_le_bytes());
+        script.push(0x00);
+        script.extend(0x20u32.to_le_bytes());
+        script.push(0x26);
+        assert_eq!(
+            listing(&script, (4, 27), 4)?,
+            "0000: EX_Let Prop10\n\
+             0005:     EX_LocalVariable Prop20\n\
+             000A:     EX_IntOne\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_call() -> Result<()> {
+        // EX_FinalFunction Delay(EX_Self, EX_FloatConst 0.5)
+        let mut script = vec![0x1C];
+        script.extend(0x1000u64.to_le_bytes());
+        script.extend([0x17, 0x1E, 0x00, 0x00, 0x00, 0x3F, 0x16]);
+        let statements = disassemble(&script, (4, 27), 8, &TestResolver)?;
+        assert_eq!(statements.len(), 1);
+        let [Operand::Object(0x1000, Some(path)), Operand::Exprs(params)] =
+            statements[0].operands.as_slice()
+        else {
+            panic!("unexpected operands {:?}", statements[0].operands);
+        };
+        assert_eq!(path, "/Script/Engine.KismetSystemLibrary:Delay");
+        assert_eq!(
+            params.iter().map(|p| p.token).collect::<Vec<_>>(),
+            [EExprToken::EX_Self, EExprToken::EX_FloatConst]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_names() -> Result<()> {
+        // FScriptName has a display index from 4.22
+        let script = [0x21, 5, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0];
+        assert_eq!(
+            listing(&script, (4, 27), 8)?,
+            "0000: EX_NameConst Name5_1\n"
+        );
+        let script = [0x21, 5, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(listing(&script, (4, 21), 8)?, "0000: EX_NameConst Name5\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_jumps() -> Result<()> {
+        // EX_JumpIfNot 0x0010 EX_False, EX_Jump 0x0020
+        let script = [0x07, 0x10, 0, 0, 0, 0x28, 0x06, 0x20, 0, 0, 0];
+        assert_eq!(
+            listing(&script, (5, 3), 8)?,
+            "0000: EX_JumpIfNot 0x0010\n\
+             0005:     EX_False\n\
+             0006: EX_Jump 0x0020\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lwc_vectors() -> Result<()> {
+        let mut script = vec![0x23];
+        for v in [1.0f64, 2.0, 3.0] {
+            script.extend(v.to_le_bytes());
+        }
+        assert_eq!(
+            listing(&script, (5, 0), 8)?,
+            "0000: EX_VectorConst [1.0, 2.0, 3.0]\n"
+        );
+        let mut script = vec![0x23];
+        for v in [1.0f32, 2.0, 3.0] {
+            script.extend(v.to_le_bytes());
+        }
+        assert_eq!(
+            listing(&script, (4, 27), 8)?,
+            "0000: EX_VectorConst [1.0, 2.0, 3.0]\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed() {
+        // truncated operand
+        assert!(listing(&[0x1D, 0x2A, 0x00], (4, 27), 8).is_err());
+        // unknown token
+        assert!(listing(&[0xFF], (4, 27), 8).is_err());
+        // unterminated parameter list
+        assert!(
+            listing(
+                &[0x1B, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x17],
+                (4, 27),
+                8
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        // EX_Return EX_Return ... EX_Nothing
+        let mut script = vec![0x04; MAX_DEPTH - 1];
+        script.push(0x0B);
+        assert!(disassemble(&script, (4, 27), 8, &TestResolver).is_ok());
+
+        let mut script = vec![0x04; 100_000];
+        script.push(0x0B);
+        let err = disassemble(&script, (4, 27), 8, &TestResolver).unwrap_err();
+        assert!(err.to_string().contains("nested deeper"), "{err}");
+    }
+}
@@ -0,0 +1,180 @@
+//! Pages read from a target kept on disk across runs (`--cache-dir`), so dumping the same target
+//! again with other output options reads it from disk instead of the backend. Every build and
+//! source gets its own directory under the cache directory, named after a hash of the image
+//! headers (which hold the PE timestamp and image size) and the source, with the pages appended
+//! to `pages.bin` and their addresses and offsets to `pages.idx`.
+//!
+//! Live processes are keyed on their PID and start time, so a restarted game gets a fresh
+//! directory. Within one process nothing is ever invalidated: a later run replays the memory of
+//! the first, which is only what's wanted as long as the process hasn't moved on, so reusing
+//! cached pages is warned about.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result};
+use tracing::{info, warn};
+
+use crate::mem::{Mem, PAGE_SIZE, Target};
+
+/// `pages.idx` entry: page address and offset in `pages.bin`, both little endian
+const ENTRY_SIZE: usize = 16;
+
+struct Files {
+    pages: File,
+    index: File,
+}
+
+pub struct PageStore {
+    /// Page address -> offset in `pages.bin`
+    offsets: Mutex<HashMap<u64, u64>>,
+    files: Mutex<Files>,
+}
+
+impl PageStore {
+    /// Store of the target whose image at `image_base` is read through `mem`, described by
+    /// `source` (e.g. the PID or dump path) so different targets of one build stay apart
+    pub fn open(dir: &Path, mem: &impl Mem, image_base: u64, source: &str) -> Result<Self> {
+        let mut headers = vec![0; PAGE_SIZE];
+        mem.read_buf(image_base, &mut headers)
+            .context("Failed to read the image headers to key the cache")?;
+        let key = fnv1a(fnv1a(FNV_OFFSET, &headers), source.as_bytes());
+        let dir = dir.join(format!("{key:016x}"));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+
+        let open = |name: &str| {
+            OpenOptions::new()
+                .read(true)
+                .append(true)
+                .create(true)
+                .open(dir.join(name))
+                .with_context(|| format!("Failed to open {}", dir.join(name).display()))
+        };
+        let pages = open("pages.bin")?;
+        let mut index = open("pages.idx")?;
+
+        // entries are only appended once their page is written, so anything past the end of
+        // either file is what an interrupted run left behind
+        let pages_len = pages.metadata()?.len();
+        let mut entries = vec![];
+        index.read_to_end(&mut entries)?;
+        let offsets = entries
+            .chunks_exact(ENTRY_SIZE)
+            .map(|e| {
+                let page = u64::from_le_bytes(e[..8].try_into().unwrap());
+                let offset = u64::from_le_bytes(e[8..].try_into().unwrap());
+                (page, offset)
+            })
+            .filter(|(_, offset)| offset + PAGE_SIZE as u64 <= pages_len)
+            .collect::<HashMap<_, _>>();
+        if offsets.is_empty() {
+            info!("caching pages in {}", dir.display());
+        } else {
+            warn!(
+                "reusing {} pages cached by an earlier run in {}, changes the target made since \
+                 then won't be seen (delete the directory to read it afresh)",
+                offsets.len(),
+                dir.display()
+            );
+        }
+
+        Ok(Self {
+            offsets: Mutex::new(offsets),
+            files: Mutex::new(Files { pages, index }),
+        })
+    }
+
+    /// `buf.len() / PAGE_SIZE` pages from `address` if all of them are stored
+    fn read(&self, address: u64, buf: &mut [u8]) -> Result<bool> {
+        let offsets = {
+            let stored = self.offsets.lock().unwrap();
+            let offsets = (0..buf.len() / PAGE_SIZE)
+                .map(|i| stored.get(&(address + (i * PAGE_SIZE) as u64)).copied())
+                .collect::<Option<Vec<_>>>();
+            match offsets {
+                Some(offsets) => offsets,
+                None => return Ok(false),
+            }
+        };
+        let mut files = self.files.lock().unwrap();
+        for (offset, page) in offsets.into_iter().zip(buf.chunks_exact_mut(PAGE_SIZE)) {
+            files.pages.seek(SeekFrom::Start(offset))?;
+            files.pages.read_exact(page)?;
+        }
+        Ok(true)
+    }
+
+    /// Add the pages of `data` from `address` that aren't stored yet
+    fn write(&self, address: u64, data: &[u8]) -> Result<()> {
+        let mut files = self.files.lock().unwrap();
+        let mut offsets = self.offsets.lock().unwrap();
+        let mut end = files.pages.seek(SeekFrom::End(0))?;
+        let mut entries = vec![];
+        for (i, page) in data.chunks_exact(PAGE_SIZE).enumerate() {
+            let address = address + (i * PAGE_SIZE) as u64;
+            if offsets.contains_key(&address) {
+                continue;
+            }
+            files.pages.write_all(page)?;
+            entries.extend(address.to_le_bytes());
+            entries.extend(end.to_le_bytes());
+            offsets.insert(address, end);
+            end += PAGE_SIZE as u64;
+        }
+        files.pages.flush()?;
+        files.index.write_all(&entries)?;
+        Ok(())
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// FNV-1a, which unlike the std hashers is stable across builds of the dumper
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Reads through a [`PageStore`] if there is one. Sits beneath [`MemCache`](crate::mem::MemCache),
+/// which only ever reads whole pages, and leaves any other read to the backend.
+#[derive(Clone)]
+pub struct DiskCache<M> {
+    inner: M,
+    store: Option<Arc<PageStore>>,
+}
+impl<M: Mem> DiskCache<M> {
+    pub fn wrap(inner: M, store: Option<PageStore>) -> Self {
+        Self {
+            inner,
+            store: store.map(Arc::new),
+        }
+    }
+}
+impl<M: Mem> Mem for DiskCache<M> {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        let Some(store) = &self.store else {
+            return self.inner.read_buf(address, buf);
+        };
+        if !address.is_multiple_of(PAGE_SIZE as u64) || !buf.len().is_multiple_of(PAGE_SIZE) {
+            return self.inner.read_buf(address, buf);
+        }
+        match store.read(address, buf) {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            Err(err) => warn!("failed to read cached pages at 0x{address:x}: {err:#}"),
+        }
+        self.inner.read_buf(address, buf)?;
+        if let Err(err) = store.write(address, buf) {
+            warn!("failed to cache pages at 0x{address:x}: {err:#}");
+        }
+        Ok(())
+    }
+    fn target(&self) -> Target {
+        self.inner.target()
+    }
+}
@@ -0,0 +1,99 @@
+//! Minimal ELF header parsing of the mapped image (Linux server builds), the counterpart of
+//! [`pe`](crate::pe). Section headers usually aren't mapped, so the loadable segments stand in for
+//! sections, their permissions translated to the PE characteristics the rest of the dumper checks.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use jmap::{Address, ImageFormat, ImageInfo, ImageSection};
+
+use crate::mem::Mem;
+
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x20000000;
+const IMAGE_SCN_MEM_READ: u32 = 0x40000000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x80000000;
+
+fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+fn u64_at(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+/// Whether the image mapped at `base` is an ELF rather than a PE
+pub fn is_elf<M: Mem>(mem: &M, base: u64) -> bool {
+    let mut magic = [0; 4];
+    mem.read_buf(base, &mut magic).is_ok() && &magic == b"\x7fELF"
+}
+
+pub fn read_image_info<M: Mem>(
+    mem: &M,
+    base: u64,
+    globals: BTreeMap<String, Address>,
+) -> Result<ImageInfo> {
+    let mut header = vec![0; 0x1000];
+    mem.read_buf(base, &mut header)?;
+
+    if &header[0..4] != b"\x7fELF" {
+        bail!("missing ELF header at 0x{base:x}");
+    }
+    if header[4] != 2 || header[5] != 1 {
+        bail!("only 64-bit little endian ELF images are supported");
+    }
+    let phoff = u64_at(&header, 0x20) as usize;
+    let phentsize = u16_at(&header, 0x36) as usize;
+    let phnum = u16_at(&header, 0x38) as usize;
+    if phentsize < 0x38 || phoff + phnum * phentsize > header.len() {
+        bail!("program headers extend past the first page");
+    }
+
+    let segments = (0..phnum)
+        .map(|i| &header[phoff + i * phentsize..phoff + (i + 1) * phentsize])
+        .filter(|p| u32_at(p, 0) == PT_LOAD)
+        .collect::<Vec<_>>();
+    // addresses are relative to the image for position independent executables and absolute
+    // otherwise, where the first segment is loaded at the base
+    let Some(first) = segments.iter().map(|p| u64_at(p, 0x10) & !0xfff).min() else {
+        bail!("image has no loadable segments");
+    };
+
+    let sections = segments
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let flags = u32_at(p, 4);
+            let characteristics = [
+                (PF_X, IMAGE_SCN_MEM_EXECUTE),
+                (PF_R, IMAGE_SCN_MEM_READ),
+                (PF_W, IMAGE_SCN_MEM_WRITE),
+            ]
+            .into_iter()
+            .filter(|(flag, _)| flags & flag != 0)
+            .fold(0, |c, (_, characteristic)| c | characteristic);
+            ImageSection {
+                name: format!("LOAD{i}"),
+                virtual_address: (u64_at(p, 0x10) - first) as u32,
+                virtual_size: u64_at(p, 0x28) as u32,
+                size_of_raw_data: u64_at(p, 0x20) as u32,
+                pointer_to_raw_data: u64_at(p, 8) as u32,
+                characteristics,
+            }
+        })
+        .collect();
+
+    Ok(ImageInfo {
+        format: ImageFormat::Elf,
+        globals,
+        sections,
+        codeview: None,
+        modules: vec![],
+    })
+}
@@ -0,0 +1,77 @@
+//! Patch version and branch of the engine from the `FEngineVersion::Current` global, which the
+//! resolvers don't report. The struct lives in a writable section of the image:
+//!
+//! ```text
+//! 0x00 uint16 Major
+//! 0x02 uint16 Minor
+//! 0x04 uint16 Patch
+//! 0x08 uint32 Changelist
+//! 0x10 FString Branch    e.g. "++UE5+Release-5.3"
+//! ```
+//!
+//! so it is found by scanning for the already known major and minor version followed by a
+//! readable branch name.
+
+use jmap::ImageSection;
+
+use crate::containers::FString;
+use crate::mem::{Mem, Ptr};
+
+const IMAGE_SCN_MEM_WRITE: u32 = 0x80000000;
+/// Bytes read from the target at once while scanning
+const CHUNK: usize = 0x10000;
+
+pub struct FEngineVersion {
+    pub patch: u16,
+    pub branch: String,
+}
+
+fn is_branch(name: &str) -> bool {
+    name.len() < 128
+        && name.starts_with("++")
+        && name[2..].contains('+')
+        && name.chars().all(|c| c.is_ascii_graphic())
+}
+
+pub fn find_engine_version<M: Mem>(
+    mem: &M,
+    base: u64,
+    sections: &[ImageSection],
+    (major, minor): (u16, u16),
+) -> Option<FEngineVersion> {
+    let mut needle = [0; 4];
+    needle[..2].copy_from_slice(&major.to_le_bytes());
+    needle[2..].copy_from_slice(&minor.to_le_bytes());
+
+    let mut buf = vec![0; CHUNK];
+    for section in sections
+        .iter()
+        .filter(|s| s.characteristics & IMAGE_SCN_MEM_WRITE != 0)
+    {
+        let start = base + section.virtual_address as u64;
+        let size = section.virtual_size as usize;
+        for chunk_offset in (0..size).step_by(CHUNK) {
+            let chunk = &mut buf[..CHUNK.min(size - chunk_offset)];
+            let chunk_address = start + chunk_offset as u64;
+            if mem.read_buf(chunk_address, chunk).is_err() {
+                continue;
+            }
+            for offset in (0..chunk.len().saturating_sub(4)).step_by(8) {
+                if chunk[offset..offset + 4] != needle {
+                    continue;
+                }
+                let address = chunk_address + offset as u64;
+                let Ok(branch) = Ptr::<FString, _>::new(address + 0x10, mem.clone()).read() else {
+                    continue;
+                };
+                if !is_branch(&branch) {
+                    continue;
+                }
+                if let Ok(patch) = mem.read::<u16>(address + 4) {
+                    return Some(FEngineVersion { patch, branch });
+                }
+            }
+        }
+    }
+    None
+}
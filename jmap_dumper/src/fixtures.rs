@@ -0,0 +1,75 @@
+//! Small in-memory dumps for tests, written as the JSON of the types they contain with every
+//! field the tests don't care about filled in
+
+use jmap::Jmap;
+use serde_json::{Value, json};
+
+/// Dump of `types`, a map of object path to the fields of the type differing from the defaults.
+/// The `type` field picks the object type and defaults to `ScriptStruct`.
+pub fn dump(types: Value) -> Jmap {
+    let mut objects = serde_json::Map::new();
+    for (path, fields) in types.as_object().expect("types must be an object") {
+        let r#type = fields["type"].as_str().unwrap_or("ScriptStruct");
+        let (package, name) = path.split_once('.').expect("path must be in a package");
+        let mut object = json!({
+            "type": r#type,
+            "address": "0x0",
+            "vtable": "0x0",
+            "object_flags": "",
+            "outer": package,
+            "class": format!("/Script/CoreUObject.{}", r#type),
+            "children": [],
+            "property_values": {},
+        });
+        let defaults = match r#type {
+            "Enum" => json!({
+                "cpp_type": name,
+                "cpp_form": "EnumClass",
+                "names": [],
+            }),
+            _ => {
+                let mut defaults = json!({
+                    "properties": [],
+                    "properties_size": 0,
+                    "min_alignment": 1,
+                });
+                let extra = match r#type {
+                    "Class" => json!({ "class_flags": "", "class_cast_flags": "" }),
+                    _ => json!({ "struct_flags": "" }),
+                };
+                extend(&mut defaults, extra);
+                defaults
+            }
+        };
+        extend(&mut object, defaults);
+        extend(&mut object, fields.clone());
+        objects.insert(path.clone(), object);
+    }
+    serde_json::from_value(json!({
+        "image_base_address": "0x0",
+        "objects": objects,
+        "vtables": {},
+    }))
+    .expect("invalid fixture")
+}
+
+/// Property of `size` bytes at `offset`, `r#type` being the property type and its fields, e.g.
+/// `{"type": "IntProperty"}`
+pub fn property(name: &str, offset: usize, size: usize, r#type: Value) -> Value {
+    let mut property = json!({
+        "name": name,
+        "offset": offset,
+        "array_dim": 1,
+        "size": size,
+        "flags": "",
+    });
+    extend(&mut property, r#type);
+    property
+}
+
+fn extend(object: &mut Value, fields: Value) {
+    let (Value::Object(object), Value::Object(fields)) = (object, fields) else {
+        panic!("fixture fields must be objects");
+    };
+    object.extend(fields);
+}
@@ -0,0 +1,159 @@
+//! [`Mem`] serviced by a Frida agent injected into the game, for processes where
+//! ReadProcessMemory is blocked but script injection is allowed. Frida objects borrow each other
+//! and aren't `Send`, so they live on their own thread and reads are passed to it over a channel.
+
+use std::sync::mpsc;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use serde_json::{Value, json};
+
+use crate::mem::Mem;
+
+const AGENT: &str = r#"
+rpc.exports = {
+    info() {
+        const main = Process.mainModule ?? Process.enumerateModules()[0];
+        return [main.base.toString(), main.name];
+    },
+    read(address, size) {
+        const bytes = new Uint8Array(ptr(address).readByteArray(size));
+        let hex = "";
+        for (const b of bytes) {
+            hex += (b < 16 ? "0" : "") + b.toString(16);
+        }
+        return hex;
+    },
+};
+"#;
+
+struct Request {
+    address: u64,
+    len: usize,
+    reply: mpsc::Sender<Result<Vec<u8>>>,
+}
+
+#[derive(Clone)]
+pub struct FridaMem {
+    requests: mpsc::Sender<Request>,
+}
+
+/// Main module of the attached process
+#[derive(Debug, Clone)]
+pub struct FridaInfo {
+    pub image_base: u64,
+    pub name: String,
+}
+
+fn call(script: &frida::Script<'_>, function: &str, args: Value) -> Result<Value> {
+    script
+        .exports
+        .call(function, Some(args))
+        .map_err(|e| anyhow!("frida agent {function}: {e}"))?
+        .with_context(|| format!("frida agent {function} returned nothing"))
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| Ok(u8::from_str_radix(&hex[i..i + 2], 16)?))
+        .collect()
+}
+
+impl FridaMem {
+    /// Attach to `pid` on the local device and inject the read agent
+    pub fn attach(pid: i32) -> Result<(Self, FridaInfo)> {
+        let (requests, receiver) = mpsc::channel::<Request>();
+        let (info_tx, info_rx) = mpsc::channel::<Result<FridaInfo>>();
+
+        std::thread::spawn(move || {
+            let frida = unsafe { frida::Frida::obtain() };
+            let device_manager = frida::DeviceManager::obtain(&frida);
+            let setup = || -> Result<_> {
+                let device = device_manager
+                    .get_local_device()
+                    .map_err(|e| anyhow!("frida: {e}"))?;
+                let session = device
+                    .attach(pid as u32)
+                    .map_err(|e| anyhow!("frida attach to {pid}: {e}"))?;
+                Ok((device, session))
+            };
+            let (_device, session) = match setup() {
+                Ok(s) => s,
+                Err(e) => {
+                    let _ = info_tx.send(Err(e));
+                    return;
+                }
+            };
+            let script = match session
+                .create_script(AGENT, &mut frida::ScriptOption::default())
+                .map_err(|e| anyhow!("frida create script: {e}"))
+                .and_then(|script| {
+                    script
+                        .load()
+                        .map_err(|e| anyhow!("frida load script: {e}"))?;
+                    Ok(script)
+                }) {
+                Ok(s) => s,
+                Err(e) => {
+                    let _ = info_tx.send(Err(e));
+                    return;
+                }
+            };
+
+            let info = call(&script, "info", json!([])).and_then(|info| {
+                let base = info[0].as_str().context("bad info reply")?;
+                Ok(FridaInfo {
+                    image_base: u64::from_str_radix(base.trim_start_matches("0x"), 16)?,
+                    name: info[1].as_str().unwrap_or_default().to_string(),
+                })
+            });
+            if info_tx.send(info).is_err() {
+                return;
+            }
+
+            // runs until every FridaMem clone is dropped
+            for request in receiver {
+                let result = call(
+                    &script,
+                    "read",
+                    json!([format!("0x{:x}", request.address), request.len]),
+                )
+                .and_then(|hex| decode_hex(hex.as_str().context("bad read reply")?));
+                let _ = request.reply.send(result);
+            }
+            let _ = script.unload();
+        });
+
+        let info = info_rx
+            .recv()
+            .context("frida thread exited before attaching")??;
+        Ok((Self { requests }, info))
+    }
+}
+
+impl Mem for FridaMem {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        let (reply, response) = mpsc::channel();
+        self.requests
+            .send(Request {
+                address,
+                len: buf.len(),
+                reply,
+            })
+            .map_err(|_| anyhow!("frida thread exited"))?;
+        let data = response
+            .recv()
+            .context("frida thread exited")?
+            .with_context(|| format!("reading {} bytes at 0x{:x}", buf.len(), address))?;
+        if data.len() != buf.len() {
+            bail!(
+                "reading {} bytes at 0x{:x}: agent returned {} bytes",
+                buf.len(),
+                address,
+                data.len()
+            );
+        }
+        buf.copy_from_slice(&data);
+        Ok(())
+    }
+}
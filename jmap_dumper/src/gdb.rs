@@ -0,0 +1,170 @@
+//! [`Mem`] over the GDB remote serial protocol, for anything exposing a gdbserver stub
+//! (emulators, hypervisors, devkits). Only `m addr,len` reads are used so the target is never
+//! modified.
+
+use std::io::{BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result, bail};
+
+use crate::mem::Mem;
+
+/// Used when the stub doesn't advertise `PacketSize`
+const DEFAULT_PACKET_SIZE: usize = 0x1000;
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    no_ack: bool,
+    /// Largest number of bytes per `m` request
+    max_read: usize,
+}
+
+impl Connection {
+    fn send(&mut self, packet: &str) -> Result<()> {
+        let checksum = packet.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        loop {
+            write!(self.writer, "${packet}#{checksum:02x}")?;
+            self.writer.flush()?;
+            if self.no_ack {
+                return Ok(());
+            }
+            match self.read_byte()? {
+                b'+' => return Ok(()),
+                b'-' => continue,
+                other => bail!("gdb stub sent {:?} instead of an ack", other as char),
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        let mut byte = [0];
+        self.reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    /// Receive a packet, skipping stray acks and expanding run length encoding
+    fn receive(&mut self) -> Result<Vec<u8>> {
+        loop {
+            while self.read_byte()? != b'$' {}
+
+            let mut data = vec![];
+            let mut checksum = 0u8;
+            loop {
+                let b = self.read_byte()?;
+                if b == b'#' {
+                    break;
+                }
+                checksum = checksum.wrapping_add(b);
+                data.push(b);
+            }
+            let mut expected = [0; 2];
+            self.reader.read_exact(&mut expected)?;
+            let expected = u8::from_str_radix(std::str::from_utf8(&expected)?, 16)?;
+
+            if !self.no_ack {
+                if expected != checksum {
+                    self.writer.write_all(b"-")?;
+                    continue;
+                }
+                self.writer.write_all(b"+")?;
+            }
+
+            let mut expanded = Vec::with_capacity(data.len());
+            let mut iter = data.into_iter();
+            while let Some(b) = iter.next() {
+                if b == b'*' {
+                    let count = iter.next().context("truncated run length encoding")?;
+                    let last = *expanded
+                        .last()
+                        .context("run length encoding without a byte")?;
+                    expanded.extend(std::iter::repeat_n(last, count as usize - 29));
+                } else {
+                    expanded.push(b);
+                }
+            }
+            return Ok(expanded);
+        }
+    }
+
+    fn request(&mut self, packet: &str) -> Result<Vec<u8>> {
+        self.send(packet)?;
+        self.receive()
+    }
+}
+
+fn decode_hex(hex: &[u8], out: &mut [u8]) -> Result<()> {
+    if hex.len() != out.len() * 2 {
+        bail!("expected {} hex digits, got {}", out.len() * 2, hex.len());
+    }
+    for (byte, pair) in out.iter_mut().zip(hex.chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair)?, 16)?;
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct GdbMem {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl GdbMem {
+    /// Connect to a stub at `host:port`
+    pub fn connect(address: &str) -> Result<Self> {
+        let stream = TcpStream::connect(address)
+            .with_context(|| format!("Failed to connect to gdb stub at {address}"))?;
+        stream.set_nodelay(true)?;
+        let mut conn = Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            no_ack: false,
+            max_read: DEFAULT_PACKET_SIZE / 2,
+        };
+
+        let supported = conn.request("qSupported:multiprocess-")?;
+        let supported = String::from_utf8_lossy(&supported);
+        for feature in supported.split(';') {
+            if let Some(size) = feature.strip_prefix("PacketSize=")
+                && let Ok(size) = usize::from_str_radix(size, 16)
+            {
+                // reply is `$<hex>#xx`
+                conn.max_read = (size.saturating_sub(4) / 2).max(1);
+            }
+        }
+        if supported.split(';').any(|f| f == "QStartNoAckMode+")
+            && conn.request("QStartNoAckMode")? == b"OK"
+        {
+            conn.no_ack = true;
+        }
+        // some stubs only serve reads once the halt reason was queried
+        conn.request("?")?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+}
+
+impl Mem for GdbMem {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let max_read = conn.max_read;
+        for (i, chunk) in buf.chunks_mut(max_read).enumerate() {
+            let chunk_address = address + (i * max_read) as u64;
+            let reply = conn.request(&format!("m{chunk_address:x},{:x}", chunk.len()))?;
+            if reply.first() == Some(&b'E') && reply.len() == 3 {
+                bail!(
+                    "reading {} bytes at 0x{:x}: gdb stub error {}",
+                    chunk.len(),
+                    chunk_address,
+                    String::from_utf8_lossy(&reply[1..])
+                );
+            }
+            decode_hex(&reply, chunk).with_context(|| {
+                format!("reading {} bytes at 0x{:x}", chunk.len(), chunk_address)
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+# Importer appended to the generated TYPES, FUNCTIONS and VTABLES tables. Works with both Jython
+# and PyGhidra.
+
+from ghidra.app.util import NamespaceUtils
+from ghidra.program.model.data import (
+    ArrayDataType,
+    BooleanDataType,
+    ByteDataType,
+    CategoryPath,
+    DataTypeConflictHandler,
+    DoubleDataType,
+    EnumDataType,
+    FloatDataType,
+    IntegerDataType,
+    LongLongDataType,
+    PointerDataType,
+    ShortDataType,
+    SignedByteDataType,
+    StructureDataType,
+    UnsignedIntegerDataType,
+    UnsignedLongLongDataType,
+    UnsignedShortDataType,
+)
+from ghidra.program.model.symbol import SourceType
+
+CATEGORY = CategoryPath("/Unreal")
+
+PRIMITIVES = {
+    "bool": BooleanDataType.dataType,
+    "u8": ByteDataType.dataType,
+    "i8": SignedByteDataType.dataType,
+    "u16": UnsignedShortDataType.dataType,
+    "i16": ShortDataType.dataType,
+    "u32": UnsignedIntegerDataType.dataType,
+    "i32": IntegerDataType.dataType,
+    "u64": UnsignedLongLongDataType.dataType,
+    "i64": LongLongDataType.dataType,
+    "f32": FloatDataType.dataType,
+    "f64": DoubleDataType.dataType,
+}
+
+
+class Importer(object):
+    def __init__(self, program):
+        self.program = program
+        self.dtm = program.getDataTypeManager()
+        self.types = {}
+
+    def add(self, dt):
+        return self.dtm.addDataType(dt, DataTypeConflictHandler.REPLACE_HANDLER)
+
+    def display(self, spec):
+        kind = spec[0]
+        if kind == "prim":
+            return spec[1]
+        if kind in ("struct", "enum"):
+            return spec[1]
+        if kind == "ptr":
+            return self.display(spec[1]) + "*"
+        if kind == "tarray":
+            return "TArray<" + self.display(spec[1]) + ">"
+        raise ValueError("unknown type spec %r" % (spec,))
+
+    def resolve(self, spec):
+        kind = spec[0]
+        if kind == "prim":
+            return PRIMITIVES[spec[1]]
+        if kind in ("struct", "enum"):
+            dt = self.types.get(spec[1])
+            if dt is None:
+                # referenced but not dumped
+                dt = self.add(StructureDataType(CATEGORY, spec[1], 0))
+                self.types[spec[1]] = dt
+            return dt
+        if kind == "ptr":
+            return PointerDataType(self.resolve(spec[1]), 8)
+        if kind == "tarray":
+            name = self.display(spec)
+            dt = self.types.get(name)
+            if dt is None:
+                s = StructureDataType(CATEGORY, name, 0x10)
+                s.replaceAtOffset(0, PointerDataType(self.resolve(spec[1]), 8), 8, "Data", None)
+                s.replaceAtOffset(8, IntegerDataType.dataType, 4, "Num", None)
+                s.replaceAtOffset(0xC, IntegerDataType.dataType, 4, "Max", None)
+                dt = self.add(s)
+                self.types[name] = dt
+            return dt
+        raise ValueError("unknown type spec %r" % (spec,))
+
+    def import_types(self):
+        # create every type up front so members can reference types defined later
+        for t in TYPES:
+            if t[0] == "enum":
+                _, name, size, values = t
+                e = EnumDataType(CATEGORY, name, size)
+                for value_name, value in values:
+                    e.add(value_name, value)
+                self.types[name] = self.add(e)
+            else:
+                _, name, size, _ = t
+                self.types[name] = self.add(StructureDataType(CATEGORY, name, size))
+
+        for t in TYPES:
+            if t[0] != "struct":
+                continue
+            _, name, size, members = t
+            s = self.types[name]
+            for member in members:
+                kind = member[0]
+                if kind == "field":
+                    _, offset, field_name, spec, length, dim = member
+                    dt = self.resolve(spec)
+                    if dim > 1:
+                        dt = ArrayDataType(dt, dim, length // dim)
+                    s.replaceAtOffset(offset, dt, length, field_name, None)
+                elif kind == "bit":
+                    _, offset, bit, field_name = member
+                    s.insertBitFieldAt(offset, 1, bit, ByteDataType.dataType, 1, field_name, None)
+                elif kind == "bytes":
+                    _, offset, field_name, length = member
+                    s.replaceAtOffset(
+                        offset, ArrayDataType(ByteDataType.dataType, length, 1), length, field_name, None
+                    )
+                elif kind == "super":
+                    _, super_name, length = member
+                    s.replaceAtOffset(0, self.types[super_name], length, "Super", None)
+
+    def namespace(self, name):
+        return NamespaceUtils.createNamespaceHierarchy(
+            name, None, self.program, SourceType.IMPORTED
+        )
+
+    def import_symbols(self):
+        base = self.program.getImageBase()
+        symbols = self.program.getSymbolTable()
+        listing = self.program.getListing()
+        for rva, owner, name in FUNCTIONS:
+            address = base.add(rva)
+            namespace = self.namespace(owner)
+            function = listing.getFunctionAt(address)
+            if function is None:
+                function = createFunction(address, name)  # noqa: F821 (GhidraScript builtin)
+            if function is not None:
+                function.setParentNamespace(namespace)
+                function.setName(name, SourceType.IMPORTED)
+            else:
+                symbols.createLabel(address, name, namespace, SourceType.IMPORTED)
+        for rva, owner in VTABLES:
+            symbols.createLabel(base.add(rva), "vtable", self.namespace(owner), SourceType.IMPORTED)
+
+
+def run():
+    importer = Importer(currentProgram)  # noqa: F821 (GhidraScript builtin)
+    importer.import_types()
+    importer.import_symbols()
+    print("imported %d types, %d functions, %d vtables" % (len(TYPES), len(FUNCTIONS), len(VTABLES)))
+
+
+run()
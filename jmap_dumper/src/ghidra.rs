@@ -0,0 +1,303 @@
+//! Ghidra export: a self contained Python script which creates the dumped class, struct and enum
+//! datatypes and names exec functions and vtables at their RVAs, similar to what ue_binja does
+//! for Binary Ninja.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use jmap::{Jmap, ObjectType, Property, PropertyType};
+
+use crate::sdkgen::{Member, Sdk, sanitize_ident, short_name};
+
+const IMPORTER: &str = include_str!("ghidra.py");
+
+/// Type in the form understood by the importer script
+enum Spec {
+    Prim(&'static str),
+    Struct(String),
+    Enum(String),
+    Ptr(Box<Spec>),
+    TArray(Box<Spec>),
+}
+
+impl std::fmt::Display for Spec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Spec::Prim(name) => write!(f, "(\"prim\", \"{name}\")"),
+            Spec::Struct(name) => write!(f, "(\"struct\", \"{name}\")"),
+            Spec::Enum(name) => write!(f, "(\"enum\", \"{name}\")"),
+            Spec::Ptr(inner) => write!(f, "(\"ptr\", {inner})"),
+            Spec::TArray(inner) => write!(f, "(\"tarray\", {inner})"),
+        }
+    }
+}
+
+fn int_type(size: usize) -> &'static str {
+    match size {
+        1 => "u8",
+        2 => "u16",
+        4 => "u32",
+        _ => "u64",
+    }
+}
+
+struct Generator<'a> {
+    sdk: Sdk<'a>,
+}
+
+impl Generator<'_> {
+    fn enum_spec(&self, r#enum: Option<&String>, size: usize) -> Spec {
+        match r#enum {
+            Some(e)
+                if matches!(self.sdk.object(e), Some(ObjectType::Enum(_)))
+                    && self.sdk.enum_size(e) == size =>
+            {
+                Spec::Enum(self.sdk.type_name(e))
+            }
+            _ => Spec::Prim(int_type(size)),
+        }
+    }
+
+    /// Type of a property. Returns None if it can only be represented as raw bytes.
+    fn spec(&self, prop: &Property) -> Option<Spec> {
+        let sdk = &self.sdk;
+        let opaque = |name: &str| Spec::Struct(name.into());
+        Some(match &prop.r#type {
+            PropertyType::Struct { r#struct } => {
+                if !matches!(sdk.object(r#struct), Some(ObjectType::ScriptStruct(_))) {
+                    return None;
+                }
+                Spec::Struct(sdk.type_name(r#struct))
+            }
+            PropertyType::Str => opaque("FString"),
+            PropertyType::Utf8Str => opaque("FUtf8String"),
+            PropertyType::AnsiStr => opaque("FAnsiString"),
+            PropertyType::Name => opaque("FName"),
+            PropertyType::Text => opaque("FText"),
+            PropertyType::FieldPath => opaque("FFieldPath"),
+            PropertyType::MulticastInlineDelegate { .. } => opaque("FMulticastInlineDelegate"),
+            PropertyType::MulticastSparseDelegate { .. } => opaque("FMulticastSparseDelegate"),
+            PropertyType::MulticastDelegate { .. } => opaque("FMulticastDelegate"),
+            PropertyType::Delegate { .. } => opaque("FDelegate"),
+            PropertyType::WeakObject { .. } => opaque("TWeakObjectPtr"),
+            PropertyType::SoftObject { .. } => opaque("TSoftObjectPtr"),
+            PropertyType::SoftClass { .. } => opaque("TSoftClassPtr"),
+            PropertyType::LazyObject { .. } => opaque("TLazyObjectPtr"),
+            PropertyType::Interface { .. } => opaque("TScriptInterface"),
+            PropertyType::Map { .. } => opaque("TMap"),
+            PropertyType::Set { .. } => opaque("TSet"),
+            PropertyType::Array { inner } => Spec::TArray(self.type_arg(inner).into()),
+            PropertyType::Bool { .. } => Spec::Prim("bool"),
+            PropertyType::Enum { container, r#enum } => {
+                self.enum_spec(r#enum.as_ref(), container.size)
+            }
+            PropertyType::Byte { r#enum } => self.enum_spec(r#enum.as_ref(), 1),
+            PropertyType::Float => Spec::Prim("f32"),
+            PropertyType::Double => Spec::Prim("f64"),
+            PropertyType::UInt16 => Spec::Prim("u16"),
+            PropertyType::UInt32 => Spec::Prim("u32"),
+            PropertyType::UInt64 => Spec::Prim("u64"),
+            PropertyType::Int8 => Spec::Prim("i8"),
+            PropertyType::Int16 => Spec::Prim("i16"),
+            PropertyType::Int => Spec::Prim("i32"),
+            PropertyType::Int64 => Spec::Prim("i64"),
+            PropertyType::Object { property_class }
+            | PropertyType::Class { property_class, .. } => {
+                Spec::Ptr(Spec::Struct(sdk.type_name(property_class)).into())
+            }
+            PropertyType::Optional { .. } => return None,
+        })
+    }
+
+    /// Type stored behind a TArray pointer where the definition is not required
+    fn type_arg(&self, prop: &Property) -> Spec {
+        match &prop.r#type {
+            PropertyType::Struct { r#struct } => Spec::Struct(self.sdk.type_name(r#struct)),
+            _ => self.spec(prop).unwrap_or(Spec::Prim("u8")),
+        }
+    }
+
+    fn types(&self, b: &mut String) {
+        let sdk = &self.sdk;
+        let sizes = sdk.opaque_sizes();
+
+        writeln!(b, "TYPES = [").unwrap();
+        for (name, size) in [
+            ("FString", 0x10),
+            ("FUtf8String", 0x10),
+            ("FAnsiString", 0x10),
+            ("FName", sizes.name),
+            ("FText", sizes.text),
+            ("FFieldPath", sizes.field_path),
+            ("FDelegate", sizes.delegate),
+            ("FMulticastInlineDelegate", sizes.multicast_inline_delegate),
+            ("FMulticastSparseDelegate", sizes.multicast_sparse_delegate),
+            ("FMulticastDelegate", sizes.multicast_delegate),
+            ("TWeakObjectPtr", sizes.weak_object),
+            ("TSoftObjectPtr", sizes.soft_object),
+            ("TSoftClassPtr", sizes.soft_class),
+            ("TLazyObjectPtr", sizes.lazy_object),
+            ("TScriptInterface", sizes.interface),
+            ("TMap", sizes.map),
+            ("TSet", sizes.set),
+        ] {
+            writeln!(b, "    (\"struct\", \"{name}\", 0x{size:x}, []),").unwrap();
+        }
+
+        for package in &sdk.packages {
+            for path in &package.enums {
+                let Some(ObjectType::Enum(e)) = sdk.object(path) else {
+                    continue;
+                };
+                let repr = sdk.enum_repr(path);
+                let (min, max) = repr.range();
+                let mut seen = HashSet::new();
+                let values: Vec<String> = e
+                    .names
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        let name = sanitize_ident(name.rsplit("::").next().unwrap_or(name));
+                        ((min..=max).contains(value) && seen.insert(name.clone()))
+                            .then(|| format!("(\"{name}\", {value})"))
+                    })
+                    .collect();
+                writeln!(
+                    b,
+                    "    (\"enum\", \"{}\", {}, [{}]),",
+                    sdk.type_name(path),
+                    repr.size,
+                    values.join(", ")
+                )
+                .unwrap();
+            }
+            for path in package.structs.iter().chain(&package.classes) {
+                self.generate_struct(b, path);
+            }
+        }
+        writeln!(b, "]").unwrap();
+    }
+
+    fn generate_struct(&self, b: &mut String, path: &str) {
+        let sdk = &self.sdk;
+        let s = sdk.object(path).and_then(|o| o.get_struct()).unwrap();
+        let layout = sdk.layout(path);
+
+        let mut members = vec![];
+        if let Some(super_struct) = &s.super_struct
+            && layout.base_size > 0
+        {
+            members.push(format!(
+                "(\"super\", \"{}\", 0x{:x})",
+                sdk.type_name(super_struct),
+                layout.base_size
+            ));
+        }
+        for member in &layout.members {
+            match member {
+                Member::Property(prop) => {
+                    let length = prop.size * prop.array_dim;
+                    members.push(match self.spec(prop) {
+                        Some(spec) => format!(
+                            "(\"field\", 0x{:x}, \"{}\", {spec}, 0x{length:x}, {})",
+                            prop.offset,
+                            sanitize_ident(&prop.name),
+                            prop.array_dim
+                        ),
+                        None => format!(
+                            "(\"bytes\", 0x{:x}, \"{}\", 0x{length:x})",
+                            prop.offset,
+                            sanitize_ident(&prop.name)
+                        ),
+                    });
+                }
+                Member::BitField { offset, bits } => {
+                    for (bit, prop) in bits {
+                        members.push(format!(
+                            "(\"bit\", 0x{offset:x}, {bit}, \"{}\")",
+                            sanitize_ident(&prop.name)
+                        ));
+                    }
+                }
+                // left undefined in the structure
+                Member::Padding { .. } => {}
+            }
+        }
+
+        writeln!(
+            b,
+            "    (\"struct\", \"{}\", 0x{:x}, [{}]),",
+            sdk.type_name(path),
+            layout.size,
+            members.join(", ")
+        )
+        .unwrap();
+    }
+
+    fn symbols(&self, b: &mut String) {
+        let jmap = self.sdk.jmap;
+        let base = jmap.image_base_address.0;
+
+        writeln!(b, "FUNCTIONS = [").unwrap();
+        for (path, obj) in &jmap.objects {
+            let ObjectType::Function(func) = obj else {
+                continue;
+            };
+            let Some(outer) = func.r#struct.object.outer.as_deref() else {
+                continue;
+            };
+            if func.func.0 < base {
+                continue;
+            }
+            writeln!(
+                b,
+                "    (0x{:x}, \"{}\", \"exec{}\"),",
+                func.func.0 - base,
+                self.sdk.type_name(outer),
+                sanitize_ident(short_name(path))
+            )
+            .unwrap();
+        }
+        writeln!(b, "]").unwrap();
+
+        writeln!(b, "VTABLES = [").unwrap();
+        for (path, obj) in &jmap.objects {
+            let Some(vtable) = obj.get_class().and_then(|c| c.instance_vtable) else {
+                continue;
+            };
+            if vtable.0 < base {
+                continue;
+            }
+            writeln!(
+                b,
+                "    (0x{:x}, \"{}\"),",
+                vtable.0 - base,
+                self.sdk.type_name(path)
+            )
+            .unwrap();
+        }
+        writeln!(b, "]").unwrap();
+    }
+}
+
+/// Generate a Ghidra script (Jython or PyGhidra) which imports the dump into the current program
+pub fn into_ghidra_script(jmap: &Jmap) -> String {
+    let generator = Generator {
+        sdk: Sdk::new(jmap),
+    };
+    let mut buffer = String::new();
+
+    writeln!(
+        buffer,
+        "# Import Unreal Engine reflection data generated by jmap_dumper"
+    )
+    .unwrap();
+    writeln!(buffer, "# @category Unreal").unwrap();
+    writeln!(buffer).unwrap();
+    generator.types(&mut buffer);
+    writeln!(buffer).unwrap();
+    generator.symbols(&mut buffer);
+    writeln!(buffer).unwrap();
+    buffer.push_str(IMPORTER);
+
+    buffer
+}
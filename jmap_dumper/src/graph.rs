@@ -0,0 +1,134 @@
+//! Graphviz DOT export of the super struct hierarchy and implemented interfaces
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use anyhow::{Result, bail};
+use jmap::{Jmap, ObjectType, split::package_of};
+
+#[derive(Debug, Default)]
+pub struct GraphOptions {
+    /// Only include types in these packages (all if empty)
+    pub packages: Vec<String>,
+    /// Only include this type and everything deriving from it
+    pub root: Option<String>,
+    /// Include script structs in addition to classes
+    pub structs: bool,
+    /// Draw edges to implemented interfaces
+    pub interfaces: bool,
+}
+
+fn node_name(path: &str) -> &str {
+    path.rsplit(['/', '.', ':']).next().unwrap()
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn into_dot(jmap: &Jmap, options: &GraphOptions) -> Result<String> {
+    let objects = &jmap.objects;
+    let included = |obj: &ObjectType| match obj {
+        ObjectType::Class(_) => true,
+        ObjectType::ScriptStruct(_) => options.structs,
+        _ => false,
+    };
+
+    let mut children = BTreeMap::<&str, Vec<&str>>::new();
+    for (path, obj) in objects {
+        if included(obj)
+            && let Some(super_struct) = obj.get_struct().and_then(|s| s.super_struct.as_deref())
+        {
+            children.entry(super_struct).or_default().push(path);
+        }
+    }
+
+    let mut selected: BTreeSet<&str> = match &options.root {
+        Some(root) => {
+            if !objects.get(root).is_some_and(included) {
+                bail!(
+                    "{root} is not a class{} in the dump",
+                    if options.structs { " or struct" } else { "" }
+                );
+            }
+            let mut selected = BTreeSet::new();
+            let mut queue = vec![root.as_str()];
+            while let Some(path) = queue.pop() {
+                if selected.insert(path) {
+                    queue.extend(children.get(path).into_iter().flatten());
+                }
+            }
+            selected
+        }
+        None => objects
+            .iter()
+            .filter(|(_, obj)| included(obj))
+            .map(|(path, _)| path.as_str())
+            .collect(),
+    };
+    if !options.packages.is_empty() {
+        selected.retain(|path| options.packages.iter().any(|p| p == package_of(path)));
+    }
+
+    let mut edges = vec![];
+    let mut external = BTreeSet::new();
+    for path in &selected {
+        let obj = &objects[*path];
+        if let Some(super_struct) = obj.get_struct().and_then(|s| s.super_struct.as_deref()) {
+            edges.push((*path, super_struct, "solid"));
+            external.insert(super_struct);
+        }
+        if options.interfaces
+            && let ObjectType::Class(class) = obj
+        {
+            for interface in &class.interfaces {
+                edges.push((*path, interface.class.as_str(), "dashed"));
+                external.insert(interface.class.as_str());
+            }
+        }
+    }
+
+    let mut buffer = String::new();
+    writeln!(buffer, "digraph classes {{").unwrap();
+    writeln!(buffer, "    rankdir=BT;").unwrap();
+    writeln!(buffer, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+
+    for path in &selected {
+        let shape = match objects[*path] {
+            ObjectType::ScriptStruct(_) => ", style=rounded",
+            _ => "",
+        };
+        writeln!(
+            buffer,
+            "    {} [label={}, tooltip={}{shape}];",
+            quote(path),
+            quote(node_name(path)),
+            quote(path)
+        )
+        .unwrap();
+    }
+    // supers and interfaces outside the selection so every edge has a labeled endpoint
+    for path in external.difference(&selected) {
+        writeln!(
+            buffer,
+            "    {} [label={}, tooltip={}, color=gray, fontcolor=gray];",
+            quote(path),
+            quote(node_name(path)),
+            quote(path)
+        )
+        .unwrap();
+    }
+
+    for (from, to, style) in edges {
+        writeln!(
+            buffer,
+            "    {} -> {} [arrowhead=empty, style={style}];",
+            quote(from),
+            quote(to)
+        )
+        .unwrap();
+    }
+    writeln!(buffer, "}}").unwrap();
+
+    Ok(buffer)
+}
@@ -0,0 +1,184 @@
+//! Fallback for engine versions without a built-in layout: start from the layout of the closest
+//! known version and find the `UObject` members every other read depends on by probing live
+//! objects. GUObjectArray always begins with the CoreUObject package and its classes, which makes
+//! the probes cheap and unambiguous:
+//!
+//! - `NamePrivate` is where most objects carry a readable name and one of them is `Object`
+//! - `ClassPrivate` is the pointer that leads from the `Class` class back to itself
+//! - `OuterPrivate` is the pointer from the `Object` class to the `CoreUObject` package
+//! - `InternalIndex` is the int32 matching each object's index in the array
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, bail};
+use patternsleuth::resolvers::unreal::engine_version::EngineVersion;
+use tracing::{info, warn};
+
+use crate::mem::{CtxPtr, Mem, Ptr};
+use crate::objects::{FUObjectArray, UObject};
+use crate::structs::{self, Structs};
+
+/// Objects sampled from the start of GUObjectArray
+const SAMPLES: usize = 256;
+/// Share of sampled objects a candidate offset has to be consistent with
+const MIN_AGREEMENT: f64 = 0.9;
+/// Offsets probed for UObjectBase members after the vtable
+const PROBE_SIZE: usize = 0x40;
+
+/// Built-in layout of the known version closest to `version`, preferring older versions
+pub fn closest_layout(
+    version: &EngineVersion,
+    case_preserving: bool,
+) -> Result<(EngineVersion, Structs)> {
+    let target = version.major as i32 * 100 + version.minor as i32;
+    let mut candidates = structs::builtin_versions()
+        .filter(|v| (v.major, v.minor) != (version.major, version.minor))
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|v| {
+        let candidate = v.major as i32 * 100 + v.minor as i32;
+        ((candidate - target).abs(), candidate > target)
+    });
+    for candidate in candidates {
+        if let Ok(structs) = structs::get_struct_info_for_version(&candidate, case_preserving) {
+            return Ok((candidate, structs));
+        }
+    }
+    bail!("No built-in layout to start from")
+}
+
+/// Detect the `UObject` offsets of `ctx`'s layout from live objects and return a context using
+/// the corrected layout
+#[tracing::instrument(skip_all)]
+pub fn detect_uobject_layout<M: Mem>(ctx: CtxPtr<M>) -> Result<CtxPtr<M>> {
+    let array = Ptr::<FUObjectArray, _>::new(ctx.guobjectarray, ctx.clone());
+    let count = (array.num_elements()? as usize).min(SAMPLES);
+    let mut objects = vec![];
+    for index in 0..count {
+        if let Ok(Some(object)) = array.read_item_ptr(index) {
+            objects.push((index, object));
+        }
+    }
+    if objects.len() < 16 {
+        bail!(
+            "Only {} objects readable from GUObjectArray, can't detect offsets",
+            objects.len()
+        );
+    }
+    let agrees = |matching: usize| matching as f64 >= objects.len() as f64 * MIN_AGREEMENT;
+    let read_ptr = |address: u64| ctx.read_ptr(address).unwrap_or(0);
+
+    let name_at = |object: &Ptr<UObject, CtxPtr<M>>, offset: usize| {
+        object
+            .byte_offset(offset)
+            .cast::<crate::containers::FName>()
+            .read()
+            .ok()
+            .filter(|name| {
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_graphic() && c != '.' && c != ':')
+            })
+    };
+    let name_private = (8..PROBE_SIZE)
+        .step_by(4)
+        .map(|offset| {
+            let names = objects
+                .iter()
+                .map(|(_, object)| name_at(object, offset))
+                .collect::<Vec<_>>();
+            (offset, names)
+        })
+        .filter(|(_, names)| names.iter().any(|n| n.as_deref() == Some("Object")))
+        .max_by_key(|(_, names)| names.iter().filter(|n| n.is_some()).count())
+        .filter(|(_, names)| agrees(names.iter().filter(|n| n.is_some()).count()));
+    let Some((name_private, names)) = name_private else {
+        bail!("No offset yields readable object names");
+    };
+    let find = |name: &str| {
+        objects
+            .iter()
+            .zip(&names)
+            .find(|(_, n)| n.as_deref() == Some(name))
+            .map(|((_, object), _)| object.address())
+            .with_context(|| format!("No object named {name} among the first {count}"))
+    };
+    let class = find("Class")?;
+    let object = find("Object")?;
+    let core_uobject = find("CoreUObject")?;
+
+    let pointer_offsets = (8..PROBE_SIZE).step_by(8).filter(|offset| {
+        // an FName can't also be a pointer
+        !(name_private..name_private + 8).contains(offset)
+    });
+    let class_private = pointer_offsets
+        .clone()
+        .filter(|&offset| read_ptr(class + offset as u64) == class)
+        .find(|&offset| {
+            // every class of an object is an instance of `Class`
+            agrees(
+                objects
+                    .iter()
+                    .filter(|(_, o)| {
+                        let object_class = read_ptr(o.address() + offset as u64);
+                        object_class != 0 && read_ptr(object_class + offset as u64) == class
+                    })
+                    .count(),
+            )
+        })
+        .context("No pointer leads from Class to itself")?;
+    let outer_private = pointer_offsets
+        .filter(|&offset| offset != class_private)
+        .find(|&offset| read_ptr(object + offset as u64) == core_uobject)
+        .context("No pointer leads from Object to CoreUObject")?;
+    let internal_index = (8..PROBE_SIZE).step_by(4).find(|&offset| {
+        agrees(
+            objects
+                .iter()
+                .filter(|(index, o)| {
+                    Ptr::<i32, _>::new(o.address() + offset as u64, ctx.clone())
+                        .read()
+                        .is_ok_and(|value| value as usize == *index)
+                })
+                .count(),
+        )
+    });
+
+    let mut structs = (*ctx.structs).clone();
+    let info = structs
+        .get_mut("UObject")
+        .context("UObject missing from the starting layout")?;
+    let detected = [
+        ("ClassPrivate", Some(class_private)),
+        ("NamePrivate", Some(name_private)),
+        ("OuterPrivate", Some(outer_private)),
+        ("InternalIndex", internal_index),
+    ];
+    for (name, offset) in detected {
+        let Some(member) = info.members.iter_mut().find(|m| m.name == name) else {
+            continue;
+        };
+        match offset {
+            Some(offset) if offset as u64 != member.offset => {
+                info!(
+                    "UObject::{name}: detected 0x{offset:x} (starting layout 0x{:x})",
+                    member.offset
+                );
+                member.offset = offset as u64;
+            }
+            Some(offset) => info!("UObject::{name}: confirmed 0x{offset:x}"),
+            None => warn!(
+                "UObject::{name}: not detected, keeping 0x{:x}",
+                member.offset
+            ),
+        }
+    }
+    info.members.sort_by_key(|m| m.offset);
+
+    // paths depend on the offsets, so none read with the old ones may be kept
+    Ok(CtxPtr {
+        structs: Arc::new(structs),
+        path_cache: Default::default(),
+        ..ctx
+    })
+}
@@ -0,0 +1,142 @@
+//! In-process dumping: `jmap_inject.dll` is loaded into the game with `LoadLibraryW` and serves
+//! the [`remote`](crate::remote) protocol over a named pipe, reading memory directly instead of
+//! through ReadProcessMemory. Running inside the game also lets it answer path lookups with the
+//! engine's own `UObjectBaseUtility::GetPathName`, which the dumper uses to validate its output.
+//! The agent itself lives in the `jmap_inject` crate, this is the injecting and connecting side.
+
+use std::ffi::c_void;
+use std::fs::File;
+use std::os::windows::ffi::OsStrExt as _;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result, bail};
+use jmap_remote::pipe_name;
+
+use crate::remote::{RemoteInfo, RemoteMem};
+
+/// Load `dll` into `pid` through a remote `LoadLibraryW` thread
+fn load_library(pid: u32, dll: &Path) -> Result<()> {
+    use windows::Win32::System::Diagnostics::Debug::WriteProcessMemory;
+    use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+    use windows::Win32::System::Memory::{
+        MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_READWRITE, VirtualAllocEx, VirtualFreeEx,
+    };
+    use windows::Win32::System::Threading::{
+        CreateRemoteThread, GetExitCodeThread, INFINITE, OpenProcess, PROCESS_CREATE_THREAD,
+        PROCESS_QUERY_INFORMATION, PROCESS_VM_OPERATION, PROCESS_VM_WRITE, WaitForSingleObject,
+    };
+    use windows::core::s;
+
+    use crate::proc_name::HandleGuard;
+
+    let dll = dll
+        .canonicalize()
+        .with_context(|| format!("Failed to find {}", dll.display()))?;
+    let path: Vec<u16> = dll.as_os_str().encode_wide().chain([0]).collect();
+
+    unsafe {
+        let process = OpenProcess(
+            PROCESS_CREATE_THREAD
+                | PROCESS_QUERY_INFORMATION
+                | PROCESS_VM_OPERATION
+                | PROCESS_VM_WRITE,
+            false,
+            pid,
+        )
+        .with_context(|| format!("Failed to open process {pid} for injection"))?;
+        let _process = HandleGuard(process);
+
+        let size = path.len() * 2;
+        let remote = VirtualAllocEx(
+            process,
+            None,
+            size,
+            MEM_COMMIT | MEM_RESERVE,
+            PAGE_READWRITE,
+        );
+        if remote.is_null() {
+            bail!("VirtualAllocEx failed in process {pid}");
+        }
+        let result = (|| -> Result<()> {
+            WriteProcessMemory(process, remote, path.as_ptr() as *const c_void, size, None)?;
+
+            // kernel32 is mapped at the same address in every process of a session
+            let kernel32 = GetModuleHandleW(windows::core::w!("kernel32.dll"))?;
+            let load_library =
+                GetProcAddress(kernel32, s!("LoadLibraryW")).context("LoadLibraryW not found")?;
+            let thread = CreateRemoteThread(
+                process,
+                None,
+                0,
+                Some(std::mem::transmute::<
+                    unsafe extern "system" fn() -> isize,
+                    unsafe extern "system" fn(*mut c_void) -> u32,
+                >(load_library)),
+                Some(remote),
+                0,
+                None,
+            )?;
+            let _thread = HandleGuard(thread);
+            WaitForSingleObject(thread, INFINITE);
+            let mut module = 0;
+            GetExitCodeThread(thread, &mut module)?;
+            if module == 0 {
+                bail!("LoadLibraryW({}) failed inside the game", dll.display());
+            }
+            Ok(())
+        })();
+        let _ = VirtualFreeEx(process, remote, 0, MEM_RELEASE);
+        result
+    }
+}
+
+/// Inject `dll` into `pid` (unless its agent is already listening) and connect to it
+pub fn connect(pid: i32, dll: &Path) -> Result<(RemoteMem, RemoteInfo)> {
+    let name = pipe_name(pid as u32);
+    let open = || File::options().read(true).write(true).open(&name);
+    let pipe = match open() {
+        Ok(pipe) => pipe,
+        Err(_) => {
+            load_library(pid as u32, dll)?;
+            let start = Instant::now();
+            loop {
+                match open() {
+                    Ok(pipe) => break pipe,
+                    Err(_) if start.elapsed() < Duration::from_secs(30) => {
+                        std::thread::sleep(Duration::from_millis(100))
+                    }
+                    Err(err) => {
+                        return Err(err)
+                            .with_context(|| format!("Injected agent never opened {name}"));
+                    }
+                }
+            }
+        }
+    };
+    RemoteMem::handshake(Box::new(pipe.try_clone()?), Box::new(pipe))
+}
+
+/// Compare the paths of a sample of dumped objects against the engine's `GetPathName`
+pub(crate) fn validate_paths(mem: &RemoteMem, jmap: &jmap::Jmap) -> Result<()> {
+    const SAMPLES: usize = 1000;
+
+    let step = (jmap.objects.len() / SAMPLES).max(1);
+    let mut checked = 0;
+    let mut mismatches = vec![];
+    for (path, object) in jmap.objects.iter().step_by(step) {
+        let engine_path = mem.path_name(object.get_object().address.0)?;
+        checked += 1;
+        if engine_path != *path {
+            mismatches.push((path, engine_path));
+        }
+    }
+    println!(
+        "Validated {checked} object paths against GetPathName: {} mismatches",
+        mismatches.len()
+    );
+    for (path, engine_path) in mismatches.iter().take(10) {
+        println!("  {path} (engine: {engine_path})");
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Live instances of a class, found by walking GUObjectArray and reading only each object's
+//! class, without dumping anything else. Matching objects can have their property values read.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+
+use anyhow::{Context as _, Result};
+use jmap::{Address, Jmap, PropertyValue};
+use ordermap::OrderMap;
+use serde::Serialize;
+use tracing::{info_span, warn};
+
+use crate::mem::{Ctx, Ptr};
+use crate::objects::{FUObjectArray, UClass};
+use crate::progress::Progress;
+use crate::report::Failure;
+use crate::{DumpOptions, interrupted, keep_going, read_object};
+
+#[derive(Debug, Clone)]
+pub struct InstanceQuery {
+    /// Path of the class, e.g. `/Script/Engine.PlayerController`
+    pub class: String,
+    /// Also find instances of classes deriving from it
+    pub derived: bool,
+    /// Read the property values of each instance
+    pub values: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Instance {
+    pub path: String,
+    pub address: Address,
+    pub outer: Option<String>,
+    pub class: String,
+    pub property_values: Option<OrderMap<String, PropertyValue>>,
+}
+
+fn is_match<C: Ctx>(class: &Ptr<UClass, C>, query: &InstanceQuery) -> Result<bool> {
+    if class.path()? == query.class {
+        return Ok(true);
+    }
+    if query.derived {
+        let mut next = class.ustruct().super_struct().read()?;
+        while let Some(s) = next {
+            if s.path()? == query.class {
+                return Ok(true);
+            }
+            next = s.super_struct().read()?;
+        }
+    }
+    Ok(false)
+}
+
+/// Object `i` if it is an instance `query` is looking for
+fn read_instance<C: Ctx>(
+    uobjectarray: &Ptr<FUObjectArray, C>,
+    i: i32,
+    query: &InstanceQuery,
+    options: &DumpOptions,
+) -> Result<Option<Instance>> {
+    let Some(obj) = uobjectarray.read_item_ptr(i as usize)? else {
+        return Ok(None);
+    };
+    let class = obj.class_private().read()?;
+    if !is_match(&class, query)? {
+        return Ok(None);
+    }
+    let path = obj.path()?;
+    if !options.filter.matches(&path) {
+        return Ok(None);
+    }
+    let property_values = if query.values {
+        read_object(obj.clone(), &path, options)
+            .with_context(|| format!("Failed to read {path}"))?
+            .and_then(|o| o.get_object().property_values.values().cloned())
+    } else {
+        None
+    };
+    Ok(Some(Instance {
+        address: obj.address().into(),
+        outer: obj.outer_private().read()?.map(|s| s.path()).transpose()?,
+        class: class.path()?,
+        path,
+        property_values,
+    }))
+}
+
+/// Instances of the query's class in GUObjectArray. `options` must dump all objects for the
+/// values of instances outside `/Script/` to be read.
+pub(crate) fn find_live<C: Ctx>(
+    uobjectarray: &Ptr<FUObjectArray, C>,
+    query: &InstanceQuery,
+    options: &DumpOptions,
+    bytes_read: &Arc<AtomicU64>,
+) -> Result<Vec<Instance>> {
+    let num = uobjectarray.num_elements().context(Failure::Read)?;
+    let _span = info_span!("instances", count = num).entered();
+    let progress = Progress::new(options.progress, "Objects", num as u64, bytes_read.clone());
+    let mut instances = vec![];
+    let mut errors = vec![];
+    for i in 0..num {
+        if options.cancel.is_cancelled() {
+            interrupted(i, num);
+            break;
+        }
+        let instance = read_instance(uobjectarray, i, query, options);
+        let instance = keep_going(instance, uobjectarray, i, options, &mut errors)?;
+        progress.inc(instance.as_ref().map(|i| i.path.as_str()));
+        instances.extend(instance);
+    }
+    if !errors.is_empty() {
+        warn!("{} objects couldn't be read", errors.len());
+    }
+    Ok(instances)
+}
+
+/// Instances of the query's class among the objects of an existing dump
+pub fn find_in_dump(jmap: &Jmap, query: &InstanceQuery) -> Vec<Instance> {
+    let is_match = |class: &str| {
+        let mut next = Some(class);
+        while let Some(class) = next {
+            if class == query.class {
+                return true;
+            }
+            if !query.derived {
+                break;
+            }
+            next = jmap
+                .objects
+                .get(class)
+                .and_then(|c| c.get_struct())
+                .and_then(|s| s.super_struct.as_deref());
+        }
+        false
+    };
+    jmap.objects
+        .iter()
+        .filter(|(_, obj)| is_match(&obj.get_object().class))
+        .map(|(path, obj)| {
+            let object = obj.get_object();
+            Instance {
+                path: path.clone(),
+                address: object.address,
+                outer: object.outer.clone(),
+                class: object.class.clone(),
+                property_values: if query.values {
+                    object.property_values.values().cloned()
+                } else {
+                    None
+                },
+            }
+        })
+        .collect()
+}
+
+impl std::fmt::Display for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:x} {}", self.address.0, self.path)?;
+        if let Some(outer) = &self.outer {
+            write!(f, " (outer {outer})")?;
+        }
+        if let Some(values) = &self.property_values {
+            for (name, value) in values {
+                write!(
+                    f,
+                    "\n    {name} = {}",
+                    serde_json::to_string(value).unwrap()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! Windows x64 complete memory dumps (`PAGEDU64`, full or bitmap). Physical pages are located
+//! from the dump's run list or bitmap and a process' virtual address space is translated by
+//! walking its page tables starting at the `DirectoryTableBase` of its EPROCESS.
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, bail};
+
+use crate::mem::Mem;
+
+pub(crate) const PAGE_SIZE: u64 = 0x1000;
+const HEADER_SIZE: u64 = 0x2000;
+
+const DUMP_TYPE_FULL: u32 = 1;
+const DUMP_TYPE_BITMAP_FULL: u32 = 5;
+const DUMP_TYPE_BITMAP_KERNEL: u32 = 6;
+
+const PTE_PRESENT: u64 = 1;
+const PTE_LARGE_PAGE: u64 = 1 << 7;
+const PTE_FRAME: u64 = 0x000f_ffff_ffff_f000;
+
+/// Offset of `KPROCESS::DirectoryTableBase`, unchanged across x64 builds
+const EPROCESS_DIRECTORY_TABLE_BASE: u64 = 0x28;
+/// Offset of `PEB::ImageBaseAddress`
+const PEB_IMAGE_BASE_ADDRESS: u64 = 0x10;
+
+/// EPROCESS member offsets that move between Windows builds
+#[derive(Debug, Clone, Copy)]
+struct EprocessOffsets {
+    unique_process_id: u64,
+    active_process_links: u64,
+    peb: u64,
+    image_file_name: u64,
+}
+
+fn eprocess_offsets(build: u32) -> Result<EprocessOffsets> {
+    let (unique_process_id, active_process_links, peb, image_file_name) = match build {
+        17134..=17763 => (0x2e0, 0x2e8, 0x3f8, 0x450),
+        18362..=18363 => (0x2e8, 0x2f0, 0x3f8, 0x450),
+        19041..=22631 => (0x440, 0x448, 0x550, 0x5a8),
+        26100.. => (0x1d0, 0x1d8, 0x2e0, 0x338),
+        _ => bail!("unsupported Windows build {build} in kernel dump"),
+    };
+    Ok(EprocessOffsets {
+        unique_process_id,
+        active_process_links,
+        peb,
+        image_file_name,
+    })
+}
+
+/// Contiguous physical pages stored contiguously in the file
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    first_page: u64,
+    pages: u64,
+    file_offset: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DumpProcess {
+    pub pid: u64,
+    /// `EPROCESS::ImageFileName`, truncated to 15 characters by the kernel
+    pub name: String,
+    pub directory_table_base: u64,
+    /// `PEB::ImageBaseAddress` if the PEB is resident
+    pub image_base: Option<u64>,
+}
+
+/// Physical memory captured page by page, with x64 virtual address translation on top
+pub(crate) trait PhysicalMemory {
+    /// Contents of the physical page at `pfn` if present
+    fn page(&self, pfn: u64) -> Option<&[u8]>;
+
+    fn read_phys_u64(&self, address: u64) -> Option<u64> {
+        let page = self.page(address / PAGE_SIZE)?;
+        let offset = (address % PAGE_SIZE) as usize;
+        Some(u64_at(page, offset))
+    }
+
+    /// Translate a virtual address through the 4-level page tables rooted at `dtb`
+    fn translate(&self, dtb: u64, address: u64) -> Option<u64> {
+        let mut table = dtb & PTE_FRAME;
+        for (level, shift) in [39, 30, 21, 12].into_iter().enumerate() {
+            let index = (address >> shift) & 0x1ff;
+            let entry = self.read_phys_u64(table + index * 8)?;
+            if entry & PTE_PRESENT == 0 {
+                return None;
+            }
+            // 1GiB pages at the PDPT and 2MiB pages at the PD
+            if (level == 1 || level == 2) && entry & PTE_LARGE_PAGE != 0 {
+                let mask = (1 << shift) - 1;
+                return Some((entry & PTE_FRAME & !mask) | (address & mask));
+            }
+            table = entry & PTE_FRAME;
+        }
+        Some(table | (address & (PAGE_SIZE - 1)))
+    }
+
+    fn read_virt(&self, dtb: u64, address: u64, buf: &mut [u8]) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            let cur = address + done as u64;
+            let offset = (cur % PAGE_SIZE) as usize;
+            let len = (buf.len() - done).min(PAGE_SIZE as usize - offset);
+            let page = self
+                .translate(dtb, cur)
+                .and_then(|phys| self.page(phys / PAGE_SIZE))
+                .with_context(|| format!("reading {len} bytes at 0x{cur:x}: page not in dump"))?;
+            buf[done..done + len].copy_from_slice(&page[offset..offset + len]);
+            done += len;
+        }
+        Ok(())
+    }
+
+    fn read_virt_u64(&self, dtb: u64, address: u64) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_virt(dtb, address, &mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+pub struct KernelDump {
+    mmap: memmap2::Mmap,
+    runs: Vec<Run>,
+    build: u32,
+    kernel_directory_table_base: u64,
+    ps_active_process_head: u64,
+}
+
+pub(crate) fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+pub(crate) fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+pub(crate) fn u64_at(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+impl KernelDump {
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let mmap = unsafe { memmap2::MmapOptions::new().map(&file)? };
+        if mmap.len() < HEADER_SIZE as usize || &mmap[0..8] != b"PAGEDU64" {
+            bail!("not a 64-bit kernel memory dump (expected PAGEDU64 header)");
+        }
+        let build = u32_at(&mmap, 0xc);
+        let kernel_directory_table_base = u64_at(&mmap, 0x10);
+        let ps_active_process_head = u64_at(&mmap, 0x28);
+        let dump_type = u32_at(&mmap, 0xf98);
+
+        let runs = match dump_type {
+            DUMP_TYPE_FULL => {
+                // PHYSICAL_MEMORY_DESCRIPTOR64 at 0x88, pages follow the header in run order
+                let count = u32_at(&mmap, 0x88) as usize;
+                let mut file_offset = HEADER_SIZE;
+                let mut runs = Vec::with_capacity(count);
+                for i in 0..count {
+                    let entry = 0x98 + i * 16;
+                    let run = Run {
+                        first_page: u64_at(&mmap, entry),
+                        pages: u64_at(&mmap, entry + 8),
+                        file_offset,
+                    };
+                    file_offset += run.pages * PAGE_SIZE;
+                    runs.push(run);
+                }
+                runs
+            }
+            DUMP_TYPE_BITMAP_FULL | DUMP_TYPE_BITMAP_KERNEL => {
+                let bmp = &mmap[HEADER_SIZE as usize..];
+                if !matches!(&bmp[0..4], b"SDMP" | b"FDMP") || &bmp[4..8] != b"DUMP" {
+                    bail!("bad bitmap dump header");
+                }
+                let mut file_offset = u64_at(bmp, 0x20);
+                let bits = u64_at(bmp, 0x30);
+                let bitmap = &bmp[0x38..0x38 + bits.div_ceil(8) as usize];
+                // coalesce set bits into runs to keep lookups a binary search
+                let mut runs: Vec<Run> = vec![];
+                for page in 0..bits {
+                    if bitmap[(page / 8) as usize] & (1 << (page % 8)) == 0 {
+                        continue;
+                    }
+                    match runs.last_mut() {
+                        Some(run) if run.first_page + run.pages == page => run.pages += 1,
+                        _ => runs.push(Run {
+                            first_page: page,
+                            pages: 1,
+                            file_offset,
+                        }),
+                    }
+                    file_offset += PAGE_SIZE;
+                }
+                runs
+            }
+            other => bail!("unsupported kernel dump type {other} (need a complete memory dump)"),
+        };
+
+        Ok(Self {
+            mmap,
+            runs,
+            build,
+            kernel_directory_table_base,
+            ps_active_process_head,
+        })
+    }
+
+    /// Walk `PsActiveProcessHead` in kernel space
+    pub fn processes(&self) -> Result<Vec<DumpProcess>> {
+        let offsets = eprocess_offsets(self.build)?;
+        let dtb = self.kernel_directory_table_base;
+
+        let mut processes = vec![];
+        let mut link = self.read_virt_u64(dtb, self.ps_active_process_head)?;
+        while link != self.ps_active_process_head {
+            if processes.len() > 0x10000 {
+                bail!("process list does not terminate, EPROCESS offsets likely wrong");
+            }
+            let eprocess = link - offsets.active_process_links;
+            let mut name = [0; 15];
+            self.read_virt(dtb, eprocess + offsets.image_file_name, &mut name)?;
+            let name = &name[..name.iter().position(|b| *b == 0).unwrap_or(name.len())];
+
+            let directory_table_base =
+                self.read_virt_u64(dtb, eprocess + EPROCESS_DIRECTORY_TABLE_BASE)?;
+            let peb = self.read_virt_u64(dtb, eprocess + offsets.peb)?;
+            let image_base = (peb != 0)
+                .then(|| self.read_virt_u64(directory_table_base, peb + PEB_IMAGE_BASE_ADDRESS))
+                .and_then(Result::ok);
+
+            processes.push(DumpProcess {
+                pid: self.read_virt_u64(dtb, eprocess + offsets.unique_process_id)?,
+                name: String::from_utf8_lossy(name).into_owned(),
+                directory_table_base,
+                image_base,
+            });
+            link = self.read_virt_u64(dtb, link)?;
+        }
+        Ok(processes)
+    }
+
+    /// Find a process by image name (matched against the truncated `ImageFileName`) or PID
+    pub fn find_process(&self, name_or_pid: &str) -> Result<DumpProcess> {
+        let processes = self.processes()?;
+        let matches = |p: &DumpProcess| {
+            name_or_pid.parse::<u64>().is_ok_and(|pid| pid == p.pid)
+                || (!p.name.is_empty() && {
+                    let (query, name) = (
+                        name_or_pid.to_ascii_lowercase(),
+                        p.name.to_ascii_lowercase(),
+                    );
+                    query.starts_with(&name) || name.starts_with(&query)
+                })
+        };
+        let mut found: Vec<_> = processes.iter().filter(|p| matches(p)).collect();
+        match found.len() {
+            1 => Ok(found.remove(0).clone()),
+            n => {
+                let list = if n == 0 {
+                    processes.iter().collect()
+                } else {
+                    found
+                };
+                let list = list
+                    .iter()
+                    .map(|p| format!("  {:>6} {}", p.pid, p.name))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if n == 0 {
+                    bail!("no process matching {name_or_pid:?} in dump, processes:\n{list}")
+                } else {
+                    bail!("{name_or_pid:?} is ambiguous, pass a PID instead:\n{list}")
+                }
+            }
+        }
+    }
+
+    /// View of a process' virtual address space
+    pub fn process_mem(self: &Arc<Self>, process: &DumpProcess) -> KernelDumpMem {
+        KernelDumpMem {
+            dump: self.clone(),
+            directory_table_base: process.directory_table_base,
+        }
+    }
+}
+
+impl PhysicalMemory for KernelDump {
+    fn page(&self, pfn: u64) -> Option<&[u8]> {
+        let i = self.runs.partition_point(|r| r.first_page + r.pages <= pfn);
+        let run = self.runs.get(i).filter(|r| r.first_page <= pfn)?;
+        let offset = (run.file_offset + (pfn - run.first_page) * PAGE_SIZE) as usize;
+        self.mmap.get(offset..offset + PAGE_SIZE as usize)
+    }
+}
+
+#[derive(Clone)]
+pub struct KernelDumpMem {
+    dump: Arc<KernelDump>,
+    directory_table_base: u64,
+}
+
+impl Mem for KernelDumpMem {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        self.dump.read_virt(self.directory_table_base, address, buf)
+    }
+}
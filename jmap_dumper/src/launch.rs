@@ -0,0 +1,80 @@
+//! Pausing and resuming every thread of a process, used to hold a freshly launched game still
+//! while it is dumped
+
+use anyhow::Result;
+
+#[cfg(target_os = "windows")]
+fn for_each_thread(pid: i32, f: impl Fn(windows::Win32::Foundation::HANDLE)) -> Result<()> {
+    use std::mem;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First, Thread32Next,
+    };
+    use windows::Win32::System::Threading::{OpenThread, THREAD_SUSPEND_RESUME};
+
+    use crate::proc_name::HandleGuard;
+
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)?;
+        let _guard = HandleGuard(snapshot);
+
+        let mut entry: THREADENTRY32 = mem::zeroed();
+        entry.dwSize = mem::size_of::<THREADENTRY32>() as u32;
+
+        if Thread32First(snapshot, &mut entry).is_ok() {
+            loop {
+                if entry.th32OwnerProcessID == pid as u32
+                    && let Ok(thread) = OpenThread(THREAD_SUSPEND_RESUME, false, entry.th32ThreadID)
+                {
+                    let _guard = HandleGuard(thread);
+                    f(thread);
+                }
+                if Thread32Next(snapshot, &mut entry).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn suspend(pid: i32) -> Result<()> {
+    for_each_thread(pid, |thread| unsafe {
+        windows::Win32::System::Threading::SuspendThread(thread);
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub fn resume(pid: i32) -> Result<()> {
+    for_each_thread(pid, |thread| unsafe {
+        windows::Win32::System::Threading::ResumeThread(thread);
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn signal(pid: i32, signal: libc::c_int) -> Result<()> {
+    if unsafe { libc::kill(pid, signal) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn suspend(pid: i32) -> Result<()> {
+    signal(pid, libc::SIGSTOP)
+}
+
+#[cfg(target_os = "linux")]
+pub fn resume(pid: i32) -> Result<()> {
+    signal(pid, libc::SIGCONT)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn suspend(_pid: i32) -> Result<()> {
+    anyhow::bail!("Unimplemented for target: {}", std::env::consts::OS)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn resume(_pid: i32) -> Result<()> {
+    anyhow::bail!("Unimplemented for target: {}", std::env::consts::OS)
+}
@@ -0,0 +1,162 @@
+//! Member layout of every class and struct in offset order with the padding between members, as
+//! laid out for the SDK, and the problems found on the way: overlapping members, members past
+//! `PropertiesSize`, misaligned members and structs smaller than their super struct. Both SDK
+//! consumers and the dumper itself can be checked against it.
+
+use std::fmt::Write;
+
+use jmap::{Jmap, ObjectType, Property, PropertyType, Struct};
+
+use crate::sdkgen::property_type_name;
+use crate::sdkgen::{Layout, Member, Sdk};
+
+#[derive(Debug, Default)]
+pub struct LayoutReportOptions {
+    /// Only list types with issues, and only their issues
+    pub issues_only: bool,
+}
+
+/// Alignment `prop` requires, if it can be told from the dump
+fn alignment(sdk: &Sdk, prop: &Property) -> Option<usize> {
+    match &prop.r#type {
+        PropertyType::Struct { r#struct } => sdk
+            .object(r#struct)
+            .and_then(|o| o.get_struct())
+            .map(|s| s.min_alignment),
+        PropertyType::Enum { container, .. } => alignment(sdk, container),
+        PropertyType::Bool { .. }
+        | PropertyType::Byte { .. }
+        | PropertyType::Int8
+        | PropertyType::Int16
+        | PropertyType::UInt16
+        | PropertyType::Int
+        | PropertyType::UInt32
+        | PropertyType::Float
+        | PropertyType::Int64
+        | PropertyType::UInt64
+        | PropertyType::Double => Some(prop.size),
+        _ => None,
+    }
+}
+
+fn issues(sdk: &Sdk, s: &Struct, layout: &Layout) -> Vec<String> {
+    let mut issues = vec![];
+    for prop in &layout.skipped {
+        if prop.offset < layout.base_size {
+            issues.push(format!(
+                "{} at 0x{:x} overlaps the super struct ending at 0x{:x}",
+                prop.name, prop.offset, layout.base_size
+            ));
+        } else {
+            issues.push(format!(
+                "{} at 0x{:x} overlaps the previous member",
+                prop.name, prop.offset
+            ));
+        }
+    }
+    for prop in &s.properties {
+        let end = prop.offset + prop.size * prop.array_dim;
+        if end > s.properties_size {
+            issues.push(format!(
+                "{} ends at 0x{end:x}, past PropertiesSize 0x{:x}",
+                prop.name, s.properties_size
+            ));
+        }
+        if let Some(align) = alignment(sdk, prop).filter(|a| *a > 1)
+            && prop.offset % align != 0
+        {
+            issues.push(format!(
+                "{} at 0x{:x} isn't aligned to {align}",
+                prop.name, prop.offset
+            ));
+        }
+    }
+    if !s.min_alignment.is_power_of_two() {
+        issues.push(format!(
+            "MinAlignment {} isn't a power of two",
+            s.min_alignment
+        ));
+    }
+    if let Some(super_struct) = s
+        .super_struct
+        .as_deref()
+        .and_then(|p| sdk.object(p))
+        .and_then(|o| o.get_struct())
+        && super_struct.properties_size > s.properties_size
+    {
+        issues.push(format!(
+            "PropertiesSize 0x{:x} is smaller than the super struct's 0x{:x}",
+            s.properties_size, super_struct.properties_size
+        ));
+    }
+    issues
+}
+
+fn write_type(buffer: &mut String, sdk: &Sdk, path: &str, options: &LayoutReportOptions) {
+    let obj = &sdk.jmap.objects[path];
+    let s = obj.get_struct().expect("layout of struct");
+    let layout = sdk.layout(path);
+    let issues = issues(sdk, s, &layout);
+    if options.issues_only && issues.is_empty() {
+        return;
+    }
+    let kind = match obj {
+        ObjectType::Class(_) => "class",
+        _ => "struct",
+    };
+    writeln!(
+        buffer,
+        "{kind} {path} (PropertiesSize 0x{:x}, MinAlignment {}, members from 0x{:x}, size 0x{:x})",
+        s.properties_size, s.min_alignment, layout.base_size, layout.size
+    )
+    .unwrap();
+    if !options.issues_only {
+        let mut padding = 0;
+        for member in &layout.members {
+            match member {
+                Member::Property(prop) => {
+                    let mut ty = property_type_name(&sdk.jmap.objects, prop);
+                    if prop.array_dim != 1 {
+                        write!(ty, "[{}]", prop.array_dim).unwrap();
+                    }
+                    writeln!(
+                        buffer,
+                        "    0x{:04x} 0x{:04x} {ty} {}",
+                        prop.offset,
+                        prop.size * prop.array_dim,
+                        prop.name
+                    )
+                    .unwrap();
+                }
+                Member::BitField { offset, bits } => {
+                    for (bit, prop) in bits {
+                        writeln!(buffer, "    0x{offset:04x} bit {bit}  bool {}", prop.name)
+                            .unwrap();
+                    }
+                }
+                Member::Padding { offset, size } => {
+                    padding += size;
+                    writeln!(buffer, "    0x{offset:04x} 0x{size:04x} (padding)").unwrap();
+                }
+            }
+        }
+        if padding > 0 {
+            writeln!(buffer, "    0x{padding:x} bytes of padding").unwrap();
+        }
+    }
+    for issue in issues {
+        writeln!(buffer, "    ! {issue}").unwrap();
+    }
+    writeln!(buffer).unwrap();
+}
+
+pub fn into_layout_report(jmap: &Jmap, options: &LayoutReportOptions) -> String {
+    let sdk = Sdk::new(jmap);
+    let mut buffer = String::new();
+    for (path, obj) in &jmap.objects {
+        if matches!(obj, ObjectType::Class(_) | ObjectType::ScriptStruct(_)) {
+            write_type(&mut buffer, &sdk, path, options);
+        }
+    }
+    buffer
+}
@@ -1,40 +1,111 @@
+pub mod backend;
+mod constructors;
 mod containers;
-mod header;
+pub mod decode;
+pub mod diff;
+pub mod disasm;
+mod disk_cache;
+mod elf;
+mod engine_version;
+#[cfg(test)]
+mod fixtures;
+#[cfg(feature = "frida")]
+mod frida;
+mod gdb;
+mod ghidra;
+mod graph;
+mod heuristics;
+#[cfg(target_os = "windows")]
+pub mod inject;
+pub mod instances;
+mod kernel_dump;
+mod launch;
+mod layout_report;
+#[cfg(target_os = "linux")]
+pub mod linux;
+mod map;
+pub mod mappings;
 mod mem;
+pub mod merge;
+pub mod names;
 mod objects;
+mod pdb;
+mod pdb_symbols;
+mod pe;
 mod proc_name;
+mod progress;
+pub mod query;
+pub mod remote;
+pub mod report;
+mod scope;
+pub mod sdkgen;
+pub mod signatures;
+pub mod snapshot;
+pub mod source_layout;
+pub mod sqlite;
+pub mod stats;
 pub mod structs;
+mod symbols;
+pub mod table;
+mod thunks;
+mod tree;
+mod validate;
+mod vm_snapshot;
 mod vtable;
-
-pub use header::into_header;
-
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+mod x64dbg;
+
+pub use crate::pdb::into_pdb;
+pub use ghidra::into_ghidra_script;
+pub use graph::{GraphOptions, into_dot};
+pub use layout_report::{LayoutReportOptions, into_layout_report};
+pub use map::into_map;
+pub use mem::{Endian, Target};
+pub use proc_name::{ProcessEntry, find_process, list_processes, list_unreal_processes};
+pub use scope::ClassScope;
+pub use sdkgen::{into_header, into_package_headers};
+pub use tree::{TreeOptions, into_tree};
+pub use x64dbg::into_x64dbg_script;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, bail};
 use containers::{FName, FString};
+use disk_cache::{DiskCache, PageStore};
 use jmap::{
-    BytePropertyValue, Class, EClassCastFlags, EObjectFlags, EngineVersion, Enum,
-    EnumPropertyValue, Function, Jmap, Metadata, Object, ObjectType, Package, Property,
-    PropertyType, PropertyValue, ScriptStruct, Struct,
+    Address, BytePropertyValue, Class, EClassCastFlags, ECppForm, EFunctionFlags, EObjectFlags,
+    EngineVersion, Enum, EnumPropertyValue, Function, ImplementedInterface, Jmap, Metadata, Object,
+    ObjectType, Package, Property, PropertyType, PropertyValue, ScriptStruct, Struct,
 };
-use mem::{CtxPtr, Mem, MemCache, Ptr};
+use mem::{CtxPtr, Mem, MemCache, Ptr, ReadCounter};
 use objects::FOptionalProperty;
 use ordermap::OrderMap;
 use patternsleuth::image::Image;
 use patternsleuth::resolvers::{impl_collector, impl_try_collector, resolve};
+use rayon::prelude::*;
+#[cfg(not(target_os = "linux"))]
 use read_process_memory::{Pid, ProcessHandle};
+use regex::Regex;
+use tracing::{debug, info, info_span, trace, warn};
 
-use crate::containers::{FUtf8String, PtrFNamePool};
+use crate::containers::{FUtf8String, Names};
+use crate::decode::Decoders;
+use crate::instances::{Instance, InstanceQuery};
 use crate::mem::Ctx;
+use crate::names::NameResolver;
 use crate::objects::{
-    FUObjectArray, UClass, UEnum, UFunction, UObject, UScriptStruct, UStruct, ZArrayProperty,
-    ZBoolProperty, ZByteProperty, ZClassProperty, ZDelegateProperty, ZEnumProperty,
-    ZInterfaceProperty, ZLazyObjectProperty, ZMapProperty, ZMulticastDelegateProperty,
-    ZObjectProperty, ZProperty, ZSetProperty, ZSoftClassProperty, ZSoftObjectProperty,
-    ZStructProperty, ZWeakObjectProperty,
+    FObjectPtr, FSoftObjectPtr, FUObjectArray, ObjectRef, UClass, UEnum, UFunction, UObject,
+    UPackage, UScriptStruct, UStruct, ZArrayProperty, ZBoolProperty, ZByteProperty, ZClassProperty,
+    ZDelegateProperty, ZEnumProperty, ZInterfaceProperty, ZLazyObjectProperty, ZMapProperty,
+    ZMulticastDelegateProperty, ZObjectProperty, ZProperty, ZSetProperty, ZSoftClassProperty,
+    ZSoftObjectProperty, ZStructProperty, ZWeakObjectProperty,
 };
+use crate::progress::Progress;
+use crate::report::{ErrorLog, Failure, UnsupportedProperty};
 use crate::structs::Structs;
 
 impl_try_collector! {
@@ -51,22 +122,94 @@ impl_collector! {
     #[derive(Debug, PartialEq, Clone)]
     struct OptResolution {
         build: patternsleuth::resolvers::unreal::engine_version::BuildChangeList,
+        gworld: patternsleuth::resolvers::unreal::GWorld,
+        gengine: patternsleuth::resolvers::unreal::GEngine,
+        gnatives: patternsleuth::resolvers::unreal::kismet::GNatives,
+        process_event: patternsleuth::resolvers::unreal::UObjectProcessEvent,
     }
 }
 
-fn read_path<C: Ctx>(obj: &Ptr<UObject, C>) -> Result<String> {
-    let mut objects = vec![obj.clone()];
+impl Resolution {
+    /// Addresses of everything resolved, named as the symbols written for them
+    fn addresses(&self) -> BTreeMap<String, Address> {
+        let opt = &self.opt;
+        let optional = [
+            ("GWorld", opt.gworld.as_ref().map(|r| r.0)),
+            ("GEngine", opt.gengine.as_ref().map(|r| r.0)),
+            ("GNatives", opt.gnatives.as_ref().map(|r| r.0)),
+            (
+                "UObject::ProcessEvent",
+                opt.process_event.as_ref().map(|r| r.0),
+            ),
+        ];
+        [
+            ("GUObjectArray", self.guobject_array.0),
+            ("FNamePool", self.fname_pool.0),
+        ]
+        .into_iter()
+        .chain(
+            optional
+                .into_iter()
+                .filter_map(|(name, address)| Some((name, address.ok()? as u64))),
+        )
+        .map(|(name, address)| (name.to_string(), address.into()))
+        .collect()
+    }
+}
 
-    let mut obj = obj.clone();
-    while let Some(outer) = obj.outer_private().read()? {
-        objects.push(outer.clone());
-        obj = outer;
+/// Resolve the engine globals, taking any address or version given in `overrides` as is instead
+/// of scanning for it
+#[tracing::instrument(skip_all, fields(base = format_args!("0x{:x}", image.base_address)))]
+fn resolve_globals(image: &Image<'_>, overrides: &Overrides) -> Result<Resolution> {
+    use patternsleuth::resolvers::unreal::{
+        engine_version::EngineVersion, fname::FNamePool, guobject_array::GUObjectArray,
+    };
+
+    if overrides.is_empty() {
+        return Ok(resolve(image, Resolution::resolver())?);
+    }
+    let guobject_array = match overrides.guobject_array {
+        Some(address) => GUObjectArray(address),
+        None => resolve(image, GUObjectArray::resolver()).context("GUObjectArray")?,
+    };
+    let fname_pool = match overrides.fname_pool {
+        Some(address) => FNamePool(address),
+        None => resolve(image, FNamePool::resolver()).context("FNamePool")?,
+    };
+    let engine_version = match overrides.engine_version {
+        Some((major, minor)) => EngineVersion { major, minor },
+        None => resolve(image, EngineVersion::resolver()).context("EngineVersion")?,
+    };
+    Ok(Resolution {
+        guobject_array,
+        fname_pool,
+        engine_version,
+        opt: resolve(image, OptResolution::resolver())?,
+    })
+}
+
+fn read_path<C: Ctx>(obj: &Ptr<UObject, C>) -> Result<String> {
+    let cache = obj.ctx().path_cache();
+
+    // walk out until an outer whose path is already known
+    let mut objects = vec![];
+    let mut known = None;
+    let mut next = Some(obj.clone());
+    while let Some(obj) = next {
+        if let Some(path) = cache.get(obj.address()) {
+            known = Some((obj, path));
+            break;
+        }
+        next = obj.outer_private().read()?;
+        objects.push(obj);
     }
 
-    let mut path = String::new();
-    let mut prev: Option<&Ptr<UObject, C>> = None;
-    for obj in objects.iter().rev() {
-        if let Some(prev) = prev {
+    let (mut prev, mut path) = match known {
+        Some((obj, path)) => (Some(obj), path),
+        None => (None, String::new()),
+    };
+    for obj in objects.into_iter().rev() {
+        if let Some(prev) = &prev {
             let sep = if prev
                 .class_private()
                 .read()?
@@ -81,12 +224,18 @@ fn read_path<C: Ctx>(obj: &Ptr<UObject, C>) -> Result<String> {
             path.push(sep);
         }
         path.push_str(&obj.name_private().read()?);
+        cache.insert(obj.address(), path.clone());
         prev = Some(obj);
     }
 
     Ok(path)
 }
 
+/// `FLargeWorldCoordinatesRealProperty` has double storage but some UE5 builds don't give it the
+/// double cast flag
+const DOUBLE_PROPERTY: EClassCastFlags = EClassCastFlags::CASTCLASS_FDoubleProperty
+    .union(EClassCastFlags::CASTCLASS_FLargeWorldCoordinatesRealProperty);
+
 fn map_prop<C: Ctx>(ptr: &Ptr<ZProperty, C>) -> Result<Property> {
     let name = ptr.zfield().name_private().read()?;
     let f = ptr.zfield().cast_flags()?;
@@ -165,7 +314,7 @@ fn map_prop<C: Ctx>(ptr: &Ptr<ZProperty, C>) -> Result<Property> {
         }
     } else if f.contains(EClassCastFlags::CASTCLASS_FFloatProperty) {
         PropertyType::Float
-    } else if f.contains(EClassCastFlags::CASTCLASS_FDoubleProperty) {
+    } else if f.intersects(DOUBLE_PROPERTY) {
         PropertyType::Double
     } else if f.contains(EClassCastFlags::CASTCLASS_FByteProperty) {
         let prop = ptr.cast::<ZByteProperty>();
@@ -239,7 +388,7 @@ fn map_prop<C: Ctx>(ptr: &Ptr<ZProperty, C>) -> Result<Property> {
     } else if f.contains(EClassCastFlags::CASTCLASS_FAnsiStrProperty) {
         PropertyType::AnsiStr
     } else {
-        unimplemented!("{f:?}");
+        return Err(UnsupportedProperty(format!("{f:?}")).into());
     };
 
     let prop = ptr.cast::<ZProperty>();
@@ -357,20 +506,323 @@ impl Mem for MinidumpMem<'_> {
     }
 }
 
+/// Flat memory dump mapped starting at `base`
+#[derive(Clone)]
+struct RawMem {
+    data: Arc<memmap2::Mmap>,
+    base: u64,
+}
+
+impl Mem for RawMem {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        let data = address
+            .checked_sub(self.base)
+            .and_then(|offset| {
+                let offset = offset as usize;
+                self.data.get(offset..offset.checked_add(buf.len())?)
+            })
+            .with_context(|| {
+                format!(
+                    "reading {} bytes at 0x{:x}: outside of raw dump 0x{:x}..0x{:x}",
+                    buf.len(),
+                    address,
+                    self.base,
+                    self.base + self.data.len() as u64
+                )
+            })?;
+        buf.copy_from_slice(data);
+        Ok(())
+    }
+}
+
 pub enum Input {
     Process(i32),
+    /// Start `exe`, wait for the UObject system to come up, then dump it while suspended
+    Launch(LaunchOptions),
+    /// Process ID read through a [`backend`] registered under the name in `spec`
+    /// (`name:argument`)
+    Backend {
+        spec: String,
+        pid: i32,
+    },
     Dump(PathBuf),
+    /// Complete Windows memory dump and the name or PID of the process to dump from it
+    KernelDump {
+        path: PathBuf,
+        process: String,
+    },
+    /// `jmap_agent` listening at this address
+    Remote(String),
+    /// Process ID to load the `jmap_inject` agent DLL at `dll` into and read in process
+    #[cfg(target_os = "windows")]
+    Inject {
+        pid: i32,
+        dll: PathBuf,
+    },
+    /// Process ID to attach to with Frida
+    #[cfg(feature = "frida")]
+    Frida(i32),
+    /// GDB remote protocol stub at `address` (`host:port`), with the game image mapped at
+    /// `image_base`
+    Gdb {
+        address: String,
+        image_base: u64,
+    },
+    /// Guest physical memory snapshot of a VM (QEMU ELF core or flat image), read through the
+    /// page tables at `cr3` with the game image mapped at `image_base`
+    VmSnapshot {
+        path: PathBuf,
+        cr3: u64,
+        image_base: u64,
+    },
+    /// Flat memory dump starting at `base`, with the executable on disk used for pattern scanning
+    /// (expected to be loaded at its preferred image base)
+    Raw {
+        path: PathBuf,
+        base: u64,
+        image_path: PathBuf,
+    },
+}
+
+pub struct LaunchOptions {
+    pub exe: PathBuf,
+    pub args: Vec<String>,
+    /// Give up if the game isn't ready after this long
+    pub timeout: Duration,
+    /// Kill the game after dumping instead of resuming it
+    pub kill: bool,
+}
+
+/// Set from another thread (e.g. a Ctrl-C handler) to stop dumping early. What was read until then
+/// is still returned or written, marked with [`Metadata::partial`].
+#[derive(Debug, Default, Clone)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+pub struct DumpOptions {
+    /// Struct layout info to use instead of the built-in info for the detected version
+    pub struct_info: Option<Structs>,
+    /// Include UStruct::Script bytecode
+    pub bytecode: bool,
+    /// Pointer width and byte order of the dumped game (console builds)
+    pub target: Target,
+    /// Decoders for obfuscated GUObjectArray pointers
+    pub decoders: Decoders,
+    /// Substitutes names for games storing hashes in place of FName text
+    pub name_resolver: Option<Arc<dyn NameResolver>>,
+    /// Check the struct layout against the target and print a verdict per member before dumping
+    pub validate_layout: bool,
+    /// Engine globals to use instead of resolving them from the image
+    pub overrides: Overrides,
+    /// Show progress bars on stderr while reading objects
+    pub progress: bool,
+    /// Record objects that fail to read in [`Jmap::errors`] and carry on instead of failing
+    pub keep_going: bool,
+    /// Also records skipped objects, for reporting them after streaming dumps
+    pub error_log: Option<Arc<ErrorLog>>,
+    /// Stops the object loops and vtable analysis early
+    pub cancel: Cancel,
+    /// Dump every object in GUObjectArray instead of only the reflection data under `/Script/`
+    pub all_objects: bool,
+    /// Object paths to dump
+    pub filter: PathFilter,
+    /// Classes to restrict the dump to, along with the types they depend on
+    pub classes: ClassScope,
+    /// Skip reading property values, leaving `property_values` empty
+    pub no_values: bool,
+    /// Levels of nested struct values to read, deeper struct values are left out
+    pub value_depth: Option<usize>,
+    /// Threads reading objects, 0 for one per core
+    pub threads: usize,
+    /// Bytes of target memory to keep cached, least recently used pages are dropped beyond it
+    /// (no limit if unset)
+    pub cache_size: Option<usize>,
+    /// Directory to keep pages read from the target in across runs
+    pub cache_dir: Option<PathBuf>,
+    /// Log what reading the target took once the dump is done
+    pub io_stats: bool,
+    /// PDB of the image, or a directory to look for it in, to name virtual functions and natives
+    pub symbols: Option<PathBuf>,
+    /// Symbol server to download the PDB of the image from if it isn't found locally
+    pub symbol_server: Option<String>,
+    /// Scan the code for the `GetPrivateStaticClass` of every class
+    pub static_class: bool,
+    /// Skip vtable analysis, leaving `vtables`, `instance_vtable` and `virtual_functions` empty
+    pub no_vtables: bool,
+    /// Generate byte signatures of the engine globals and UFunction natives
+    pub signatures: bool,
+}
+
+/// Regexes searched for in object paths, e.g. `^/Script/Engine\.` for one module. Objects are
+/// dumped if any include (or no include at all) and no exclude matches.
+#[derive(Debug, Default, Clone)]
+pub struct PathFilter {
+    pub include: Vec<Regex>,
+    pub exclude: Vec<Regex>,
+}
+
+impl PathFilter {
+    pub fn matches(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|r| r.is_match(path)))
+            && !self.exclude.iter().any(|r| r.is_match(path))
+    }
+}
+
+/// Manually found engine globals, for packed or obfuscated binaries where individual resolvers
+/// fail. Anything left unset is still resolved from the image.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Overrides {
+    pub guobject_array: Option<u64>,
+    pub fname_pool: Option<u64>,
+    pub engine_version: Option<(u16, u16)>,
+}
+
+impl Overrides {
+    pub fn is_empty(&self) -> bool {
+        self.guobject_array.is_none() && self.fname_pool.is_none() && self.engine_version.is_none()
+    }
+}
+
+enum Sink<'a> {
+    /// Collect every object into the returned [`Jmap`]
+    Collect,
+    /// Serialize objects as JSON as they are read
+    Stream(&'a mut dyn Write),
+    /// Only collect instances of a class
+    Instances(&'a InstanceQuery, &'a mut Vec<Instance>),
 }
 
-pub fn dump(input: Input, struct_info: Option<Structs>) -> Result<Jmap> {
+pub fn dump(input: Input, options: DumpOptions) -> Result<Jmap> {
+    Ok(dump_to(input, options, Sink::Collect)?.expect("collected dump"))
+}
+
+/// Dump as JSON directly to `writer`, serializing each object as soon as it is read rather than
+/// holding the whole dump in memory. Object paths, outers, classes and vtables are gathered in a
+/// first pass so `children` and `instance_vtable` can be filled in during the second.
+pub fn dump_streaming(input: Input, options: DumpOptions, writer: &mut dyn Write) -> Result<()> {
+    dump_to(input, options, Sink::Stream(writer))?;
+    Ok(())
+}
+
+/// Live instances of a class, reading only the class of every other object
+pub fn find_instances(
+    input: Input,
+    options: DumpOptions,
+    query: &InstanceQuery,
+) -> Result<Vec<Instance>> {
+    let mut instances = vec![];
+    dump_to(input, options, Sink::Instances(query, &mut instances))?;
+    Ok(instances)
+}
+
+/// Memory of a live process using the best backend for the platform
+fn open_process(pid: i32) -> Result<impl Mem> {
+    #[cfg(target_os = "linux")]
+    let mem = linux::Process::open(pid)?;
+    #[cfg(not(target_os = "linux"))]
+    let mem = mem::SharedProcessHandle(ProcessHandle::try_from(pid as Pid)?);
+    Ok(mem)
+}
+
+/// Page cache in front of `mem`, backed by [`DumpOptions::cache_dir`] if set. `source` tells
+/// apart targets of the same build whose memory differs, targets a later run can't be sure to
+/// be the same as (e.g. a GDB stub) have none and are never cached on disk.
+fn cache<M: Mem>(
+    mem: M,
+    image_base: u64,
+    source: Option<&str>,
+    options: &DumpOptions,
+) -> Result<MemCache<DiskCache<M>>> {
+    let store = match (&options.cache_dir, source) {
+        (Some(dir), Some(source)) => Some(PageStore::open(dir, &mem, image_base, source)?),
+        (Some(_), None) => {
+            warn!("not caching pages on disk, a later run couldn't tell this target apart");
+            None
+        }
+        (None, _) => None,
+    };
+    Ok(MemCache::wrap(
+        DiskCache::wrap(mem, store),
+        options.cache_size,
+    ))
+}
+
+/// Cache source of a live process, keyed on its start time as well so a later process reusing
+/// the PID isn't served the pages of this one
+fn process_source(pid: i32) -> Option<String> {
+    proc_name::start_time(pid)
+        .inspect_err(|err| debug!("no start time of process {pid}: {err:#}"))
+        .ok()
+        .map(|start| format!("pid {pid} started {start}"))
+}
+
+fn dump_to(input: Input, options: DumpOptions, sink: Sink<'_>) -> Result<Option<Jmap>> {
+    // the built-in layouts are Win64 ones, a 32-bit target lays out every struct differently
+    if options.target.pointer_size != 8 && options.struct_info.is_none() {
+        return Err(anyhow::anyhow!(
+            "the built-in struct info only describes 64-bit targets, a {}-bit target needs \
+             struct info of its own",
+            options.target.pointer_size as u32 * 8
+        )
+        .context(Failure::StructInfo));
+    }
     match input {
         Input::Process(pid) => {
             let source_name = proc_name::get_process_name(pid).unwrap_or_default();
 
-            let handle: ProcessHandle = (pid as Pid).try_into()?;
-            let mem = MemCache::wrap(handle);
             let image = patternsleuth::process::external::read_image_from_pid(pid)?;
-            dump_inner(mem, &image, struct_info, &source_name)
+            let mem = cache(
+                open_process(pid)?,
+                image.base_address as u64,
+                process_source(pid).as_deref(),
+                &options,
+            )?;
+            let modules = proc_name::list_modules(pid).unwrap_or_default();
+            dump_modules(mem, &image, modules, options, &source_name, sink)
+        }
+        Input::Launch(launch) => {
+            let mut child = std::process::Command::new(&launch.exe)
+                .args(&launch.args)
+                .spawn()
+                .with_context(|| format!("Failed to launch {}", launch.exe.display()))?;
+            let pid = child.id() as i32;
+            info!("Launched {} (PID {pid})", launch.exe.display());
+
+            let result =
+                wait_for_uobjects(pid, launch.timeout, &options.overrides).and_then(|()| {
+                    launch::suspend(pid)?;
+                    let result = dump_to(Input::Process(pid), options, sink);
+                    if !launch.kill {
+                        launch::resume(pid)?;
+                    }
+                    result
+                });
+            if launch.kill {
+                let _ = child.kill();
+            }
+            result
+        }
+        Input::Backend { spec, pid } => {
+            let source_name = proc_name::get_process_name(pid).unwrap_or_default();
+
+            let mem = backend::open(&spec, pid)?;
+            let image_base = match mem.image_base()? {
+                Some(base) => base,
+                None => proc_name::main_module(pid)?.1,
+            };
+            let mem = cache(mem, image_base, process_source(pid).as_deref(), &options)?;
+            let file = pe::read_image_file(&mem, image_base)?;
+            let img = Image::read(Some(image_base as usize), &file, None::<&str>, false)?;
+            dump_inner(mem, &img, options, &source_name, sink)
         }
         Input::Dump(path) => {
             let source_name = path.file_name().unwrap_or_default().to_string_lossy();
@@ -381,7 +833,105 @@ pub fn dump(input: Input, struct_info: Option<Structs>) -> Result<Jmap> {
             let minidump = minidump::Minidump::read(&*mmap)?;
             let mem = MinidumpMem::new(&minidump)?;
             let img = patternsleuth::image::pe::read_image_from_minidump(&minidump)?;
-            dump_inner(mem, &img, struct_info, &source_name)
+            let modules = minidump_modules(&minidump, img.base_address as u64);
+            dump_modules(mem, &img, modules, options, &source_name, sink)
+        }
+        Input::KernelDump { path, process } => {
+            let dump = Arc::new(kernel_dump::KernelDump::open(&path)?);
+            let process = dump.find_process(&process)?;
+            let base = process
+                .image_base
+                .with_context(|| format!("PEB of {} is not in the dump", process.name))?;
+
+            let source = format!("{} {}", path.display(), process.name);
+            let mem = cache(dump.process_mem(&process), base, Some(&source), &options)?;
+            let file = pe::read_image_file(&mem, base)?;
+            let img = Image::read(Some(base as usize), &file, None::<&str>, false)?;
+            dump_inner(mem, &img, options, &process.name, sink)
+        }
+        Input::Remote(address) => {
+            let (mem, info) = remote::RemoteMem::connect(&address)?;
+            // the agent doesn't tell which process it is in
+            let mem = cache(mem, info.image_base, None, &options)?;
+            let file = pe::read_image_file(&mem, info.image_base)?;
+            let img = Image::read(Some(info.image_base as usize), &file, None::<&str>, false)?;
+            dump_inner(mem, &img, options, &info.name, sink)
+        }
+        #[cfg(feature = "frida")]
+        Input::Frida(pid) => {
+            let (mem, info) = frida::FridaMem::attach(pid)?;
+            let mem = cache(
+                mem,
+                info.image_base,
+                process_source(pid).as_deref(),
+                &options,
+            )?;
+            let file = pe::read_image_file(&mem, info.image_base)?;
+            let img = Image::read(Some(info.image_base as usize), &file, None::<&str>, false)?;
+            dump_inner(mem, &img, options, &info.name, sink)
+        }
+        #[cfg(target_os = "windows")]
+        Input::Inject { pid, dll } => {
+            let (remote, info) = inject::connect(pid, &dll)?;
+            let mem = cache(
+                remote.clone(),
+                info.image_base,
+                process_source(pid).as_deref(),
+                &options,
+            )?;
+            let file = pe::read_image_file(&mem, info.image_base)?;
+            let img = Image::read(Some(info.image_base as usize), &file, None::<&str>, false)?;
+            let jmap = dump_inner(mem, &img, options, &info.name, sink)?;
+            if let Some(jmap) = &jmap
+                && let Err(err) = inject::validate_paths(&remote, jmap)
+            {
+                warn!("failed to validate object paths: {err:#}");
+            }
+            Ok(jmap)
+        }
+        Input::Gdb {
+            address,
+            image_base,
+        } => {
+            let mem = cache(gdb::GdbMem::connect(&address)?, image_base, None, &options)?;
+            let file = pe::read_image_file(&mem, image_base)?;
+            let img = Image::read(Some(image_base as usize), &file, None::<&str>, false)?;
+            dump_inner(mem, &img, options, &address, sink)
+        }
+        Input::VmSnapshot {
+            path,
+            cr3,
+            image_base,
+        } => {
+            let source_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+            let snapshot = Arc::new(vm_snapshot::VmSnapshot::open(&path)?);
+            let source = format!("{} cr3 0x{cr3:x}", path.display());
+            let mem = cache(
+                snapshot.process_mem(cr3),
+                image_base,
+                Some(&source),
+                &options,
+            )?;
+            let file = pe::read_image_file(&mem, image_base)?;
+            let img = Image::read(Some(image_base as usize), &file, None::<&str>, false)?;
+            dump_inner(mem, &img, options, &source_name, sink)
+        }
+        Input::Raw {
+            path,
+            base,
+            image_path,
+        } => {
+            let source_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+            let file = std::fs::File::open(&path)?;
+            let mem = RawMem {
+                data: Arc::new(unsafe { memmap2::MmapOptions::new().map(&file)? }),
+                base,
+            };
+            let exe = std::fs::read(&image_path)?;
+            let img = Image::read(None, &exe, Some(&image_path), false)?;
+            dump_inner(mem, &img, options, &source_name, sink)
         }
     }
 }
@@ -397,39 +947,28 @@ mod script_containers {
             self.byte_offset(0).cast()
         }
         pub fn num(&self) -> Ptr<u32, C> {
-            self.byte_offset(8).cast()
+            self.byte_offset(self.ctx().target().pointer_size as usize)
+                .cast()
         }
     }
 }
 
-fn dump_inner<M: Mem>(
+fn ctx_ptr<M: Mem>(
     mem: M,
-    image: &Image<'_>,
-    struct_info: Option<Structs>,
-    source_name: &str,
-) -> Result<Jmap> {
-    let results = resolve(image, Resolution::resolver())?;
-    println!("{results:X?}");
-
-    let fnamepool = PtrFNamePool(results.fname_pool.0);
-
-    let case_preserving = false;
-
-    let struct_info = if let Some(provided_info) = struct_info {
-        provided_info
-    } else {
-        structs::get_struct_info_for_version(&results.engine_version, case_preserving)
-            .with_context(|| {
-                format!(
-                    "Failed to compute struct offsets via Gospel for {:?}",
-                    results.engine_version
-                )
-            })?
-    };
-
-    let mem = CtxPtr {
+    results: &Resolution,
+    struct_info: Structs,
+    case_preserving: bool,
+    options: &DumpOptions,
+) -> CtxPtr<M> {
+    CtxPtr {
         mem,
-        fnamepool,
+        names: Names::for_version(
+            results.fname_pool.0,
+            (results.engine_version.major, results.engine_version.minor),
+        ),
+        name_cache: Default::default(),
+        path_cache: Default::default(),
+        guobjectarray: results.guobject_array.0,
         structs: Arc::new(
             struct_info
                 .0
@@ -439,36 +978,325 @@ fn dump_inner<M: Mem>(
         ),
         version: (results.engine_version.major, results.engine_version.minor),
         case_preserving,
+        target: options.target,
+        decoders: options.decoders.clone(),
+        name_resolver: options.name_resolver.clone(),
+    }
+}
+
+/// Number of UObjects currently allocated in `pid`, failing while the globals can't be resolved
+fn uobject_count(pid: i32, overrides: &Overrides) -> Result<i32> {
+    let image = patternsleuth::process::external::read_image_from_pid(pid)?;
+    let results = resolve_globals(&image, overrides)?;
+    let struct_info = structs::get_struct_info_for_version(&results.engine_version, false)?;
+    let mem = ctx_ptr(
+        MemCache::wrap(open_process(pid)?, None),
+        &results,
+        struct_info,
+        false,
+        &DumpOptions::default(),
+    );
+    Ptr::<FUObjectArray, _>::new(results.guobject_array.0, mem).num_elements()
+}
+
+/// Poll until the engine globals resolve and the object count has stopped growing, so the dump
+/// captures startup state once the initial packages have loaded
+fn wait_for_uobjects(pid: i32, timeout: Duration, overrides: &Overrides) -> Result<()> {
+    let start = Instant::now();
+    let mut last = None;
+    loop {
+        let count = uobject_count(pid, overrides).ok().filter(|n| *n > 0);
+        if let Some(n) = count
+            && count == last
+        {
+            info!("UObject system ready with {n} objects");
+            return Ok(());
+        }
+        last = count;
+        if start.elapsed() > timeout {
+            bail!("Timed out after {timeout:?} waiting for the UObject system of PID {pid}");
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Modules listed in a minidump, with the one at `main_base` first
+fn minidump_modules(minidump: &minidump::Minidump<'_, &[u8]>, main_base: u64) -> Vec<jmap::Module> {
+    use minidump::Module as _;
+
+    let Ok(list) = minidump.get_stream::<minidump::MinidumpModuleList>() else {
+        return vec![];
     };
+    let mut modules: Vec<_> = list
+        .iter()
+        .map(|m| jmap::Module {
+            name: m
+                .code_file()
+                .rsplit(['\\', '/'])
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            base: m.base_address().into(),
+            size: m.size(),
+        })
+        .collect();
+    modules.sort_by_key(|m| m.base.0 != main_base);
+    modules
+}
 
-    let uobjectarray = Ptr::<FUObjectArray, _>::new(results.guobject_array.0, mem.clone());
+fn dump_inner<M: Mem>(
+    mem: M,
+    image: &Image<'_>,
+    options: DumpOptions,
+    source_name: &str,
+    sink: Sink<'_>,
+) -> Result<Option<Jmap>> {
+    let results = resolve_globals(image, &options.overrides).context(Failure::Resolution)?;
+    dump_resolved(mem, image, results, options, source_name, vec![], sink)
+}
 
-    let mut objects = BTreeMap::<String, ObjectType>::default();
-    let mut child_map = HashMap::<String, BTreeSet<String>>::default();
+/// Dump a process made of several modules. Modular builds keep the UObject system in a DLL (e.g.
+/// `UnrealEditor-CoreUObject.dll`), so when resolution fails against the main image the other
+/// Unreal modules are tried in turn.
+fn dump_modules<M: Mem>(
+    mem: M,
+    main_image: &Image<'_>,
+    modules: Vec<jmap::Module>,
+    options: DumpOptions,
+    source_name: &str,
+    sink: Sink<'_>,
+) -> Result<Option<Jmap>> {
+    let main_error = match resolve_globals(main_image, &options.overrides) {
+        Ok(results) => {
+            return dump_resolved(
+                mem,
+                main_image,
+                results,
+                options,
+                source_name,
+                modules,
+                sink,
+            );
+        }
+        Err(err) => err,
+    };
 
-    for i in 0..uobjectarray.num_elements()? {
-        let object = uobjectarray.read_item_ptr(i as usize)?;
-        let Some(obj) = object else {
+    let mut candidates: Vec<_> = modules
+        .iter()
+        .skip(1)
+        .filter(|m| proc_name::is_unreal_module(&m.name))
+        .collect();
+    candidates.sort_by_key(|m| !m.name.to_lowercase().contains("coreuobject"));
+    for module in candidates {
+        let base = module.base.0;
+        let Ok(file) = pe::read_image_file(&mem, base) else {
+            continue;
+        };
+        let Ok(image) = Image::read(Some(base as usize), &file, None::<&str>, false) else {
             continue;
         };
+        let _span = info_span!("module", name = %module.name).entered();
+        if let Ok(results) = resolve_globals(&image, &options.overrides) {
+            info!("Resolved engine globals in {}", module.name);
+            return dump_resolved(mem, &image, results, options, source_name, modules, sink);
+        }
+    }
+    Err(main_error.context(Failure::Resolution))
+}
 
-        let path = obj.path()?;
+fn dump_resolved<M: Mem>(
+    mem: M,
+    image: &Image<'_>,
+    results: Resolution,
+    mut options: DumpOptions,
+    source_name: &str,
+    modules: Vec<jmap::Module>,
+    sink: Sink<'_>,
+) -> Result<Option<Jmap>> {
+    debug!("{results:X?}");
+    info!(
+        "GUObjectArray at 0x{:x}, FNamePool at 0x{:x}",
+        results.guobject_array.0, results.fname_pool.0
+    );
+
+    let mem = ReadCounter::wrap(mem);
+    let bytes_read = mem.bytes();
+    let case_preserving = false;
 
-        let obj = read_object(obj, &path);
-        // let obj = match obj {
-        //     Err(err) => {
-        //         eprintln!("{i}: {path} Failed to read: {err}");
-        //         continue;
-        //     }
-        //     r => r,
-        // };
+    let mut heuristic = false;
+    let struct_info = if let Some(provided_info) = options.struct_info.take() {
+        match structs::get_struct_info_for_version(&results.engine_version, case_preserving) {
+            Ok(built_in) => provided_info
+                .validate(&built_in)
+                .context(Failure::StructInfo)?,
+            Err(err) => {
+                warn!("Not validating struct info, no built-in layout to compare: {err}")
+            }
+        }
+        provided_info
+    } else {
+        match structs::get_struct_info_for_version(&results.engine_version, case_preserving) {
+            Ok(info) => {
+                let version = &results.engine_version;
+                if !structs::builtin_versions()
+                    .any(|v| (v.major, v.minor) == (version.major, version.minor))
+                {
+                    warn!(
+                        "built-in struct info was never checked against {}.{}, detecting \
+                         UObject offsets from live objects",
+                        version.major, version.minor
+                    );
+                    heuristic = true;
+                }
+                info
+            }
+            Err(err) => {
+                let (closest, info) =
+                    heuristics::closest_layout(&results.engine_version, case_preserving)
+                        .with_context(|| {
+                            format!(
+                                "Failed to compute struct offsets via Gospel for {:?}: {err:#}",
+                                results.engine_version
+                            )
+                        })
+                        .context(Failure::StructInfo)?;
+                warn!(
+                    "no built-in struct info for {}.{} ({err:#}), detecting UObject \
+                     offsets starting from the {}.{} layout",
+                    results.engine_version.major,
+                    results.engine_version.minor,
+                    closest.major,
+                    closest.minor
+                );
+                heuristic = true;
+                info
+            }
+        }
+    };
 
-        let Some(object) = obj? else {
-            continue;
-        };
+    let mut mem = ctx_ptr(mem, &results, struct_info, case_preserving, &options);
+    if heuristic {
+        mem = heuristics::detect_uobject_layout(mem)
+            .context("Failed to detect UObject offsets heuristically")
+            .context(Failure::StructInfo)?;
+    }
+
+    let uobjectarray = Ptr::<FUObjectArray, _>::new(results.guobject_array.0, mem.clone());
+
+    if options.validate_layout {
+        let checks = validate::validate_layout(&uobjectarray);
+        let mut failed = 0;
+        for check in &checks {
+            if matches!(check.verdict, validate::Verdict::Fail(_)) {
+                failed += 1;
+                warn!("layout check {check}");
+            } else {
+                info!("layout check {check}");
+            }
+        }
+        if failed > 0 {
+            warn!("{failed} layout checks failed, the dump is likely incomplete");
+        }
+    }
+
+    if let Sink::Instances(query, instances) = sink {
+        options.all_objects = true;
+        *instances = instances::find_live(&uobjectarray, query, &options, &bytes_read)?;
+        return Ok(None);
+    }
+
+    let resolved_addresses = results.addresses();
+    let image_info = if elf::is_elf(&mem, image.base_address) {
+        elf::read_image_info(&mem, image.base_address, resolved_addresses.clone())
+            .context("Failed to read ELF headers")
+    } else {
+        pe::read_image_info(&mem, image.base_address, resolved_addresses.clone())
+            .context("Failed to read PE headers")
+    };
+    let mut image_info = match image_info {
+        Ok(info) => Some(info),
+        Err(err) => {
+            warn!("{err:#}");
+            None
+        }
+    };
+    if !modules.is_empty() {
+        image_info.get_or_insert_default().modules = modules;
+    }
+
+    let version = (results.engine_version.major, results.engine_version.minor);
+    let found = image_info.as_ref().and_then(|info| {
+        engine_version::find_engine_version(&mem, image.base_address, &info.sections, version)
+    });
+    match &found {
+        Some(found) => info!(
+            "Engine version {}.{}.{} ({})",
+            version.0, version.1, found.patch, found.branch
+        ),
+        None => info!("Engine version {}.{}", version.0, version.1),
+    }
+    let metadata = Metadata {
+        tool: "https://github.com/trumank/jmap".to_string(),
+        timestamp: time::OffsetDateTime::now_utc().to_string(),
+        source: source_name.to_string(),
+        engine_version: EngineVersion {
+            major: version.0,
+            minor: version.1,
+            patch: found.as_ref().map(|f| f.patch),
+            branch: found.map(|f| f.branch),
+            overridden: options.overrides.engine_version.is_some(),
+        },
+        build_change_list: results.opt.build.as_ref().ok().map(|cl| cl.0.clone()),
+        partial: false,
+        pointer_size: mem.target().pointer_size,
+    };
 
-        // println!("{i} {path}");
+    let mut jmap = Jmap {
+        metadata: Some(metadata),
+        image_base_address: image.base_address.into(),
+        objects: Default::default(),
+        vtables: Default::default(),
+        script_references: None,
+        image: image_info,
+        resolved_addresses,
+        errors: vec![],
+        natives: vec![],
+        signatures: vec![],
+    };
+    jmap.natives = disasm::read_natives(&mem, &jmap);
+    let symbols = pdb_symbols::Symbols::find(&jmap, &options);
+    if let Some(symbols) = &symbols {
+        symbols.apply_natives(&mut jmap.natives);
+    }
+
+    if let Sink::Stream(writer) = sink {
+        if !options.classes.is_empty() {
+            bail!("Dumping single classes isn't supported when streaming");
+        }
+        stream_objects(
+            &mem,
+            &uobjectarray,
+            &options,
+            &jmap,
+            symbols.as_ref(),
+            &bytes_read,
+            writer,
+        )?;
+        log_io_stats(&mem, &options);
+        return Ok(None);
+    }
+
+    let mut objects = BTreeMap::<String, ObjectType>::default();
+    let mut child_map = HashMap::<String, BTreeSet<String>>::default();
 
+    let num = uobjectarray.num_elements().context(Failure::Read)?;
+    let span = info_span!("objects", count = num).entered();
+    let progress = Progress::new(options.progress, "Objects", num as u64, bytes_read);
+    let (items, errors) = read_items(&uobjectarray, num, &options, &progress)?;
+    drop(progress);
+    drop(span);
+
+    for (path, object) in items {
         // update child_map
         if let Some(outer) = object.get_object().outer.clone() {
             child_map.entry(outer).or_default().insert(path.clone());
@@ -479,58 +1307,542 @@ fn dump_inner<M: Mem>(
 
     for (outer, children) in child_map {
         if let Some(outer) = objects.get_mut(&outer) {
-            match outer {
-                ObjectType::Package(obj) => &mut obj.object,
-                ObjectType::Enum(obj) => &mut obj.object,
-                ObjectType::ScriptStruct(obj) => &mut obj.r#struct.object,
-                ObjectType::Class(obj) => &mut obj.r#struct.object,
-                ObjectType::Function(obj) => &mut obj.r#struct.object,
-                ObjectType::Object(obj) => obj,
+            outer.get_object_mut().children = children;
+        }
+    }
+
+    if !options.classes.is_empty() {
+        scope::retain_scope(&mut objects, &options.classes)?;
+    }
+
+    if !options.no_vtables {
+        jmap.vtables = vtable::analyze_vtables(&mem, &jmap, &mut objects, &options.cancel);
+    }
+    constructors::analyze_constructors(&mem, &jmap, &mut objects, &options);
+    thunks::analyze_thunks(&mem, &jmap, &mut objects);
+    if options.signatures {
+        let natives = objects
+            .iter()
+            .filter_map(|(path, obj)| Some((path.clone(), signatures::native(obj)?)));
+        jmap.signatures = signatures::find_signatures(&mem, &jmap, natives);
+    }
+    if let Some(symbols) = &symbols {
+        objects.values_mut().for_each(|obj| symbols.apply(obj));
+    }
+
+    jmap.script_references = options
+        .bytecode
+        .then(|| disasm::collect_references(&mem, &objects));
+
+    jmap.objects = objects;
+    jmap.errors = errors;
+    if options.cancel.is_cancelled()
+        && let Some(metadata) = &mut jmap.metadata
+    {
+        metadata.partial = true;
+    }
+    log_io_stats(&mem, &options);
+    Ok(Some(jmap))
+}
+
+fn log_io_stats(mem: &impl Mem, options: &DumpOptions) {
+    if options.io_stats
+        && let Some(stats) = mem.io_stats()
+    {
+        info!("Memory I/O: {stats}");
+    }
+}
+
+/// Only reflection objects (or all with [`DumpOptions::all_objects`]) passing the path filter
+/// are dumped
+fn is_dumped(path: &str, options: &DumpOptions) -> bool {
+    (options.all_objects || path.starts_with("/Script/")) && options.filter.matches(path)
+}
+
+/// Before 4.25 properties are UObjects (UProperty) and live in GUObjectArray next to the structs
+/// owning them. They are already dumped as the properties of their struct so, like FProperties,
+/// they don't get an object of their own.
+fn is_uproperty<C: Ctx>(class: &Ptr<UClass, C>) -> Result<bool> {
+    Ok(class.ctx().ue_version() < (4, 25)
+        && class
+            .class_cast_flags()
+            .read()?
+            .contains(EClassCastFlags::CASTCLASS_FProperty))
+}
+
+/// Read the object at GUObjectArray index `i` along with its FUObjectItem
+fn read_item<C: Ctx>(
+    uobjectarray: &Ptr<FUObjectArray, C>,
+    i: i32,
+    options: &DumpOptions,
+) -> Result<Option<(String, ObjectType)>> {
+    let Some(obj) = uobjectarray.read_item_ptr(i as usize)? else {
+        return Ok(None);
+    };
+
+    let path = obj.path()?;
+    let _span = tracing::trace_span!("object", index = i, path = %path).entered();
+    trace!("reading");
+
+    let obj = read_object(obj, &path, options).with_context(|| format!("Failed to read {path}"));
+    // let obj = match obj {
+    //     Err(err) => {
+    //         eprintln!("{i}: {path} Failed to read: {err}");
+    //         continue;
+    //     }
+    //     r => r,
+    // };
+
+    let Some(mut object) = obj? else {
+        return Ok(None);
+    };
+
+    let base = object.get_object_mut();
+    base.internal_index = i;
+    if let Some(item) = uobjectarray.read_object_item(i as usize)? {
+        base.internal_flags = Some(item.internal_flags()?);
+        base.cluster_root_index = Some(item.cluster_root_index()?);
+        base.serial_number = Some(item.serial_number().read()?);
+    }
+
+    Ok(Some((path, object)))
+}
+
+/// GUObjectArray indices read by one task of [`read_items`]
+const CHUNK_SIZE: i32 = 1024;
+
+/// Objects read by [`read_items`] by path and the errors of those skipped
+type ReadItems = (Vec<(String, ObjectType)>, Vec<jmap::ObjectError>);
+
+/// Read objects `0..num` of GUObjectArray on [`DumpOptions::threads`] threads. Chunks of indices
+/// are read on their own copy of the context and come back in index order along with the errors
+/// of objects skipped by [`DumpOptions::keep_going`].
+fn read_items<C: Ctx>(
+    uobjectarray: &Ptr<FUObjectArray, C>,
+    num: i32,
+    options: &DumpOptions,
+    progress: &Progress,
+) -> Result<ReadItems> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads)
+        .build()?;
+    pool.install(|| prefetch_objects(uobjectarray, num));
+    let read = AtomicI32::new(0);
+    let chunks = pool.install(|| {
+        (0..(num + CHUNK_SIZE - 1) / CHUNK_SIZE)
+            .into_par_iter()
+            .map(|chunk| -> Result<_> {
+                let uobjectarray = uobjectarray.clone();
+                let mut items = vec![];
+                let mut errors = vec![];
+                let start = chunk * CHUNK_SIZE;
+                for i in start..num.min(start + CHUNK_SIZE) {
+                    if options.cancel.is_cancelled() {
+                        break;
+                    }
+                    let item = read_item(&uobjectarray, i, options);
+                    let item = keep_going(item, &uobjectarray, i, options, &mut errors)?;
+                    read.fetch_add(1, Ordering::Relaxed);
+                    progress.inc(item.as_ref().map(|(path, _)| path.as_str()));
+                    items.extend(item);
+                }
+                Ok((items, errors))
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    if options.cancel.is_cancelled() {
+        interrupted(read.into_inner(), num);
+    }
+    let (items, errors): (Vec<_>, Vec<_>) = chunks.into_iter().unzip();
+    Ok((
+        items.into_iter().flatten().collect(),
+        errors.into_iter().flatten().collect(),
+    ))
+}
+
+/// Objects whose headers are closer than this are fetched in one read along with the gap
+const PREFETCH_GAP: u64 = 0x4000;
+/// Largest single read of object headers
+const PREFETCH_RUN: u64 = 0x100000;
+
+/// Fetch the items of GUObjectArray and the `UObject` header of every object in a handful of
+/// large reads, rather than the many small random ones of walking it object by object. Objects
+/// that can't be found here are left for the walk to report.
+fn prefetch_objects<C: Ctx>(uobjectarray: &Ptr<FUObjectArray, C>, num: i32) {
+    let _span = info_span!("prefetch", count = num).entered();
+    if let Err(err) = uobjectarray.prefetch_items(num as usize) {
+        debug!("failed to prefetch GUObjectArray items: {err:#}");
+        return;
+    }
+    let ctx = uobjectarray.ctx();
+    let size = ctx.get_struct("UObject").size;
+    let mut addresses = (0..num)
+        .filter_map(|i| uobjectarray.read_item_ptr(i as usize).ok().flatten())
+        .map(|obj| obj.address())
+        .collect::<Vec<_>>();
+    addresses.sort_unstable();
+
+    let mut runs = Vec::<(u64, u64)>::new();
+    for address in addresses {
+        match runs.last_mut() {
+            Some((start, end))
+                if address <= *end + PREFETCH_GAP && address + size - *start <= PREFETCH_RUN =>
+            {
+                *end = (*end).max(address + size)
             }
-            .children = children;
+            _ => runs.push((address, address + size)),
         }
     }
+    runs.par_iter()
+        .for_each(|(start, end)| ctx.prefetch(*start, (end - start) as usize));
+}
 
-    let vtables = vtable::analyze_vtables(&mem, &mut objects);
+fn interrupted(read: i32, num: i32) {
+    warn!("Interrupted after {read} of {num} objects, writing what was read so far");
+}
 
-    Ok(Jmap {
-        metadata: Some(Metadata {
-            tool: "https://github.com/trumank/jmap".to_string(),
-            timestamp: time::OffsetDateTime::now_utc().to_string(),
-            source: source_name.to_string(),
-            engine_version: EngineVersion {
-                major: results.engine_version.major,
-                minor: results.engine_version.minor,
-            },
-            build_change_list: results.opt.build.as_ref().ok().map(|cl| cl.0.clone()),
-        }),
-        image_base_address: image.base_address.into(),
-        objects,
-        vtables,
+/// With [`DumpOptions::keep_going`] record the error of object `i` and skip it, otherwise fail
+fn keep_going<C: Ctx, T>(
+    result: Result<Option<T>>,
+    uobjectarray: &Ptr<FUObjectArray, C>,
+    i: i32,
+    options: &DumpOptions,
+    errors: &mut Vec<jmap::ObjectError>,
+) -> Result<Option<T>> {
+    let err = match result {
+        Err(err) if options.keep_going => err,
+        Err(err) => return Err(err.context(Failure::Read)),
+        result => return result,
+    };
+    let obj = uobjectarray.read_item_ptr(i as usize).ok().flatten();
+    let error = jmap::ObjectError {
+        index: i,
+        address: obj.as_ref().map(|o| o.address().into()),
+        path: obj.and_then(|o| o.path().ok()),
+        error: format!("{err:#}"),
+    };
+    warn!(
+        "skipping object {i} ({}): {}",
+        error.path.as_deref().unwrap_or("unknown path"),
+        error.error
+    );
+    if let Some(log) = &options.error_log {
+        log.object(&error, &err);
+    }
+    errors.push(error);
+    Ok(None)
+}
+
+/// What the streaming dump needs to know about every object before serializing any of them
+struct Skeleton {
+    outer: Option<String>,
+    class: String,
+    vtable: Address,
+    super_struct: Option<String>,
+    /// Set for classes
+    constructors: Option<constructors::Constructors>,
+    /// Set for native functions
+    native: Option<u64>,
+}
+
+/// Path of object `i` and its skeleton if it is dumped
+fn scan_item<C: Ctx>(
+    uobjectarray: &Ptr<FUObjectArray, C>,
+    i: i32,
+    options: &DumpOptions,
+) -> Result<Option<(String, Option<Skeleton>)>> {
+    let Some(obj) = uobjectarray.read_item_ptr(i as usize)? else {
+        return Ok(None);
+    };
+    let path = obj.path()?;
+    let skeleton = if is_dumped(&path, options) && !is_uproperty(&obj.class_private().read()?)? {
+        Some(read_skeleton(&obj)?)
+    } else {
+        None
+    };
+    Ok(Some((path, skeleton)))
+}
+
+fn read_skeleton<C: Ctx>(obj: &Ptr<UObject, C>) -> Result<Skeleton> {
+    let class = obj.class_private().read()?;
+    let object_flags = obj.object_flags().read()?;
+    let is_basic_object = object_flags.contains(EObjectFlags::RF_ArchetypeObject)
+        || object_flags.contains(EObjectFlags::RF_ClassDefaultObject);
+    let cast_flags = class.class_cast_flags().read()?;
+    let is_class = !is_basic_object && cast_flags.contains(EClassCastFlags::CASTCLASS_UClass);
+    let is_function = !is_basic_object && cast_flags.contains(EClassCastFlags::CASTCLASS_UFunction);
+    let native = if is_function {
+        let function = obj.cast::<UFunction>();
+        let func = function.func().read()? as u64;
+        let is_native = function
+            .function_flags()
+            .read()?
+            .contains(EFunctionFlags::FUNC_Native);
+        (is_native && func != 0).then_some(func)
+    } else {
+        None
+    };
+    let (super_struct, constructors) = if is_class {
+        let super_struct = obj
+            .cast::<UStruct>()
+            .super_struct()
+            .read()?
+            .map(|s| s.path())
+            .transpose()?;
+        let constructors = constructors::Constructors::read(&obj.cast())?;
+        (super_struct, Some(constructors))
+    } else {
+        (None, None)
+    };
+    Ok(Skeleton {
+        outer: obj.outer_private().read()?.map(|s| s.path()).transpose()?,
+        class: class.path()?,
+        vtable: (obj.vtable().read()? as u64).into(),
+        super_struct,
+        constructors,
+        native,
     })
 }
 
-fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<ObjectType>> {
+/// Write `header` as JSON with objects streamed from GUObjectArray
+#[tracing::instrument(skip_all)]
+fn stream_objects<C: Ctx>(
+    ctx: &C,
+    uobjectarray: &Ptr<FUObjectArray, C>,
+    options: &DumpOptions,
+    header: &Jmap,
+    symbols: Option<&pdb_symbols::Symbols>,
+    bytes_read: &Arc<AtomicU64>,
+    w: &mut dyn Write,
+) -> Result<()> {
+    let num = uobjectarray.num_elements().context(Failure::Read)?;
+    prefetch_objects(uobjectarray, num);
+
+    // first pass: paths, outers and vtables
+    let mut skeletons = HashMap::<String, Skeleton>::new();
+    let mut errors = vec![];
+    let mut failed = HashSet::new();
+    let progress = Progress::new(options.progress, "Scanning", num as u64, bytes_read.clone());
+    let mut scanned = num;
+    for i in 0..num {
+        if options.cancel.is_cancelled() {
+            interrupted(i, num);
+            scanned = i;
+            break;
+        }
+        let errors_before = errors.len();
+        let item = keep_going(
+            scan_item(uobjectarray, i, options),
+            uobjectarray,
+            i,
+            options,
+            &mut errors,
+        );
+        let Some((path, skeleton)) = item? else {
+            // already reported, don't read it again in the second pass
+            if errors.len() > errors_before {
+                failed.insert(i);
+            }
+            progress.inc(None);
+            continue;
+        };
+        progress.inc(Some(&path));
+        if let Some(skeleton) = skeleton {
+            skeletons.insert(path, skeleton);
+        }
+    }
+
+    drop(progress);
+
+    let mut child_map = HashMap::<&str, BTreeSet<String>>::default();
+    for (path, skeleton) in &skeletons {
+        if let Some(outer) = &skeleton.outer {
+            child_map
+                .entry(outer.as_str())
+                .or_default()
+                .insert(path.clone());
+        }
+    }
+    let (class_vtables, vtables) = if options.no_vtables {
+        Default::default()
+    } else {
+        vtable::find_vtables(
+            ctx,
+            &vtable::Code::new(header),
+            vtable::Abi::new(header),
+            &options.cancel,
+            skeletons.values().map(|s| (s.class.as_str(), s.vtable)),
+            // classes of dumped objects can be filtered out
+            |class| skeletons.get(class)?.super_struct.as_deref(),
+        )
+    };
+    let slot_names = vtable::slot_names(header, &class_vtables, &vtables);
+    let static_classes = if options.static_class {
+        constructors::find_static_classes(
+            ctx,
+            header,
+            skeletons
+                .values()
+                .filter_map(|s| s.constructors.as_ref())
+                .map(|c| c.class_constructor),
+            &options.cancel,
+        )
+    } else {
+        Default::default()
+    };
+    let implementations = thunks::resolve_thunks(ctx, skeletons.values().filter_map(|s| s.native));
+
+    // metadata goes last, whether the dump is partial is only known at the end
+    write!(w, "{{\"image_base_address\":")?;
+    serde_json::to_writer(&mut *w, &header.image_base_address)?;
+    write!(w, ",\"image\":")?;
+    serde_json::to_writer(&mut *w, &header.image)?;
+    write!(w, ",\"resolved_addresses\":")?;
+    serde_json::to_writer(&mut *w, &header.resolved_addresses)?;
+    write!(w, ",\"natives\":")?;
+    serde_json::to_writer(&mut *w, &header.natives)?;
+    write!(w, ",\"vtables\":")?;
+    serde_json::to_writer(&mut *w, &vtables)?;
+
+    // second pass: full objects
+    let references = disasm::ReferenceCollector::new(ctx);
+    let mut natives = vec![];
+    write!(w, ",\"objects\":{{")?;
+    let mut first = true;
+    let progress = Progress::new(options.progress, "Objects", num as u64, bytes_read.clone());
+    for i in 0..scanned {
+        if options.cancel.is_cancelled() {
+            // reported already if it happened in the first pass
+            if scanned == num {
+                interrupted(i, num);
+            }
+            break;
+        }
+        if failed.contains(&i) {
+            progress.inc(None);
+            continue;
+        }
+        let item = keep_going(
+            read_item(uobjectarray, i, options),
+            uobjectarray,
+            i,
+            options,
+            &mut errors,
+        )?;
+        progress.inc(item.as_ref().map(|(path, _)| path.as_str()));
+        let Some((path, mut object)) = item else {
+            continue;
+        };
+        if let Some(children) = child_map.remove(path.as_str()) {
+            object.get_object_mut().children = children;
+        }
+        if let ObjectType::Class(class) = &mut object {
+            class.instance_vtable = class_vtables.get(&path).copied();
+            if let Some(vtable) = class.instance_vtable {
+                class.virtual_functions =
+                    vtable::virtual_functions(header, &vtables, vtable, &slot_names);
+            }
+            if let Some(constructors) = skeletons.get(&path).and_then(|s| s.constructors.as_ref()) {
+                constructors::apply(header, class, constructors, &static_classes);
+            }
+        }
+        if let ObjectType::Function(function) = &mut object
+            && let Some(implementation) = implementations.get(&function.func.0)
+        {
+            let virtual_functions = skeletons
+                .get(&path)
+                .and_then(|s| class_vtables.get(s.outer.as_ref()?))
+                .map(|vtable| vtable::virtual_functions(header, &vtables, *vtable, &slot_names))
+                .unwrap_or_default();
+            function.implementation = implementation.resolve(header, &virtual_functions);
+        }
+        if let Some(symbols) = symbols {
+            symbols.apply(&mut object);
+        }
+        if options.bytecode {
+            references.add(&path, &object);
+        }
+        if options.signatures
+            && let Some(native) = signatures::native(&object)
+        {
+            natives.push((path.clone(), native));
+        }
+
+        if !first {
+            write!(w, ",")?;
+        }
+        first = false;
+        serde_json::to_writer(&mut *w, &path)?;
+        write!(w, ":")?;
+        serde_json::to_writer(&mut *w, &object)?;
+    }
+    write!(w, "}}")?;
+
+    write!(w, ",\"signatures\":")?;
+    let signatures = if options.signatures {
+        signatures::find_signatures(ctx, header, natives)
+    } else {
+        vec![]
+    };
+    serde_json::to_writer(&mut *w, &signatures)?;
+
+    write!(w, ",\"script_references\":")?;
+    serde_json::to_writer(&mut *w, &options.bytecode.then(|| references.finish()))?;
+    write!(w, ",\"errors\":")?;
+    serde_json::to_writer(&mut *w, &errors)?;
+    let mut metadata = header.metadata.clone();
+    if let Some(metadata) = &mut metadata {
+        metadata.partial = options.cancel.is_cancelled();
+    }
+    write!(w, ",\"metadata\":")?;
+    serde_json::to_writer(&mut *w, &metadata)?;
+    write!(w, "}}")?;
+    w.flush()?;
+
+    Ok(())
+}
+
+fn read_object<C: Ctx>(
+    obj: Ptr<UObject, C>,
+    path: &str,
+    options: &DumpOptions,
+) -> Result<Option<ObjectType>> {
     let class = obj.class_private().read()?;
 
+    fn read_values<C: Ctx>(
+        ustruct: &Ptr<UStruct, C>,
+        ptr: &Ptr<(), C>,
+        options: &DumpOptions,
+    ) -> Result<OrderMap<String, PropertyValue>> {
+        if options.no_values {
+            return Ok(OrderMap::new());
+        }
+        read_props(ustruct, ptr, options.value_depth)
+    }
     fn read_props<C: Ctx>(
         ustruct: &Ptr<UStruct, C>,
         ptr: &Ptr<(), C>,
+        depth: Option<usize>,
     ) -> Result<OrderMap<String, PropertyValue>> {
         let mut properties = OrderMap::new();
         for prop in ustruct.properties(true) {
             let prop = prop?;
             let array_dim = prop.array_dim().read()? as usize;
             let name = prop.zfield().name_private().read()?;
+            trace!(property = %name, "reading");
             if array_dim == 1 {
-                if let Some(value) = read_prop(&prop, ptr, 0)? {
+                if let Some(value) =
+                    read_prop(&prop, ptr, 0, depth).with_context(|| format!("property {name}"))?
+                {
                     properties.insert(name, value);
                 }
             } else {
                 let mut elements = vec![];
                 let mut success = true;
                 for i in 0..array_dim {
-                    if let Some(value) = read_prop(&prop, ptr, i)? {
+                    if let Some(value) = read_prop(&prop, ptr, i, depth)
+                        .with_context(|| format!("property {name}[{i}]"))?
+                    {
                         elements.push(value);
                     } else {
                         success = false;
@@ -547,14 +1859,19 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
         prop: &Ptr<ZProperty, C>,
         ptr: &Ptr<(), C>,
         index: usize,
+        depth: Option<usize>,
     ) -> Result<Option<PropertyValue>> {
         let size = prop.element_size().read()? as usize;
         let ptr = ptr.byte_offset(prop.offset_internal().read()? as usize + index * size);
         let f = prop.zfield().cast_flags()?;
 
         let value = if f.contains(EClassCastFlags::CASTCLASS_FStructProperty) {
+            if depth == Some(0) {
+                return Ok(None);
+            }
             let prop = prop.cast::<ZStructProperty>();
-            PropertyValue::Struct(read_props(&prop.struct_().read()?.ustruct(), &ptr)?)
+            let depth = depth.map(|d| d - 1);
+            PropertyValue::Struct(read_props(&prop.struct_().read()?.ustruct(), &ptr, depth)?)
         } else if f.contains(EClassCastFlags::CASTCLASS_FStrProperty) {
             PropertyValue::Str(ptr.cast::<FString>().read()?)
         } else if f.contains(EClassCastFlags::CASTCLASS_FNameProperty) {
@@ -585,7 +1902,7 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
                 let inner_prop = prop.inner().read()?;
                 for i in 0..num {
                     // TODO handle size != alignment
-                    let value = read_prop(&inner_prop, &data_ptr, i)?;
+                    let value = read_prop(&inner_prop, &data_ptr, i, depth)?;
                     if let Some(value) = value {
                         data.push(value);
                     } else {
@@ -597,7 +1914,7 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
             PropertyValue::Array(data)
         } else if f.contains(EClassCastFlags::CASTCLASS_FEnumProperty) {
             let prop = prop.cast::<ZEnumProperty>();
-            let underlying = read_prop(&prop.underlying_prop().read()?, &ptr, 0)?
+            let underlying = read_prop(&prop.underlying_prop().read()?, &ptr, 0, depth)?
                 .expect("valid underlying prop");
             let value = match underlying {
                 PropertyValue::Byte(BytePropertyValue::Value(v)) => v as i64,
@@ -610,7 +1927,7 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
                 PropertyValue::UInt64(v) => v as i64,
                 e => bail!("underlying enum prop {e:?}"),
             };
-            let names = read_enum(&prop.enum_().read()?.expect("valid enum"))?.names;
+            let names = prop.enum_().read()?.expect("valid enum").read_names()?;
             let name = names
                 .into_iter()
                 .find_map(|(name, v)| (v == value).then_some(name));
@@ -644,7 +1961,7 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
             return Ok(None);
         } else if f.contains(EClassCastFlags::CASTCLASS_FFloatProperty) {
             PropertyValue::Float(ptr.cast::<f32>().read()?.into())
-        } else if f.contains(EClassCastFlags::CASTCLASS_FDoubleProperty) {
+        } else if f.intersects(DOUBLE_PROPERTY) {
             PropertyValue::Double(ptr.cast::<f64>().read()?.into())
         } else if f.contains(EClassCastFlags::CASTCLASS_FByteProperty) {
             let prop = prop.cast::<ZByteProperty>();
@@ -653,10 +1970,10 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
                 if let Some(name) = prop
                     .enum_()
                     .read()?
-                    .map(|e| read_enum(&e))
+                    .map(|e| e.read_names())
                     .transpose()?
-                    .and_then(|e| {
-                        e.names
+                    .and_then(|names| {
+                        names
                             .into_iter()
                             .find_map(|(name, v)| (v == value as i64).then_some(name))
                     })
@@ -681,16 +1998,19 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
         } else if f.contains(EClassCastFlags::CASTCLASS_FInt64Property) {
             PropertyValue::Int64(ptr.cast::<i64>().read()?)
         } else if f.contains(EClassCastFlags::CASTCLASS_FObjectProperty) {
-            let obj = ptr
-                .cast::<Option<Ptr<UObject, _>>>()
-                .read()?
-                .map(|e| e.path())
-                .transpose()?;
+            let obj = match ptr.cast::<FObjectPtr>().resolve()? {
+                ObjectRef::Object(object) => Some(object.path()?),
+                ObjectRef::Null => None,
+                ObjectRef::Unresolved(packed) => {
+                    tracing::debug!("unresolved object reference {packed:?}");
+                    None
+                }
+            };
             PropertyValue::Object(obj)
         } else if f.contains(EClassCastFlags::CASTCLASS_FWeakObjectProperty) {
             return Ok(None);
         } else if f.contains(EClassCastFlags::CASTCLASS_FSoftObjectProperty) {
-            return Ok(None);
+            PropertyValue::SoftObject(ptr.cast::<FSoftObjectPtr>().object_id().read()?)
         } else if f.contains(EClassCastFlags::CASTCLASS_FLazyObjectProperty) {
             return Ok(None);
         } else if f.contains(EClassCastFlags::CASTCLASS_FInterfaceProperty) {
@@ -705,12 +2025,12 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
             // technically needs to be C locale but probably never going to encounter non-ASCII characters anyway
             PropertyValue::Utf8Str(ptr.cast::<FUtf8String>().read()?)
         } else {
-            unimplemented!("{f:?}");
+            return Err(UnsupportedProperty(format!("{f:?}")).into());
         };
         Ok(Some(value))
     }
 
-    fn read_object<C: Ctx>(obj: &Ptr<UObject, C>) -> Result<Object> {
+    fn read_object<C: Ctx>(obj: &Ptr<UObject, C>, options: &DumpOptions) -> Result<Object> {
         let outer = obj.outer_private().read()?.map(|s| s.path()).transpose()?;
 
         let class = obj.class_private().read()?;
@@ -723,11 +2043,16 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
             outer,
             class: class_name,
             children: Default::default(),
-            property_values: read_props(&class.ustruct(), &obj.cast())?.into(),
+            property_values: read_values(&class.ustruct(), &obj.cast(), options)?.into(),
+            // filled in from the FUObjectItem by the caller
+            internal_index: Default::default(),
+            internal_flags: None,
+            cluster_root_index: None,
+            serial_number: None,
         })
     }
 
-    fn read_struct<C: Ctx>(obj: &Ptr<UStruct, C>) -> Result<Struct> {
+    fn read_struct<C: Ctx>(obj: &Ptr<UStruct, C>, options: &DumpOptions) -> Result<Struct> {
         let mut properties = vec![];
         for prop in obj.properties(false) {
             let prop = prop?;
@@ -738,23 +2063,34 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
         }
 
         let super_struct = obj.super_struct().read()?.map(|s| s.path()).transpose()?;
+        let script = if options.bytecode {
+            Some(obj.read_script()?)
+                .filter(|s| !s.is_empty())
+                .map(Into::into)
+        } else {
+            None
+        };
         Ok(Struct {
-            object: read_object(&obj.cast())?,
+            object: read_object(&obj.cast(), options)?,
             super_struct,
             properties,
             properties_size: obj.properties_size().read()? as usize,
             min_alignment: obj.min_alignment().read()? as usize,
+            script,
         })
     }
 
-    fn read_script_struct<C: Ctx>(obj: &Ptr<UScriptStruct, C>) -> Result<ScriptStruct> {
+    fn read_script_struct<C: Ctx>(
+        obj: &Ptr<UScriptStruct, C>,
+        options: &DumpOptions,
+    ) -> Result<ScriptStruct> {
         Ok(ScriptStruct {
-            r#struct: read_struct(&obj.ustruct())?,
+            r#struct: read_struct(&obj.ustruct(), options)?,
             struct_flags: obj.struct_flags().read()?,
         })
     }
 
-    fn read_class<C: Ctx>(obj: &Ptr<UClass, C>) -> Result<Class> {
+    fn read_class<C: Ctx>(obj: &Ptr<UClass, C>, options: &DumpOptions) -> Result<Class> {
         let class_flags = obj.class_flags().read()?;
         let class_cast_flags = obj.class_cast_flags().read()?;
         let class_default_object = obj
@@ -762,28 +2098,116 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
             .read()?
             .map(|s| s.path())
             .transpose()?;
+        let class_within = obj.class_within().read()?.map(|s| s.path()).transpose()?;
+        let config_name = Some(obj.class_config_name().read()?).filter(|n| n != "None");
+
+        let mut sparse_class_data_struct = None;
+        let mut sparse_class_data = None;
+        if obj.ctx().ue_version() >= (4, 24)
+            && let Some(sparse_struct) = obj.sparse_class_data_struct().read()?
+        {
+            sparse_class_data_struct = Some(sparse_struct.path()?);
+            if !options.no_values
+                && let Some(data) = obj.sparse_class_data().read()?
+            {
+                sparse_class_data =
+                    Some(read_values(&sparse_struct.ustruct(), &data, options)?.into());
+            }
+        }
+
+        let mut interfaces = vec![];
+        for interface in obj.interfaces().iter()? {
+            let Some(class) = interface.class().read()? else {
+                continue;
+            };
+            interfaces.push(ImplementedInterface {
+                class: class.path()?,
+                pointer_offset: interface.pointer_offset().read()?,
+                implemented_by_k2: interface.implemented_by_k2().read()? != 0,
+            });
+        }
+
         Ok(Class {
-            r#struct: read_struct(&obj.cast())?,
+            r#struct: read_struct(&obj.cast(), options)?,
             class_flags,
             class_cast_flags,
             class_default_object,
+            class_within,
+            config_name,
             instance_vtable: None,
+            virtual_functions: vec![],
+            class_constructor: None,
+            vtable_helper_ctor_caller: None,
+            static_class: None,
+            sparse_class_data_struct,
+            sparse_class_data,
+            interfaces,
         })
     }
 
-    fn read_enum<C: Ctx>(obj: &Ptr<UEnum, C>) -> Result<Enum> {
+    fn read_enum<C: Ctx>(obj: &Ptr<UEnum, C>, options: &DumpOptions) -> Result<Enum> {
+        let version = obj.ctx().ue_version();
+        let cpp_form: ECppForm = obj.cpp_form().read()?;
+        let names = obj.read_names()?;
+        let display_name_fn = if version >= (4, 15) {
+            Some(obj.enum_display_name_fn().read()? as u64)
+                .filter(|f| *f != 0)
+                .map(Into::into)
+        } else {
+            None
+        };
+        // without a display name function cooked builds show the name itself, the DisplayName
+        // metadata only exists in the editor
+        let display_names = if display_name_fn.is_none() {
+            names
+                .iter()
+                .map(|(name, _)| cpp_form.short_name(name).to_string())
+                .collect()
+        } else {
+            vec![]
+        };
         Ok(Enum {
-            object: read_object(&obj.cast())?,
+            object: read_object(&obj.cast(), options)?,
             cpp_type: obj.cpp_type().read()?,
-            cpp_form: obj.cpp_form().read()?,
-            enum_flags: (obj.ctx().ue_version() >= (4, 26))
+            cpp_form,
+            enum_flags: (version >= (4, 26))
                 .then(|| obj.enum_flags().read())
                 .transpose()?,
-            names: obj.read_names()?,
+            names,
+            display_name_fn,
+            display_names,
+            enum_package: (version >= (5, 1))
+                .then(|| obj.enum_package().read())
+                .transpose()?,
+        })
+    }
+
+    fn read_package<C: Ctx>(obj: &Ptr<UPackage, C>, options: &DumpOptions) -> Result<Package> {
+        let version = obj.ctx().ue_version();
+        let none_filter = |name: String| Some(name).filter(|n| n != "None");
+        Ok(Package {
+            object: read_object(&obj.cast(), options)?,
+            package_flags: obj.package_flags().read()?,
+            file_name: if version < (5, 5) {
+                none_filter(obj.file_name().read()?)
+            } else {
+                None
+            },
+            loaded_path: if version >= (5, 1) {
+                none_filter(obj.loaded_path().package_name().read()?)
+            } else {
+                None
+            },
+            guid: (version < (5, 4)).then(|| obj.guid().read()).transpose()?,
         })
     }
 
-    if !path.starts_with("/Script/") {
+    let is_instance = || -> Result<bool> {
+        Ok(options.classes.instances
+            && options.filter.matches(path)
+            && options.classes.is_instance(&class.path()?))
+    };
+    if !(is_dumped(path, options) || is_instance()?) || is_uproperty(&class)? {
         return Ok(None);
     }
     let object_flags = obj.object_flags().read()?;
@@ -792,26 +2216,31 @@ fn read_object<C: Ctx>(obj: Ptr<UObject, C>, path: &str) -> Result<Option<Object
 
     let f = class.class_cast_flags().read()?;
     let object = if !is_basic_object && f.contains(EClassCastFlags::CASTCLASS_UClass) {
-        ObjectType::Class(read_class(&obj.cast())?)
+        obj.prefetch("UClass");
+        ObjectType::Class(read_class(&obj.cast(), options)?)
     } else if !is_basic_object && f.contains(EClassCastFlags::CASTCLASS_UFunction) {
+        obj.prefetch("UFunction");
         let full_obj = obj.cast::<UFunction>();
         let function_flags = full_obj.function_flags().read()?;
         ObjectType::Function(Function {
-            r#struct: read_struct(&obj.cast())?,
+            r#struct: read_struct(&obj.cast(), options)?,
             function_flags,
             func: (full_obj.func().read()? as u64).into(),
+            symbol: None,
+            implementation: None,
         })
     } else if !is_basic_object && f.contains(EClassCastFlags::CASTCLASS_UScriptStruct) {
-        ObjectType::ScriptStruct(read_script_struct(&obj.cast())?)
+        obj.prefetch("UScriptStruct");
+        ObjectType::ScriptStruct(read_script_struct(&obj.cast(), options)?)
     } else if !is_basic_object && f.contains(EClassCastFlags::CASTCLASS_UEnum) {
-        ObjectType::Enum(read_enum(&obj.cast())?)
+        obj.prefetch("UEnum");
+        ObjectType::Enum(read_enum(&obj.cast(), options)?)
     } else if !is_basic_object && f.contains(EClassCastFlags::CASTCLASS_UPackage) {
-        ObjectType::Package(Package {
-            object: read_object(&obj)?,
-        })
+        obj.prefetch("UPackage");
+        ObjectType::Package(read_package(&obj.cast(), options)?)
     } else {
         let obj = obj.cast::<UObject>();
-        ObjectType::Object(read_object(&obj)?)
+        ObjectType::Object(read_object(&obj, options)?)
         //println!("{path:?} {:?}", f);
     };
     Ok(Some(object))
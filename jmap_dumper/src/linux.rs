@@ -0,0 +1,116 @@
+//! Live Linux process backend: memory is read with `process_vm_readv` and modules are enumerated
+//! from `/proc/<pid>/maps`, which covers both native processes and Windows games under Proton
+//! or Wine where the .exe is mapped like any other file.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result, bail};
+
+use crate::mem::Mem;
+
+/// File backed mapping of a process, spanning all of its mapped segments
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    pub base: u64,
+    pub size: u64,
+}
+
+/// Modules of `pid` ordered by base address
+pub fn modules(pid: i32) -> Result<Vec<Module>> {
+    let maps = proc_maps::get_process_maps(pid)
+        .with_context(|| format!("Failed to read proc maps for {pid}"))?;
+
+    let mut modules = BTreeMap::<PathBuf, Module>::new();
+    for map in maps {
+        let Some(path) = map.filename() else {
+            continue;
+        };
+        let start = map.start() as u64;
+        let end = start + map.size() as u64;
+        modules
+            .entry(path.to_path_buf())
+            .and_modify(|m| {
+                let module_end = (m.base + m.size).max(end);
+                m.base = m.base.min(start);
+                m.size = module_end - m.base;
+            })
+            .or_insert_with(|| Module {
+                name: path
+                    .to_string_lossy()
+                    .rsplit(['\\', '/'])
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                path: path.to_path_buf(),
+                base: start,
+                size: end - start,
+            });
+    }
+    let mut modules: Vec<_> = modules.into_values().collect();
+    modules.sort_by_key(|m| m.base);
+    Ok(modules)
+}
+
+/// The game module: the mapped .exe under Proton/Wine, otherwise the process executable
+pub fn main_module(pid: i32) -> Result<Module> {
+    let modules = modules(pid)?;
+    if let Some(exe) = modules.iter().find(|m| m.name.ends_with(".exe")) {
+        return Ok(exe.clone());
+    }
+    let exe = std::fs::read_link(format!("/proc/{pid}/exe"))
+        .with_context(|| format!("Failed to read executable of {pid}"))?;
+    modules
+        .into_iter()
+        .find(|m| m.path == exe)
+        .with_context(|| format!("Failed to find main module of {pid}"))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Process {
+    pid: libc::pid_t,
+}
+
+impl Process {
+    pub fn open(pid: i32) -> Result<Self> {
+        if !std::path::Path::new(&format!("/proc/{pid}")).exists() {
+            bail!("Process {pid} not found");
+        }
+        Ok(Self { pid })
+    }
+}
+
+impl Mem for Process {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        // reads stop at the first unreadable page so keep going until done or nothing was read
+        let mut done = 0;
+        while done < buf.len() {
+            let local = libc::iovec {
+                iov_base: buf[done..].as_mut_ptr().cast(),
+                iov_len: buf.len() - done,
+            };
+            let remote = libc::iovec {
+                iov_base: (address as usize + done) as *mut libc::c_void,
+                iov_len: buf.len() - done,
+            };
+            let read = unsafe { libc::process_vm_readv(self.pid, &local, 1, &remote, 1, 0) };
+            if read <= 0 {
+                let err = std::io::Error::last_os_error();
+                let hint = if err.raw_os_error() == Some(libc::EPERM) {
+                    " (run as the same user with kernel.yama.ptrace_scope=0, or as root)"
+                } else {
+                    ""
+                };
+                bail!(
+                    "reading {} bytes at 0x{:x}: {err}{hint}",
+                    buf.len(),
+                    address + done as u64
+                );
+            }
+            done += read as usize;
+        }
+        Ok(())
+    }
+}
@@ -1,244 +1,1545 @@
-use anyhow::{Result, bail};
-use clap::{ArgGroup, Parser};
-use jmap::Jmap;
-use jmap_dumper::{Input, into_header, structs::Structs};
-use std::io::Cursor;
+use anyhow::{Context as _, Result, anyhow, bail};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use jmap::{Jmap, split::SplitIndex};
+use jmap_dumper::{
+    Cancel, ClassScope, DumpOptions, GraphOptions, Input, LaunchOptions, LayoutReportOptions,
+    Overrides, PathFilter, Target, TreeOptions,
+    instances::InstanceQuery,
+    into_dot, into_ghidra_script, into_header, into_layout_report, into_map, into_package_headers,
+    into_pdb, into_tree, into_x64dbg_script,
+    names::{NameDictionary, NameResolver},
+    report::{ErrorLog, Report},
+    sdkgen,
+    structs::Structs,
+};
+use patternsleuth::resolvers::unreal::engine_version::EngineVersion;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, LazyLock};
 use std::{collections::BTreeMap, fs::File, io::BufWriter, path::PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None,
-        group = ArgGroup::new("input").args(&["pid", "minidump", "jmap"]).required(true))]
+        args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    load: LoadArgs,
+
+    #[command(flatten)]
+    usmap: UsmapArgs,
+
+    /// Serialize objects as they are read instead of holding the whole dump in memory (JSON output
+    /// only)
+    #[arg(long)]
+    stream: bool,
+
+    /// Output dump .jmap path
+    #[arg(index = 1, required = true)]
+    output: Option<PathBuf>,
+
+    /// Log more detail, -v for debug and -vv for trace output (RUST_LOG takes precedence)
+    #[arg(long, short, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Write a JSON report of failures and skipped objects to this file (- for stderr). Exit codes
+    /// are 3 when engine globals can't be resolved, 4 without a usable struct layout and 5 for
+    /// read errors, including objects skipped by --keep-going
+    #[arg(long, global = true)]
+    error_report: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct UsmapArgs {
+    /// Version of .usmap format to write [default: explicit-enum-values]
+    #[arg(long, value_enum)]
+    usmap_version: Option<UsmapVersion>,
+
+    /// Compression method of .usmap output [default: none]
+    #[arg(long, value_enum)]
+    usmap_compression: Option<UsmapCompression>,
+
+    /// Path to Oodle shared library (required for Oodle .usmap compression)
+    #[arg(long)]
+    oodle: Option<PathBuf>,
+}
+
+/// Objects skipped by --keep-going, for --error-report
+static ERROR_LOG: LazyLock<Arc<ErrorLog>> = LazyLock::new(Default::default);
+/// Set by Ctrl-C to stop dumping and write what was read so far
+static CANCEL: LazyLock<Cancel> = LazyLock::new(Default::default);
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate an SDK from a dump
+    Sdk {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// SDK language
+        #[arg(long, value_enum, default_value_t = SdkFormat::Cpp)]
+        format: SdkFormat,
+
+        /// Output directory
+        #[arg(index = 1)]
+        output: PathBuf,
+    },
+    /// Write a PDB with public symbols for exec functions, vtables and engine globals
+    Pdb {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Output .pdb path
+        #[arg(index = 1)]
+        output: PathBuf,
+    },
+    /// Write an x64dbg script labelling exec functions, vtables and engine globals
+    X64dbg {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Output script path
+        #[arg(index = 1)]
+        output: PathBuf,
+    },
+    /// Write a Graphviz DOT graph of the class hierarchy
+    Graph {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Only include types in this package (e.g. /Script/Engine), may be repeated
+        #[arg(long)]
+        package: Vec<String>,
+
+        /// Only include this class (e.g. /Script/Engine.Actor) and everything deriving from it
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Include script structs
+        #[arg(long)]
+        structs: bool,
+
+        /// Omit implemented interface edges
+        #[arg(long)]
+        no_interfaces: bool,
+
+        /// Output .dot path
+        #[arg(index = 1)]
+        output: PathBuf,
+    },
+    /// Print object counts per class and package and the classes whose instances take up the
+    /// most memory. Live games are dumped with --all-objects and --no-values for this
+    Stats {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Entries of each list, 0 for all
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the live instances of a class with their address and outer
+    FindInstances {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Class (e.g. Engine.PlayerController or /Script/Engine.PlayerController)
+        #[arg(index = 1, value_parser = parse_class_path)]
+        class: String,
+
+        /// Include instances of classes deriving from it
+        #[arg(long)]
+        derived: bool,
+
+        /// Print the property values of each instance
+        #[arg(long)]
+        values: bool,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the members of every class and struct in offset order with the padding between
+    /// them and layout problems like overlapping or misaligned members
+    Layout {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Only print types with problems, and only their problems
+        #[arg(long)]
+        issues: bool,
+
+        /// Output path (stdout if omitted)
+        #[arg(index = 1)]
+        output: Option<PathBuf>,
+    },
+    /// Print the class hierarchy as an indented tree
+    Tree {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Only print this class (e.g. Engine.Actor or /Script/Engine.Actor) and everything
+        /// deriving from it
+        #[arg(index = 1, value_parser = parse_class_path)]
+        root: Option<String>,
+
+        /// Include script structs
+        #[arg(long)]
+        structs: bool,
+
+        /// Levels below the root to print
+        #[arg(long)]
+        depth: Option<usize>,
+
+        /// Print full paths instead of names
+        #[arg(long)]
+        paths: bool,
+    },
+    /// Write one file per package plus an index into a directory
+    Split {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Per package file format
+        #[arg(long, value_enum, default_value_t = SplitFormat::Jmap)]
+        format: SplitFormat,
+
+        /// Output directory
+        #[arg(index = 1)]
+        output: PathBuf,
+    },
+    /// Write a full memory minidump of a running process for dumping later with --minidump
+    Snapshot {
+        /// Process ID to snapshot
+        #[arg(
+            long,
+            short,
+            required_unless_present = "process",
+            conflicts_with = "process"
+        )]
+        pid: Option<i32>,
+
+        /// Process to snapshot by executable name or window title
+        #[arg(long)]
+        process: Option<String>,
+
+        /// Output .dmp path
+        #[arg(index = 1)]
+        output: PathBuf,
+    },
+    /// Inspect the built-in struct layouts
+    StructInfo {
+        #[command(subcommand)]
+        command: StructInfoCommand,
+    },
+    /// Convert a dump between .jmap, .jmap.gz, .jmap.zst, .json.zst and binary .jmapb, or .usmap
+    /// mappings into a dump
+    Convert {
+        /// Input dump path
+        #[arg(index = 1)]
+        input: PathBuf,
+
+        /// Output dump path
+        #[arg(index = 2)]
+        output: PathBuf,
+    },
+    /// Write a .usmap from an existing dump, e.g. to regenerate mappings with a different
+    /// version or compression without attaching to the game again
+    Usmap {
+        /// Input dump path (any format accepted by --jmap)
+        #[arg(index = 1)]
+        input: PathBuf,
+
+        /// Output .usmap path
+        #[arg(index = 2)]
+        output: PathBuf,
+
+        #[command(flatten)]
+        usmap: UsmapArgs,
+    },
+    /// Print the classes, structs and enums that changed between two dumps, e.g. after a game
+    /// update
+    Diff {
+        #[command(flatten)]
+        load: LoadArgs,
+
+        /// Dump of the old version
+        #[arg(index = 1)]
+        old: PathBuf,
+
+        /// Dump of the new version, read from the game or --dump if omitted
+        #[arg(index = 2)]
+        new: Option<PathBuf>,
+
+        /// Print the changes as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merge dumps of the same game taken in different states into one. Objects found in several
+    /// dumps are taken from the one with the most objects and type differences between them are
+    /// printed
+    Merge {
+        /// Dumps to merge, in any order
+        #[arg(index = 1, required = true, num_args = 2..)]
+        inputs: Vec<PathBuf>,
+
+        /// Output dump path
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Print the objects of a dump matching a query, e.g.
+    /// `classes where super == "Actor" select name, properties.count`
+    Query {
+        /// Dump to query
+        #[arg(index = 1)]
+        dump: PathBuf,
+
+        /// Query as `<objects|classes|structs|enums|functions|packages> [where <condition>]
+        /// [select <field>, ...] [sort <field> [desc]] [limit <n>]`
+        #[arg(index = 2)]
+        query: String,
+
+        /// Print the rows as JSON instead of tab separated values
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum StructInfoCommand {
+    /// Print the built-in layouts for an engine version, e.g. `struct-info print 4.27`
+    Print {
+        /// Engine version as major.minor
+        #[arg(index = 1, value_parser = parse_version)]
+        version: (u16, u16),
+
+        /// Layouts for games built with WITH_CASE_PRESERVING_NAME
+        #[arg(long)]
+        case_preserving: bool,
+
+        /// Print as JSON usable with --struct-info
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compute the layouts from engine source headers and write them as a --struct-info file,
+    /// e.g. `struct-info generate 5.3 UE_5.3/Engine/Source/Runtime/CoreUObject out.json`
+    Generate {
+        /// Engine version as major.minor, selects preprocessor conditions
+        #[arg(index = 1, value_parser = parse_version)]
+        version: (u16, u16),
+
+        /// Header files or directories to scan recursively
+        #[arg(index = 2, required = true, num_args = 1..)]
+        source: Vec<PathBuf>,
+
+        /// Output .json path
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// Layouts for games built with WITH_CASE_PRESERVING_NAME
+        #[arg(long)]
+        case_preserving: bool,
+
+        /// Additional preprocessor define as NAME=value
+        #[arg(long = "define", short = 'D', value_parser = parse_define)]
+        defines: Vec<(String, i64)>,
+
+        /// Size of a type the headers don't define as NAME=size or NAME=size:alignment
+        #[arg(long = "type-size", value_parser = parse_type_size)]
+        type_sizes: Vec<(String, u64, u64)>,
+    },
+}
+
+fn parse_version(s: &str) -> Result<(u16, u16), String> {
+    s.split_once('.')
+        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+        .ok_or_else(|| format!("expected a version like 4.27, got {s:?}"))
+}
+
+fn parse_class_path(s: &str) -> Result<String, String> {
+    if s.starts_with('/') {
+        Ok(s.to_string())
+    } else if s.contains('.') {
+        Ok(format!("/Script/{s}"))
+    } else {
+        Err(format!("expected a class like Engine.Actor, got {s:?}"))
+    }
+}
+
+fn parse_define(s: &str) -> Result<(String, i64), String> {
+    let (name, value) = s.split_once('=').unwrap_or((s, "1"));
+    let value = value
+        .parse()
+        .map_err(|e| format!("invalid value in {s:?}: {e}"))?;
+    Ok((name.to_string(), value))
+}
+
+fn parse_type_size(s: &str) -> Result<(String, u64, u64), String> {
+    let parse = || {
+        let (name, size) = s.split_once('=')?;
+        let (size, align) = match size.split_once(':') {
+            Some((size, align)) => (parse_address(size).ok()?, parse_address(align).ok()?),
+            // largest power of two dividing the size, at most 8
+            None => {
+                let size = parse_address(size).ok()?;
+                (size, 1 << size.trailing_zeros().min(3))
+            }
+        };
+        Some((name.to_string(), size, align))
+    };
+    parse().ok_or_else(|| format!("expected NAME=size or NAME=size:alignment, got {s:?}"))
+}
+
+#[derive(Args, Debug)]
+struct LoadArgs {
+    #[command(flatten)]
+    source: Source,
+
+    /// meatloaf.toml profile with per-game settings, overridden by options given here
+    #[arg(long)]
+    profile: Option<PathBuf>,
+
+    /// Struct layout info .json overriding the built-in layouts (see `struct-info print --json`)
+    #[arg(long, short)]
+    struct_info: Option<PathBuf>,
+
+    /// Target profile .json describing pointer size and byte order of console builds, e.g.
+    /// {"pointer_size": 8, "endian": "little"} (default: 64-bit little endian). 32-bit targets
+    /// need --struct-info
+    #[arg(long)]
+    target: Option<PathBuf>,
+    /// Decoder profile .json for games obfuscating GUObjectArray pointers, mapping "objects",
+    /// "chunks" and "object" to expressions over the stored value x, e.g. {"object": "x ^ 0x1234"}
+    #[arg(long)]
+    decoders: Option<PathBuf>,
+    /// Dictionary of `<hash> <name>` lines for games storing hashes in place of FName text.
+    /// Names that aren't in it are dumped as read
+    #[arg(long)]
+    name_dictionary: Option<PathBuf>,
+    /// Dump 8 or 16 hex digit names missing from --name-dictionary as Hash_<hash>
+    #[arg(long)]
+    hash_placeholders: bool,
+
+    /// Include UStruct::Script bytecode for every struct and function
+    #[arg(long)]
+    bytecode: bool,
+
+    /// Sanity check the struct layout against the game and log a verdict per member before
+    /// dumping
+    #[arg(long)]
+    validate_layout: bool,
+
+    /// Address of GUObjectArray, skipping its resolver for binaries where scanning fails
+    #[arg(long, value_parser = parse_address)]
+    guobjectarray: Option<u64>,
+    /// Address of FNamePool (GNames before 4.23), skipping its resolver
+    #[arg(long, value_parser = parse_address)]
+    fnamepool: Option<u64>,
+    /// Engine version (e.g. 5.3) to use instead of detecting it
+    #[arg(long, value_parser = parse_version)]
+    engine_version: Option<(u16, u16)>,
+
+    /// Dump every object instead of only the reflection data under /Script/
+    #[arg(long)]
+    all_objects: bool,
+    /// Only dump objects whose path matches this regex, may be repeated (e.g. '^/Script/Engine\.')
+    #[arg(long)]
+    include: Vec<Regex>,
+    /// Don't dump objects whose path matches this regex, may be repeated
+    #[arg(long)]
+    exclude: Vec<Regex>,
+    /// Only dump this class (e.g. Engine.Actor or /Script/Engine.Actor) and the types it depends
+    /// on, may be repeated
+    #[arg(long, value_parser = parse_class_path)]
+    class: Vec<String>,
+    /// Also dump all live instances of the --class classes
+    #[arg(long, requires = "class")]
+    instances: bool,
+
+    /// Don't read property values, for a much faster dump of only the type information
+    #[arg(long, conflicts_with = "value_depth")]
+    no_values: bool,
+    /// Levels of nested struct values to read, 0 reads only the objects' own non-struct values
+    #[arg(long)]
+    value_depth: Option<usize>,
+
+    /// Skip objects that fail to read, listing them in the dump's `errors`, instead of aborting
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Threads reading objects, 0 for one per core
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+
+    /// MiB of game memory to keep cached while dumping, 0 for no limit
+    #[arg(long, default_value_t = 1024)]
+    cache_size: usize,
+    /// Keep the memory read from the game in this directory and read it from there on later runs
+    /// against the same build and process or dump
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+    /// Log reads, bytes requested and read from the game and the cache hit rate after dumping
+    #[arg(long)]
+    stats: bool,
+
+    /// PDB of the game executable, or a directory (e.g. a symbol store) to look for it in, to
+    /// name virtual functions and natives. The path the executable was built with is tried too.
+    #[arg(long)]
+    symbols: Option<PathBuf>,
+    /// Symbol server to download the PDB of the game executable from if it isn't found locally
+    #[arg(long)]
+    symbol_server: Option<String>,
+    /// Scan the game's code for the GetPrivateStaticClass of every class, which registers it
+    #[arg(long)]
+    static_class: bool,
+    /// Skip vtable analysis, for dumps only needed for usmap or SDK output
+    #[arg(long)]
+    no_vtables: bool,
+    /// Generate byte signatures of the engine globals and UFunction natives (implied by .sig
+    /// output)
+    #[arg(long)]
+    signatures: bool,
+
+    /// Don't show progress bars while dumping
+    #[arg(long, short)]
+    quiet: bool,
+
+    /// Read the process selected by --pid/--process through a registered backend instead
+    /// (e.g. driver:\\.\MyReader)
+    #[arg(long)]
+    backend: Option<String>,
+
+    /// Argument passed to the --launch executable, may be repeated
+    #[arg(long, requires = "launch", allow_hyphen_values = true)]
+    launch_arg: Vec<String>,
+
+    /// Seconds to wait for the --launch game to initialize before giving up
+    #[arg(long, requires = "launch", default_value_t = 120)]
+    launch_timeout: u64,
+
+    /// Kill the --launch game after dumping instead of resuming it
+    #[arg(long, requires = "launch")]
+    launch_kill: bool,
+
+    /// Agent DLL for --inject (default: jmap_inject.dll next to this executable)
+    #[cfg(target_os = "windows")]
+    #[arg(long, requires = "inject")]
+    inject_dll: Option<PathBuf>,
+
+    /// Image name (e.g. Game-Win64-Shipping.exe) or PID of the process inside --kernel-dump
+    #[arg(long, requires = "kernel_dump")]
+    dump_process: Option<String>,
+
+    /// Address the first byte of --raw was read from (hex with 0x prefix or decimal)
+    #[arg(long, requires = "raw", value_parser = parse_address)]
+    raw_base: Option<u64>,
+
+    /// Address the game executable is mapped at behind --gdb
+    #[arg(long, requires = "gdb", value_parser = parse_address)]
+    gdb_image_base: Option<u64>,
+
+    /// Guest CR3 (page table root) of the game process inside --vm-snapshot
+    #[arg(long, requires = "vm_snapshot", value_parser = parse_address)]
+    cr3: Option<u64>,
+
+    /// Guest virtual address the game executable is mapped at inside --vm-snapshot
+    #[arg(long, requires = "vm_snapshot", value_parser = parse_address)]
+    vm_image_base: Option<u64>,
+
+    /// Executable loaded in --raw, used for pattern scanning
+    #[arg(long, requires = "raw")]
+    raw_image: Option<PathBuf>,
+}
+
+fn parse_address(s: &str) -> Result<u64, String> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| format!("invalid address {s:?}: {e}"))
+}
+
+// Without any of these a running game is picked interactively
+#[derive(Args, Debug)]
+#[group(required = false, multiple = false)]
+struct Source {
     /// Dump from process ID
-    #[arg(long, short, group = "input")]
+    #[arg(long, short)]
     pid: Option<i32>,
 
+    /// Dump from process by executable name (e.g. FSD-Win64-Shipping.exe) or window title
+    #[arg(long)]
+    process: Option<String>,
+
+    /// Start this game executable and dump it once the UObject system is initialized
+    #[arg(long)]
+    launch: Option<PathBuf>,
+
     /// Dump from minidump
-    #[arg(long, short, group = "input")]
+    #[arg(long, short)]
     minidump: Option<PathBuf>,
 
-    /// Use existing .jmap dump
-    #[arg(long, short, group = "input")]
+    /// Dump from a complete Windows memory dump (MEMORY.DMP), see --dump-process
+    #[arg(long)]
+    kernel_dump: Option<PathBuf>,
+
+    /// Dump from a jmap_agent running on another machine (host or host:port)
+    #[arg(long)]
+    remote: Option<String>,
+
+    /// Dump from process ID by injecting a Frida agent, for games that block ReadProcessMemory
+    #[cfg(feature = "frida")]
+    #[arg(long)]
+    frida: Option<i32>,
+
+    /// Dump from process ID by loading jmap_inject.dll into it and reading in process
+    #[cfg(target_os = "windows")]
+    #[arg(long)]
+    inject: Option<i32>,
+
+    /// Dump through a GDB remote protocol stub (host:port), requires --gdb-image-base
+    #[arg(long, requires = "gdb_image_base")]
+    gdb: Option<String>,
+
+    /// Dump from a VM guest physical memory snapshot (QEMU dump-guest-memory ELF or pmemsave
+    /// image), requires --cr3 and --vm-image-base
+    #[arg(long, requires_all = ["cr3", "vm_image_base"])]
+    vm_snapshot: Option<PathBuf>,
+
+    /// Dump from a flat memory dump, requires --raw-base and --raw-image
+    #[arg(long, requires_all = ["raw_base", "raw_image"])]
+    raw: Option<PathBuf>,
+
+    /// Use existing dump (.jmap or plain JSON, compressed or binary .jmapb, or a split directory)
+    /// or .usmap mappings
+    #[arg(long, short, visible_alias = "dump")]
     jmap: Option<PathBuf>,
+}
 
-    /// Struct layout info .json (from pdb_dumper)
-    #[arg(long, short)]
-    struct_info: Option<PathBuf>,
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SdkFormat {
+    Cpp,
+    Rust,
+    Csharp,
+    Lua,
+}
+impl From<SdkFormat> for sdkgen::Format {
+    fn from(value: SdkFormat) -> Self {
+        match value {
+            SdkFormat::Cpp => Self::Cpp,
+            SdkFormat::Rust => Self::Rust,
+            SdkFormat::Csharp => Self::CSharp,
+            SdkFormat::Lua => Self::Lua,
+        }
+    }
+}
 
-    /// Output dump .jmap path
-    #[arg(index = 1)]
-    output: PathBuf,
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SplitFormat {
+    /// .jmap per package and index.json, loadable again by passing the directory to --jmap
+    Jmap,
+    /// C++ header per package including the packages it depends on, Basic.hpp and index.hpp
+    Header,
 }
 
-fn main() -> Result<()> {
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum UsmapVersion {
+    Initial,
+    PackageVersioning,
+    LongFName,
+    LargeEnums,
+    ExplicitEnumValues,
+}
+impl From<UsmapVersion> for usmap::UsmapVersion {
+    fn from(value: UsmapVersion) -> Self {
+        match value {
+            UsmapVersion::Initial => Self::Initial,
+            UsmapVersion::PackageVersioning => Self::PackageVersioning,
+            UsmapVersion::LongFName => Self::LongFName,
+            UsmapVersion::LargeEnums => Self::LargeEnums,
+            UsmapVersion::ExplicitEnumValues => Self::ExplicitEnumValues,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum UsmapCompression {
+    None,
+    Oodle,
+    Brotli,
+    Zstd,
+}
+impl From<UsmapCompression> for Option<usmap::CompressionMethod> {
+    fn from(value: UsmapCompression) -> Self {
+        match value {
+            UsmapCompression::None => None,
+            UsmapCompression::Oodle => Some(usmap::CompressionMethod::Oodle),
+            UsmapCompression::Brotli => Some(usmap::CompressionMethod::Brotli),
+            UsmapCompression::Zstd => Some(usmap::CompressionMethod::Zstd),
+        }
+    }
+}
+
+/// Log to stderr, at info level for the dumper unless raised with -v or set with RUST_LOG
+fn init_logging(verbose: u8) {
+    let filter = match verbose {
+        0 => "warn,jmap_dumper=info",
+        1 => "warn,jmap_dumper=debug",
+        _ => "debug,jmap_dumper=trace",
+    };
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(filter));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .init();
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    init_logging(cli.verbose);
+    let error_report = cli.error_report.clone();
+    let handler = ctrlc::set_handler(|| {
+        if CANCEL.is_cancelled() {
+            // second Ctrl-C, give up on writing a partial dump
+            std::process::exit(130);
+        }
+        eprintln!("Interrupted, stopping (press Ctrl-C again to abort)");
+        CANCEL.cancel();
+    });
+    if let Err(err) = handler {
+        tracing::warn!("failed to install Ctrl-C handler: {err}");
+    }
+
+    let result = run(cli);
+    if let Err(err) = &result {
+        eprintln!("Error: {err:?}");
+    }
+    let report = Report::new(
+        result.as_ref().map(|_| ()),
+        &ERROR_LOG,
+        CANCEL.is_cancelled(),
+    );
+    if let Some(path) = error_report
+        && let Err(err) = write_report(&path, &report)
+    {
+        eprintln!(
+            "Failed to write error report to {}: {err:#}",
+            path.display()
+        );
+    }
+    ExitCode::from(report.exit_code)
+}
+
+fn write_report(path: &Path, report: &Report) -> Result<()> {
+    if path == Path::new("-") {
+        serde_json::to_writer_pretty(std::io::stderr(), report)?;
+    } else {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), report)?;
+    }
+    Ok(())
+}
+
+fn run(mut cli: Cli) -> Result<()> {
+    if let Some(path) = cli.load.profile.clone() {
+        Profile::read(&path)?.apply_output(&mut cli);
+    }
+
+    match cli.command {
+        Some(Command::Sdk {
+            load,
+            format,
+            output,
+        }) => {
+            let reflection_data = load_jmap(load)?;
+            let files = sdkgen::generate(&reflection_data, format.into());
+            std::fs::create_dir_all(&output)?;
+            for (name, contents) in files {
+                std::fs::write(output.join(name), contents)?;
+            }
+            println!("Success! SDK written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::Pdb { load, output }) => {
+            let reflection_data = load_jmap(load)?;
+            std::fs::write(&output, into_pdb(&reflection_data)?)?;
+            println!("Success! PDB written to {}", output.display());
+            if let Some(codeview) = reflection_data
+                .image
+                .as_ref()
+                .and_then(|i| i.codeview.as_ref())
+            {
+                println!(
+                    "Matches {} (GUID {}, age {}), place it next to the executable under that name",
+                    codeview.pdb_path, codeview.guid, codeview.age
+                );
+            }
+            return Ok(());
+        }
+        Some(Command::Stats {
+            mut load,
+            top,
+            json,
+        }) => {
+            if load.source.jmap.is_none() {
+                load.all_objects = true;
+                load.no_values = true;
+            }
+            let stats = jmap_dumper::stats::stats(&load_jmap(load)?, top);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print!("{stats}");
+            }
+            return Ok(());
+        }
+        Some(Command::FindInstances {
+            load,
+            class,
+            derived,
+            values,
+            json,
+        }) => {
+            let query = InstanceQuery {
+                class,
+                derived,
+                values,
+            };
+            let instances = match &load.source.jmap {
+                Some(path) => jmap_dumper::instances::find_in_dump(&read_jmap(path)?, &query),
+                None => {
+                    let (input, options) = live_input(load)?;
+                    jmap_dumper::find_instances(input, options, &query)?
+                }
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&instances)?);
+            } else {
+                for instance in &instances {
+                    println!("{instance}");
+                }
+                println!("{} instances of {}", instances.len(), query.class);
+            }
+            return Ok(());
+        }
+        Some(Command::Layout {
+            load,
+            issues,
+            output,
+        }) => {
+            let options = LayoutReportOptions {
+                issues_only: issues,
+            };
+            let report = into_layout_report(&load_jmap(load)?, &options);
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, report)?;
+                    println!("Success! Output written to {}", output.display());
+                }
+                None => print!("{report}"),
+            }
+            return Ok(());
+        }
+        Some(Command::Tree {
+            load,
+            root,
+            structs,
+            depth,
+            paths,
+        }) => {
+            let options = TreeOptions {
+                root,
+                structs,
+                depth,
+                paths,
+            };
+            print!("{}", into_tree(&load_jmap(load)?, &options)?);
+            return Ok(());
+        }
+        Some(Command::Split {
+            load,
+            format,
+            output,
+        }) => {
+            let jmap = load_jmap(load)?;
+            match format {
+                SplitFormat::Jmap => {
+                    let (header, packages) = jmap.split_by_package();
+                    let mut index = BTreeMap::new();
+                    for (package, jmap) in packages {
+                        let file = format!("{}.jmap", package.trim_start_matches('/'));
+                        let path = output.join(&file);
+                        std::fs::create_dir_all(path.parent().unwrap())?;
+                        write_jmap(&path, &jmap)?;
+                        index.insert(package, file);
+                    }
+                    let file = BufWriter::new(File::create(output.join("index.json"))?);
+                    serde_json::to_writer_pretty(
+                        file,
+                        &SplitIndex {
+                            header,
+                            packages: index,
+                        },
+                    )?;
+                }
+                SplitFormat::Header => {
+                    // generated from the whole dump so types are named and ordered as in the
+                    // single header
+                    for (file, contents) in into_package_headers(&jmap) {
+                        let path = output.join(file);
+                        std::fs::create_dir_all(path.parent().unwrap())?;
+                        std::fs::write(&path, contents)?;
+                    }
+                }
+            }
+            println!("Success! Output written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::X64dbg { load, output }) => {
+            let script = into_x64dbg_script(&load_jmap(load)?);
+            std::fs::write(&output, script)?;
+            println!("Success! Output written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::Graph {
+            load,
+            package,
+            root,
+            structs,
+            no_interfaces,
+            output,
+        }) => {
+            let options = GraphOptions {
+                packages: package,
+                root,
+                structs,
+                interfaces: !no_interfaces,
+            };
+            std::fs::write(&output, into_dot(&load_jmap(load)?, &options)?)?;
+            println!("Success! Output written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::Snapshot {
+            pid,
+            process,
+            output,
+        }) => {
+            let pid = match (pid, process) {
+                (Some(pid), _) => pid,
+                (None, Some(query)) => jmap_dumper::find_process(&query)?.pid,
+                (None, None) => unreachable!("clap requires --pid or --process"),
+            };
+            jmap_dumper::snapshot::write_minidump(pid, &output)?;
+            println!("Success! Minidump written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::StructInfo {
+            command:
+                StructInfoCommand::Print {
+                    version: (major, minor),
+                    case_preserving,
+                    json,
+                },
+        }) => {
+            let structs = jmap_dumper::structs::get_struct_info_for_version(
+                &EngineVersion { major, minor },
+                case_preserving,
+            )?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&structs)?);
+            } else {
+                print!("{structs}");
+            }
+            return Ok(());
+        }
+        Some(Command::StructInfo {
+            command:
+                StructInfoCommand::Generate {
+                    version,
+                    source,
+                    output,
+                    case_preserving,
+                    defines,
+                    type_sizes,
+                },
+        }) => {
+            use jmap_dumper::source_layout::{SourceOptions, generate};
+
+            let generated = generate(
+                &source,
+                &SourceOptions {
+                    version,
+                    case_preserving,
+                    defines,
+                    type_sizes,
+                },
+            )?;
+            for (name, err) in &generated.failed {
+                println!("{name}: {err}");
+            }
+            if !generated.not_found.is_empty() {
+                println!(
+                    "Not defined in the headers: {}",
+                    generated.not_found.join(", ")
+                );
+            }
+
+            // differences to the built-in table point at either a stale table or a missing define
+            let (major, minor) = version;
+            match jmap_dumper::structs::get_struct_info_for_version(
+                &EngineVersion { major, minor },
+                case_preserving,
+            ) {
+                Ok(builtin) => {
+                    for info in &generated.structs.0 {
+                        let Some(expected) = builtin.0.iter().find(|s| s.name == info.name) else {
+                            continue;
+                        };
+                        if expected.size != info.size {
+                            println!(
+                                "{}: size 0x{:x}, built-in 0x{:x}",
+                                info.name, info.size, expected.size
+                            );
+                        }
+                        for member in &expected.members {
+                            match info.members.iter().find(|m| m.name == member.name) {
+                                Some(m) if m.offset != member.offset => println!(
+                                    "{}::{}: offset 0x{:x}, built-in 0x{:x}",
+                                    info.name, member.name, m.offset, member.offset
+                                ),
+                                Some(_) => {}
+                                None => println!("{}::{}: not found", info.name, member.name),
+                            }
+                        }
+                    }
+                }
+                Err(err) => println!("No built-in layout to compare with: {err:#}"),
+            }
+
+            let file = BufWriter::new(File::create(&output)?);
+            serde_json::to_writer_pretty(file, &generated.structs)?;
+            println!(
+                "Success! {} structs written to {}",
+                generated.structs.0.len(),
+                output.display()
+            );
+            return Ok(());
+        }
+        Some(Command::Convert { input, output }) => {
+            write_jmap(&output, &read_jmap(&input)?)?;
+            println!("Success! Output written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::Usmap {
+            input,
+            output,
+            usmap,
+        }) => {
+            write_usmap(&read_jmap(&input)?, &output, &usmap)?;
+            println!("Success! Output written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::Diff {
+            load,
+            old,
+            new,
+            json,
+        }) => {
+            let old = read_jmap(&old)?;
+            let new = match new {
+                Some(new) => read_jmap(&new)?,
+                None => load_jmap(load)?,
+            };
+            let diff = jmap_dumper::diff::diff(&old, &new);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else if diff.is_empty() {
+                println!("No changes");
+            } else {
+                print!("{diff}");
+            }
+            return Ok(());
+        }
+        Some(Command::Query { dump, query, json }) => {
+            let query = jmap_dumper::query::Query::parse(&query)?;
+            let rows = query.run(&read_jmap(&dump)?)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&rows.to_json())?);
+            } else {
+                print!("{rows}");
+            }
+            return Ok(());
+        }
+        Some(Command::Merge { inputs, output }) => {
+            let dumps = inputs
+                .iter()
+                .map(|input| Ok((input.display().to_string(), read_jmap(input)?)))
+                .collect::<Result<_>>()?;
+            let (merged, discrepancies) = jmap_dumper::merge::merge(dumps)?;
+            for discrepancy in &discrepancies {
+                println!("{discrepancy}");
+            }
+            write_jmap(&output, &merged)?;
+            println!(
+                "Success! {} objects with {} discrepancies written to {}",
+                merged.objects.len(),
+                discrepancies.len(),
+                output.display()
+            );
+            return Ok(());
+        }
+        None => {}
+    }
 
     enum OutputType {
         Jmap,
-        JmapGz,
         Usmap,
         Header,
+        Disasm,
+        Ghidra,
+        Sqlite,
+        Csv,
+        Map,
+        Parquet,
+        Signatures,
     }
 
-    let output_type = match cli.output.file_name().and_then(|e| e.to_str()) {
-        Some(n) if n.ends_with(".jmap") => OutputType::Jmap,
-        Some(n) if n.ends_with(".jmap.gz") => OutputType::JmapGz,
+    let output = cli.output.unwrap();
+    let output_type = match output.file_name().and_then(|e| e.to_str()) {
+        Some(_) if jmap::io::is_dump_path(&output) => OutputType::Jmap,
         Some(n) if n.ends_with(".usmap") => OutputType::Usmap,
         Some(n) if n.ends_with(".h") || n.ends_with(".hpp") => OutputType::Header,
-        _ => bail!("Error: Expected .jmap, .jmap.gz, .usmap, or .hpp output type"),
+        Some(n) if n.ends_with(".disasm") => OutputType::Disasm,
+        Some(n) if n.ends_with(".py") => OutputType::Ghidra,
+        Some(n) if n.ends_with(".sqlite") || n.ends_with(".db") => OutputType::Sqlite,
+        Some(n) if n.ends_with(".csv") => OutputType::Csv,
+        Some(n) if n.ends_with(".map") => OutputType::Map,
+        Some(n) if n.ends_with(".parquet") => OutputType::Parquet,
+        Some(n) if n.ends_with(".sig") => OutputType::Signatures,
+        _ => bail!(
+            "Error: Expected .jmap, .jmap.gz, .jmap.zst, .json.zst, .jmapb, .usmap, .hpp, .disasm, .py, .sqlite, .csv, .parquet, .map or .sig output type"
+        ),
     };
+    if matches!(output_type, OutputType::Signatures) {
+        cli.load.signatures = true;
+    }
 
-    let struct_info: Option<Structs> = if let Some(path) = cli.struct_info {
-        Some(serde_json::from_slice(&std::fs::read(path)?)?)
-    } else {
-        None
-    };
+    if cli.stream {
+        if !jmap::io::is_dump_path(&output) || output.extension().is_some_and(|e| e == "jmapb") {
+            bail!("Error: --stream requires .jmap, .jmap.gz, .jmap.zst or .json.zst output");
+        }
+        let (input, options) = live_input(cli.load)?;
+        let mut file = jmap::io::create(&output)?;
+        jmap_dumper::dump_streaming(input, options, &mut file)?;
+        file.finish()?;
+        println!("Success! Output written to {}", output.display());
+        return Ok(());
+    }
 
-    let reflection_data: Jmap = if let Some(path) = cli.jmap {
-        let filename = path.file_name().unwrap().to_str().unwrap();
-        if filename.ends_with(".jmap.gz") {
-            let compressed = std::fs::read(path)?;
-            let decoder = flate2::read::GzDecoder::new(Cursor::new(&compressed));
-            serde_json::from_reader(decoder)?
-        } else if filename.ends_with(".jmap") {
-            serde_json::from_slice(&std::fs::read(path)?)?
-        } else {
-            bail!("Error: Expected .jmap or .jmap.gz file as input");
-        }
-    } else if let Some(pid) = cli.pid {
-        jmap_dumper::dump(Input::Process(pid), struct_info)?
-    } else if let Some(path) = cli.minidump {
-        jmap_dumper::dump(Input::Dump(path), struct_info)?
-    } else {
-        unreachable!();
-    };
+    let reflection_data = load_jmap(cli.load)?;
 
     match output_type {
-        OutputType::Jmap => {
-            let mut file = BufWriter::new(File::create(&cli.output)?);
-            serde_json::to_writer_pretty(&mut file, &reflection_data)?;
-        }
-        OutputType::JmapGz => {
-            let mut file = BufWriter::new(File::create(&cli.output)?);
-            let mut e = flate2::write::GzEncoder::new(&mut file, flate2::Compression::default());
-            serde_json::to_writer_pretty(&mut e, &reflection_data)?;
-            e.finish()?;
-        }
-        OutputType::Usmap => {
-            let usmap = into_usmap(&reflection_data);
-            usmap.write(&mut std::io::BufWriter::new(std::fs::File::create(
-                &cli.output,
-            )?))?;
-        }
+        OutputType::Jmap => write_jmap(&output, &reflection_data)?,
+        OutputType::Usmap => write_usmap(&reflection_data, &output, &cli.usmap)?,
         OutputType::Header => {
             let header = into_header(&reflection_data);
-            std::fs::write(&cli.output, header)?;
+            std::fs::write(&output, header)?;
+        }
+        OutputType::Disasm => {
+            let listing = jmap_dumper::disasm::into_listing(&reflection_data)?;
+            std::fs::write(&output, listing)?;
+        }
+        OutputType::Ghidra => {
+            let script = into_ghidra_script(&reflection_data);
+            std::fs::write(&output, script)?;
+        }
+        OutputType::Sqlite => {
+            jmap_dumper::sqlite::write_sqlite(&reflection_data, &output)?;
+        }
+        OutputType::Csv => {
+            jmap_dumper::table::write_csv(&reflection_data, &output)?;
+        }
+        OutputType::Map => {
+            std::fs::write(&output, into_map(&reflection_data)?)?;
+        }
+        OutputType::Parquet => {
+            jmap_dumper::table::write_parquet(&reflection_data, &output)?;
+        }
+        OutputType::Signatures => {
+            let signatures = jmap_dumper::signatures::into_signatures(&reflection_data)?;
+            std::fs::write(&output, signatures)?;
         }
     }
 
-    println!("Success! Output written to {}", cli.output.display());
+    println!("Success! Output written to {}", output.display());
 
     Ok(())
 }
 
-fn obj_name(path: &str) -> &str {
-    path.rsplit(['/', '.', ':']).next().unwrap()
+/// Per-game settings from a `meatloaf.toml` profile, so the options a game needs can be shared as
+/// one file. Options given on the command line take precedence and relative paths are relative
+/// to the profile:
+///
+/// ```toml
+/// struct_info = "layout.json"
+/// exclude = ["Editor"]
+/// decoders = "decoders.json"
+/// validate_layout = true
+/// guobjectarray = 0x14b3c2a80
+/// engine_version = "5.3"
+///
+/// [output]
+/// usmap_compression = "zstd"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Profile {
+    struct_info: Option<PathBuf>,
+    target: Option<PathBuf>,
+    decoders: Option<PathBuf>,
+    name_dictionary: Option<PathBuf>,
+    hash_placeholders: bool,
+    bytecode: bool,
+    validate_layout: bool,
+    keep_going: bool,
+    no_values: bool,
+    value_depth: Option<usize>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    class: Vec<String>,
+    instances: bool,
+    guobjectarray: Option<u64>,
+    fnamepool: Option<u64>,
+    engine_version: Option<String>,
+    output: OutputProfile,
 }
 
-fn into_usmap(reflection_data: &Jmap) -> usmap::Usmap {
-    let mut enums = vec![];
-    let mut structs = vec![];
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct OutputProfile {
+    usmap_version: Option<UsmapVersion>,
+    usmap_compression: Option<UsmapCompression>,
+    oodle: Option<PathBuf>,
+    stream: bool,
+}
 
-    for (path, obj) in &reflection_data.objects {
-        let struct_ = match &obj {
-            jmap::ObjectType::ScriptStruct(obj) => Some(&obj.r#struct),
-            jmap::ObjectType::Class(obj) => Some(&obj.r#struct),
-            _ => None,
-        };
-        if let Some(s) = struct_ {
-            let mut properties = vec![];
-            let mut index = 0;
-            for prop in &s.properties {
-                properties.push(into_usmap_prop(index, prop));
-                index += prop.array_dim;
-            }
-            structs.push(usmap::Struct {
-                name: obj_name(path).to_string(),
-                super_struct: s.super_struct.as_ref().map(|s| obj_name(s).to_string()),
-                properties,
-            });
-        } else if let Some(e) = obj.get_enum() {
-            let prefix = format!("{}::", obj_name(path));
-            let mut entries = BTreeMap::new();
-            for (name, value) in &e.names {
-                let variant_name = if let Some(variant_name) = name.strip_prefix(&prefix) {
-                    variant_name
-                } else {
-                    assert!(!name.contains("::"), "enum prefix was not stripped");
-                    name
-                };
-                entries.insert(*value, variant_name.to_string());
-            }
-            enums.push(usmap::Enum {
-                name: obj_name(path).to_string(),
-                entries,
-            });
-        }
-    }
-
-    usmap::Usmap {
-        enums,
-        structs,
-        cext: None,
-        eatr: None,
-        envp: None,
-        ppth: None,
-    }
-}
-
-fn into_usmap_prop(index: usize, prop: &jmap::Property) -> usmap::Property {
-    usmap::Property {
-        name: prop.name.clone(),
-        array_dim: prop.array_dim.try_into().unwrap(),
-        index: index.try_into().unwrap(),
-        inner: into_usmap_prop_inner(&prop.r#type),
-    }
-}
-
-fn into_usmap_prop_inner(prop: &jmap::PropertyType) -> usmap::PropertyInner {
-    use jmap::PropertyType as PT;
-    use usmap::PropertyInner as PI;
-    match &prop {
-        PT::Struct { r#struct } => PI::Struct {
-            name: obj_name(r#struct).to_string(),
-        },
-        PT::Str => PI::Str,
-        PT::Name => PI::Name,
-        PT::Text => PI::Text,
-        // TODO distinguish between sparse/inline?
-        PT::MulticastInlineDelegate { .. } => PI::MulticastDelegate,
-        PT::MulticastSparseDelegate { .. } => PI::MulticastDelegate,
-        PT::MulticastDelegate { .. } => PI::MulticastDelegate,
-        PT::Delegate { .. } => PI::Delegate,
-        PT::Bool {
-            field_size: _,
-            byte_offset: _,
-            byte_mask: _,
-            field_mask: _,
-        } => PI::Bool,
-        PT::Array { inner } => PI::Array {
-            inner: into_usmap_prop_inner(&inner.r#type).into(),
-        },
-        PT::Enum { container, r#enum } => PI::Enum {
-            inner: into_usmap_prop_inner(&container.r#type).into(),
-            name: r#enum
-                .as_ref()
-                .map(|e| obj_name(e))
-                .unwrap_or("None")
-                .to_string(),
-        },
-        PT::Map {
-            key_prop,
-            value_prop,
-        } => PI::Map {
-            key: into_usmap_prop_inner(&key_prop.r#type).into(),
-            value: into_usmap_prop_inner(&value_prop.r#type).into(),
+impl Profile {
+    fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read profile {}", path.display()))?;
+        let mut profile: Self = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse profile {}", path.display()))?;
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for path in [
+            &mut profile.struct_info,
+            &mut profile.target,
+            &mut profile.decoders,
+            &mut profile.name_dictionary,
+            &mut profile.output.oodle,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = dir.join(&*path);
+        }
+        Ok(profile)
+    }
+
+    fn apply(self, load: &mut LoadArgs) -> Result<()> {
+        fn fill<T>(arg: &mut Option<T>, profile: Option<T>) {
+            if arg.is_none() {
+                *arg = profile;
+            }
+        }
+        fn regexes(patterns: &[String]) -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).with_context(|| format!("Invalid filter {p:?} in profile")))
+                .collect()
+        }
+        fill(&mut load.struct_info, self.struct_info);
+        fill(&mut load.target, self.target);
+        fill(&mut load.decoders, self.decoders);
+        fill(&mut load.name_dictionary, self.name_dictionary);
+        load.hash_placeholders |= self.hash_placeholders;
+        load.bytecode |= self.bytecode;
+        load.validate_layout |= self.validate_layout;
+        load.keep_going |= self.keep_going;
+        if load.value_depth.is_none() {
+            load.no_values |= self.no_values;
+        }
+        if !load.no_values {
+            fill(&mut load.value_depth, self.value_depth);
+        }
+        if load.include.is_empty() {
+            load.include = regexes(&self.include)?;
+        }
+        if load.exclude.is_empty() {
+            load.exclude = regexes(&self.exclude)?;
+        }
+        if load.class.is_empty() {
+            load.class = self
+                .class
+                .iter()
+                .map(|c| parse_class_path(c))
+                .collect::<Result<_, _>>()
+                .map_err(|err| anyhow!("Invalid class in profile: {err}"))?;
+            load.instances |= self.instances;
+        }
+        fill(&mut load.guobjectarray, self.guobjectarray);
+        fill(&mut load.fnamepool, self.fnamepool);
+        let engine_version = self
+            .engine_version
+            .as_deref()
+            .map(parse_version)
+            .transpose()
+            .map_err(|err| anyhow!("Invalid engine_version in profile: {err}"))?;
+        fill(&mut load.engine_version, engine_version);
+        Ok(())
+    }
+
+    fn apply_output(self, cli: &mut Cli) {
+        let output = self.output;
+        let usmap = &mut cli.usmap;
+        usmap.usmap_version = usmap.usmap_version.or(output.usmap_version);
+        usmap.usmap_compression = usmap.usmap_compression.or(output.usmap_compression);
+        usmap.oodle = usmap.oodle.take().or(output.oodle);
+        cli.stream |= output.stream;
+    }
+}
+
+impl LoadArgs {
+    /// Fill in options not given on the command line from `--profile`
+    fn with_profile(mut self) -> Result<Self> {
+        if let Some(path) = &self.profile {
+            Profile::read(path)?.apply(&mut self)?;
+        }
+        Ok(self)
+    }
+}
+
+fn load_jmap(load: LoadArgs) -> Result<Jmap> {
+    if let Some(path) = &load.source.jmap {
+        return read_jmap(path);
+    }
+    let (input, options) = live_input(load)?;
+    jmap_dumper::dump(input, options)
+}
+
+/// Process or minidump to dump from
+fn live_input(load: LoadArgs) -> Result<(Input, DumpOptions)> {
+    let load = load.with_profile()?;
+    let struct_info = load.struct_info.as_deref().map(Structs::read).transpose()?;
+    let target = match load.target {
+        Some(path) => serde_json::from_slice(
+            &std::fs::read(&path)
+                .with_context(|| format!("Failed to read target profile {}", path.display()))?,
+        )
+        .with_context(|| format!("Failed to parse target profile {}", path.display()))?,
+        None => Target::default(),
+    };
+    let decoders = match load.decoders {
+        Some(path) => serde_json::from_slice(
+            &std::fs::read(&path)
+                .with_context(|| format!("Failed to read decoder profile {}", path.display()))?,
+        )
+        .with_context(|| format!("Failed to parse decoder profile {}", path.display()))?,
+        None => Default::default(),
+    };
+    let name_resolver: Option<Arc<dyn NameResolver>> = match load.name_dictionary {
+        Some(path) => {
+            let mut dictionary = NameDictionary::read(&path)?;
+            dictionary.placeholders = load.hash_placeholders;
+            tracing::info!("Loaded {} names from {}", dictionary.len(), path.display());
+            Some(Arc::new(dictionary))
+        }
+        None => None,
+    };
+    let options = DumpOptions {
+        struct_info,
+        bytecode: load.bytecode,
+        target,
+        decoders,
+        name_resolver,
+        validate_layout: load.validate_layout,
+        overrides: Overrides {
+            guobject_array: load.guobjectarray,
+            fname_pool: load.fnamepool,
+            engine_version: load.engine_version,
         },
-        PT::Set { key_prop } => PI::Set {
-            key: into_usmap_prop_inner(&key_prop.r#type).into(),
+        progress: !load.quiet,
+        keep_going: load.keep_going,
+        error_log: Some(ERROR_LOG.clone()),
+        cancel: CANCEL.clone(),
+        all_objects: load.all_objects,
+        filter: PathFilter {
+            include: load.include,
+            exclude: load.exclude,
         },
-        PT::Float => PI::Float,
-        PT::Double => PI::Double,
-        PT::Byte { r#enum } => {
-            // usmap special cases ByteProperty to transform into EnumProperty if enum member is populated
-            if let Some(e) = r#enum {
-                PI::Enum {
-                    inner: PI::Byte.into(),
-                    name: obj_name(e).to_string(),
-                }
-            } else {
-                PI::Byte
-            }
-        }
-        PT::UInt16 => PI::UInt16,
-        PT::UInt32 => PI::UInt32,
-        PT::UInt64 => PI::UInt64,
-        PT::Int8 => PI::Int8,
-        PT::Int16 => PI::Int16,
-        PT::Int => PI::Int,
-        PT::Int64 => PI::Int64,
-        PT::Object { property_class: _ } => PI::Object,
-        PT::Class { .. } => PI::Object,
-        PT::WeakObject { property_class: _ } => PI::WeakObject,
-        PT::SoftObject { property_class: _ } => PI::SoftObject,
-        PT::SoftClass { .. } => PI::SoftObject,
-        PT::LazyObject { property_class: _ } => PI::LazyObject,
-        PT::Interface { interface_class: _ } => PI::Interface,
-        PT::FieldPath => PI::FieldPath,
-        PT::Optional { inner } => PI::Optional {
-            inner: into_usmap_prop_inner(&inner.r#type).into(),
+        classes: ClassScope {
+            classes: load.class,
+            instances: load.instances,
         },
-        PT::Utf8Str => PI::Utf8Str,
-        PT::AnsiStr => PI::AnsiStr,
+        no_values: load.no_values,
+        value_depth: load.value_depth,
+        threads: load.threads,
+        cache_size: (load.cache_size != 0).then_some(load.cache_size << 20),
+        cache_dir: load.cache_dir,
+        io_stats: load.stats,
+        symbols: load.symbols,
+        symbol_server: load.symbol_server,
+        static_class: load.static_class,
+        no_vtables: load.no_vtables,
+        signatures: load.signatures,
+    };
+
+    #[cfg(feature = "frida")]
+    if let Some(pid) = load.source.frida {
+        return Ok((Input::Frida(pid), options));
+    }
+    #[cfg(target_os = "windows")]
+    if let Some(pid) = load.source.inject {
+        let dll = match load.inject_dll {
+            Some(dll) => dll,
+            None => std::env::current_exe()?.with_file_name("jmap_inject.dll"),
+        };
+        return Ok((Input::Inject { pid, dll }, options));
     }
+    let input = if let Some(pid) = load.source.pid {
+        Input::Process(pid)
+    } else if let Some(query) = load.source.process {
+        let process = jmap_dumper::find_process(&query)?;
+        tracing::info!("Found {} (PID {})", process.name, process.pid);
+        Input::Process(process.pid)
+    } else if let Some(exe) = load.source.launch {
+        Input::Launch(LaunchOptions {
+            exe,
+            args: load.launch_arg,
+            timeout: std::time::Duration::from_secs(load.launch_timeout),
+            kill: load.launch_kill,
+        })
+    } else if let Some(path) = load.source.minidump {
+        Input::Dump(path)
+    } else if let Some(path) = load.source.kernel_dump {
+        let Some(process) = load.dump_process else {
+            bail!("Error: --kernel-dump requires --dump-process");
+        };
+        Input::KernelDump { path, process }
+    } else if let Some(address) = load.source.remote {
+        Input::Remote(address)
+    } else if let Some(address) = load.source.gdb {
+        Input::Gdb {
+            address,
+            image_base: load.gdb_image_base.unwrap(),
+        }
+    } else if let Some(path) = load.source.vm_snapshot {
+        Input::VmSnapshot {
+            path,
+            cr3: load.cr3.unwrap(),
+            image_base: load.vm_image_base.unwrap(),
+        }
+    } else if let Some(path) = load.source.raw {
+        Input::Raw {
+            path,
+            base: load.raw_base.unwrap(),
+            image_path: load.raw_image.unwrap(),
+        }
+    } else {
+        Input::Process(pick_process()?)
+    };
+    let input = match (load.backend, input) {
+        (None, input) => input,
+        (Some(spec), Input::Process(pid)) => Input::Backend { spec, pid },
+        (Some(_), _) => bail!("Error: --backend reads a live process, use --pid or --process"),
+    };
+    Ok((input, options))
+}
+
+/// Ask which running Unreal Engine game to dump
+fn pick_process() -> Result<i32> {
+    use std::io::{BufRead as _, IsTerminal as _, Write as _};
+
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "Error: Expected --pid, --process, --launch, --minidump, --kernel-dump, --remote, --gdb, --vm-snapshot, --raw or --jmap"
+        );
+    }
+    let candidates = jmap_dumper::list_unreal_processes()?;
+    if candidates.is_empty() {
+        bail!("Error: No running Unreal Engine games found, pass --pid or another input");
+    }
+
+    for (i, process) in candidates.iter().enumerate() {
+        match process.window_titles.first() {
+            Some(title) => println!(
+                "{:>3}) {:>6} {} ({title})",
+                i + 1,
+                process.pid,
+                process.name
+            ),
+            None => println!("{:>3}) {:>6} {}", i + 1, process.pid, process.name),
+        }
+    }
+    let mut line = String::new();
+    loop {
+        print!("Process to dump [1-{}]: ", candidates.len());
+        std::io::stdout().flush()?;
+        line.clear();
+        if std::io::stdin().lock().read_line(&mut line)? == 0 {
+            bail!("Error: No process selected");
+        }
+        match line.trim().parse::<usize>() {
+            Ok(n) if (1..=candidates.len()).contains(&n) => return Ok(candidates[n - 1].pid),
+            _ => println!("Enter a number between 1 and {}", candidates.len()),
+        }
+    }
+}
+
+/// Read a .jmap, .jmap.gz, .jmap.zst, .json.zst or .jmapb dump, a directory written by the
+/// split command, or .usmap mappings with estimated offsets
+fn read_jmap(path: &Path) -> Result<Jmap> {
+    if path.extension().is_some_and(|e| e == "usmap") {
+        let usmap = usmap::Usmap::read(&mut std::io::BufReader::new(File::open(path)?))
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        return jmap_dumper::mappings::from_usmap(&usmap)
+            .with_context(|| format!("failed to convert {}", path.display()));
+    }
+    jmap::io::read(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+/// Write a .jmap, .jmap.gz, .jmap.zst, .json.zst or .jmapb dump
+fn write_jmap(path: &Path, reflection_data: &Jmap) -> Result<()> {
+    jmap::io::write(path, reflection_data)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn write_usmap(reflection_data: &Jmap, output: &Path, args: &UsmapArgs) -> Result<()> {
+    if let Some(oodle) = &args.oodle {
+        usmap::load_oodle(oodle)?;
+    }
+    let usmap = jmap_dumper::mappings::into_usmap(reflection_data);
+    usmap.write_with_options(
+        &mut std::io::BufWriter::new(std::fs::File::create(output)?),
+        usmap::WriteOptions {
+            version: args
+                .usmap_version
+                .unwrap_or(UsmapVersion::ExplicitEnumValues)
+                .into(),
+            compression_method: args
+                .usmap_compression
+                .unwrap_or(UsmapCompression::None)
+                .into(),
+        },
+    )
 }
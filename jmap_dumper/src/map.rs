@@ -0,0 +1,110 @@
+//! MSVC linker style .map export of exec functions, vtables and engine globals. Tools that read
+//! map files only look at the leading `section:offset name` of each public, so a symbol size
+//! column is appended after the address.
+
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+use jmap::{Address, Jmap};
+
+use crate::symbols::{SymbolKind, collect_symbols};
+
+const IMAGE_SCN_CNT_CODE: u32 = 0x20;
+const IMAGE_SCN_CNT_UNINITIALIZED_DATA: u32 = 0x80;
+
+/// Generate a .map file listing sections and symbols sorted by address
+pub fn into_map(jmap: &Jmap) -> Result<String> {
+    let image = jmap
+        .image
+        .as_ref()
+        .context("dump does not contain PE image info, re-dump with this version")?;
+    let base = jmap.image_base_address.0;
+    let module = image
+        .codeview
+        .as_ref()
+        .and_then(|cv| cv.pdb_path.rsplit(['\\', '/']).next())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or("image");
+
+    // (section index, section offset, rva, symbol)
+    let mut symbols = collect_symbols(jmap)
+        .into_iter()
+        .filter_map(|symbol| {
+            let rva = symbol.address.checked_sub(base)?;
+            let (index, section) = image.sections.iter().enumerate().find(|(_, s)| {
+                (s.virtual_address as u64..(s.virtual_address + s.virtual_size) as u64)
+                    .contains(&rva)
+            })?;
+            Some((index, rva - section.virtual_address as u64, rva, symbol))
+        })
+        .collect::<Vec<_>>();
+    symbols.sort_by_key(|(_, _, rva, _)| *rva);
+
+    let mut buffer = String::new();
+    writeln!(buffer, " {module}").unwrap();
+    writeln!(buffer).unwrap();
+    writeln!(buffer, " Preferred load address is {base:016x}").unwrap();
+    writeln!(buffer).unwrap();
+    writeln!(
+        buffer,
+        " Start         Length     Name                   Class"
+    )
+    .unwrap();
+    for (index, section) in image.sections.iter().enumerate() {
+        let class = if section.characteristics & IMAGE_SCN_CNT_CODE != 0 {
+            "CODE"
+        } else if section.characteristics & IMAGE_SCN_CNT_UNINITIALIZED_DATA != 0 {
+            "BSS"
+        } else {
+            "DATA"
+        };
+        writeln!(
+            buffer,
+            " {:04x}:00000000 {:08x}H {:<23}{class}",
+            index + 1,
+            section.virtual_size,
+            section.name
+        )
+        .unwrap();
+    }
+    writeln!(buffer).unwrap();
+    writeln!(
+        buffer,
+        "  Address         Publics by Value              Rva+Base               Size"
+    )
+    .unwrap();
+    writeln!(buffer).unwrap();
+
+    for (i, (index, offset, rva, symbol)) in symbols.iter().enumerate() {
+        let section = &image.sections[*index];
+        let section_end = (section.virtual_address + section.virtual_size) as u64;
+        // vtables know their length, everything else extends to the next symbol
+        let size = match symbol.kind {
+            SymbolKind::Vtable => jmap
+                .vtables
+                .get(&Address(symbol.address))
+                .map(|entries| entries.len() as u64 * 8),
+            SymbolKind::Function | SymbolKind::Global => None,
+        }
+        .unwrap_or_else(|| {
+            let next = symbols
+                .get(i + 1)
+                .map_or(section_end, |(_, _, next, _)| *next);
+            next.min(section_end) - rva
+        });
+        let flag = match symbol.kind {
+            SymbolKind::Function => "f",
+            SymbolKind::Vtable | SymbolKind::Global => " ",
+        };
+        writeln!(
+            buffer,
+            " {:04x}:{offset:08x}       {:<26} {:016x} {flag}   {size:08x}",
+            index + 1,
+            symbol.name,
+            base + rva
+        )
+        .unwrap();
+    }
+
+    Ok(buffer)
+}
@@ -0,0 +1,740 @@
+//! Conversion of .usmap mappings (e.g. made by other tools) into a dump, so they can be diffed
+//! against meatloaf dumps, saved as JSON or fed to the SDK and header generators, and of dumps
+//! into mappings.
+//!
+//! Mappings only hold names, types and the order of properties. Offsets and sizes are laid out
+//! with the natural alignment of a 64-bit target on top of a 0x28 byte UObject, and `bool`s are
+//! assumed to be native rather than bitfields. Object references without a known class point to
+//! `UObject`, and types are placed in [`UNKNOWN_PACKAGE`] if the mappings have no package paths.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use anyhow::{Context as _, Result, bail};
+use jmap::{
+    Address, Class, EClassCastFlags, EClassFlags, ECppForm, EEnumFlags, EObjectFlags,
+    EPackageFlags, EPropertyFlags, EStructFlags, Enum, Jmap, Object, ObjectType, Package, Property,
+    PropertyType, ScriptStruct, Struct,
+};
+use ordermap::OrderMap;
+use usmap::{FlagsType, PropertyInner, Usmap};
+
+use crate::sdkgen::{package_name, short_name};
+
+/// Package of types in mappings without package paths
+pub const UNKNOWN_PACKAGE: &str = "/Script/Unknown";
+
+const OBJECT: &str = "/Script/CoreUObject.Object";
+const INTERFACE: &str = "/Script/CoreUObject.Interface";
+/// Size of UObject's members before the first reflected property
+const UOBJECT_SIZE: usize = 0x28;
+
+fn align(offset: usize, alignment: usize) -> usize {
+    offset.next_multiple_of(alignment)
+}
+
+struct Layout {
+    properties: Vec<Property>,
+    properties_size: usize,
+    min_alignment: usize,
+}
+
+impl Layout {
+    fn size(&self) -> usize {
+        align(self.properties_size, self.min_alignment)
+    }
+}
+
+struct Converter<'a> {
+    structs: HashMap<&'a str, &'a usmap::Struct>,
+    classes: HashSet<&'a str>,
+    /// Paths of structs and enums by name
+    paths: HashMap<&'a str, String>,
+    layouts: HashMap<&'a str, Layout>,
+    /// Structs being laid out, to catch structs containing themselves
+    visiting: HashSet<&'a str>,
+}
+
+impl<'a> Converter<'a> {
+    fn path(&self, name: &str) -> String {
+        self.paths
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| format!("{UNKNOWN_PACKAGE}.{name}"))
+    }
+
+    /// Size and alignment of struct `name`
+    fn struct_layout(&mut self, name: &'a str) -> Result<(usize, usize)> {
+        if let Some(layout) = self.layouts.get(name) {
+            return Ok((layout.size(), layout.min_alignment));
+        }
+        let Some(s) = self.structs.get(name).copied() else {
+            bail!("struct {name} isn't in the mappings");
+        };
+        if !self.visiting.insert(name) {
+            bail!("struct {name} contains itself");
+        }
+
+        let (mut cursor, mut min_alignment) = match s.super_struct.as_deref() {
+            Some(super_struct) if self.structs.contains_key(super_struct) => {
+                self.struct_layout(super_struct)?;
+                let layout = &self.layouts[super_struct];
+                (layout.properties_size, layout.min_alignment)
+            }
+            _ if self.classes.contains(name) => (UOBJECT_SIZE, 8),
+            _ => (0, 1),
+        };
+        let mut properties = vec![];
+        for prop in &s.properties {
+            let (r#type, size, alignment) = self
+                .property_type(&prop.name, &prop.inner)
+                .with_context(|| format!("in {name}::{}", prop.name))?;
+            let offset = align(cursor, alignment);
+            cursor = offset + size * prop.array_dim as usize;
+            min_alignment = min_alignment.max(alignment);
+            properties.push(Property {
+                name: prop.name.clone(),
+                offset,
+                array_dim: prop.array_dim as usize,
+                size,
+                r#type,
+                flags: EPropertyFlags::empty(),
+            });
+        }
+
+        self.visiting.remove(name);
+        let layout = Layout {
+            properties,
+            properties_size: cursor,
+            min_alignment,
+        };
+        let result = (layout.size(), layout.min_alignment);
+        self.layouts.insert(name, layout);
+        Ok(result)
+    }
+
+    /// Inner property of a container, named after the property containing it
+    fn inner(&mut self, name: &str, inner: &'a PropertyInner) -> Result<(Box<Property>, usize)> {
+        let (r#type, size, alignment) = self.property_type(name, inner)?;
+        let prop = Property {
+            name: name.to_string(),
+            offset: 0,
+            array_dim: 1,
+            size,
+            r#type,
+            flags: EPropertyFlags::empty(),
+        };
+        Ok((prop.into(), alignment))
+    }
+
+    /// Type, size and alignment of a property
+    fn property_type(
+        &mut self,
+        name: &str,
+        inner: &'a PropertyInner,
+    ) -> Result<(PropertyType, usize, usize)> {
+        use PropertyInner as PI;
+        use PropertyType as PT;
+        let object = || OBJECT.to_string();
+        Ok(match inner {
+            PI::Struct { name } => {
+                let (size, alignment) = self.struct_layout(name)?;
+                let r#struct = self.path(name);
+                (PT::Struct { r#struct }, size, alignment)
+            }
+            PI::Str => (PT::Str, 0x10, 8),
+            PI::Name => (PT::Name, 8, 4),
+            PI::Text => (PT::Text, 0x18, 8),
+            PI::MulticastDelegate => (
+                PT::MulticastInlineDelegate {
+                    signature_function: None,
+                },
+                0x10,
+                8,
+            ),
+            PI::Delegate => (
+                PT::Delegate {
+                    signature_function: None,
+                },
+                0x10,
+                4,
+            ),
+            PI::Bool => (
+                PT::Bool {
+                    field_size: 1,
+                    byte_offset: 0,
+                    byte_mask: 0xff,
+                    field_mask: 0xff,
+                },
+                1,
+                1,
+            ),
+            PI::Array { inner } => {
+                let (inner, _) = self.inner(name, inner)?;
+                (PT::Array { inner }, 0x10, 8)
+            }
+            // usmap writes byte properties with an enum as enum properties of a byte
+            PI::Enum { inner, name: e } if **inner == PI::Byte => {
+                let r#enum = (e != "None").then(|| self.path(e));
+                (PT::Byte { r#enum }, 1, 1)
+            }
+            PI::Enum { inner, name: e } => {
+                let (container, alignment) = self.inner(name, inner)?;
+                let size = container.size;
+                let r#enum = (e != "None").then(|| self.path(e));
+                (PT::Enum { container, r#enum }, size, alignment)
+            }
+            PI::Map { key, value } => {
+                let (key_prop, _) = self.inner(name, key)?;
+                let (value_prop, _) = self.inner(name, value)?;
+                (
+                    PT::Map {
+                        key_prop,
+                        value_prop,
+                    },
+                    0x50,
+                    8,
+                )
+            }
+            PI::Set { key } => {
+                let (key_prop, _) = self.inner(name, key)?;
+                (PT::Set { key_prop }, 0x50, 8)
+            }
+            PI::Float => (PT::Float, 4, 4),
+            PI::Double => (PT::Double, 8, 8),
+            PI::Byte => (PT::Byte { r#enum: None }, 1, 1),
+            PI::UInt16 => (PT::UInt16, 2, 2),
+            PI::UInt32 => (PT::UInt32, 4, 4),
+            PI::UInt64 => (PT::UInt64, 8, 8),
+            PI::Int8 => (PT::Int8, 1, 1),
+            PI::Int16 => (PT::Int16, 2, 2),
+            PI::Int => (PT::Int, 4, 4),
+            PI::Int64 => (PT::Int64, 8, 8),
+            PI::Object => (
+                PT::Object {
+                    property_class: object(),
+                },
+                8,
+                8,
+            ),
+            PI::WeakObject => (
+                PT::WeakObject {
+                    property_class: object(),
+                },
+                8,
+                4,
+            ),
+            PI::SoftObject | PI::AssetObject => (
+                PT::SoftObject {
+                    property_class: object(),
+                },
+                0x28,
+                8,
+            ),
+            PI::LazyObject => (
+                PT::LazyObject {
+                    property_class: object(),
+                },
+                0x1c,
+                4,
+            ),
+            PI::Interface => (
+                PT::Interface {
+                    interface_class: INTERFACE.to_string(),
+                },
+                0x10,
+                8,
+            ),
+            PI::FieldPath => (PT::FieldPath, 0x20, 8),
+            PI::Optional { inner } => {
+                let (inner, alignment) = self.inner(name, inner)?;
+                // value followed by the is-set flag
+                let size = align(inner.size + 1, alignment);
+                (PT::Optional { inner }, size, alignment)
+            }
+            PI::Utf8Str => (PT::Utf8Str, 0x10, 8),
+            PI::AnsiStr => (PT::AnsiStr, 0x10, 8),
+            PI::Unknown => bail!("unknown property type"),
+        })
+    }
+}
+
+fn object(class: &str, outer: Option<String>, internal_index: usize) -> Object {
+    Object {
+        address: Address(0),
+        vtable: Address(0),
+        object_flags: EObjectFlags::RF_Public,
+        outer,
+        class: class.to_string(),
+        children: BTreeSet::new(),
+        property_values: OrderMap::new().into(),
+        internal_index: internal_index as i32,
+        internal_flags: None,
+        cluster_root_index: None,
+        serial_number: None,
+    }
+}
+
+/// Package path `i` of the mappings, if they have package paths
+fn package(packages: Option<&[String]>, i: usize) -> &str {
+    packages
+        .and_then(|p| p.get(i))
+        .map_or(UNKNOWN_PACKAGE, |p| p.as_str())
+}
+
+/// Dump of the types in `usmap`
+pub fn from_usmap(usmap: &Usmap) -> Result<Jmap> {
+    let ppth = usmap.ppth.as_ref();
+    let eatr = usmap.eatr.as_ref();
+    let struct_package = |i| package(ppth.map(|p| p.structs.as_slice()), i);
+    let enum_package = |i| package(ppth.map(|p| p.enums.as_slice()), i);
+
+    let mut paths = HashMap::new();
+    for (i, s) in usmap.structs.iter().enumerate() {
+        paths
+            .entry(s.name.as_str())
+            .or_insert_with(|| format!("{}.{}", struct_package(i), s.name));
+    }
+    for (i, e) in usmap.enums.iter().enumerate() {
+        paths
+            .entry(e.name.as_str())
+            .or_insert_with(|| format!("{}.{}", enum_package(i), e.name));
+    }
+
+    let structs: HashMap<_, _> = usmap.structs.iter().map(|s| (s.name.as_str(), s)).collect();
+    // without flags anything deriving from UObject is a class
+    let is_class = |i: usize, s: &usmap::Struct| {
+        if let Some(flags) = eatr.and_then(|e| e.struct_flags.get(i))
+            && flags.type_ != FlagsType::Unknown
+        {
+            return flags.type_ == FlagsType::Class;
+        }
+        let mut next = Some(s);
+        while let Some(s) = next {
+            if s.name == "Object" && s.super_struct.is_none() {
+                return true;
+            }
+            next = s
+                .super_struct
+                .as_deref()
+                .and_then(|s| structs.get(s).copied());
+        }
+        false
+    };
+    let classes: HashSet<_> = usmap
+        .structs
+        .iter()
+        .enumerate()
+        .filter(|(i, s)| is_class(*i, s))
+        .map(|(_, s)| s.name.as_str())
+        .collect();
+
+    let mut converter = Converter {
+        structs,
+        classes,
+        paths,
+        layouts: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+
+    let mut objects = BTreeMap::new();
+    for (i, s) in usmap.structs.iter().enumerate() {
+        converter.struct_layout(&s.name)?;
+        let layout = &converter.layouts[s.name.as_str()];
+        let mut properties = layout.properties.clone();
+        let flags = eatr.and_then(|e| e.struct_flags.get(i));
+        if let Some(flags) = flags {
+            for (prop, flags) in properties.iter_mut().zip(&flags.prop_flags) {
+                prop.flags = EPropertyFlags::from_bits_retain(*flags);
+            }
+        }
+
+        let path = format!("{}.{}", struct_package(i), s.name);
+        let is_class = converter.classes.contains(s.name.as_str());
+        let class = if is_class {
+            "/Script/CoreUObject.Class"
+        } else {
+            "/Script/CoreUObject.ScriptStruct"
+        };
+        let r#struct = Struct {
+            object: object(class, Some(struct_package(i).to_string()), objects.len()),
+            super_struct: s.super_struct.as_deref().map(|s| converter.path(s)),
+            properties,
+            properties_size: layout.properties_size,
+            min_alignment: layout.min_alignment,
+            script: None,
+        };
+        let flags = flags.map_or(0, |f| f.value);
+        let obj = if is_class {
+            ObjectType::Class(Class {
+                r#struct,
+                class_flags: EClassFlags::from_bits_retain(flags),
+                class_cast_flags: EClassCastFlags::empty(),
+                class_default_object: None,
+                class_within: None,
+                config_name: None,
+                instance_vtable: None,
+                virtual_functions: vec![],
+                class_constructor: None,
+                vtable_helper_ctor_caller: None,
+                static_class: None,
+                sparse_class_data_struct: None,
+                sparse_class_data: None,
+                interfaces: vec![],
+            })
+        } else {
+            ObjectType::ScriptStruct(ScriptStruct {
+                r#struct,
+                struct_flags: EStructFlags::from_bits_retain(flags),
+            })
+        };
+        objects.entry(path).or_insert(obj);
+    }
+
+    for (i, e) in usmap.enums.iter().enumerate() {
+        // full names with their values if the mappings have them, otherwise assume enum classes
+        let names: Vec<(String, i64)> =
+            match usmap.envp.as_ref().and_then(|envp| envp.value_pairs.get(i)) {
+                Some(pairs) => pairs
+                    .iter()
+                    .map(|(name, value)| (name.clone(), *value as i64))
+                    .collect(),
+                None => e
+                    .entries
+                    .iter()
+                    .map(|(value, name)| (format!("{}::{name}", e.name), *value))
+                    .collect(),
+            };
+        let enum_flags = eatr
+            .and_then(|eatr| eatr.enum_flags.get(i))
+            .map(|f| EEnumFlags::from_bits_retain(*f as u8));
+        let path = format!("{}.{}", enum_package(i), e.name);
+        let obj = ObjectType::Enum(Enum {
+            object: object(
+                "/Script/CoreUObject.Enum",
+                Some(enum_package(i).to_string()),
+                objects.len(),
+            ),
+            cpp_type: e.name.clone(),
+            enum_flags,
+            cpp_form: ECppForm::EnumClass,
+            display_names: names
+                .iter()
+                .map(|(name, _)| ECppForm::EnumClass.short_name(name).to_string())
+                .collect(),
+            names,
+            display_name_fn: None,
+            enum_package: None,
+        });
+        objects.entry(path).or_insert(obj);
+    }
+
+    let mut packages = BTreeMap::<String, BTreeSet<String>>::new();
+    for (path, obj) in &objects {
+        let outer = obj.get_object().outer.clone().unwrap();
+        packages.entry(outer).or_default().insert(path.clone());
+    }
+    for (path, children) in packages {
+        let mut package_object = object("/Script/CoreUObject.Package", None, objects.len());
+        package_object.children = children;
+        let package = ObjectType::Package(Package {
+            object: package_object,
+            package_flags: EPackageFlags::empty(),
+            file_name: None,
+            loaded_path: None,
+            guid: None,
+        });
+        objects.insert(path, package);
+    }
+
+    Ok(Jmap {
+        metadata: None,
+        image_base_address: Address(0),
+        objects,
+        vtables: BTreeMap::new(),
+        script_references: None,
+        image: None,
+        resolved_addresses: BTreeMap::new(),
+        errors: vec![],
+        natives: vec![],
+        signatures: vec![],
+    })
+}
+
+/// Mappings of the types in `reflection_data`
+pub fn into_usmap(reflection_data: &Jmap) -> Usmap {
+    let mut enums = vec![];
+    let mut structs = vec![];
+
+    let mut ppth = usmap::ExtPpth {
+        version: 0,
+        enums: vec![],
+        structs: vec![],
+    };
+    let mut eatr = usmap::ExtEatr {
+        version: 0,
+        enum_flags: vec![],
+        struct_flags: vec![],
+    };
+    let mut envp = usmap::ExtEnvp {
+        version: 0,
+        value_pairs: vec![],
+    };
+
+    for (path, obj) in &reflection_data.objects {
+        let struct_ = match &obj {
+            ObjectType::ScriptStruct(obj) => Some(&obj.r#struct),
+            ObjectType::Class(obj) => Some(&obj.r#struct),
+            _ => None,
+        };
+        if let Some(s) = struct_ {
+            let mut properties = vec![];
+            let mut index = 0;
+            for prop in &s.properties {
+                properties.push(into_usmap_prop(index, prop));
+                index += prop.array_dim;
+            }
+            structs.push(usmap::Struct {
+                name: short_name(path).to_string(),
+                super_struct: s.super_struct.as_ref().map(|s| short_name(s).to_string()),
+                properties,
+            });
+            ppth.structs.push(package_name(path).to_string());
+            let (type_, value) = match &obj {
+                ObjectType::ScriptStruct(obj) => {
+                    (usmap::FlagsType::Struct, obj.struct_flags.bits())
+                }
+                ObjectType::Class(obj) => (usmap::FlagsType::Class, obj.class_flags.bits()),
+                _ => unreachable!(),
+            };
+            eatr.struct_flags.push(usmap::StructFlags {
+                type_,
+                value,
+                prop_flags: s.properties.iter().map(|p| p.flags.bits()).collect(),
+            });
+        } else if let Some(e) = obj.get_enum() {
+            let prefix = format!("{}::", short_name(path));
+            let mut entries = BTreeMap::new();
+            for (name, value) in &e.names {
+                let variant_name = if let Some(variant_name) = name.strip_prefix(&prefix) {
+                    variant_name
+                } else {
+                    assert!(!name.contains("::"), "enum prefix was not stripped");
+                    name
+                };
+                entries.insert(*value, variant_name.to_string());
+            }
+            // unstripped entry names with their full 64 bit values
+            envp.value_pairs.push(
+                e.names
+                    .iter()
+                    .map(|(name, value)| (name.clone(), *value as u64))
+                    .collect(),
+            );
+            enums.push(usmap::Enum {
+                name: short_name(path).to_string(),
+                entries,
+            });
+            ppth.enums.push(package_name(path).to_string());
+            eatr.enum_flags
+                .push(e.enum_flags.map_or(0, |f| f.bits() as u32));
+        }
+    }
+
+    Usmap {
+        enums,
+        structs,
+        cext: Some(usmap::ExtCext {
+            version: 0,
+            num_ext: 3,
+        }),
+        eatr: Some(eatr),
+        envp: Some(envp),
+        ppth: Some(ppth),
+    }
+}
+
+fn into_usmap_prop(index: usize, prop: &Property) -> usmap::Property {
+    usmap::Property {
+        name: prop.name.clone(),
+        array_dim: prop.array_dim.try_into().unwrap(),
+        index: index.try_into().unwrap(),
+        inner: into_usmap_prop_inner(&prop.r#type),
+    }
+}
+
+fn into_usmap_prop_inner(prop: &PropertyType) -> usmap::PropertyInner {
+    use PropertyType as PT;
+    use usmap::PropertyInner as PI;
+    match &prop {
+        PT::Struct { r#struct } => PI::Struct {
+            name: short_name(r#struct).to_string(),
+        },
+        PT::Str => PI::Str,
+        PT::Name => PI::Name,
+        PT::Text => PI::Text,
+        // TODO distinguish between sparse/inline?
+        PT::MulticastInlineDelegate { .. } => PI::MulticastDelegate,
+        PT::MulticastSparseDelegate { .. } => PI::MulticastDelegate,
+        PT::MulticastDelegate { .. } => PI::MulticastDelegate,
+        PT::Delegate { .. } => PI::Delegate,
+        PT::Bool {
+            field_size: _,
+            byte_offset: _,
+            byte_mask: _,
+            field_mask: _,
+        } => PI::Bool,
+        PT::Array { inner } => PI::Array {
+            inner: into_usmap_prop_inner(&inner.r#type).into(),
+        },
+        PT::Enum { container, r#enum } => PI::Enum {
+            inner: into_usmap_prop_inner(&container.r#type).into(),
+            name: r#enum
+                .as_ref()
+                .map(|e| short_name(e))
+                .unwrap_or("None")
+                .to_string(),
+        },
+        PT::Map {
+            key_prop,
+            value_prop,
+        } => PI::Map {
+            key: into_usmap_prop_inner(&key_prop.r#type).into(),
+            value: into_usmap_prop_inner(&value_prop.r#type).into(),
+        },
+        PT::Set { key_prop } => PI::Set {
+            key: into_usmap_prop_inner(&key_prop.r#type).into(),
+        },
+        PT::Float => PI::Float,
+        PT::Double => PI::Double,
+        PT::Byte { r#enum } => {
+            // usmap special cases ByteProperty to transform into EnumProperty if enum member is populated
+            if let Some(e) = r#enum {
+                PI::Enum {
+                    inner: PI::Byte.into(),
+                    name: short_name(e).to_string(),
+                }
+            } else {
+                PI::Byte
+            }
+        }
+        PT::UInt16 => PI::UInt16,
+        PT::UInt32 => PI::UInt32,
+        PT::UInt64 => PI::UInt64,
+        PT::Int8 => PI::Int8,
+        PT::Int16 => PI::Int16,
+        PT::Int => PI::Int,
+        PT::Int64 => PI::Int64,
+        PT::Object { property_class: _ } => PI::Object,
+        PT::Class { .. } => PI::Object,
+        PT::WeakObject { property_class: _ } => PI::WeakObject,
+        PT::SoftObject { property_class: _ } => PI::SoftObject,
+        PT::SoftClass { .. } => PI::SoftObject,
+        PT::LazyObject { property_class: _ } => PI::LazyObject,
+        PT::Interface { interface_class: _ } => PI::Interface,
+        PT::FieldPath => PI::FieldPath,
+        PT::Optional { inner } => PI::Optional {
+            inner: into_usmap_prop_inner(&inner.r#type).into(),
+        },
+        PT::Utf8Str => PI::Utf8Str,
+        PT::AnsiStr => PI::AnsiStr,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{dump, property};
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let jmap = dump(json!({
+            "/Script/CoreUObject.Object": {
+                "type": "Class",
+                "properties_size": UOBJECT_SIZE,
+                "min_alignment": 8,
+            },
+            "/Script/Game.Stats": {
+                "properties": [
+                    property("Health", 0, 4, json!({ "type": "FloatProperty" })),
+                    property("Level", 4, 4, json!({ "type": "IntProperty" })),
+                ],
+                "properties_size": 8,
+                "min_alignment": 4,
+            },
+            "/Script/Game.EMode": {
+                "type": "Enum",
+                "names": [["EMode::Normal", 0], ["EMode::Hard", 1], ["EMode::EMode_MAX", 2]],
+            },
+            "/Script/Game.Player": {
+                "type": "Class",
+                "super_struct": OBJECT,
+                "properties": [
+                    property("Mode", 0x28, 1, json!({
+                        "type": "ByteProperty",
+                        "enum": "/Script/Game.EMode",
+                    })),
+                    property("bAlive", 0x29, 1, json!({
+                        "type": "BoolProperty",
+                        "field_size": 1,
+                        "byte_offset": 0,
+                        "byte_mask": 0xff,
+                        "field_mask": 0xff,
+                        "flags": "CPF_Edit | CPF_BlueprintVisible",
+                    })),
+                    property("Stats", 0x2c, 8, json!({
+                        "type": "StructProperty",
+                        "struct": "/Script/Game.Stats",
+                    })),
+                    property("Name", 0x38, 0x10, json!({ "type": "StrProperty" })),
+                    property("Items", 0x48, 0x10, json!({
+                        "type": "ArrayProperty",
+                        "inner": property("Items", 0, 8, json!({
+                            "type": "ObjectProperty",
+                            "property_class": OBJECT,
+                        })),
+                    })),
+                    property("Kind", 0x58, 4, json!({
+                        "type": "EnumProperty",
+                        "container": property("Kind", 0, 4, json!({ "type": "IntProperty" })),
+                        "enum": "/Script/Game.EMode",
+                    })),
+                    json!({
+                        "name": "Scores",
+                        "offset": 0x5c,
+                        "array_dim": 3,
+                        "size": 4,
+                        "flags": "",
+                        "type": "IntProperty",
+                    }),
+                ],
+                "properties_size": 0x68,
+                "min_alignment": 8,
+            },
+        }));
+
+        let mut written = vec![];
+        into_usmap(&jmap).write(&mut written)?;
+        let read = from_usmap(&Usmap::read(&mut written.as_slice())?)?;
+
+        for (path, obj) in &jmap.objects {
+            let other = read
+                .objects
+                .get(path)
+                .with_context(|| format!("{path} is missing"))?;
+            let (value, other) = match obj {
+                ObjectType::Enum(e) => (json!(e.names), json!(other.get_enum().unwrap().names)),
+                _ => {
+                    let (s, other) = (obj.get_struct().unwrap(), other.get_struct().unwrap());
+                    assert_eq!(s.super_struct, other.super_struct, "{path}");
+                    assert_eq!(s.properties_size, other.properties_size, "{path}");
+                    (json!(s.properties), json!(other.properties))
+                }
+            };
+            assert_eq!(value, other, "{path}");
+            let class = &read.objects[path].get_object().class;
+            assert_eq!(&obj.get_object().class, class, "{path}");
+        }
+        Ok(())
+    }
+}
@@ -1,15 +1,28 @@
-use crate::{containers::PtrFNamePool, structs::StructInfo};
-use anyhow::{Context as _, Result};
+use crate::{
+    containers::{NameCache, Names},
+    decode::Decoders,
+    names::NameResolver,
+    objects::PathCache,
+    structs::StructInfo,
+};
+#[cfg(not(target_os = "linux"))]
+use anyhow::Context as _;
+use anyhow::Result;
 use jmap::{
-    EClassCastFlags, EClassFlags, ECppForm, EEnumFlags, EFunctionFlags, EObjectFlags,
-    EPropertyFlags, EStructFlags,
+    EClassCastFlags, EClassFlags, ECppForm, EEnumFlags, EFunctionFlags, EInternalObjectFlags,
+    EObjectFlags, EPackageFlags, EPropertyFlags, EStructFlags,
 };
+#[cfg(not(target_os = "linux"))]
 use read_process_memory::{CopyAddress as _, ProcessHandle};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     marker::PhantomData,
     num::NonZero,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
 };
 
 pub trait VirtSize<C: Ctx> {
@@ -69,6 +82,14 @@ impl<T: VirtSize<C>, C: Clone + Ctx> Ptr<T, C> {
         self.byte_offset(n * T::size(self.ctx()))
     }
 }
+impl<T, C: Ctx> Ptr<T, C> {
+    /// Fetch the `struct_name` of the struct info at this address in one read before its members
+    /// are read one by one
+    pub fn prefetch(&self, struct_name: &str) {
+        let size = self.ctx.get_struct(struct_name).size as usize;
+        self.ctx.prefetch(self.address(), size);
+    }
+}
 impl<T: Pod, C: Mem> Ptr<T, C> {
     pub fn read(&self) -> Result<T> {
         self.ctx.read(self.address.into())
@@ -79,7 +100,7 @@ impl<T: Pod, C: Mem> Ptr<T, C> {
 }
 impl<T, C: Mem> Ptr<Option<Ptr<T, C>>, C> {
     pub fn read(&self) -> Result<Option<Ptr<T, C>>> {
-        let addr = self.ctx.read::<u64>(self.address.into())?;
+        let addr = self.ctx.read_ptr(self.address.into())?;
         Ok(if addr != 0 {
             Some(self.map(|_| addr).cast())
         } else {
@@ -89,7 +110,7 @@ impl<T, C: Mem> Ptr<Option<Ptr<T, C>>, C> {
 }
 impl<T, C: Mem> Ptr<Ptr<T, C>, C> {
     pub fn read(&self) -> Result<Ptr<T, C>> {
-        let addr = self.ctx.read::<u64>(self.address.into())?;
+        let addr = self.ctx.read_ptr(self.address.into())?;
         Ok(self.map(|_| addr).cast())
     }
 }
@@ -98,7 +119,74 @@ pub trait TryFromBytes: Sized {
     fn try_from_bytes(bytes: &[u8]) -> Result<Self>;
 }
 
-pub trait Pod: TryFromBytes {}
+/// Plain values read straight from target memory. [`TryFromBytes`] sees them in host (little
+/// endian, 64-bit) form, [`Mem::read`] converts from the [`Target`] first.
+pub trait Pod: TryFromBytes {
+    /// Target pointer sized integer (`usize`) whose width comes from [`Target::pointer_size`]
+    const POINTER_SIZED: bool = false;
+}
+
+/// Byte order of the target
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/// ABI details of the machine the memory was read from that the container and object readers
+/// depend on, loaded from a target profile for console builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Target {
+    /// Size of pointers and `usize` in bytes (4 or 8)
+    #[serde(deserialize_with = "pointer_size")]
+    pub pointer_size: u8,
+    #[serde(default)]
+    pub endian: Endian,
+}
+
+/// Reject pointer sizes the readers can't decode when the profile is loaded rather than reading
+/// garbage (or out of bounds) later
+fn pointer_size<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    match u8::deserialize(deserializer)? {
+        size @ (4 | 8) => Ok(size),
+        size => Err(serde::de::Error::custom(format!(
+            "unsupported pointer size {size}, expected 4 or 8"
+        ))),
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self {
+            pointer_size: 8,
+            endian: Endian::Little,
+        }
+    }
+}
+
+impl Target {
+    /// Number of bytes a `T` occupies on the target
+    pub fn size_of<T: Pod>(&self) -> usize {
+        if T::POINTER_SIZED {
+            self.pointer_size as usize
+        } else {
+            std::mem::size_of::<T>()
+        }
+    }
+
+    /// Decode a `T` from its [`Target::size_of`] bytes of target memory
+    fn decode<T: Pod>(&self, bytes: &[u8]) -> Result<T> {
+        let mut host = [0; 8];
+        host[..bytes.len()].copy_from_slice(bytes);
+        if self.endian == Endian::Big {
+            host[..bytes.len()].reverse();
+        }
+        // pointer sized values are zero extended to the host width
+        T::try_from_bytes(&host[..std::mem::size_of::<T>()])
+    }
+}
 
 macro_rules! impl_try_from_bytes_pod {
     ($($t:ty),* $(,)?) => {
@@ -136,6 +224,8 @@ impl_try_from_bytes_bitflags!(
     (EStructFlags, u32),
     (EPropertyFlags, u64),
     (EEnumFlags, u8),
+    (EPackageFlags, u32),
+    (EInternalObjectFlags, u32),
 );
 
 impl TryFromBytes for ECppForm {
@@ -154,7 +244,9 @@ impl Pod for i32 {}
 impl Pod for u32 {}
 impl Pod for i64 {}
 impl Pod for u64 {}
-impl Pod for usize {}
+impl Pod for usize {
+    const POINTER_SIZED: bool = true;
+}
 impl Pod for f32 {}
 impl Pod for f64 {}
 impl Pod for EObjectFlags {}
@@ -164,122 +256,347 @@ impl Pod for EFunctionFlags {}
 impl Pod for EStructFlags {}
 impl Pod for EPropertyFlags {}
 impl Pod for EEnumFlags {}
+impl Pod for EPackageFlags {}
+impl Pod for EInternalObjectFlags {}
 impl Pod for ECppForm {}
 
 impl<T: Pod, C: Ctx> VirtSize<C> for T {
-    fn size(_ctx: &C) -> usize {
-        std::mem::size_of::<Self>()
+    fn size(ctx: &C) -> usize {
+        ctx.target().size_of::<T>()
     }
 }
 
 impl<T, C: Ctx> VirtSize<C> for Ptr<T, C> {
-    fn size(_ctx: &C) -> usize {
-        8
+    fn size(ctx: &C) -> usize {
+        ctx.target().pointer_size as usize
     }
 }
 impl<T, C: Ctx> VirtSize<C> for Option<Ptr<T, C>> {
-    fn size(_ctx: &C) -> usize {
-        8
+    fn size(ctx: &C) -> usize {
+        ctx.target().pointer_size as usize
     }
 }
 
-pub trait Mem: Clone {
+/// Memory of the target, shared by the threads of the object loop
+pub trait Mem: Clone + Send + Sync {
     fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()>;
+
+    /// Pointer width and byte order values are read with
+    fn target(&self) -> Target {
+        Target::default()
+    }
+
     fn read<T: Pod>(&self, address: u64) -> Result<T> {
-        let mut buf = vec![0u8; std::mem::size_of::<T>()];
-        self.read_buf(address, &mut buf)?;
-        T::try_from_bytes(&buf)
+        let target = self.target();
+        let mut buf = [0u8; 8];
+        let buf = &mut buf[..target.size_of::<T>()];
+        self.read_buf(address, buf)?;
+        target.decode(buf)
     }
 
     fn read_vec<T: Pod>(&self, address: u64, count: usize) -> Result<Vec<T>> {
-        let size = std::mem::size_of::<T>();
+        let target = self.target();
+        let size = target.size_of::<T>();
         let mut buf = vec![0u8; count * size];
         self.read_buf(address, &mut buf)?;
-        let mut result = Vec::with_capacity(count);
-        for i in 0..count {
-            let start = i * size;
-            let end = start + size;
-            result.push(T::try_from_bytes(&buf[start..end])?);
+        buf.chunks_exact(size).map(|b| target.decode(b)).collect()
+    }
+
+    fn read_ptr(&self, address: u64) -> Result<u64> {
+        Ok(self.read::<usize>(address)? as u64)
+    }
+
+    /// Hint that `len` bytes at `address` are about to be read piecemeal, so caching readers can
+    /// fetch them in one read up front
+    fn prefetch(&self, _address: u64, _len: usize) {}
+
+    /// Counters of the page cache beneath, if there is one
+    fn io_stats(&self) -> Option<&IoStats> {
+        None
+    }
+}
+
+/// What reading the target cost, kept by [`MemCache`]
+#[derive(Debug, Default)]
+pub struct IoStats {
+    /// Reads of the dumper and the bytes they asked for
+    pub reads: AtomicU64,
+    pub bytes_requested: AtomicU64,
+    /// Pages of those reads and prefetches found in the cache or read from the backend
+    pub page_hits: AtomicU64,
+    pub page_misses: AtomicU64,
+    /// Reads the backend made (about one syscall or request each) and the bytes they read
+    pub backend_reads: AtomicU64,
+    pub backend_bytes: AtomicU64,
+}
+impl std::fmt::Display for IoStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let (hits, misses) = (load(&self.page_hits), load(&self.page_misses));
+        write!(
+            f,
+            "{} reads of {} requested, {hits} of {} pages from the cache ({:.1}% hit rate), \
+             {} backend reads of {}",
+            load(&self.reads),
+            indicatif::HumanBytes(load(&self.bytes_requested)),
+            hits + misses,
+            hits as f64 * 100.0 / (hits + misses).max(1) as f64,
+            load(&self.backend_reads),
+            indicatif::HumanBytes(load(&self.backend_bytes)),
+        )
+    }
+}
+
+pub(crate) const PAGE_SIZE: usize = 0x1000;
+/// Locks the page cache is split across, so readers of different pages don't contend
+const SHARDS: usize = 64;
+
+/// Cached page, empty while it hasn't been read yet. Its lock is held while the page is read, so
+/// readers of the same page wait for that read instead of reading it again.
+type Slot = Arc<Mutex<Option<Arc<[u8]>>>>;
+
+#[derive(Default)]
+struct Shard {
+    /// Pages along with whether they were used since the clock last passed them
+    slots: HashMap<u64, (Slot, bool)>,
+    /// Pages in the order the clock passes them when evicting. Pages used since the last pass get
+    /// another round, the first unused one is dropped.
+    clock: VecDeque<u64>,
+}
+
+impl Shard {
+    fn evict(&mut self) {
+        while let Some(page) = self.clock.pop_front() {
+            let used = &mut self.slots.get_mut(&page).unwrap().1;
+            if std::mem::take(used) {
+                self.clock.push_back(page);
+            } else {
+                self.slots.remove(&page);
+                return;
+            }
         }
-        Ok(result)
     }
 }
-const PAGE_SIZE: usize = 0x1000;
+
+/// Page cache in front of a slow reader. With a size limit the least recently used pages are
+/// dropped (approximately, by the CLOCK algorithm) once it is reached.
 #[derive(Clone)]
 pub struct MemCache<M> {
     inner: M,
-    pages: Arc<Mutex<HashMap<u64, Vec<u8>>>>,
+    shards: Arc<[Mutex<Shard>]>,
+    /// Pages each shard holds at most
+    capacity: Option<usize>,
+    stats: Arc<IoStats>,
 }
 impl<M: Mem> MemCache<M> {
-    pub fn wrap(inner: M) -> Self {
+    /// Cache reads of `inner`, keeping at most `size` bytes if given
+    pub fn wrap(inner: M, size: Option<usize>) -> Self {
         Self {
             inner,
-            pages: Default::default(),
+            shards: (0..SHARDS).map(|_| Default::default()).collect(),
+            capacity: size.map(|size| (size / PAGE_SIZE / SHARDS).max(1)),
+            stats: Default::default(),
         }
     }
-}
-impl<M: Mem> Mem for MemCache<M> {
-    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
-        let mut remaining = buf.len();
-        let mut cur = 0;
 
-        let mut lock = self.pages.lock().unwrap();
+    fn slot(&self, page: u64) -> Slot {
+        let shard = &self.shards[(page / PAGE_SIZE as u64) as usize % SHARDS];
+        let mut shard = shard.lock().unwrap();
+        if let Some((slot, used)) = shard.slots.get_mut(&page) {
+            *used = true;
+            return slot.clone();
+        }
+        if let Some(capacity) = self.capacity {
+            while shard.slots.len() >= capacity {
+                shard.evict();
+            }
+        }
+        let slot = Slot::default();
+        shard.slots.insert(page, (slot.clone(), false));
+        shard.clock.push_back(page);
+        slot
+    }
 
-        while remaining > 0 {
-            let page_start = (address + cur as u64) & !(PAGE_SIZE as u64 - 1);
-            let page_offset = address as usize + cur - page_start as usize;
-            let to_copy = remaining.min(PAGE_SIZE - page_offset);
+    /// Pages of `address..address + len`, reading those that aren't cached yet from the backend
+    /// with one read per run of adjacent missing pages
+    fn pages(&self, address: u64, len: usize) -> Result<Vec<Arc<[u8]>>> {
+        if len == 0 {
+            return Ok(vec![]);
+        }
+        let first = address & !(PAGE_SIZE as u64 - 1);
+        let end = address + len as u64;
+        let slots: Vec<_> = (first..end)
+            .step_by(PAGE_SIZE)
+            .map(|page| self.slot(page))
+            .collect();
+        // locked in address order so overlapping reads can't deadlock
+        let mut pages: Vec<_> = slots.iter().map(|slot| slot.lock().unwrap()).collect();
+        let hits = pages.iter().filter(|page| page.is_some()).count();
+        let stats = &self.stats;
+        stats.page_hits.fetch_add(hits as u64, Ordering::Relaxed);
+        stats
+            .page_misses
+            .fetch_add((pages.len() - hits) as u64, Ordering::Relaxed);
 
-            let buf_region = &mut buf[cur..cur + to_copy];
-            let page_range = page_offset..page_offset + to_copy;
-            if let Some(page) = lock.get(&page_start) {
-                buf_region.copy_from_slice(&page[page_range]);
-            } else {
-                let mut page = vec![0; PAGE_SIZE];
-                self.inner.read_buf(page_start, &mut page)?;
-                buf_region.copy_from_slice(&page[page_range]);
-                lock.insert(page_start, page);
+        let mut i = 0;
+        while i < pages.len() {
+            if pages[i].is_some() {
+                i += 1;
+                continue;
             }
-
-            remaining -= to_copy;
+            let run_end = (i..pages.len())
+                .find(|j| pages[*j].is_some())
+                .unwrap_or(pages.len());
+            let mut data = vec![0; (run_end - i) * PAGE_SIZE];
+            self.inner
+                .read_buf(first + (i * PAGE_SIZE) as u64, &mut data)?;
+            stats.backend_reads.fetch_add(1, Ordering::Relaxed);
+            stats
+                .backend_bytes
+                .fetch_add(data.len() as u64, Ordering::Relaxed);
+            for (page, data) in pages[i..run_end]
+                .iter_mut()
+                .zip(data.chunks_exact(PAGE_SIZE))
+            {
+                **page = Some(data.into());
+            }
+            i = run_end;
+        }
+        Ok(pages
+            .into_iter()
+            .map(|page| page.clone().unwrap())
+            .collect())
+    }
+}
+impl<M: Mem> Mem for MemCache<M> {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        self.stats.reads.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .bytes_requested
+            .fetch_add(buf.len() as u64, Ordering::Relaxed);
+        let mut offset = address as usize % PAGE_SIZE;
+        let mut cur = 0;
+        for page in self.pages(address, buf.len())? {
+            let to_copy = (buf.len() - cur).min(PAGE_SIZE - offset);
+            buf[cur..cur + to_copy].copy_from_slice(&page[offset..offset + to_copy]);
             cur += to_copy;
+            offset = 0;
         }
+        Ok(())
+    }
+    fn prefetch(&self, address: u64, len: usize) {
+        // only a hint, whatever fails to read here fails again when it is actually read
+        let _ = self.pages(address, len);
+    }
+    fn io_stats(&self) -> Option<&IoStats> {
+        Some(&self.stats)
+    }
+}
 
+/// Counts the bytes read through it, for progress reporting
+#[derive(Clone)]
+pub struct ReadCounter<M> {
+    inner: M,
+    bytes: Arc<AtomicU64>,
+}
+impl<M: Mem> ReadCounter<M> {
+    pub fn wrap(inner: M) -> Self {
+        Self {
+            inner,
+            bytes: Default::default(),
+        }
+    }
+    pub fn bytes(&self) -> Arc<AtomicU64> {
+        self.bytes.clone()
+    }
+}
+impl<M: Mem> Mem for ReadCounter<M> {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        self.inner.read_buf(address, buf)?;
+        self.bytes.fetch_add(buf.len() as u64, Ordering::Relaxed);
         Ok(())
     }
+    fn target(&self) -> Target {
+        self.inner.target()
+    }
+    fn prefetch(&self, address: u64, len: usize) {
+        self.inner.prefetch(address, len);
+    }
+    fn io_stats(&self) -> Option<&IoStats> {
+        self.inner.io_stats()
+    }
 }
 
-impl Mem for ProcessHandle {
+/// [`ProcessHandle`] usable from the threads of the object loop
+#[cfg(not(target_os = "linux"))]
+#[derive(Clone)]
+pub struct SharedProcessHandle(pub ProcessHandle);
+
+// SAFETY: process handles are valid on every thread of the process that opened them and reading
+// through one doesn't touch any state of the handle
+#[cfg(not(target_os = "linux"))]
+unsafe impl Send for SharedProcessHandle {}
+#[cfg(not(target_os = "linux"))]
+unsafe impl Sync for SharedProcessHandle {}
+
+#[cfg(not(target_os = "linux"))]
+impl Mem for SharedProcessHandle {
     fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
-        self.copy_address(address as usize, buf)
+        self.0
+            .copy_address(address as usize, buf)
             .with_context(|| format!("reading {} bytes at 0x{:x}", buf.len(), address))
     }
 }
 
 pub trait Ctx: Mem {
-    fn fnamepool(&self) -> PtrFNamePool;
+    fn names(&self) -> Names;
+    fn name_cache(&self) -> &NameCache;
+    fn path_cache(&self) -> &PathCache;
     fn get_struct(&self, struct_name: &str) -> &StructInfo;
     fn struct_member(&self, struct_name: &str, member_name: &str) -> usize;
     fn ue_version(&self) -> (u16, u16);
     fn case_preserving(&self) -> bool;
+    fn decoders(&self) -> &Decoders;
+    fn name_resolver(&self) -> Option<&dyn NameResolver>;
 }
 
 #[derive(Clone)]
 pub struct CtxPtr<M: Mem> {
     pub mem: M,
-    pub fnamepool: PtrFNamePool,
+    pub names: Names,
+    pub name_cache: Arc<NameCache>,
+    pub path_cache: Arc<PathCache>,
+    pub guobjectarray: u64,
     pub structs: Arc<HashMap<String, StructInfo>>,
     pub version: (u16, u16),
     pub case_preserving: bool,
+    pub target: Target,
+    pub decoders: Decoders,
+    pub name_resolver: Option<Arc<dyn NameResolver>>,
 }
 impl<M: Mem> Mem for CtxPtr<M> {
     fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
         self.mem.read_buf(address, buf)
     }
+    fn target(&self) -> Target {
+        self.target
+    }
+    fn prefetch(&self, address: u64, len: usize) {
+        self.mem.prefetch(address, len);
+    }
+    fn io_stats(&self) -> Option<&IoStats> {
+        self.mem.io_stats()
+    }
 }
 impl<M: Mem> Ctx for CtxPtr<M> {
-    fn fnamepool(&self) -> PtrFNamePool {
-        self.fnamepool
+    fn names(&self) -> Names {
+        self.names
+    }
+    fn name_cache(&self) -> &NameCache {
+        &self.name_cache
+    }
+    fn path_cache(&self) -> &PathCache {
+        &self.path_cache
     }
     fn get_struct(&self, struct_name: &str) -> &StructInfo {
         let Some(s) = self.structs.get(struct_name) else {
@@ -304,4 +621,32 @@ impl<M: Mem> Ctx for CtxPtr<M> {
     fn case_preserving(&self) -> bool {
         self.case_preserving
     }
+    fn decoders(&self) -> &Decoders {
+        &self.decoders
+    }
+    fn name_resolver(&self) -> Option<&dyn NameResolver> {
+        self.name_resolver.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_target_pointer_size() {
+        let target: Target =
+            serde_json::from_str(r#"{"pointer_size": 4, "endian": "big"}"#).unwrap();
+        assert_eq!(target.size_of::<usize>(), 4);
+        assert_eq!(target.endian, Endian::Big);
+
+        for size in [0, 2, 6, 16] {
+            let err = serde_json::from_str::<Target>(&format!(r#"{{"pointer_size": {size}}}"#))
+                .unwrap_err();
+            assert!(
+                err.to_string().contains("unsupported pointer size"),
+                "{err}"
+            );
+        }
+    }
 }
@@ -0,0 +1,286 @@
+//! Combining dumps of the same game taken in different states (e.g. main menu and in-game, or
+//! runs with different filters) into one. The most complete dump takes precedence: an object
+//! present in several dumps is kept as it is in the dump with the most objects, only its children
+//! are combined. Type differences between those copies are reported as discrepancies. Neither
+//! depends on the order the dumps are given in.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use anyhow::{Result, bail};
+use jmap::Jmap;
+use serde::Serialize;
+
+use crate::diff::{Kind, diff_type};
+
+#[derive(Debug, Serialize)]
+pub struct Discrepancy {
+    /// Object path, absent for differences in the dumps as a whole
+    pub path: Option<String>,
+    /// Label of the dump disagreeing with the merged result
+    pub dump: String,
+    pub description: String,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.dump)?;
+        if let Some(path) = &self.path {
+            write!(f, "{path}: ")?;
+        }
+        f.write_str(&self.description)
+    }
+}
+
+/// Precedence of a dump, lowest first: complete dumps before partial ones, then the dumps with
+/// the most objects, then the oldest
+fn precedence(dump: &Jmap) -> (bool, Reverse<usize>, Option<&str>) {
+    let metadata = dump.metadata.as_ref();
+    (
+        metadata.is_some_and(|m| m.partial),
+        Reverse(dump.objects.len()),
+        metadata.map(|m| m.timestamp.as_str()),
+    )
+}
+
+/// Merge `dumps`, each labelled with where it came from (e.g. its file name) for the
+/// discrepancies. Dumps tied on precedence are ordered by label, so labels should be unique.
+pub fn merge(mut dumps: Vec<(String, Jmap)>) -> Result<(Jmap, Vec<Discrepancy>)> {
+    dumps.sort_by(|(a_label, a), (b_label, b)| {
+        precedence(a)
+            .cmp(&precedence(b))
+            .then_with(|| a_label.cmp(b_label))
+    });
+    let mut dumps = dumps.into_iter();
+    let Some((merged_label, mut merged)) = dumps.next() else {
+        bail!("Nothing to merge");
+    };
+    let mut discrepancies = vec![];
+    for (label, dump) in dumps {
+        let mut discrepancy = |path: Option<&str>, description: String| {
+            discrepancies.push(Discrepancy {
+                path: path.map(Into::into),
+                dump: label.clone(),
+                description,
+            })
+        };
+
+        if let (Some(a), Some(b)) = (&merged.metadata, &dump.metadata) {
+            let (a, b) = (&a.engine_version, &b.engine_version);
+            if (a.major, a.minor) != (b.major, b.minor) {
+                bail!(
+                    "{label} is of engine version {}.{}, not {}.{} like {merged_label}",
+                    b.major,
+                    b.minor,
+                    a.major,
+                    a.minor
+                );
+            }
+        }
+        if dump.image_base_address != merged.image_base_address {
+            discrepancy(
+                None,
+                format!(
+                    "image base 0x{:x} instead of 0x{:x}, addresses of objects only found in \
+                     this dump are relative to it",
+                    dump.image_base_address.0, merged.image_base_address.0
+                ),
+            );
+        }
+        match (&mut merged.metadata, &dump.metadata) {
+            (Some(metadata), Some(other)) => metadata.partial |= other.partial,
+            (None, other) => merged.metadata = other.clone(),
+            _ => {}
+        }
+
+        for (path, obj) in &dump.objects {
+            let Some(existing) = merged.objects.get(path) else {
+                continue;
+            };
+            let (kind, other_kind) = (Kind::of(existing), Kind::of(obj));
+            if kind != other_kind {
+                let name = |kind: Option<Kind>| kind.map_or("object".into(), |k| k.to_string());
+                discrepancy(
+                    Some(path),
+                    format!("{} instead of {}", name(other_kind), name(kind)),
+                );
+            } else if kind.is_some() {
+                for change in diff_type(&merged.objects, existing, &dump.objects, obj) {
+                    discrepancy(Some(path), change.to_string());
+                }
+            } else if existing.get_object().class != obj.get_object().class {
+                discrepancy(
+                    Some(path),
+                    format!(
+                        "instance of {} instead of {}",
+                        obj.get_object().class,
+                        existing.get_object().class
+                    ),
+                );
+            }
+        }
+        for (path, obj) in dump.objects {
+            match merged.objects.get_mut(&path) {
+                Some(existing) => existing
+                    .get_object_mut()
+                    .children
+                    .extend(obj.get_object().children.iter().cloned()),
+                None => {
+                    merged.objects.insert(path, obj);
+                }
+            }
+        }
+
+        for (address, entries) in dump.vtables {
+            merged.vtables.entry(address).or_insert(entries);
+        }
+        if let Some(references) = dump.script_references {
+            let merged = merged.script_references.get_or_insert_default();
+            for (index, name) in references.names {
+                merged.names.entry(index).or_insert(name);
+            }
+            for (address, path) in references.objects {
+                merged.objects.entry(address).or_insert(path);
+            }
+            for (address, name) in references.properties {
+                merged.properties.entry(address).or_insert(name);
+            }
+        }
+        if merged.image.is_none() {
+            merged.image = dump.image;
+        }
+        for (name, address) in dump.resolved_addresses {
+            merged.resolved_addresses.entry(name).or_insert(address);
+        }
+        merged.errors.extend(dump.errors);
+        if merged.natives.is_empty() {
+            merged.natives = dump.natives;
+        }
+        if merged.signatures.is_empty() {
+            merged.signatures = dump.signatures;
+        }
+    }
+
+    // objects that failed in one dump but were read by another aren't missing anymore
+    let objects = &merged.objects;
+    merged.errors.retain(|e| {
+        e.path
+            .as_ref()
+            .is_none_or(|path| !objects.contains_key(path))
+    });
+
+    Ok((merged, discrepancies))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{dump, property};
+    use serde_json::json;
+
+    fn menu() -> Jmap {
+        dump(json!({
+            "/Script/Game.Player": {
+                "type": "Class",
+                "children": ["/Script/Game.Player.OpenMenu"],
+                "properties": [property("Health", 0x10, 4, json!({ "type": "FloatProperty" }))],
+                "properties_size": 0x14,
+            },
+            "/Script/Game.Thing": { "type": "Class" },
+            "/Script/Game.MenuState": {},
+        }))
+    }
+
+    fn ingame() -> Jmap {
+        dump(json!({
+            "/Script/Game.Player": {
+                "type": "Class",
+                "children": ["/Script/Game.Player.Fire"],
+                "properties": [
+                    property("Health", 0x18, 4, json!({ "type": "FloatProperty" })),
+                    property("Ammo", 0x1c, 4, json!({ "type": "IntProperty" })),
+                ],
+                "properties_size": 0x20,
+            },
+            "/Script/Game.Thing": {},
+            "/Script/Game.Weapon": {},
+            "/Script/Game.Level": {},
+        }))
+    }
+
+    fn merged(dumps: Vec<(&str, Jmap)>) -> (serde_json::Value, Vec<String>) {
+        let dumps = dumps.into_iter().map(|(l, d)| (l.to_string(), d)).collect();
+        let (merged, discrepancies) = merge(dumps).unwrap();
+        (
+            serde_json::to_value(&merged).unwrap(),
+            discrepancies.iter().map(|d| d.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_order() {
+        let forward = merged(vec![("menu", menu()), ("ingame", ingame())]);
+        let backward = merged(vec![("ingame", ingame()), ("menu", menu())]);
+        assert_eq!(forward, backward);
+
+        // dumps of the same size are ordered by label
+        let small = || dump(json!({ "/Script/Game.Thing": { "properties_size": 8 } }));
+        let other = || dump(json!({ "/Script/Game.Thing": { "properties_size": 4 } }));
+        assert_eq!(
+            merged(vec![("b", small()), ("a", other())]),
+            merged(vec![("a", other()), ("b", small())]),
+        );
+    }
+
+    #[test]
+    fn test_precedence() {
+        let (merged, discrepancies) = merged(vec![("menu", menu()), ("ingame", ingame())]);
+        let objects = &merged["objects"];
+        for path in ["Player", "Thing", "MenuState", "Weapon", "Level"] {
+            assert!(objects.get(format!("/Script/Game.{path}")).is_some());
+        }
+        // the bigger in-game dump wins, children are combined
+        let player = &objects["/Script/Game.Player"];
+        assert_eq!(player["properties_size"], 0x20);
+        assert_eq!(
+            player["children"],
+            json!(["/Script/Game.Player.Fire", "/Script/Game.Player.OpenMenu"])
+        );
+        assert_eq!(objects["/Script/Game.Thing"]["type"], "ScriptStruct");
+        assert_eq!(
+            discrepancies,
+            [
+                "menu: /Script/Game.Player: size 0x20 -> 0x14",
+                "menu: /Script/Game.Player: ~ Health: offset 0x18 -> 0x10",
+                "menu: /Script/Game.Player: - Ammo: int32_t at 0x1c (size 0x4)",
+                "menu: /Script/Game.Thing: class instead of struct",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial() {
+        let mut partial = ingame();
+        partial.metadata = Some(jmap::Metadata {
+            tool: String::new(),
+            timestamp: String::new(),
+            source: String::new(),
+            engine_version: jmap::EngineVersion {
+                major: 5,
+                minor: 3,
+                patch: None,
+                branch: None,
+                overridden: false,
+            },
+            build_change_list: None,
+            partial: true,
+            pointer_size: 8,
+        });
+        let (merged, _) = merged(vec![("menu", menu()), ("ingame", partial)]);
+        assert_eq!(
+            merged["objects"]["/Script/Game.Player"]["properties_size"],
+            0x14
+        );
+        assert_eq!(merged["metadata"]["partial"], true);
+    }
+}
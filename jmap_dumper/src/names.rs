@@ -0,0 +1,144 @@
+//! Name resolution for games that replace FName text with hashes. A [`NameResolver`] sees every
+//! name entry as read from the game and may substitute the real name. The bundled
+//! [`NameDictionary`] looks hashes up in a user supplied file and can give unknown hashes stable
+//! placeholders so dumps of different builds still line up.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context as _, Result, bail};
+
+/// Maps name entries read from the game to the names that get dumped
+pub trait NameResolver: Send + Sync {
+    /// `None` keeps the entry as read
+    fn resolve(&self, entry: &str) -> Option<String>;
+}
+
+/// Hash to name dictionary, one `<hash> <name>` pair per line (`#` starts a comment). Hashes are
+/// 8 or 16 hex digits with or without `0x` and match entries case insensitively.
+#[derive(Debug, Default)]
+pub struct NameDictionary {
+    names: HashMap<u64, String>,
+    /// Dump entries that look like hashes but aren't in the dictionary as `Hash_<hash>` instead
+    /// of as read. Off by default as real names can be 8 or 16 hex digits too (e.g. `DeadBeef`).
+    pub placeholders: bool,
+}
+
+/// Entries of exactly 8 or 16 hex digits are taken to be hashes; anything shorter would catch
+/// real names like `Face` or `Add`
+fn parse_hash(entry: &str) -> Option<u64> {
+    let digits = entry.strip_prefix("0x").unwrap_or(entry);
+    if !matches!(digits.len(), 8 | 16) {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok()
+}
+
+impl NameDictionary {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read name dictionary {}", path.display()))?;
+        Self::parse(&text)
+            .with_context(|| format!("Failed to parse name dictionary {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut names = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((hash, name)) = line.split_once(char::is_whitespace) else {
+                bail!("line {}: expected `<hash> <name>`", i + 1);
+            };
+            let hash = parse_hash(hash).with_context(|| {
+                format!(
+                    "line {}: bad hash {hash:?}, expected 8 or 16 hex digits",
+                    i + 1
+                )
+            })?;
+            names.insert(hash, name.trim().to_string());
+        }
+        Ok(Self {
+            names,
+            placeholders: false,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl NameResolver for NameDictionary {
+    fn resolve(&self, entry: &str) -> Option<String> {
+        let hash = parse_hash(entry)?;
+        match self.names.get(&hash) {
+            Some(name) => Some(name.clone()),
+            None => self.placeholders.then(|| format!("Hash_{hash:016X}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let dictionary = NameDictionary::parse(
+            "# names of the 1.2 build\n\
+             0x1a2b3c4d PlayerController\n\
+             \n\
+             DEADBEEFCAFEF00D   Default__Pawn # trailing comment\n",
+        )?;
+        assert_eq!(dictionary.len(), 2);
+        assert_eq!(dictionary.names[&0x1a2b3c4d], "PlayerController");
+        assert_eq!(dictionary.names[&0xdeadbeefcafef00d], "Default__Pawn");
+
+        for (text, error) in [
+            ("1a2b3c4d", "line 1: expected `<hash> <name>`"),
+            ("1a2b Pawn", "line 1: bad hash \"1a2b\""),
+            ("\n0x1a2b3c4d5 Pawn", "line 2: bad hash \"0x1a2b3c4d5\""),
+            ("1a2b3c4g Pawn", "line 1: bad hash \"1a2b3c4g\""),
+        ] {
+            let err = format!("{:#}", NameDictionary::parse(text).unwrap_err());
+            assert!(err.starts_with(error), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve() -> Result<()> {
+        let mut dictionary = NameDictionary::parse("1a2b3c4d PlayerController")?;
+        assert_eq!(
+            dictionary.resolve("1A2B3C4D").as_deref(),
+            Some("PlayerController")
+        );
+        assert_eq!(
+            dictionary.resolve("0x1a2b3c4d").as_deref(),
+            Some("PlayerController")
+        );
+        // real names are kept, including ones that happen to be hex
+        assert_eq!(dictionary.resolve("Face"), None);
+        assert_eq!(dictionary.resolve("DeadBeef"), None);
+        assert_eq!(dictionary.resolve("0123456789abcdef"), None);
+
+        dictionary.placeholders = true;
+        assert_eq!(
+            dictionary.resolve("DeadBeef").as_deref(),
+            Some("Hash_00000000DEADBEEF")
+        );
+        assert_eq!(dictionary.resolve("Face"), None);
+        assert_eq!(
+            dictionary.resolve("1a2b3c4d").as_deref(),
+            Some("PlayerController")
+        );
+        Ok(())
+    }
+}
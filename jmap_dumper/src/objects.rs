@@ -1,13 +1,16 @@
 use crate::{
-    containers::{FName, FString, TArray},
+    containers::{FGuid, FName, FString, TArray},
+    decode::{self, Decoders, Hook},
     mem::{Ctx, Ptr, VirtSize},
     read_path,
 };
 use anyhow::Result;
 use jmap::{
-    EClassCastFlags, EClassFlags, ECppForm, EEnumFlags, EFunctionFlags, EObjectFlags,
-    EPropertyFlags, EStructFlags,
+    EClassCastFlags, EClassFlags, ECppForm, EEnumFlags, EFunctionFlags, EInternalObjectFlags,
+    EObjectFlags, EPackageFlags, EPropertyFlags, EStructFlags,
 };
+use std::collections::HashMap;
+use std::sync::RwLock;
 
 macro_rules! inherit {
     ($class:ident : UObject) => {
@@ -96,6 +99,68 @@ impl<C: Ctx> Ptr<UObject, C> {
     }
 }
 
+/// Paths of the objects read so far by address, shared by all copies of a context so each outer
+/// chain is walked once per dump rather than once per reference to an object
+#[derive(Debug, Default)]
+pub struct PathCache(RwLock<HashMap<u64, String>>);
+impl PathCache {
+    pub fn get(&self, address: u64) -> Option<String> {
+        self.0.read().unwrap().get(&address).cloned()
+    }
+    pub fn insert(&self, address: u64, path: String) {
+        self.0.write().unwrap().insert(address, path);
+    }
+}
+
+/// `FPackedObjectRef` of a UE5 object handle that hasn't been resolved yet: ids into the
+/// engine's object handle index (package names, object paths within them and class
+/// descriptors), not into GUObjectArray. The tag bit is 0, the object path id the next 23 bits,
+/// the class descriptor id 12 bits from bit 24 and the package id the top 28 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackedObjectRef {
+    pub package_id: u32,
+    pub class_descriptor_id: u32,
+    pub object_path_id: u32,
+}
+impl PackedObjectRef {
+    /// Decode `handle` if it is a packed reference rather than an object pointer
+    pub fn decode(handle: u64) -> Option<Self> {
+        (handle & 1 != 0).then_some(Self {
+            package_id: (handle >> 36) as u32 & 0x0fff_ffff,
+            class_descriptor_id: (handle >> 24) as u32 & 0xfff,
+            object_path_id: (handle >> 1) as u32 & 0x7f_ffff,
+        })
+    }
+}
+
+/// Target of an object handle
+pub enum ObjectRef<C> {
+    Null,
+    Object(Ptr<UObject, C>),
+    /// Packed reference to an export that was never loaded, so there is no object to read
+    Unresolved(PackedObjectRef),
+}
+
+/// UE5 `TObjectPtr` / `FObjectHandle`. Normally a plain `UObject*`, but builds with late resolve
+/// enabled tag packed references with the low bit, which can never be set on an aligned pointer.
+#[derive(Clone, Copy)]
+pub struct FObjectPtr;
+impl<C: Ctx> Ptr<FObjectPtr, C> {
+    pub fn resolve(&self) -> Result<ObjectRef<C>> {
+        let ctx = self.ctx();
+        let handle = ctx.read_ptr(self.address())?;
+        if ctx.ue_version() >= (5, 0)
+            && let Some(packed) = PackedObjectRef::decode(handle)
+        {
+            return Ok(ObjectRef::Unresolved(packed));
+        }
+        Ok(match self.cast::<Option<Ptr<UObject, C>>>().read()? {
+            Some(object) => ObjectRef::Object(object),
+            None => ObjectRef::Null,
+        })
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct UField;
 inherit!(UField : UObject);
@@ -130,6 +195,10 @@ impl<C: Ctx> Ptr<UStruct, C> {
         let offset = self.ctx().struct_member("UStruct", "MinAlignment");
         self.byte_offset(offset).cast()
     }
+    pub fn script(&self) -> Ptr<TArray<u8>, C> {
+        let offset = self.ctx().struct_member("UStruct", "Script");
+        self.byte_offset(offset).cast()
+    }
 }
 
 impl<C: Ctx> Ptr<UStruct, C> {
@@ -147,6 +216,14 @@ impl<C: Ctx> Ptr<UStruct, C> {
             self.child_properties()
         }
     }
+    pub fn read_script(&self) -> Result<Vec<u8>> {
+        let script = self.script();
+        Ok(if let Some(data) = script.data()? {
+            data.read_vec(script.len()?)?
+        } else {
+            vec![]
+        })
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -161,10 +238,70 @@ impl<C: Ctx> Ptr<UClass, C> {
         let offset = self.ctx().struct_member("UClass", "ClassCastFlags");
         self.byte_offset(offset).cast()
     }
+    pub fn class_within(&self) -> Ptr<Option<Ptr<UClass, C>>, C> {
+        let offset = self.ctx().struct_member("UClass", "ClassWithin");
+        self.byte_offset(offset).cast()
+    }
+    pub fn class_config_name(&self) -> Ptr<FName, C> {
+        let offset = self.ctx().struct_member("UClass", "ClassConfigName");
+        self.byte_offset(offset).cast()
+    }
+    pub fn class_constructor(&self) -> Ptr<usize, C> {
+        let offset = self.ctx().struct_member("UClass", "ClassConstructor");
+        self.byte_offset(offset).cast()
+    }
+    /// only present for >= 4.8
+    pub fn class_vtable_helper_ctor_caller(&self) -> Ptr<usize, C> {
+        let offset = self
+            .ctx()
+            .struct_member("UClass", "ClassVTableHelperCtorCaller");
+        self.byte_offset(offset).cast()
+    }
     pub fn class_default_object(&self) -> Ptr<Option<Ptr<UObject, C>>, C> {
         let offset = self.ctx().struct_member("UClass", "ClassDefaultObject");
         self.byte_offset(offset).cast()
     }
+    /// only present for >= 4.24
+    pub fn sparse_class_data(&self) -> Ptr<Option<Ptr<(), C>>, C> {
+        let offset = self.ctx().struct_member("UClass", "SparseClassData");
+        self.byte_offset(offset).cast()
+    }
+    /// only present for >= 4.24
+    pub fn sparse_class_data_struct(&self) -> Ptr<Option<Ptr<UScriptStruct, C>>, C> {
+        let offset = self.ctx().struct_member("UClass", "SparseClassDataStruct");
+        self.byte_offset(offset).cast()
+    }
+    pub fn interfaces(&self) -> Ptr<TArray<FImplementedInterface>, C> {
+        let offset = self.ctx().struct_member("UClass", "Interfaces");
+        self.byte_offset(offset).cast()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct FImplementedInterface;
+impl<C: Ctx> Ptr<FImplementedInterface, C> {
+    pub fn class(&self) -> Ptr<Option<Ptr<UClass, C>>, C> {
+        let offset = self.ctx().struct_member("FImplementedInterface", "Class");
+        self.byte_offset(offset).cast()
+    }
+    pub fn pointer_offset(&self) -> Ptr<i32, C> {
+        let offset = self
+            .ctx()
+            .struct_member("FImplementedInterface", "PointerOffset");
+        self.byte_offset(offset).cast()
+    }
+    /// bool
+    pub fn implemented_by_k2(&self) -> Ptr<u8, C> {
+        let offset = self
+            .ctx()
+            .struct_member("FImplementedInterface", "bImplementedByK2");
+        self.byte_offset(offset).cast()
+    }
+}
+impl<C: Ctx> VirtSize<C> for FImplementedInterface {
+    fn size(ctx: &C) -> usize {
+        ctx.get_struct("FImplementedInterface").size as usize
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -212,7 +349,121 @@ impl<C: Ctx> Ptr<UEnum, C> {
         let offset = self.ctx().struct_member("UEnum", "EnumFlags");
         self.byte_offset(offset).cast()
     }
+    /// only present for >= 4.15
+    pub fn enum_display_name_fn(&self) -> Ptr<usize, C> {
+        let offset = self.ctx().struct_member("UEnum", "EnumDisplayNameFn");
+        self.byte_offset(offset).cast()
+    }
+    /// only present for >= 5.1
+    pub fn enum_package(&self) -> Ptr<FName, C> {
+        let offset = self.ctx().struct_member("UEnum", "EnumPackage");
+        self.byte_offset(offset).cast()
+    }
+}
+#[derive(Clone, Copy)]
+pub struct UPackage;
+inherit!(UPackage : UObject);
+impl<C: Ctx> Ptr<UPackage, C> {
+    pub fn package_flags(&self) -> Ptr<EPackageFlags, C> {
+        let name = if self.ctx().ue_version() < (4, 19) {
+            "PackageFlags"
+        } else {
+            "PackageFlagsPrivate"
+        };
+        let offset = self.ctx().struct_member("UPackage", name);
+        self.byte_offset(offset).cast()
+    }
+    /// only present for < 5.5
+    pub fn file_name(&self) -> Ptr<FName, C> {
+        let offset = self.ctx().struct_member("UPackage", "FileName");
+        self.byte_offset(offset).cast()
+    }
+    /// only present for >= 5.1
+    pub fn loaded_path(&self) -> Ptr<FPackagePath, C> {
+        let offset = self.ctx().struct_member("UPackage", "LoadedPath");
+        self.byte_offset(offset).cast()
+    }
+    /// only present for < 5.4
+    pub fn guid(&self) -> Ptr<FGuid, C> {
+        let offset = self.ctx().struct_member("UPackage", "Guid");
+        self.byte_offset(offset).cast()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct FPackagePath;
+impl<C: Ctx> Ptr<FPackagePath, C> {
+    /// only present for >= 5.1
+    pub fn package_name(&self) -> Ptr<FName, C> {
+        let offset = self.ctx().struct_member("FPackagePath", "PackageName");
+        self.byte_offset(offset).cast()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct FTopLevelAssetPath;
+impl<C: Ctx> Ptr<FTopLevelAssetPath, C> {
+    pub fn package_name(&self) -> Ptr<FName, C> {
+        let offset = self
+            .ctx()
+            .struct_member("FTopLevelAssetPath", "PackageName");
+        self.byte_offset(offset).cast()
+    }
+    pub fn asset_name(&self) -> Ptr<FName, C> {
+        let offset = self.ctx().struct_member("FTopLevelAssetPath", "AssetName");
+        self.byte_offset(offset).cast()
+    }
 }
+
+#[derive(Clone, Copy)]
+pub struct FSoftObjectPath;
+impl<C: Ctx> Ptr<FSoftObjectPath, C> {
+    pub fn sub_path_string(&self) -> Ptr<FString, C> {
+        let offset = self.ctx().struct_member("FSoftObjectPath", "SubPathString");
+        self.byte_offset(offset).cast()
+    }
+}
+impl<C: Ctx> Ptr<FSoftObjectPath, C> {
+    /// Path as formatted by `FSoftObjectPath::ToString`, empty for a null path. The layout is
+    /// taken from the struct info: 5.1+ stores an `FTopLevelAssetPath AssetPath`, older versions
+    /// a single `FName AssetPathName`.
+    pub fn read(&self) -> Result<String> {
+        let ctx = self.ctx();
+        let none_filter = |name: String| Some(name).filter(|n| n != "None");
+        let layout = ctx.get_struct("FSoftObjectPath");
+        let asset = if layout.members.iter().any(|m| m.name == "AssetPath") {
+            let offset = ctx.struct_member("FSoftObjectPath", "AssetPath");
+            let path = self.byte_offset(offset).cast::<FTopLevelAssetPath>();
+            match (
+                none_filter(path.package_name().read()?),
+                none_filter(path.asset_name().read()?),
+            ) {
+                (Some(package), Some(asset)) => format!("{package}.{asset}"),
+                (Some(package), None) => package,
+                (None, _) => String::new(),
+            }
+        } else {
+            let offset = ctx.struct_member("FSoftObjectPath", "AssetPathName");
+            none_filter(self.byte_offset(offset).cast::<FName>().read()?).unwrap_or_default()
+        };
+        let sub_path = self.sub_path_string().read()?;
+        Ok(if sub_path.is_empty() {
+            asset
+        } else {
+            format!("{asset}:{sub_path}")
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct FSoftObjectPtr;
+impl<C: Ctx> Ptr<FSoftObjectPtr, C> {
+    pub fn object_id(&self) -> Ptr<FSoftObjectPath, C> {
+        let offset = self.ctx().struct_member("FSoftObjectPtr", "ObjectID");
+        self.byte_offset(offset).cast()
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct UEnumNameTuple;
 impl<C: Ctx> Ptr<UEnumNameTuple, C> {
@@ -269,17 +520,23 @@ impl<C: Ctx> Ptr<ZField, C> {
             let class = self.cast::<UObject>().class_private().read()?;
             class.class_cast_flags().read()
         } else {
-            // FField
-            let offset = self.ctx().struct_member("FField", "ClassPrivate");
-            let class: Ptr<Ptr<FFieldClass, C>, C> = self.byte_offset(offset).cast();
-            class.read()?.cast_flags().read()
+            self.field_class().read()?.cast_flags().read()
         }
     }
+    /// `FField::ClassPrivate`, only valid from 4.25 on
+    pub fn field_class(&self) -> Ptr<Ptr<FFieldClass, C>, C> {
+        let offset = self.ctx().struct_member("FField", "ClassPrivate");
+        self.byte_offset(offset).cast()
+    }
 }
 
 #[derive(Clone, Copy)]
 pub struct FFieldClass;
 impl<C: Ctx> Ptr<FFieldClass, C> {
+    pub fn name(&self) -> Ptr<FName, C> {
+        let offset = self.ctx().struct_member("FFieldClass", "Name");
+        self.byte_offset(offset).cast()
+    }
     pub fn cast_flags(&self) -> Ptr<EClassCastFlags, C> {
         let offset = self.ctx().struct_member("FFieldClass", "CastFlags");
         self.byte_offset(offset).cast()
@@ -496,7 +753,56 @@ impl<C: Ctx> Ptr<FUObjectItem, C> {
     pub fn object(&self) -> Ptr<Option<Ptr<UObject, C>>, C> {
         self.byte_offset(0).cast()
     }
+    pub fn internal_flags(&self) -> Result<EInternalObjectFlags> {
+        if self.ctx().ue_version() < (4, 13) {
+            // low bits hold the cluster index
+            let offset = self.ctx().struct_member("FUObjectItem", "ClusterAndFlags");
+            let value = self.byte_offset(offset).cast::<u32>().read()?;
+            Ok(EInternalObjectFlags::from_bits_retain(
+                value & !CLUSTER_INDEX_MASK,
+            ))
+        } else {
+            let offset = self.ctx().struct_member("FUObjectItem", "Flags");
+            self.byte_offset(offset)
+                .cast::<EInternalObjectFlags>()
+                .read()
+        }
+    }
+    pub fn cluster_root_index(&self) -> Result<i32> {
+        let version = self.ctx().ue_version();
+        if version < (4, 13) {
+            let offset = self.ctx().struct_member("FUObjectItem", "ClusterAndFlags");
+            let value = self.byte_offset(offset).cast::<u32>().read()?;
+            Ok((value & CLUSTER_INDEX_MASK) as i32)
+        } else {
+            let name = if version < (4, 16) {
+                "ClusterIndex"
+            } else {
+                "ClusterRootIndex"
+            };
+            let offset = self.ctx().struct_member("FUObjectItem", name);
+            self.byte_offset(offset).cast::<i32>().read()
+        }
+    }
+    pub fn serial_number(&self) -> Ptr<i32, C> {
+        let offset = self.ctx().struct_member("FUObjectItem", "SerialNumber");
+        self.byte_offset(offset).cast()
+    }
+    pub fn read_object(&self) -> Result<Option<Ptr<UObject, C>>> {
+        let object = read_decoded(&self.object().cast(), |d| &d.object)?;
+        Ok((object != 0).then(|| Ptr::new(object, self.ctx().clone())))
+    }
 }
+/// Read a pointer that may be stored obfuscated, decoding it with the hook `hook` picks
+fn read_decoded<C: Ctx>(ptr: &Ptr<(), C>, hook: fn(&Decoders) -> &Option<Hook>) -> Result<u64> {
+    let ctx = ptr.ctx();
+    Ok(decode::apply(
+        hook(ctx.decoders()),
+        ctx.read_ptr(ptr.address())?,
+    ))
+}
+/// Pre 4.13 FUObjectItem::ClusterAndFlags packs the cluster index below the internal flags
+const CLUSTER_INDEX_MASK: u32 = 0x007fffff;
 impl<C: Ctx> VirtSize<C> for FUObjectItem {
     fn size(ctx: &C) -> usize {
         ctx.get_struct("FUObjectItem").size as usize
@@ -519,7 +825,14 @@ impl<C: Ctx> Ptr<FFixedUObjectArray, C> {
 }
 impl<C: Ctx> Ptr<FFixedUObjectArray, C> {
     pub fn read_item_ptr(&self, item: usize) -> Result<Ptr<FUObjectItem, C>> {
-        Ok(self.objects().read()?.offset(item))
+        let objects = read_decoded(&self.objects().cast(), |d| &d.objects)?;
+        Ok(Ptr::<FUObjectItem, C>::new(objects, self.ctx().clone()).offset(item))
+    }
+    pub fn prefetch_items(&self, num: usize) -> Result<()> {
+        let ctx = self.ctx();
+        let objects = read_decoded(&self.objects().cast(), |d| &d.objects)?;
+        ctx.prefetch(objects, num * FUObjectItem::size(ctx));
+        Ok(())
     }
 }
 
@@ -541,17 +854,37 @@ impl<C: Ctx> Ptr<FChunkedFixedUObjectArray, C> {
 }
 impl<C: Ctx> Ptr<FChunkedFixedUObjectArray, C> {
     pub fn read_item_ptr(&self, item: usize) -> Result<Ptr<FUObjectItem, C>> {
-        let max_per_chunk = 64 * 1024;
+        let max_per_chunk = CHUNKED_MAX_PER_CHUNK;
         let chunk_index = item / max_per_chunk;
 
-        Ok(self
-            .objects()
-            .read()?
+        let chunks = read_decoded(&self.objects().cast(), |d| &d.objects)?;
+        let chunk = Ptr::<Ptr<FUObjectItem, C>, C>::new(chunks, self.ctx().clone())
             .offset(chunk_index)
-            .read()?
-            .offset(item % max_per_chunk))
+            .cast();
+        let chunk = read_decoded(&chunk, |d| &d.chunks)?;
+        Ok(Ptr::<FUObjectItem, C>::new(chunk, self.ctx().clone()).offset(item % max_per_chunk))
+    }
+    pub fn prefetch_items(&self, num: usize) -> Result<()> {
+        let ctx = self.ctx();
+        let chunks = read_decoded(&self.objects().cast(), |d| &d.objects)?;
+        let chunks = Ptr::<Ptr<FUObjectItem, C>, C>::new(chunks, ctx.clone());
+        let num_chunks = num.div_ceil(CHUNKED_MAX_PER_CHUNK);
+        ctx.prefetch(
+            chunks.address(),
+            num_chunks * Ptr::<FUObjectItem, C>::size(ctx),
+        );
+        for chunk_index in 0..num_chunks {
+            let chunk = read_decoded(&chunks.offset(chunk_index).cast(), |d| &d.chunks)?;
+            let items = (num - chunk_index * CHUNKED_MAX_PER_CHUNK).min(CHUNKED_MAX_PER_CHUNK);
+            if chunk != 0 {
+                ctx.prefetch(chunk, items * FUObjectItem::size(ctx));
+            }
+        }
+        Ok(())
     }
 }
+/// Items per chunk of `FChunkedFixedUObjectArray`
+const CHUNKED_MAX_PER_CHUNK: usize = 64 * 1024;
 #[derive(Clone, Copy)]
 pub struct FUObjectArrayOld;
 impl<C: Ctx> Ptr<FUObjectArrayOld, C> {
@@ -566,7 +899,7 @@ impl<C: Ctx> Ptr<FUObjectArrayOld, C> {
 }
 impl<C: Ctx> Ptr<FUObjectArrayOld, C> {
     pub fn read_item_ptr(&self, item: usize) -> Result<Option<Ptr<UObject, C>>> {
-        let max_per_chunk = 16 * 1024;
+        let max_per_chunk = OLD_MAX_PER_CHUNK;
         let chunk_index = item / max_per_chunk;
 
         self.chunks()
@@ -575,7 +908,18 @@ impl<C: Ctx> Ptr<FUObjectArrayOld, C> {
             .offset(item % max_per_chunk)
             .read()
     }
+    pub fn prefetch_items(&self, num: usize) -> Result<()> {
+        let ctx = self.ctx();
+        for chunk_index in 0..num.div_ceil(OLD_MAX_PER_CHUNK) {
+            let chunk = self.chunks().offset(chunk_index).read()?;
+            let items = (num - chunk_index * OLD_MAX_PER_CHUNK).min(OLD_MAX_PER_CHUNK);
+            ctx.prefetch(chunk.address(), items * Ptr::<UObject, C>::size(ctx));
+        }
+        Ok(())
+    }
 }
+/// Items per chunk of `TStaticIndirectArrayThreadSafeRead<UObject>`
+const OLD_MAX_PER_CHUNK: usize = 16 * 1024;
 #[derive(Clone, Copy)]
 pub struct FUObjectArrayOlder;
 impl<C: Ctx> Ptr<FUObjectArrayOlder, C> {
@@ -591,6 +935,25 @@ impl<C: Ctx> Ptr<FUObjectArrayOlder, C> {
     pub fn read_item_ptr(&self, item: usize) -> Result<Option<Ptr<UObject, C>>> {
         self.data().read()?.offset(item).read()
     }
+    pub fn prefetch_items(&self, num: usize) -> Result<()> {
+        let ctx = self.ctx();
+        let data = self.data().read()?;
+        ctx.prefetch(data.address(), num * Ptr::<UObject, C>::size(ctx));
+        Ok(())
+    }
+}
+
+/// Layout of `FUObjectArray::ObjObjects`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjObjectsLayout {
+    /// 4.7: `TArray<UObject*>`
+    Older,
+    /// 4.8 - 4.10: `TStaticIndirectArrayThreadSafeRead<UObject>`
+    Old,
+    /// 4.11 - 4.19: flat `FFixedUObjectArray`
+    Fixed,
+    /// 4.20+: `FChunkedFixedUObjectArray`
+    Chunked,
 }
 
 #[derive(Clone, Copy)]
@@ -600,46 +963,87 @@ impl<C: Ctx> Ptr<FUObjectArray, C> {
         let offset = self.ctx().struct_member("FUObjectArray", "ObjObjects");
         self.byte_offset(offset).cast()
     }
+    /// Picked by engine version, unless the struct info sizes `ObjObjects` as exactly one of the
+    /// two FUObjectItem based arrays (games that backported or reverted the chunked array)
+    pub fn layout(&self) -> ObjObjectsLayout {
+        let ctx = self.ctx();
+        let version = ctx.ue_version();
+        if version < (4, 8) {
+            return ObjObjectsLayout::Older;
+        } else if version < (4, 11) {
+            return ObjObjectsLayout::Old;
+        }
+
+        let size = ctx
+            .get_struct("FUObjectArray")
+            .members
+            .iter()
+            .find(|m| m.name == "ObjObjects")
+            .map(|m| m.size);
+        let fixed = ctx.get_struct("FFixedUObjectArray").size;
+        let chunked = ctx.get_struct("FChunkedFixedUObjectArray").size;
+        match size {
+            Some(size) if size == fixed && size != chunked => ObjObjectsLayout::Fixed,
+            Some(size) if size == chunked && size != fixed => ObjObjectsLayout::Chunked,
+            _ if version < (4, 20) => ObjObjectsLayout::Fixed,
+            _ => ObjObjectsLayout::Chunked,
+        }
+    }
 }
 impl<C: Ctx> Ptr<FUObjectArray, C> {
     pub fn read_item_ptr(&self, item: usize) -> Result<Option<Ptr<UObject, C>>> {
-        if self.ctx().ue_version() < (4, 8) {
-            self.obj_objects()
-                .cast::<FUObjectArrayOlder>()
-                .read_item_ptr(item)
-        } else if self.ctx().ue_version() < (4, 11) {
-            self.obj_objects()
-                .cast::<FUObjectArrayOld>()
-                .read_item_ptr(item)
-        } else if self.ctx().ue_version() < (4, 20) {
-            self.obj_objects()
+        let obj_objects = self.obj_objects();
+        match self.layout() {
+            ObjObjectsLayout::Older => obj_objects.cast::<FUObjectArrayOlder>().read_item_ptr(item),
+            ObjObjectsLayout::Old => obj_objects.cast::<FUObjectArrayOld>().read_item_ptr(item),
+            ObjObjectsLayout::Fixed => obj_objects
                 .cast::<FFixedUObjectArray>()
                 .read_item_ptr(item)?
-                .object()
-                .read()
-        } else {
-            self.obj_objects()
+                .read_object(),
+            ObjObjectsLayout::Chunked => obj_objects
                 .cast::<FChunkedFixedUObjectArray>()
                 .read_item_ptr(item)?
-                .object()
-                .read()
+                .read_object(),
         }
     }
+    /// Fetch the items of objects `0..num` in a few large reads before they are read one by one
+    pub fn prefetch_items(&self, num: usize) -> Result<()> {
+        let obj_objects = self.obj_objects();
+        match self.layout() {
+            ObjObjectsLayout::Older => obj_objects.cast::<FUObjectArrayOlder>().prefetch_items(num),
+            ObjObjectsLayout::Old => obj_objects.cast::<FUObjectArrayOld>().prefetch_items(num),
+            ObjObjectsLayout::Fixed => obj_objects.cast::<FFixedUObjectArray>().prefetch_items(num),
+            ObjObjectsLayout::Chunked => obj_objects
+                .cast::<FChunkedFixedUObjectArray>()
+                .prefetch_items(num),
+        }
+    }
+    /// FUObjectItem of an object (only present for >= 4.11)
+    pub fn read_object_item(&self, item: usize) -> Result<Option<Ptr<FUObjectItem, C>>> {
+        let obj_objects = self.obj_objects();
+        Ok(match self.layout() {
+            ObjObjectsLayout::Older | ObjObjectsLayout::Old => None,
+            ObjObjectsLayout::Fixed => Some(
+                obj_objects
+                    .cast::<FFixedUObjectArray>()
+                    .read_item_ptr(item)?,
+            ),
+            ObjObjectsLayout::Chunked => Some(
+                obj_objects
+                    .cast::<FChunkedFixedUObjectArray>()
+                    .read_item_ptr(item)?,
+            ),
+        })
+    }
     pub fn num_elements(&self) -> Result<i32> {
-        if self.ctx().ue_version() < (4, 8) {
-            self.obj_objects()
-                .cast::<FUObjectArrayOlder>()
-                .num_elements()
-        } else if self.ctx().ue_version() < (4, 11) {
-            self.obj_objects().cast::<FUObjectArrayOld>().num_elements()
-        } else if self.ctx().ue_version() < (4, 20) {
-            self.obj_objects()
-                .cast::<FFixedUObjectArray>()
-                .num_elements()
-        } else {
-            self.obj_objects()
+        let obj_objects = self.obj_objects();
+        match self.layout() {
+            ObjObjectsLayout::Older => obj_objects.cast::<FUObjectArrayOlder>().num_elements(),
+            ObjObjectsLayout::Old => obj_objects.cast::<FUObjectArrayOld>().num_elements(),
+            ObjObjectsLayout::Fixed => obj_objects.cast::<FFixedUObjectArray>().num_elements(),
+            ObjObjectsLayout::Chunked => obj_objects
                 .cast::<FChunkedFixedUObjectArray>()
-                .num_elements()
+                .num_elements(),
         }
         .read()
     }
@@ -658,6 +1062,7 @@ impl<C: Ctx> Iterator for PropertyIterator<C> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             if let Some(current) = self.current_field.take() {
+                current.prefetch("ZProperty");
                 let is_property = match current.cast_flags() {
                     Ok(flags) if flags.contains(EClassCastFlags::CASTCLASS_FProperty) => true,
                     Ok(_) => false,
@@ -691,3 +1096,30 @@ impl<C: Ctx> Iterator for PropertyIterator<C> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_packed_object_ref() {
+        assert_eq!(PackedObjectRef::decode(0x7ff6_1234_5670), None);
+        // package 0x123, class descriptor 0x45, object path 0x6789
+        assert_eq!(
+            PackedObjectRef::decode(0x0000_1230_4500_cf13),
+            Some(PackedObjectRef {
+                package_id: 0x123,
+                class_descriptor_id: 0x45,
+                object_path_id: 0x6789,
+            })
+        );
+        assert_eq!(
+            PackedObjectRef::decode(u64::MAX),
+            Some(PackedObjectRef {
+                package_id: 0x0fff_ffff,
+                class_descriptor_id: 0xfff,
+                object_path_id: 0x7f_ffff,
+            })
+        );
+    }
+}
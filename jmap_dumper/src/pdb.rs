@@ -0,0 +1,522 @@
+//! PDB export: an MSF container holding only public symbols (S_PUB32) for exec functions, vtables
+//! and resolved engine globals. When the image has a CodeView record the PDB takes its GUID and
+//! age so debuggers load it like the original.
+
+use std::hash::{BuildHasher, Hasher};
+
+use anyhow::{Context, Result};
+use jmap::{ImageSection, Jmap};
+
+use crate::pe::parse_guid;
+use crate::symbols::{SymbolKind, collect_symbols};
+
+const BLOCK_SIZE: usize = 0x1000;
+const MSF_MAGIC: &[u8; 32] = b"Microsoft C/C++ MSF 7.00\r\n\x1aDS\0\0\0";
+
+const STREAM_PDB: u16 = 1;
+const STREAM_TPI: u16 = 2;
+const STREAM_DBI: u16 = 3;
+const STREAM_IPI: u16 = 4;
+const STREAM_GLOBALS: u16 = 5;
+const STREAM_PUBLICS: u16 = 6;
+const STREAM_SYMBOLS: u16 = 7;
+const STREAM_SECTION_HEADERS: u16 = 8;
+const STREAM_NAMES: u16 = 9;
+const STREAM_COUNT: usize = 10;
+
+const NIL_STREAM: u16 = 0xffff;
+
+const PDB_VERSION_VC70: u32 = 20000404;
+const PDB_FEATURE_VC140: u32 = 20140508;
+const TPI_VERSION_V80: u32 = 20040203;
+const DBI_VERSION_V70: u32 = 19990903;
+const SECTION_CONTRIB_VERSION_V60: u32 = 0xeffe0000 + 19970605;
+const GSI_HASH_VERSION_V70: u32 = 0xeffe0000 + 19990810;
+const STRING_TABLE_SIGNATURE: u32 = 0xeffeeffe;
+const MACHINE_AMD64: u16 = 0x8664;
+
+const S_PUB32: u16 = 0x110e;
+const PUB_SYM_FLAG_FUNCTION: u32 = 2;
+
+/// Number of buckets in a GSI hash table
+const IPHR_HASH: usize = 4096;
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x20000000;
+const IMAGE_SCN_MEM_READ: u32 = 0x40000000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x80000000;
+
+trait Put {
+    fn u16(&mut self, value: u16);
+    fn u32(&mut self, value: u32);
+    fn align(&mut self, alignment: usize);
+}
+impl Put for Vec<u8> {
+    fn u16(&mut self, value: u16) {
+        self.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u32(&mut self, value: u32) {
+        self.extend_from_slice(&value.to_le_bytes());
+    }
+    fn align(&mut self, alignment: usize) {
+        self.resize(self.len().next_multiple_of(alignment), 0);
+    }
+}
+
+struct Public {
+    name: String,
+    segment: u16,
+    offset: u32,
+    flags: u32,
+}
+
+/// Names hash used for GSI buckets and PDB hash tables (`hashStringV1`)
+fn hash_v1(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+    let mut chunks = bytes.chunks_exact(4);
+    let mut result = 0u32;
+    for chunk in &mut chunks {
+        result ^= u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    let mut rest = chunks.remainder();
+    if rest.len() >= 2 {
+        result ^= u16::from_le_bytes([rest[0], rest[1]]) as u32;
+        rest = &rest[2..];
+    }
+    if let [byte] = rest {
+        result ^= *byte as u32;
+    }
+    result |= 0x20202020;
+    result ^= result >> 11;
+    result ^ (result >> 16)
+}
+
+/// Order of records within a GSI bucket
+fn gsi_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    a.len().cmp(&b.len()).then_with(|| {
+        if a.is_ascii() && b.is_ascii() {
+            a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase())
+        } else {
+            a.as_bytes().cmp(b.as_bytes())
+        }
+    })
+}
+
+/// GSI hash table of `(name, symbol record offset)`
+fn gsi_hash(records: &[(&str, u32)]) -> Vec<u8> {
+    let mut buckets = vec![vec![]; IPHR_HASH];
+    for &(name, offset) in records {
+        buckets[hash_v1(name) as usize % IPHR_HASH].push((name, offset));
+    }
+
+    let mut hash_records = vec![];
+    let mut bitmap = vec![0u32; (IPHR_HASH + 32) / 32];
+    let mut starts = vec![];
+    let mut index = 0;
+    for (i, bucket) in buckets.iter_mut().enumerate() {
+        if bucket.is_empty() {
+            continue;
+        }
+        bucket.sort_by(|a, b| gsi_cmp(a.0, b.0));
+        bitmap[i / 32] |= 1 << (i % 32);
+        // offset into the in-memory records, which are 12 bytes each
+        starts.push(index * 12);
+        for &(_, offset) in bucket.iter() {
+            hash_records.u32(offset + 1);
+            hash_records.u32(1);
+            index += 1;
+        }
+    }
+
+    let mut b = vec![];
+    b.u32(0xffffffff);
+    b.u32(GSI_HASH_VERSION_V70);
+    b.u32(hash_records.len() as u32);
+    b.u32((bitmap.len() + starts.len()) as u32 * 4);
+    b.extend(hash_records);
+    for word in bitmap.into_iter().chain(starts) {
+        b.u32(word);
+    }
+    b
+}
+
+/// Empty `/names` style string table
+fn string_table() -> Vec<u8> {
+    let mut b = vec![];
+    b.u32(STRING_TABLE_SIGNATURE);
+    b.u32(1);
+    b.u32(1);
+    b.push(0);
+    b.u32(1);
+    b.u32(0);
+    b.u32(0);
+    b
+}
+
+fn pdb_info_stream(guid: &[u8; 16], age: u32, signature: u32) -> Vec<u8> {
+    let mut b = vec![];
+    b.u32(PDB_VERSION_VC70);
+    b.u32(signature);
+    b.u32(age);
+    b.extend_from_slice(guid);
+
+    // named stream map containing only /names
+    let names = b"/names\0";
+    b.u32(names.len() as u32);
+    b.extend_from_slice(names);
+    b.u32(1); // size
+    b.u32(1); // capacity
+    b.u32(1); // present bit vector words
+    b.u32(1);
+    b.u32(0); // deleted bit vector words
+    b.u32(0); // key: offset of "/names"
+    b.u32(STREAM_NAMES as u32);
+    b.u32(0); // niMac
+
+    b.u32(PDB_FEATURE_VC140);
+    b
+}
+
+fn type_stream() -> Vec<u8> {
+    let mut b = vec![];
+    b.u32(TPI_VERSION_V80);
+    b.u32(56);
+    b.u32(0x1000);
+    b.u32(0x1000);
+    b.u32(0);
+    b.u16(NIL_STREAM);
+    b.u16(NIL_STREAM);
+    b.u32(4);
+    b.u32(0x3ffff);
+    for _ in 0..6 {
+        b.u32(0);
+    }
+    b
+}
+
+fn dbi_stream(age: u32, sections: &[ImageSection]) -> Vec<u8> {
+    // a single "* Linker *" module without symbols, as emitted by linkers
+    let mut modules = vec![];
+    modules.u32(0);
+    modules.u16(NIL_STREAM); // section contribution
+    modules.u16(0);
+    modules.u32(0);
+    modules.u32(0);
+    modules.u32(0);
+    modules.u16(0);
+    modules.u16(0);
+    modules.u32(0);
+    modules.u32(0);
+    modules.u16(0); // flags
+    modules.u16(NIL_STREAM); // module symbol stream
+    modules.u32(0);
+    modules.u32(0);
+    modules.u32(0);
+    modules.u16(0);
+    modules.u16(0);
+    modules.u32(0);
+    modules.u32(0);
+    modules.u32(0);
+    modules.extend_from_slice(b"* Linker *\0\0");
+    modules.align(4);
+
+    let mut section_contribs = vec![];
+    section_contribs.u32(SECTION_CONTRIB_VERSION_V60);
+
+    let mut section_map = vec![];
+    let count = sections.len() as u16 + 1;
+    section_map.u16(count);
+    section_map.u16(count);
+    for (i, section) in sections.iter().enumerate() {
+        let mut flags = 0x8 | 0x100; // 32 bit address, selector
+        if section.characteristics & IMAGE_SCN_MEM_READ != 0 {
+            flags |= 0x1;
+        }
+        if section.characteristics & IMAGE_SCN_MEM_WRITE != 0 {
+            flags |= 0x2;
+        }
+        if section.characteristics & IMAGE_SCN_MEM_EXECUTE != 0 {
+            flags |= 0x4;
+        }
+        section_map.u16(flags);
+        section_map.u16(0);
+        section_map.u16(0);
+        section_map.u16(i as u16 + 1);
+        section_map.u16(0xffff);
+        section_map.u16(0xffff);
+        section_map.u32(0);
+        section_map.u32(section.virtual_size);
+    }
+    // absolute symbols
+    section_map.u16(0x8 | 0x200);
+    section_map.u16(0);
+    section_map.u16(0);
+    section_map.u16(count);
+    section_map.u16(0xffff);
+    section_map.u16(0xffff);
+    section_map.u32(0);
+    section_map.u32(0xffffffff);
+
+    let mut source_info = vec![];
+    source_info.u16(1); // modules
+    source_info.u16(0); // source files
+    source_info.u16(0); // module indices
+    source_info.u16(0); // module file counts
+
+    let ec_names = string_table();
+
+    let mut debug_header = vec![];
+    for i in 0..11 {
+        // only the section header stream is present
+        debug_header.u16(if i == 5 {
+            STREAM_SECTION_HEADERS
+        } else {
+            NIL_STREAM
+        });
+    }
+
+    let mut b = vec![];
+    b.u32(0xffffffff);
+    b.u32(DBI_VERSION_V70);
+    b.u32(age);
+    b.u16(STREAM_GLOBALS);
+    b.u16(0x8000 | (14 << 8) | 11); // build number: new format, 14.11
+    b.u16(STREAM_PUBLICS);
+    b.u16(0);
+    b.u16(STREAM_SYMBOLS);
+    b.u16(0);
+    b.u32(modules.len() as u32);
+    b.u32(section_contribs.len() as u32);
+    b.u32(section_map.len() as u32);
+    b.u32(source_info.len() as u32);
+    b.u32(0); // type server map
+    b.u32(0); // MFC type server index
+    b.u32(debug_header.len() as u32);
+    b.u32(ec_names.len() as u32);
+    b.u16(0); // flags
+    b.u16(MACHINE_AMD64);
+    b.u32(0);
+
+    for substream in [
+        modules,
+        section_contribs,
+        section_map,
+        source_info,
+        ec_names,
+        debug_header,
+    ] {
+        b.extend(substream);
+    }
+    b
+}
+
+fn section_headers_stream(sections: &[ImageSection]) -> Vec<u8> {
+    let mut b = vec![];
+    for section in sections {
+        let mut name = [0; 8];
+        let len = section.name.len().min(8);
+        name[..len].copy_from_slice(&section.name.as_bytes()[..len]);
+        b.extend_from_slice(&name);
+        b.u32(section.virtual_size);
+        b.u32(section.virtual_address);
+        b.u32(section.size_of_raw_data);
+        b.u32(section.pointer_to_raw_data);
+        b.u32(0);
+        b.u32(0);
+        b.u16(0);
+        b.u16(0);
+        b.u32(section.characteristics);
+    }
+    b
+}
+
+/// Symbol record stream and the publics stream referencing it
+fn public_streams(publics: &[Public]) -> (Vec<u8>, Vec<u8>) {
+    let mut symbols = vec![];
+    let mut records = vec![];
+    for public in publics {
+        let offset = symbols.len() as u32;
+        let mut record = vec![];
+        record.u16(S_PUB32);
+        record.u32(public.flags);
+        record.u32(public.offset);
+        record.u16(public.segment);
+        record.extend_from_slice(public.name.as_bytes());
+        record.push(0);
+        // length prefix + record must be 4 byte aligned
+        record.resize((record.len() + 2).next_multiple_of(4) - 2, 0);
+        symbols.u16(record.len() as u16);
+        symbols.extend(record);
+        records.push((public.name.as_str(), offset));
+    }
+
+    let hash = gsi_hash(&records);
+
+    let mut by_address: Vec<_> = publics.iter().zip(&records).collect();
+    by_address.sort_by(|(a, _), (b, _)| {
+        (a.segment, a.offset)
+            .cmp(&(b.segment, b.offset))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut b = vec![];
+    b.u32(hash.len() as u32);
+    b.u32(by_address.len() as u32 * 4);
+    b.u32(0); // thunks
+    b.u32(0); // thunk size
+    b.u16(0); // thunk table section
+    b.u16(0);
+    b.u32(0); // thunk table offset
+    b.u32(0); // sections
+    b.extend(hash);
+    for (_, (_, offset)) in by_address {
+        b.u32(*offset);
+    }
+
+    (symbols, b)
+}
+
+/// Lay out streams in an MSF container
+fn write_msf(streams: &[Vec<u8>]) -> Vec<u8> {
+    let mut next_block = 3;
+    let mut alloc = || loop {
+        let block = next_block;
+        next_block += 1;
+        // skip free block map blocks present at the start of every interval
+        if !matches!(block % BLOCK_SIZE, 1 | 2) {
+            return block;
+        }
+    };
+
+    let mut placed = vec![];
+    let mut stream_blocks = vec![];
+    for stream in streams {
+        let blocks: Vec<usize> = (0..stream.len().div_ceil(BLOCK_SIZE))
+            .map(|_| alloc())
+            .collect();
+        for (chunk, block) in stream.chunks(BLOCK_SIZE).zip(&blocks) {
+            placed.push((*block, chunk));
+        }
+        stream_blocks.push(blocks);
+    }
+
+    let mut directory = vec![];
+    directory.u32(streams.len() as u32);
+    for stream in streams {
+        directory.u32(stream.len() as u32);
+    }
+    for block in stream_blocks.iter().flatten() {
+        directory.u32(*block as u32);
+    }
+    let directory_blocks: Vec<usize> = (0..directory.len().div_ceil(BLOCK_SIZE))
+        .map(|_| alloc())
+        .collect();
+    let mut block_map = vec![];
+    for block in &directory_blocks {
+        block_map.u32(*block as u32);
+    }
+    let block_map_block = alloc();
+    let num_blocks = next_block;
+
+    let mut out = vec![0; num_blocks * BLOCK_SIZE];
+    let mut write = |block: usize, data: &[u8]| {
+        let start = block * BLOCK_SIZE;
+        out[start..start + data.len()].copy_from_slice(data);
+    };
+
+    let mut superblock = MSF_MAGIC.to_vec();
+    superblock.u32(BLOCK_SIZE as u32);
+    superblock.u32(1);
+    superblock.u32(num_blocks as u32);
+    superblock.u32(directory.len() as u32);
+    superblock.u32(0);
+    superblock.u32(block_map_block as u32);
+    write(0, &superblock);
+
+    for (block, chunk) in placed {
+        write(block, chunk);
+    }
+    for (chunk, block) in directory.chunks(BLOCK_SIZE).zip(&directory_blocks) {
+        write(*block, chunk);
+    }
+    write(block_map_block, &block_map);
+
+    // every block is in use, bits past the end of the file are free
+    let mut fpm = vec![0u8; num_blocks.div_ceil(8 * BLOCK_SIZE) * BLOCK_SIZE];
+    for block in num_blocks..fpm.len() * 8 {
+        fpm[block / 8] |= 1 << (block % 8);
+    }
+    for (interval, chunk) in fpm.chunks(BLOCK_SIZE).enumerate() {
+        let block = interval * BLOCK_SIZE + 1;
+        if block + 1 < num_blocks {
+            write(block, chunk);
+            write(block + 1, chunk);
+        }
+    }
+
+    out
+}
+
+fn collect_publics(jmap: &Jmap, sections: &[ImageSection]) -> Vec<Public> {
+    let base = jmap.image_base_address.0;
+    collect_symbols(jmap)
+        .into_iter()
+        .filter_map(|symbol| {
+            let rva = symbol.address.checked_sub(base)?;
+            let (index, section) = sections.iter().enumerate().find(|(_, s)| {
+                (s.virtual_address as u64..(s.virtual_address + s.virtual_size) as u64)
+                    .contains(&rva)
+            })?;
+            Some(Public {
+                name: symbol.name,
+                segment: index as u16 + 1,
+                offset: (rva - section.virtual_address as u64) as u32,
+                flags: match symbol.kind {
+                    SymbolKind::Function => PUB_SYM_FLAG_FUNCTION,
+                    SymbolKind::Vtable | SymbolKind::Global => 0,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Generate a PDB with public symbols for exec functions, vtables and engine globals
+pub fn into_pdb(jmap: &Jmap) -> Result<Vec<u8>> {
+    let image = jmap
+        .image
+        .as_ref()
+        .context("dump does not contain PE image info, re-dump with this version")?;
+
+    let (guid, age) = match &image.codeview {
+        Some(codeview) => (parse_guid(&codeview.guid)?, codeview.age),
+        None => {
+            // image has no debug directory so nothing to match, use a fresh GUID
+            let state = std::collections::hash_map::RandomState::new();
+            let mut guid = [0; 16];
+            for (i, half) in guid.chunks_exact_mut(8).enumerate() {
+                let mut hasher = state.build_hasher();
+                hasher.write_usize(i);
+                half.copy_from_slice(&hasher.finish().to_le_bytes());
+            }
+            (guid, 1)
+        }
+    };
+    let signature = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as u32);
+
+    let publics = collect_publics(jmap, &image.sections);
+    let (symbols, publics) = public_streams(&publics);
+
+    let mut streams = vec![vec![]; STREAM_COUNT];
+    streams[STREAM_PDB as usize] = pdb_info_stream(&guid, age, signature);
+    streams[STREAM_TPI as usize] = type_stream();
+    streams[STREAM_DBI as usize] = dbi_stream(age, &image.sections);
+    streams[STREAM_IPI as usize] = type_stream();
+    streams[STREAM_GLOBALS as usize] = gsi_hash(&[]);
+    streams[STREAM_PUBLICS as usize] = publics;
+    streams[STREAM_SYMBOLS as usize] = symbols;
+    streams[STREAM_SECTION_HEADERS as usize] = section_headers_stream(&image.sections);
+    streams[STREAM_NAMES as usize] = string_table();
+
+    Ok(write_msf(&streams))
+}
@@ -0,0 +1,230 @@
+//! Names from the PDB of the dumped image, for the vtable slots, UFunction natives and their
+//! implementations it points into. The PDB is taken from `--symbols` (a file, or a directory laid out like a symbol store or
+//! holding PDBs by name), the path in the CodeView record if it exists on this machine, or a
+//! symbol server (`--symbol-server`). Only a PDB with the GUID and age of the image is used.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result, bail};
+use jmap::{CodeView, Jmap, Native, ObjectType};
+use pdb::{FallibleIterator, PDB, SymbolData};
+use tracing::{debug, info, warn};
+
+use crate::DumpOptions;
+
+pub struct Symbols {
+    /// RVA -> name, undecorated where the PDB has private symbols
+    names: HashMap<u32, String>,
+    image_base: u64,
+}
+
+impl Symbols {
+    /// Symbols of the image of `header` if a matching PDB is found. Failing to find or read one
+    /// isn't fatal to the dump, it is only logged.
+    pub fn find(header: &Jmap, options: &DumpOptions) -> Option<Self> {
+        let codeview = header.image.as_ref()?.codeview.as_ref()?;
+        let path = match locate(codeview, options) {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                debug!("no PDB found for {}", codeview.pdb_path);
+                return None;
+            }
+            Err(err) => {
+                warn!("failed to find PDB {}: {err:#}", codeview.pdb_path);
+                return None;
+            }
+        };
+        match read(&path, codeview) {
+            Ok(names) => {
+                info!("{} symbols from {}", names.len(), path.display());
+                Some(Self {
+                    names,
+                    image_base: header.image_base_address.0,
+                })
+            }
+            Err(err) => {
+                warn!("failed to read symbols from {}: {err:#}", path.display());
+                None
+            }
+        }
+    }
+
+    fn name(&self, rva: u64) -> Option<String> {
+        self.names.get(&u32::try_from(rva).ok()?).cloned()
+    }
+
+    /// Name the native of a function and the virtual functions and natives of a class
+    pub fn apply(&self, object: &mut ObjectType) {
+        match object {
+            ObjectType::Function(function) if function.func.0 != 0 => {
+                function.symbol = self.name(function.func.0.wrapping_sub(self.image_base));
+                if let Some(implementation) = &mut function.implementation
+                    && implementation.module.is_none()
+                {
+                    implementation.symbol = self.name(implementation.rva.0);
+                }
+            }
+            ObjectType::Class(class) => {
+                for function in &mut class.virtual_functions {
+                    if function.module.is_none() {
+                        function.symbol = self.name(function.rva.0);
+                    }
+                }
+                let natives = [
+                    &mut class.class_constructor,
+                    &mut class.vtable_helper_ctor_caller,
+                    &mut class.static_class,
+                ];
+                for function in natives.into_iter().flatten() {
+                    if function.module.is_none() {
+                        function.symbol = self.name(function.rva.0);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Name the handlers of GNatives
+    pub fn apply_natives(&self, natives: &mut [Native]) {
+        for native in natives {
+            if native.function.module.is_none() {
+                native.function.symbol = self.name(native.function.rva.0);
+            }
+        }
+    }
+}
+
+/// `<name>/<GUID><age>/<name>`, where symbol stores and servers keep a PDB
+fn store_path(codeview: &CodeView) -> Option<PathBuf> {
+    let name = pdb_name(codeview)?;
+    let key = format!("{}{:X}", codeview.guid.replace('-', ""), codeview.age);
+    Some(Path::new(name).join(key).join(name))
+}
+
+/// File name of the PDB, the CodeView path is usually a Windows path of the build machine
+fn pdb_name(codeview: &CodeView) -> Option<&str> {
+    codeview
+        .pdb_path
+        .rsplit(['\\', '/'])
+        .next()
+        .filter(|n| !n.is_empty())
+}
+
+fn locate(codeview: &CodeView, options: &DumpOptions) -> Result<Option<PathBuf>> {
+    if let Some(symbols) = &options.symbols {
+        if symbols.is_file() {
+            return Ok(Some(symbols.clone()));
+        }
+        let candidates = [store_path(codeview), pdb_name(codeview).map(PathBuf::from)];
+        if let Some(path) = candidates
+            .into_iter()
+            .flatten()
+            .map(|p| symbols.join(p))
+            .find(|p| p.is_file())
+        {
+            return Ok(Some(path));
+        }
+    }
+    let built = Path::new(&codeview.pdb_path);
+    if built.is_file() {
+        return Ok(Some(built.to_path_buf()));
+    }
+    match &options.symbol_server {
+        Some(server) => download(server, codeview, options).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Fetch the PDB from a symbol server into the cache directory (or a temporary one), unless an
+/// earlier run already did
+fn download(server: &str, codeview: &CodeView, options: &DumpOptions) -> Result<PathBuf> {
+    let Some(relative) = store_path(codeview) else {
+        bail!("CodeView record doesn't name a PDB");
+    };
+    let cache = match &options.cache_dir {
+        Some(dir) => dir.join("symbols"),
+        None => std::env::temp_dir().join("jmap_dumper").join("symbols"),
+    };
+    let path = cache.join(&relative);
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    let url = format!(
+        "{}/{}",
+        server.trim_end_matches('/'),
+        relative.to_string_lossy().replace('\\', "/")
+    );
+    info!("downloading {url}");
+    let response = ureq::get(&url)
+        .call()
+        .with_context(|| format!("Failed to fetch {url}"))?;
+
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    // written under another name first so an interrupted download isn't taken for a PDB later
+    let partial = path.with_extension("partial");
+    let mut file = File::create(&partial)?;
+    std::io::copy(&mut response.into_body().into_reader(), &mut file)
+        .with_context(|| format!("Failed to download {url}"))?;
+    drop(file);
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+fn read(path: &Path, codeview: &CodeView) -> Result<HashMap<u32, String>> {
+    let mut pdb = PDB::open(File::open(path)?)?;
+
+    let info = pdb.pdb_information()?;
+    let guid = info.guid.to_string();
+    if !guid.eq_ignore_ascii_case(&codeview.guid) {
+        bail!(
+            "GUID {} doesn't match the image's {}",
+            guid.to_uppercase(),
+            codeview.guid
+        );
+    }
+    // the PDB stream age is bumped by incremental links, the DBI stream has the image's
+    let dbi = pdb.debug_information()?;
+    if let Some(age) = dbi.age()
+        && age != codeview.age
+    {
+        bail!("age {age} doesn't match the image's {}", codeview.age);
+    }
+
+    let address_map = pdb.address_map()?;
+    let mut names = HashMap::new();
+
+    // procedures of the modules have undecorated names, the publics fill in the rest
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        let Some(module) = pdb.module_info(&module)? else {
+            continue;
+        };
+        let mut symbols = module.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(procedure)) = symbol.parse()
+                && let Some(rva) = procedure.offset.to_rva(&address_map)
+            {
+                names.insert(rva.0, procedure.name.to_string().into_owned());
+            }
+        }
+    }
+
+    let globals = pdb.global_symbols()?;
+    let mut symbols = globals.iter();
+    while let Some(symbol) = symbols.next()? {
+        if let Ok(SymbolData::Public(public)) = symbol.parse()
+            && public.function
+            && let Some(rva) = public.offset.to_rva(&address_map)
+        {
+            names
+                .entry(rva.0)
+                .or_insert_with(|| public.name.to_string().into_owned());
+        }
+    }
+
+    Ok(names)
+}
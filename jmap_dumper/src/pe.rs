@@ -0,0 +1,271 @@
+//! Minimal PE header parsing of the mapped image for section headers, the CodeView record and the
+//! exception directory
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use jmap::{Address, CodeView, ImageFormat, ImageInfo, ImageSection};
+
+use crate::mem::Mem;
+
+const IMAGE_DIRECTORY_ENTRY_EXCEPTION: usize = 3;
+const IMAGE_DIRECTORY_ENTRY_DEBUG: usize = 6;
+const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
+const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+pub fn format_guid(guid: &[u8; 16]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{}-{}",
+        u32_at(guid, 0),
+        u16_at(guid, 4),
+        u16_at(guid, 6),
+        guid[8..10]
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<String>(),
+        guid[10..]
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<String>(),
+    )
+}
+
+pub fn parse_guid(guid: &str) -> Result<[u8; 16]> {
+    let hex = guid.replace('-', "");
+    if hex.len() != 32 {
+        bail!("invalid GUID {guid:?}");
+    }
+    let mut bytes = [0; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)?;
+    }
+    // first three groups are stored little endian
+    bytes[0..4].reverse();
+    bytes[4..6].reverse();
+    bytes[6..8].reverse();
+    Ok(bytes)
+}
+
+pub fn read_image_info<M: Mem>(
+    mem: &M,
+    base: u64,
+    globals: BTreeMap<String, Address>,
+) -> Result<ImageInfo> {
+    let mut header = vec![0; 0x1000];
+    mem.read_buf(base, &mut header)?;
+
+    if &header[0..2] != b"MZ" {
+        bail!("missing DOS header at 0x{base:x}");
+    }
+    let nt = u32_at(&header, 0x3c) as usize;
+    if nt + 0x108 > header.len() || &header[nt..nt + 4] != b"PE\0\0" {
+        bail!("missing NT header at 0x{:x}", base + nt as u64);
+    }
+    let number_of_sections = u16_at(&header, nt + 6) as usize;
+    let size_of_optional_header = u16_at(&header, nt + 20) as usize;
+    let optional = nt + 24;
+    if u16_at(&header, optional) != 0x20b {
+        bail!("only PE32+ images are supported");
+    }
+
+    let sections_start = optional + size_of_optional_header;
+    let mut sections = vec![];
+    for i in 0..number_of_sections {
+        let s = sections_start + i * 40;
+        if s + 40 > header.len() {
+            bail!("section headers extend past the first page");
+        }
+        let name = &header[s..s + 8];
+        let name = &name[..name.iter().position(|b| *b == 0).unwrap_or(8)];
+        sections.push(ImageSection {
+            name: String::from_utf8_lossy(name).into_owned(),
+            virtual_size: u32_at(&header, s + 8),
+            virtual_address: u32_at(&header, s + 12),
+            size_of_raw_data: u32_at(&header, s + 16),
+            pointer_to_raw_data: u32_at(&header, s + 20),
+            characteristics: u32_at(&header, s + 36),
+        });
+    }
+
+    let debug_dir = optional + 112 + IMAGE_DIRECTORY_ENTRY_DEBUG * 8;
+    let debug_rva = u32_at(&header, debug_dir) as u64;
+    let debug_size = u32_at(&header, debug_dir + 4) as usize;
+
+    let mut codeview = None;
+    if debug_rva != 0 {
+        let mut entries = vec![0; debug_size];
+        mem.read_buf(base + debug_rva, &mut entries)?;
+        for entry in entries.chunks_exact(28) {
+            if u32_at(entry, 12) != IMAGE_DEBUG_TYPE_CODEVIEW {
+                continue;
+            }
+            let size = u32_at(entry, 16) as usize;
+            let rva = u32_at(entry, 20) as u64;
+            if size < 24 {
+                continue;
+            }
+            let mut data = vec![0; size];
+            mem.read_buf(base + rva, &mut data)?;
+            if &data[0..4] != b"RSDS" {
+                continue;
+            }
+            let path = &data[24..];
+            let path = &path[..path.iter().position(|b| *b == 0).unwrap_or(path.len())];
+            codeview = Some(CodeView {
+                guid: format_guid(data[4..20].try_into().unwrap()),
+                age: u32_at(&data, 20),
+                pdb_path: String::from_utf8_lossy(path).into_owned(),
+            });
+            break;
+        }
+    }
+
+    Ok(ImageInfo {
+        format: ImageFormat::Pe,
+        globals,
+        sections,
+        codeview,
+        modules: vec![],
+    })
+}
+
+/// `RUNTIME_FUNCTION` entry of the exception directory, RVAs of the code it covers and its
+/// `UNWIND_INFO`
+pub struct RuntimeFunction {
+    pub begin: u32,
+    pub end: u32,
+    pub unwind_info: u32,
+}
+
+/// Exception directory (`.pdata`) of the image mapped at `base`, sorted by address
+pub fn read_runtime_functions<M: Mem>(mem: &M, base: u64) -> Result<Vec<RuntimeFunction>> {
+    let mut header = vec![0; 0x1000];
+    mem.read_buf(base, &mut header)?;
+    if &header[0..2] != b"MZ" {
+        bail!("missing DOS header at 0x{base:x}");
+    }
+    let nt = u32_at(&header, 0x3c) as usize;
+    if nt + 0x108 > header.len() || &header[nt..nt + 4] != b"PE\0\0" {
+        bail!("missing NT header at 0x{:x}", base + nt as u64);
+    }
+    let optional = nt + 24;
+    if u16_at(&header, optional) != 0x20b {
+        bail!("only PE32+ images are supported");
+    }
+
+    let directory = optional + 112 + IMAGE_DIRECTORY_ENTRY_EXCEPTION * 8;
+    let rva = u32_at(&header, directory) as u64;
+    let size = u32_at(&header, directory + 4) as usize;
+    if rva == 0 {
+        bail!("image has no exception directory");
+    }
+    let mut table = vec![0; size - size % 12];
+    mem.read_buf(base + rva, &mut table)?;
+    let mut functions = table
+        .chunks_exact(12)
+        .map(|e| RuntimeFunction {
+            begin: u32_at(e, 0),
+            end: u32_at(e, 4),
+            unwind_info: u32_at(e, 8),
+        })
+        .collect::<Vec<_>>();
+    functions.sort_by_key(|f| f.begin);
+    Ok(functions)
+}
+
+/// RVA of the function the code at `rva` belongs to. Parts split off a function (e.g. cold paths)
+/// have entries of their own chained to the function's, which are followed back to it.
+pub fn function_start<M: Mem>(
+    mem: &M,
+    base: u64,
+    functions: &[RuntimeFunction],
+    rva: u32,
+) -> Option<u32> {
+    let i = functions
+        .partition_point(|f| f.begin <= rva)
+        .checked_sub(1)?;
+    let function = &functions[i];
+    if rva >= function.end {
+        return None;
+    }
+    let mut start = function.begin;
+    let mut unwind_info = function.unwind_info as u64;
+    // chains are a link or two long, anything longer is garbage
+    for _ in 0..8 {
+        let mut info = [0; 4];
+        mem.read_buf(base + unwind_info, &mut info).ok()?;
+        if (info[0] >> 3) & UNW_FLAG_CHAININFO == 0 {
+            break;
+        }
+        // the parent entry follows the unwind codes, padded to an even count
+        let codes = (info[2] as u64 + 1) & !1;
+        let mut parent = [0; 12];
+        mem.read_buf(base + unwind_info + 4 + codes * 2, &mut parent)
+            .ok()?;
+        start = u32_at(&parent, 0);
+        unwind_info = u32_at(&parent, 8) as u64;
+    }
+    Some(start)
+}
+
+/// Rebuild an on-disk layout of the image mapped at `base` (headers and each section's raw data at
+/// its file offset) for pattern scanning when there is no executable or minidump module to read.
+/// Pages missing from the source are left zeroed.
+pub fn read_image_file<M: Mem>(mem: &M, base: u64) -> Result<Vec<u8>> {
+    let mut header = vec![0; 0x1000];
+    mem.read_buf(base, &mut header)?;
+    if &header[0..2] != b"MZ" {
+        bail!("missing DOS header at 0x{base:x}");
+    }
+    let nt = u32_at(&header, 0x3c) as usize;
+    if nt + 0x108 > header.len() || &header[nt..nt + 4] != b"PE\0\0" {
+        bail!("missing NT header at 0x{:x}", base + nt as u64);
+    }
+    let number_of_sections = u16_at(&header, nt + 6) as usize;
+    let size_of_optional_header = u16_at(&header, nt + 20) as usize;
+    let optional = nt + 24;
+    let size_of_headers = (u32_at(&header, optional + 60) as usize).min(header.len());
+
+    let sections_start = optional + size_of_optional_header;
+    let mut sections = vec![];
+    for i in 0..number_of_sections {
+        let s = sections_start + i * 40;
+        if s + 40 > header.len() {
+            bail!("section headers extend past the first page");
+        }
+        let virtual_address = u32_at(&header, s + 12) as u64;
+        let size_of_raw_data = u32_at(&header, s + 16) as usize;
+        let pointer_to_raw_data = u32_at(&header, s + 20) as usize;
+        sections.push((virtual_address, size_of_raw_data, pointer_to_raw_data));
+    }
+
+    let file_size = sections
+        .iter()
+        .map(|(_, size, offset)| offset + size)
+        .max()
+        .unwrap_or(0)
+        .max(size_of_headers);
+    let mut file = vec![0; file_size];
+    file[..size_of_headers].copy_from_slice(&header[..size_of_headers]);
+
+    for (virtual_address, size, offset) in sections {
+        for page_offset in (0..size).step_by(0x1000) {
+            let len = (size - page_offset).min(0x1000);
+            let dst = &mut file[offset + page_offset..offset + page_offset + len];
+            if mem
+                .read_buf(base + virtual_address + page_offset as u64, dst)
+                .is_err()
+            {
+                dst.fill(0);
+            }
+        }
+    }
+    Ok(file)
+}
@@ -1,25 +1,93 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use jmap::Module;
+
+/// A running process as listed by [`list_processes`]
+#[derive(Debug, Clone)]
+pub struct ProcessEntry {
+    pub pid: i32,
+    /// Executable file name, e.g. `FSD-Win64-Shipping.exe`
+    pub name: String,
+    /// Titles of the process' visible top level windows (Windows only)
+    pub window_titles: Vec<String>,
+}
+
+/// Module name fragments of Unreal Engine builds: `<Project>-Win64-Shipping.exe` style targets,
+/// modular editor builds and libraries that ship with the engine
+const UNREAL_MODULE_MARKERS: &[&str] = &[
+    "-win64-shipping",
+    "-win64-test",
+    "-win64-development",
+    "-wingdk-shipping",
+    "-linux-shipping",
+    "unrealeditor",
+    "ue4editor",
+    "ue4game",
+    "eossdk-win64-shipping",
+];
+
+pub(crate) fn is_unreal_module(name: &str) -> bool {
+    let name = name.to_lowercase();
+    UNREAL_MODULE_MARKERS
+        .iter()
+        .any(|marker| name.contains(marker))
+}
 
 #[cfg(target_os = "windows")]
-pub fn get_process_name(pid: i32) -> Result<String> {
+pub(crate) struct HandleGuard(pub(crate) windows::Win32::Foundation::HANDLE);
+
+#[cfg(target_os = "windows")]
+impl Drop for HandleGuard {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = windows::Win32::Foundation::CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn list_processes() -> Result<Vec<ProcessEntry>> {
     use anyhow::Context;
+    use std::collections::HashMap;
     use std::mem;
-    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Foundation::{HWND, LPARAM};
     use windows::Win32::System::Diagnostics::ToolHelp::{
         CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW,
         TH32CS_SNAPPROCESS,
     };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+    };
+    use windows::core::BOOL;
 
-    struct HandleGuard(HANDLE);
-
-    impl Drop for HandleGuard {
-        fn drop(&mut self) {
-            unsafe {
-                let _ = CloseHandle(self.0);
+    unsafe extern "system" fn collect_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let titles = unsafe { &mut *(lparam.0 as *mut HashMap<u32, Vec<String>>) };
+        unsafe {
+            if IsWindowVisible(hwnd).as_bool() {
+                let mut buf = [0u16; 512];
+                let len = GetWindowTextW(hwnd, &mut buf);
+                if len > 0 {
+                    let mut pid = 0;
+                    GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32));
+                    titles
+                        .entry(pid)
+                        .or_default()
+                        .push(String::from_utf16_lossy(&buf[..len as usize]));
+                }
             }
         }
+        true.into()
     }
 
+    let mut titles = HashMap::<u32, Vec<String>>::new();
+    unsafe {
+        // best effort, processes are still listed without titles
+        let _ = EnumWindows(
+            Some(collect_window),
+            LPARAM(&mut titles as *mut HashMap<u32, Vec<String>> as isize),
+        );
+    }
+
+    let mut processes = vec![];
     unsafe {
         let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
             .context("Failed to get process list snapshot")?;
@@ -30,16 +98,17 @@ pub fn get_process_name(pid: i32) -> Result<String> {
 
         if Process32FirstW(snapshot, &mut entry).is_ok() {
             loop {
-                if entry.th32ProcessID == pid as u32 {
-                    let len = entry
-                        .szExeFile
-                        .iter()
-                        .position(|&c| c == 0)
-                        .unwrap_or(entry.szExeFile.len());
-
-                    let exe_name = String::from_utf16_lossy(&entry.szExeFile[..len]);
-                    return Ok(exe_name);
-                }
+                let len = entry
+                    .szExeFile
+                    .iter()
+                    .position(|&c| c == 0)
+                    .unwrap_or(entry.szExeFile.len());
+
+                processes.push(ProcessEntry {
+                    pid: entry.th32ProcessID as i32,
+                    name: String::from_utf16_lossy(&entry.szExeFile[..len]),
+                    window_titles: titles.remove(&entry.th32ProcessID).unwrap_or_default(),
+                });
 
                 if Process32NextW(snapshot, &mut entry).is_err() {
                     break;
@@ -47,32 +116,223 @@ pub fn get_process_name(pid: i32) -> Result<String> {
             }
         }
     }
-    anyhow::bail!("Process {pid} not found")
+    Ok(processes)
 }
 
 #[cfg(target_os = "linux")]
-pub fn get_process_name(pid: i32) -> Result<String> {
-    use anyhow::Context;
+pub fn list_processes() -> Result<Vec<ProcessEntry>> {
+    let mut processes = vec![];
+    for entry in std::fs::read_dir("/proc")? {
+        let entry = entry?;
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        // argv[0] has the full .exe name under Proton/Wine where comm is truncated
+        let cmdline = std::fs::read(entry.path().join("cmdline")).unwrap_or_default();
+        let argv0 = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+        let argv0 = String::from_utf8_lossy(argv0);
+        let name = match argv0.rsplit(['\\', '/']).next().filter(|n| !n.is_empty()) {
+            Some(name) => name.to_string(),
+            None => match std::fs::read_to_string(entry.path().join("comm")) {
+                Ok(comm) => comm.trim_end().to_string(),
+                Err(_) => continue,
+            },
+        };
+        processes.push(ProcessEntry {
+            pid,
+            name,
+            window_titles: vec![],
+        });
+    }
+    processes.sort_by_key(|p| p.pid);
+    Ok(processes)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn list_processes() -> Result<Vec<ProcessEntry>> {
+    bail!("Unimplemented for target: {}", std::env::consts::OS)
+}
+
+/// Modules loaded in `pid`, main executable first
+#[cfg(target_os = "windows")]
+pub(crate) fn list_modules(pid: i32) -> Result<Vec<Module>> {
+    use std::mem;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, MODULEENTRY32W, Module32FirstW, Module32NextW, TH32CS_SNAPMODULE,
+        TH32CS_SNAPMODULE32,
+    };
 
-    let maps = proc_maps::get_process_maps(pid)
-        .with_context(|| format!("Failed to read proc maps for {pid}"))?;
-
-    for map in maps {
-        if let Some(name) = map.filename() {
-            let name = name.to_string_lossy();
-            if name.ends_with(".exe") {
-                return Ok(name
-                    .rsplit_once(['\\', '/'])
-                    .map(|s| s.1)
-                    .unwrap_or(&name)
-                    .to_string());
+    let mut modules = vec![];
+    unsafe {
+        let snapshot =
+            CreateToolhelp32Snapshot(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, pid as u32)?;
+        let _guard = HandleGuard(snapshot);
+
+        let mut entry: MODULEENTRY32W = mem::zeroed();
+        entry.dwSize = mem::size_of::<MODULEENTRY32W>() as u32;
+
+        if Module32FirstW(snapshot, &mut entry).is_ok() {
+            loop {
+                let len = entry
+                    .szModule
+                    .iter()
+                    .position(|&c| c == 0)
+                    .unwrap_or(entry.szModule.len());
+                modules.push(Module {
+                    name: String::from_utf16_lossy(&entry.szModule[..len]),
+                    base: (entry.modBaseAddr as u64).into(),
+                    size: entry.modBaseSize as u64,
+                });
+
+                if Module32NextW(snapshot, &mut entry).is_err() {
+                    break;
+                }
             }
         }
     }
-    anyhow::bail!("Failed to find proc exe name for {pid}")
+    Ok(modules)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn list_modules(pid: i32) -> Result<Vec<Module>> {
+    let main = crate::linux::main_module(pid)?;
+    let to_module = |m: crate::linux::Module| Module {
+        name: m.name,
+        base: m.base.into(),
+        size: m.size,
+    };
+    let mut modules: Vec<_> = crate::linux::modules(pid)?
+        .into_iter()
+        .filter(|m| m.path != main.path)
+        .map(to_module)
+        .collect();
+    modules.insert(0, to_module(main));
+    Ok(modules)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub(crate) fn list_modules(_pid: i32) -> Result<Vec<Module>> {
+    bail!("Unimplemented for target: {}", std::env::consts::OS)
+}
+
+/// Name and base address of the main executable of `pid`
+pub fn main_module(pid: i32) -> Result<(String, u64)> {
+    match list_modules(pid)?.into_iter().next() {
+        Some(module) => Ok((module.name, module.base.0)),
+        None => bail!("Process {pid} has no modules"),
+    }
+}
+
+/// Running processes that look like Unreal Engine games, judged by the executable name and
+/// otherwise by their loaded modules. Processes whose modules can't be listed (e.g. elevated
+/// ones) are skipped unless the name already matches.
+pub fn list_unreal_processes() -> Result<Vec<ProcessEntry>> {
+    Ok(list_processes()?
+        .into_iter()
+        .filter(|p| {
+            is_unreal_module(&p.name)
+                || list_modules(p.pid)
+                    .is_ok_and(|modules| modules.iter().any(|m| is_unreal_module(&m.name)))
+        })
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+pub fn get_process_name(pid: i32) -> Result<String> {
+    match list_processes()?.into_iter().find(|p| p.pid == pid) {
+        Some(process) => Ok(process.name),
+        None => bail!("Process {pid} not found"),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn get_process_name(pid: i32) -> Result<String> {
+    Ok(crate::linux::main_module(pid)?.name)
 }
 
 #[cfg(not(any(target_os = "windows", target_os = "linux")))]
 pub fn get_process_name(_pid: i32) -> Result<String> {
-    anyhow::bail!("Unimplemented for target: {}", std::env::consts::OS)
+    bail!("Unimplemented for target: {}", std::env::consts::OS)
+}
+
+/// When `pid` was started, in the platform's own units, to tell it apart from a later process
+/// reusing the PID
+#[cfg(target_os = "windows")]
+pub(crate) fn start_time(pid: i32) -> Result<u64> {
+    use anyhow::Context;
+    use windows::Win32::Foundation::FILETIME;
+    use windows::Win32::System::Threading::{
+        GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid as u32)
+            .with_context(|| format!("Failed to open process {pid}"))?;
+        let _process = HandleGuard(process);
+        let [mut creation, mut exit, mut kernel, mut user] = [FILETIME::default(); 4];
+        GetProcessTimes(process, &mut creation, &mut exit, &mut kernel, &mut user)
+            .with_context(|| format!("Failed to get the times of process {pid}"))?;
+        Ok(((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn start_time(pid: i32) -> Result<u64> {
+    use anyhow::Context;
+
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .with_context(|| format!("Failed to read stat of {pid}"))?;
+    // starttime is the 22nd field, the 2nd (comm) being parenthesized and free to hold spaces
+    stat.rsplit_once(')')
+        .and_then(|(_, fields)| fields.split_whitespace().nth(19))
+        .and_then(|start| start.parse().ok())
+        .with_context(|| format!("Malformed stat of {pid}"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub(crate) fn start_time(_pid: i32) -> Result<u64> {
+    bail!("Unimplemented for target: {}", std::env::consts::OS)
+}
+
+/// Find a single process by executable name (`.exe` optional, case insensitive) or, failing
+/// that, by a substring of one of its window titles
+pub fn find_process(query: &str) -> Result<ProcessEntry> {
+    let processes = list_processes()?;
+    let query_lower = query.to_lowercase();
+
+    let by_name: Vec<_> = processes
+        .iter()
+        .filter(|p| {
+            let name = p.name.to_lowercase();
+            name == query_lower || name.strip_suffix(".exe") == Some(query_lower.as_str())
+        })
+        .collect();
+    let candidates = if by_name.is_empty() {
+        processes
+            .iter()
+            .filter(|p| {
+                p.window_titles
+                    .iter()
+                    .any(|t| t.to_lowercase().contains(&query_lower))
+            })
+            .collect()
+    } else {
+        by_name
+    };
+
+    match candidates.as_slice() {
+        [process] => Ok((*process).clone()),
+        [] => bail!("No process named {query:?} or with a window title containing it"),
+        _ => {
+            let list = candidates
+                .iter()
+                .map(|p| match p.window_titles.first() {
+                    Some(title) => format!("  {:>6} {} ({title})", p.pid, p.name),
+                    None => format!("  {:>6} {}", p.pid, p.name),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            bail!("Multiple processes match {query:?}, pass --pid instead:\n{list}")
+        }
+    }
 }
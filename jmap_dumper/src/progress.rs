@@ -0,0 +1,52 @@
+//! Progress bars for the passes over GUObjectArray. They draw to stderr and hide themselves when
+//! it isn't a terminal, so piped output stays clean.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use jmap::split::package_of;
+
+pub struct Progress {
+    bar: ProgressBar,
+    bytes_read: Arc<AtomicU64>,
+}
+
+impl Progress {
+    /// Bar for `len` objects, showing the running total of `bytes_read`. Hidden unless `enabled`.
+    pub fn new(enabled: bool, stage: &str, len: u64, bytes_read: Arc<AtomicU64>) -> Self {
+        let bar = if enabled {
+            ProgressBar::new(len)
+        } else {
+            ProgressBar::hidden()
+        };
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{prefix} [{elapsed_precise}] {wide_bar} {pos}/{len} (ETA {eta}) {msg}",
+            )
+            .unwrap(),
+        );
+        bar.set_prefix(stage.to_string());
+        Self { bar, bytes_read }
+    }
+
+    /// One more object done, `path` being the object just read if there was one
+    pub fn inc(&self, path: Option<&str>) {
+        self.bar.inc(1);
+        if let Some(path) = path
+            && !self.bar.is_hidden()
+        {
+            self.bar.set_message(format!(
+                "{} read, {}",
+                HumanBytes(self.bytes_read.load(Ordering::Relaxed)),
+                package_of(path)
+            ));
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,687 @@
+//! Small query language over the objects of a dump, for answering questions about huge dumps
+//! without writing scripts:
+//!
+//! ```text
+//! classes where super == "Actor" and properties.count > 10 select name, properties.count
+//! enums where names.count > 100 sort names.count desc limit 10
+//! functions where function_flags contains "FUNC_Exec" select path, func
+//! ```
+//!
+//! ```text
+//! query  = source ["where" expr] ["select" field {"," field}] ["sort" field ["desc"]] ["limit" N]
+//! source = "objects" | "classes" | "structs" | "enums" | "functions" | "packages"
+//! expr   = term {"or" term}
+//! term   = factor {"and" factor}
+//! factor = "not" factor | "(" expr ")" | field [op literal]
+//! op     = "==" | "!=" | "<" | "<=" | ">" | ">=" | "~" (regex) | "contains"
+//! field  = name {"." name}
+//! ```
+//!
+//! Fields are looked up in the dump's JSON form of each object (e.g. `super_struct`,
+//! `properties`, `class_flags`) along with `path`, `name`, `package` and `super`. `count` gives
+//! the length of a list, and any other name applied to a list is looked up in each of its
+//! elements, so `properties.name` is the list of property names. Comparisons against a list are
+//! true if any element matches, a field without comparison if it's non-empty, and `==` matches
+//! paths by their full path or just their name.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use anyhow::{Context as _, Result, bail};
+use jmap::{Jmap, ObjectType, split::package_of};
+use regex::Regex;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Objects,
+    Classes,
+    Structs,
+    Enums,
+    Functions,
+    Packages,
+}
+
+impl Source {
+    fn contains(self, obj: &ObjectType) -> bool {
+        match self {
+            Source::Objects => true,
+            Source::Classes => matches!(obj, ObjectType::Class(_)),
+            Source::Structs => matches!(obj, ObjectType::ScriptStruct(_)),
+            Source::Enums => matches!(obj, ObjectType::Enum(_)),
+            Source::Functions => matches!(obj, ObjectType::Function(_)),
+            Source::Packages => matches!(obj, ObjectType::Package(_)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Truthy(Field),
+    Compare(Field, Op, Value),
+    Match(Field, Regex),
+}
+
+#[derive(Debug, Clone)]
+struct Field(Vec<String>);
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join("."))
+    }
+}
+
+#[derive(Debug)]
+pub struct Query {
+    source: Source,
+    filter: Option<Expr>,
+    select: Vec<Field>,
+    sort: Option<(Field, bool)>,
+    limit: Option<usize>,
+}
+
+/// Result of a query, one row of selected values per matching object
+#[derive(Debug)]
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Rows {
+    /// Rows as a list of objects keyed by column
+    pub fn to_json(&self) -> Value {
+        self.rows
+            .iter()
+            .map(|row| {
+                Value::Object(
+                    self.columns
+                        .iter()
+                        .cloned()
+                        .zip(row.iter().cloned())
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Rows {
+    /// Tab separated values, one row per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.rows {
+            for (i, value) in row.iter().enumerate() {
+                if i > 0 {
+                    f.write_str("\t")?;
+                }
+                match value {
+                    Value::String(s) => f.write_str(s)?,
+                    value => write!(f, "{value}")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(Value),
+    Op(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => f.write_str(ident),
+            Token::Literal(value) => write!(f, "{value}"),
+            Token::Op(op) => f.write_str(op),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    const OPS: [&str; 11] = ["==", "!=", "<=", ">=", "<", ">", "~", "(", ")", ",", "."];
+
+    let mut tokens = vec![];
+    let mut rest = query.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, c)) => value.push(c),
+                        None => bail!("Unterminated string in query"),
+                    },
+                    Some((_, c)) => value.push(c),
+                    None => bail!("Unterminated string in query"),
+                }
+            };
+            tokens.push(Token::Literal(Value::String(value)));
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() || c == '-' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+                .unwrap_or(rest.len());
+            let number = &rest[..end];
+            let value = if let Some(hex) = number.strip_prefix("0x") {
+                u64::from_str_radix(hex, 16).ok().map(Value::from)
+            } else if let Ok(int) = number.parse::<i64>() {
+                Some(Value::from(int))
+            } else {
+                number.parse::<f64>().ok().map(Value::from)
+            };
+            tokens.push(Token::Literal(
+                value.with_context(|| format!("Invalid number {number:?} in query"))?,
+            ));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(match &rest[..end] {
+                "true" => Token::Literal(Value::Bool(true)),
+                "false" => Token::Literal(Value::Bool(false)),
+                "null" => Token::Literal(Value::Null),
+                ident => Token::Ident(ident.to_string()),
+            });
+            rest = &rest[end..];
+        } else if let Some(op) = OPS.into_iter().find(|op| rest.starts_with(op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            bail!("Unexpected {c:?} in query");
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn op(&mut self, op: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Op(o)) if *o == op);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expected(&self, what: &str) -> anyhow::Error {
+        match self.peek() {
+            Some(token) => anyhow::anyhow!("Expected {what} in query, found {token}"),
+            None => anyhow::anyhow!("Expected {what} at the end of the query"),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        let source = match self.next() {
+            Some(Token::Ident(source)) => match source.as_str() {
+                "objects" => Source::Objects,
+                "classes" => Source::Classes,
+                "structs" => Source::Structs,
+                "enums" => Source::Enums,
+                "functions" => Source::Functions,
+                "packages" => Source::Packages,
+                _ => bail!(
+                    "Unknown source {source:?}, expected objects, classes, structs, enums, \
+                     functions or packages"
+                ),
+            },
+            _ => {
+                self.pos -= 1;
+                return Err(self.expected("a source like classes"));
+            }
+        };
+        let filter = self.keyword("where").then(|| self.expr()).transpose()?;
+        let mut select = vec![];
+        if self.keyword("select") {
+            select.push(self.field()?);
+            while self.op(",") {
+                select.push(self.field()?);
+            }
+        } else {
+            select.push(Field(vec!["path".into()]));
+        }
+        let sort = if self.keyword("sort") {
+            let field = self.field()?;
+            let descending = self.keyword("desc");
+            if !descending {
+                self.keyword("asc");
+            }
+            Some((field, descending))
+        } else {
+            None
+        };
+        let limit = if self.keyword("limit") {
+            let Some(Token::Literal(Value::Number(n))) = self.next() else {
+                self.pos -= 1;
+                return Err(self.expected("a row count after limit"));
+            };
+            Some(
+                n.as_u64()
+                    .context("Row count after limit must be positive")? as usize,
+            )
+        } else {
+            None
+        };
+        if self.peek().is_some() {
+            return Err(self.expected("where, select, sort or limit"));
+        }
+        Ok(Query {
+            source,
+            filter,
+            select,
+            sort,
+            limit,
+        })
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut expr = self.term()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut expr = self.factor()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.factor()?)));
+        }
+        if self.op("(") {
+            let expr = self.expr()?;
+            if !self.op(")") {
+                return Err(self.expected("\")\""));
+            }
+            return Ok(expr);
+        }
+        let field = self.field()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => Op::Eq,
+            Some(Token::Op("!=")) => Op::Ne,
+            Some(Token::Op("<")) => Op::Lt,
+            Some(Token::Op("<=")) => Op::Le,
+            Some(Token::Op(">")) => Op::Gt,
+            Some(Token::Op(">=")) => Op::Ge,
+            Some(Token::Op("~")) => {
+                self.pos += 1;
+                let Some(Token::Literal(Value::String(pattern))) = self.next() else {
+                    self.pos -= 1;
+                    return Err(self.expected("a regex string after ~"));
+                };
+                let regex = Regex::new(&pattern)
+                    .with_context(|| format!("Invalid regex {pattern:?} in query"))?;
+                return Ok(Expr::Match(field, regex));
+            }
+            Some(Token::Ident(ident)) if ident == "contains" => Op::Contains,
+            _ => return Ok(Expr::Truthy(field)),
+        };
+        self.pos += 1;
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(Expr::Compare(field, op, value)),
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("a string, number, true, false or null"))
+            }
+        }
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        let mut segments = vec![];
+        loop {
+            match self.next() {
+                Some(Token::Ident(segment)) => segments.push(segment),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.expected("a field name"));
+                }
+            }
+            if !self.op(".") {
+                return Ok(Field(segments));
+            }
+        }
+    }
+}
+
+fn short_name(path: &str) -> &str {
+    path.rsplit(['/', '.', ':']).next().unwrap()
+}
+
+/// JSON form of an object with the fields only known from its path added
+fn to_value(path: &str, obj: &ObjectType) -> Result<Value> {
+    let mut value = serde_json::to_value(obj)?;
+    if let Value::Object(map) = &mut value {
+        map.insert("path".into(), path.into());
+        map.insert("name".into(), short_name(path).into());
+        map.insert("package".into(), package_of(path).into());
+        if let Some(super_struct) = obj.get_struct().and_then(|s| s.super_struct.as_deref()) {
+            map.insert("super".into(), super_struct.into());
+        }
+    }
+    Ok(value)
+}
+
+fn get(value: &Value, segments: &[String]) -> Value {
+    let Some((segment, rest)) = segments.split_first() else {
+        return value.clone();
+    };
+    match value {
+        Value::Object(map) if map.contains_key(segment) => get(&map[segment], rest),
+        Value::Object(map) if segment == "count" => get(&Value::from(map.len()), rest),
+        Value::Array(items) if segment == "count" => get(&Value::from(items.len()), rest),
+        Value::String(s) if segment == "count" => get(&Value::from(s.chars().count()), rest),
+        Value::Array(items) => Value::Array(items.iter().map(|i| get(i, segments)).collect()),
+        _ => Value::Null,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn equals(value: &Value, literal: &Value) -> bool {
+    match (value, literal) {
+        (Value::String(a), Value::String(b)) => a == b || short_name(a) == b,
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(items), literal) => items.iter().any(|i| equals(i, literal)),
+        (a, b) => a == b,
+    }
+}
+
+fn order(value: &Value, literal: &Value) -> Option<Ordering> {
+    match (value, literal) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.as_str().cmp(b)),
+        _ => None,
+    }
+}
+
+fn compare(value: &Value, op: Op, literal: &Value) -> bool {
+    match (value, op) {
+        (_, Op::Eq) => equals(value, literal),
+        (_, Op::Ne) => !equals(value, literal),
+        (Value::String(s), Op::Contains) => literal.as_str().is_some_and(|l| s.contains(l)),
+        (Value::Array(items), Op::Contains) => items.iter().any(|i| equals(i, literal)),
+        (Value::Object(map), Op::Contains) => literal.as_str().is_some_and(|l| map.contains_key(l)),
+        (_, Op::Contains) => false,
+        (Value::Array(items), op) => items.iter().any(|i| compare(i, op, literal)),
+        (value, op) => order(value, literal).is_some_and(|o| match op {
+            Op::Lt => o.is_lt(),
+            Op::Le => o.is_le(),
+            Op::Gt => o.is_gt(),
+            Op::Ge => o.is_ge(),
+            _ => unreachable!(),
+        }),
+    }
+}
+
+fn matches_regex(value: &Value, regex: &Regex) -> bool {
+    match value {
+        Value::String(s) => regex.is_match(s),
+        Value::Array(items) => items.iter().any(|i| matches_regex(i, regex)),
+        _ => false,
+    }
+}
+
+impl Expr {
+    fn eval(&self, value: &Value) -> bool {
+        match self {
+            Expr::Or(a, b) => a.eval(value) || b.eval(value),
+            Expr::And(a, b) => a.eval(value) && b.eval(value),
+            Expr::Not(expr) => !expr.eval(value),
+            Expr::Truthy(field) => truthy(&get(value, &field.0)),
+            Expr::Compare(field, op, literal) => compare(&get(value, &field.0), *op, literal),
+            Expr::Match(field, regex) => matches_regex(&get(value, &field.0), regex),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self> {
+        Parser {
+            tokens: tokenize(query)?,
+            pos: 0,
+        }
+        .query()
+    }
+
+    pub fn run(&self, jmap: &Jmap) -> Result<Rows> {
+        let mut matched = vec![];
+        for (path, obj) in &jmap.objects {
+            if !self.source.contains(obj) {
+                continue;
+            }
+            let value = to_value(path, obj)?;
+            if self.filter.as_ref().is_none_or(|f| f.eval(&value)) {
+                matched.push(value);
+            }
+        }
+        if let Some((field, descending)) = &self.sort {
+            matched.sort_by(|a, b| {
+                let o = order(&get(a, &field.0), &get(b, &field.0)).unwrap_or(Ordering::Equal);
+                if *descending { o.reverse() } else { o }
+            });
+        }
+        if let Some(limit) = self.limit {
+            matched.truncate(limit);
+        }
+        Ok(Rows {
+            columns: self.select.iter().map(|f| f.to_string()).collect(),
+            rows: matched
+                .iter()
+                .map(|value| self.select.iter().map(|f| get(value, &f.0)).collect())
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{dump, property};
+    use serde_json::json;
+
+    fn filter(query: &str) -> Expr {
+        Query::parse(&format!("objects where {query}"))
+            .unwrap()
+            .filter
+            .unwrap()
+    }
+
+    #[test]
+    fn test_tokenize() -> Result<()> {
+        assert_eq!(
+            tokenize(r#"a.b >= -1.5 ~ "x\"y" 0x10 3 null,"#)?,
+            [
+                Token::Ident("a".into()),
+                Token::Op("."),
+                Token::Ident("b".into()),
+                Token::Op(">="),
+                Token::Literal(json!(-1.5)),
+                Token::Op("~"),
+                Token::Literal(json!("x\"y")),
+                Token::Literal(json!(16)),
+                Token::Literal(json!(3)),
+                Token::Literal(Value::Null),
+                Token::Op(","),
+            ]
+        );
+        assert!(tokenize(r#"name == "open"#).is_err());
+        assert!(tokenize("name == 0xzz").is_err());
+        assert!(tokenize("name = 1").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let query = Query::parse("enums where names.count > 100 sort names.count desc limit 10")?;
+        assert_eq!(query.source, Source::Enums);
+        assert!(matches!(query.filter, Some(Expr::Compare(_, Op::Gt, _))));
+        assert_eq!(query.select[0].to_string(), "path");
+        let (sort, descending) = query.sort.unwrap();
+        assert_eq!((sort.to_string(), descending), ("names.count".into(), true));
+        assert_eq!(query.limit, Some(10));
+
+        // and binds tighter than or, not tighter than and
+        let Expr::Or(a, b) = filter("a or not b and c") else {
+            panic!("expected or");
+        };
+        assert!(matches!(*a, Expr::Truthy(_)));
+        let Expr::And(b, c) = *b else {
+            panic!("expected and");
+        };
+        assert!(matches!(*b, Expr::Not(_)));
+        assert!(matches!(*c, Expr::Truthy(_)));
+        assert!(matches!(filter("(a or b) and c"), Expr::And(_, _)));
+
+        for (query, error) in [
+            ("", "Expected a source like classes at the end of the query"),
+            ("things", "Unknown source \"things\""),
+            (
+                "classes where",
+                "Expected a field name at the end of the query",
+            ),
+            ("classes where (a", "Expected \")\" at the end of the query"),
+            (
+                "classes where a ==",
+                "Expected a string, number, true, false or null",
+            ),
+            ("classes where a ~ 1", "Expected a regex string after ~"),
+            ("classes where a ~ \"(\"", "Invalid regex"),
+            ("classes limit -1", "Row count after limit must be positive"),
+            (
+                "classes select name name",
+                "Expected where, select, sort or limit",
+            ),
+        ] {
+            let err = Query::parse(query).unwrap_err().to_string();
+            assert!(err.starts_with(error), "{query:?}: {err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval() {
+        let value = json!({
+            "path": "/Script/Game.Player",
+            "flags": "CLASS_Native | CLASS_Config",
+            "properties": [
+                { "name": "Health", "offset": 16 },
+                { "name": "Ammo", "offset": 20 },
+            ],
+            "empty": [],
+        });
+        let eval = |query: &str| filter(query).eval(&value);
+        assert!(eval("path == \"Player\""));
+        assert!(eval("path == \"/Script/Game.Player\""));
+        assert!(!eval("path == \"Game\""));
+        assert!(eval("properties.count == 2"));
+        assert!(eval("properties.name == \"Ammo\""));
+        assert!(eval("properties.offset > 18"));
+        assert!(!eval("properties.offset > 20"));
+        assert!(eval("properties.name contains \"Health\""));
+        assert!(eval("flags contains \"CLASS_Config\""));
+        assert!(eval("properties.name ~ \"^He\""));
+        assert!(eval("properties and not empty"));
+        assert!(!eval("missing or empty"));
+        assert!(eval("path.count == 19"));
+        assert!(eval("path < \"/Script/Z\""));
+    }
+
+    #[test]
+    fn test_run() -> Result<()> {
+        let jmap = dump(json!({
+            "/Script/Engine.Actor": { "type": "Class" },
+            "/Script/Game.Player": {
+                "type": "Class",
+                "super_struct": "/Script/Engine.Actor",
+                "properties": [
+                    property("Health", 0x10, 4, json!({ "type": "FloatProperty" })),
+                    property("Ammo", 0x14, 4, json!({ "type": "IntProperty" })),
+                ],
+            },
+            "/Script/Game.Door": {
+                "type": "Class",
+                "super_struct": "/Script/Engine.Actor",
+            },
+            "/Script/Game.Pickup": {
+                "super_struct": "/Script/Engine.Actor",
+            },
+        }));
+        let rows = Query::parse("classes where super == \"Actor\" select name, properties.count")?
+            .run(&jmap)?;
+        assert_eq!(rows.columns, ["name", "properties.count"]);
+        assert_eq!(rows.to_string(), "Door\t0\nPlayer\t2\n");
+        assert_eq!(
+            rows.to_json(),
+            json!([
+                { "name": "Door", "properties.count": 0 },
+                { "name": "Player", "properties.count": 2 },
+            ])
+        );
+
+        let rows = Query::parse("objects select package, name sort properties.count desc limit 2")?
+            .run(&jmap)?;
+        assert_eq!(
+            rows.to_string(),
+            "/Script/Game\tPlayer\n/Script/Engine\tActor\n"
+        );
+        Ok(())
+    }
+}
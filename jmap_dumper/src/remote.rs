@@ -0,0 +1,136 @@
+//! Client side of the [`jmap_remote`] protocol: [`RemoteMem`] reads through `jmap_agent` over
+//! TCP or the injected `jmap_inject` agent over a named pipe, and [`serve`] is the agent side
+//! for any [`Mem`].
+
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result, bail};
+pub use jmap_remote::{DEFAULT_PORT, RemoteInfo};
+use jmap_remote::{
+    INFO, MAGIC, MAX_READ, PATH_NAME, READ, STATUS_ERROR, STATUS_OK, VERSION, read_string, read_u64,
+};
+
+use crate::mem::Mem;
+
+struct Connection {
+    reader: BufReader<Box<dyn Read + Send>>,
+    writer: BufWriter<Box<dyn Write + Send>>,
+}
+
+impl Connection {
+    /// Read the status byte of a response, turning an error response into `Err`
+    fn status(&mut self) -> Result<()> {
+        let mut status = [0];
+        self.reader.read_exact(&mut status)?;
+        match status[0] {
+            STATUS_OK => Ok(()),
+            STATUS_ERROR => bail!("agent: {}", read_string(&mut self.reader)?),
+            other => bail!("agent sent invalid status {other}"),
+        }
+    }
+}
+
+/// [`Mem`] backed by a `jmap_agent` over TCP or an injected agent over a pipe
+#[derive(Clone)]
+pub struct RemoteMem {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl RemoteMem {
+    /// Perform the handshake over an already connected stream
+    pub(crate) fn handshake(
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<(Self, RemoteInfo)> {
+        let mut conn = Connection {
+            reader: BufReader::new(reader),
+            writer: BufWriter::new(writer),
+        };
+        conn.writer.write_all(MAGIC)?;
+        conn.writer.write_all(&VERSION.to_le_bytes())?;
+        conn.writer.write_all(&[INFO])?;
+        conn.writer.flush()?;
+        conn.status()?;
+        let info = RemoteInfo {
+            image_base: read_u64(&mut conn.reader)?,
+            name: read_string(&mut conn.reader)?,
+        };
+
+        Ok((
+            Self {
+                conn: Arc::new(Mutex::new(conn)),
+            },
+            info,
+        ))
+    }
+
+    /// Connect to an agent at `address` (`host` or `host:port`)
+    pub fn connect(address: &str) -> Result<(Self, RemoteInfo)> {
+        let stream = if address.contains(':') {
+            TcpStream::connect(address)
+        } else {
+            TcpStream::connect((address, DEFAULT_PORT))
+        }
+        .with_context(|| format!("Failed to connect to agent at {address}"))?;
+        stream.set_nodelay(true)?;
+        Self::handshake(Box::new(stream.try_clone()?), Box::new(stream))
+    }
+
+    /// Path of the UObject at `address` according to the engine's own `GetPathName`
+    pub fn path_name(&self, address: u64) -> Result<String> {
+        let mut conn = self.conn.lock().unwrap();
+        conn.writer.write_all(&[PATH_NAME])?;
+        conn.writer.write_all(&address.to_le_bytes())?;
+        conn.writer.flush()?;
+        conn.status()?;
+        Ok(read_string(&mut conn.reader)?)
+    }
+}
+
+impl Mem for RemoteMem {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        for (i, chunk) in buf.chunks_mut(MAX_READ as usize).enumerate() {
+            let chunk_address = address + (i * MAX_READ as usize) as u64;
+            conn.writer.write_all(&[READ])?;
+            conn.writer.write_all(&chunk_address.to_le_bytes())?;
+            conn.writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+            conn.writer.flush()?;
+            conn.status().with_context(|| {
+                format!("reading {} bytes at 0x{:x}", chunk.len(), chunk_address)
+            })?;
+            conn.reader.read_exact(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serve reads of `mem` to one client at a time until the listener fails
+pub fn serve<M: Mem>(listener: TcpListener, mem: M, info: RemoteInfo) -> Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let peer = stream.peer_addr()?;
+        println!("{peer} connected");
+        stream.set_nodelay(true)?;
+        let read = |address, buf: &mut [u8]| mem.read_buf(address, buf);
+        match jmap_remote::handle_client(stream.try_clone()?, stream, &read, &info, None) {
+            Ok(()) => println!("{peer} disconnected"),
+            Err(err) => println!("{peer} disconnected: {err:#}"),
+        }
+    }
+    Ok(())
+}
+
+/// Serve reads of the live process `pid` on `address`
+pub fn serve_process(pid: i32, address: impl ToSocketAddrs) -> Result<()> {
+    let (name, image_base) = crate::proc_name::main_module(pid)?;
+    let mem = crate::open_process(pid)?;
+    let listener = TcpListener::bind(address)?;
+    println!(
+        "Serving {name} (PID {pid}, image base 0x{image_base:x}) on {}",
+        listener.local_addr()?
+    );
+    serve(listener, mem, RemoteInfo { image_base, name })
+}
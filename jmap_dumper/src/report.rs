@@ -0,0 +1,125 @@
+//! Machine readable summary of a run for scripts and CI wrapping the dumper: what failed, which
+//! objects were skipped and a stable process exit code per class of failure.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use jmap::ObjectError;
+use serde::Serialize;
+
+/// Class of a failed dump, attached as context where the failure happens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    /// Engine globals couldn't be resolved from the image
+    Resolution,
+    /// No usable struct layout for the engine version
+    StructInfo,
+    /// Objects couldn't be read from the target
+    Read,
+}
+
+impl Failure {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Failure::Resolution => 3,
+            Failure::StructInfo => 4,
+            Failure::Read => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Failure::Resolution => "Failed to resolve engine globals",
+            Failure::StructInfo => "No usable struct layout",
+            Failure::Read => "Failed to read objects",
+        })
+    }
+}
+
+/// Property the dumper has no reader for, by its cast flags
+#[derive(Debug)]
+pub struct UnsupportedProperty(pub String);
+
+impl std::fmt::Display for UnsupportedProperty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unsupported property class {}", self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedProperty {}
+
+/// Problems a dump carried on past, shared with the dump through
+/// [`DumpOptions::error_log`](crate::DumpOptions::error_log)
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    objects: Mutex<Vec<ObjectError>>,
+    unsupported_properties: Mutex<BTreeSet<String>>,
+}
+
+impl ErrorLog {
+    pub(crate) fn object(&self, error: &ObjectError, err: &anyhow::Error) {
+        self.objects.lock().unwrap().push(error.clone());
+        self.unsupported(err);
+    }
+
+    fn unsupported(&self, err: &anyhow::Error) {
+        if let Some(UnsupportedProperty(flags)) = err.downcast_ref() {
+            self.unsupported_properties
+                .lock()
+                .unwrap()
+                .insert(flags.clone());
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// `ok`, `partial` (objects were skipped with `--keep-going`), `interrupted` or `failed`
+    pub status: &'static str,
+    pub exit_code: u8,
+    pub failure: Option<Failure>,
+    /// Error the run failed with followed by its causes
+    pub error: Vec<String>,
+    pub skipped_objects: Vec<ObjectError>,
+    pub unsupported_properties: BTreeSet<String>,
+}
+
+impl Report {
+    /// Summary of a run ending in `result`. Failures without a class exit with 1, skipped
+    /// objects make an otherwise successful run exit with the code of [`Failure::Read`] and runs
+    /// stopped by Ctrl-C exit with 130.
+    pub fn new(result: Result<(), &anyhow::Error>, log: &ErrorLog, interrupted: bool) -> Self {
+        if let Err(err) = result {
+            log.unsupported(err);
+        }
+        let skipped_objects = log.objects.lock().unwrap().clone();
+        let unsupported_properties = log.unsupported_properties.lock().unwrap().clone();
+        let (status, failure, error) = match result {
+            Ok(()) if interrupted => ("interrupted", None, vec![]),
+            Ok(()) if skipped_objects.is_empty() => ("ok", None, vec![]),
+            Ok(()) => ("partial", Some(Failure::Read), vec![]),
+            Err(err) => (
+                "failed",
+                err.downcast_ref::<Failure>().copied(),
+                err.chain().map(|e| e.to_string()).collect(),
+            ),
+        };
+        let exit_code = match (status, failure) {
+            ("ok", _) => 0,
+            ("interrupted", _) => 130,
+            (_, Some(failure)) => failure.exit_code(),
+            (_, None) => 1,
+        };
+        Self {
+            status,
+            exit_code,
+            failure,
+            error,
+            skipped_objects,
+            unsupported_properties,
+        }
+    }
+}
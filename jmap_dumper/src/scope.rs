@@ -0,0 +1,93 @@
+//! Restricting a dump to a few classes and the types needed to make sense of them: their super
+//! chain, their functions, structs and enums referenced by properties (transitively) and the
+//! outers of all of those.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Result, bail};
+use jmap::{ObjectType, PropertyType};
+
+use crate::sdkgen::visit_props;
+
+#[derive(Debug, Default, Clone)]
+pub struct ClassScope {
+    /// Paths of the classes to dump, e.g. `/Script/Engine.Actor` (everything if empty)
+    pub classes: Vec<String>,
+    /// Also dump every live instance of the classes, including ones outside `/Script/`
+    pub instances: bool,
+}
+
+impl ClassScope {
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub(crate) fn is_instance(&self, class: &str) -> bool {
+        self.instances && self.classes.iter().any(|c| c == class)
+    }
+}
+
+/// Drop every object from `objects` which isn't in `scope`
+pub(crate) fn retain_scope(
+    objects: &mut BTreeMap<String, ObjectType>,
+    scope: &ClassScope,
+) -> Result<()> {
+    let mut queue = vec![];
+    for class in &scope.classes {
+        if !matches!(objects.get(class), Some(ObjectType::Class(_))) {
+            bail!("{class} is not a class in the dump");
+        }
+        queue.push(class.clone());
+    }
+    for (path, obj) in objects.iter() {
+        if scope.is_instance(&obj.get_object().class) {
+            queue.push(path.clone());
+        }
+    }
+
+    let mut kept = BTreeSet::new();
+    while let Some(path) = queue.pop() {
+        let Some(obj) = objects.get(&path) else {
+            continue;
+        };
+        if !kept.insert(path) {
+            continue;
+        }
+        let object = obj.get_object();
+        queue.extend(object.outer.clone());
+        if let ObjectType::Class(_) = obj {
+            queue.extend(
+                object
+                    .children
+                    .iter()
+                    .filter(|c| matches!(objects.get(*c), Some(ObjectType::Function(_))))
+                    .cloned(),
+            );
+        }
+        let Some(s) = obj.get_struct() else {
+            continue;
+        };
+        queue.extend(s.super_struct.clone());
+        for prop in &s.properties {
+            visit_props(prop, &mut |prop| match &prop.r#type {
+                PropertyType::Struct { r#struct } => queue.push(r#struct.clone()),
+                PropertyType::Enum {
+                    r#enum: Some(r#enum),
+                    ..
+                }
+                | PropertyType::Byte {
+                    r#enum: Some(r#enum),
+                } => queue.push(r#enum.clone()),
+                _ => {}
+            });
+        }
+    }
+
+    objects.retain(|path, _| kept.contains(path));
+    for obj in objects.values_mut() {
+        obj.get_object_mut()
+            .children
+            .retain(|child| kept.contains(child));
+    }
+    Ok(())
+}
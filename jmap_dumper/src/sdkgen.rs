@@ -0,0 +1,664 @@
+//! SDK generation from a dump. The language agnostic model (naming, package grouping, dependency
+//! ordering and member layout) lives here and is shared by each of the language backends.
+
+mod cpp;
+mod csharp;
+mod lua;
+mod rust;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use jmap::{EClassCastFlags, EFunctionFlags, Function, Jmap, ObjectType, Property, PropertyType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Cpp,
+    Rust,
+    CSharp,
+    /// UE4SS EmmyLua annotations
+    Lua,
+}
+
+/// Generate SDK source files, returned as relative file path -> contents
+pub fn generate(jmap: &Jmap, format: Format) -> BTreeMap<String, String> {
+    let sdk = Sdk::new(jmap);
+    match format {
+        Format::Cpp => cpp::generate(&sdk),
+        Format::Rust => rust::generate(&sdk),
+        Format::CSharp => csharp::generate(&sdk),
+        Format::Lua => lua::generate(&sdk),
+    }
+}
+
+/// The C++ SDK as a single header
+pub fn into_header(jmap: &Jmap) -> String {
+    cpp::single_header(&Sdk::new(jmap))
+}
+
+/// The C++ SDK as one header per package (named after the package path) including the headers of
+/// the packages it depends on, plus `Basic.hpp` and an `index.hpp` including every package
+pub fn into_package_headers(jmap: &Jmap) -> BTreeMap<String, String> {
+    cpp::package_headers(&Sdk::new(jmap))
+}
+
+/// C++ type of a property as the SDK spells it, for reports that compare or list properties
+/// without generating an SDK. Types are named without the package prefix the SDK gives names
+/// used by several packages.
+pub fn property_type_name(objects: &BTreeMap<String, ObjectType>, prop: &Property) -> String {
+    let name = |path: &str| {
+        objects
+            .get(path)
+            .and_then(|obj| type_base_name(path, obj))
+            .unwrap_or_else(|| sanitize_ident(short_name(path)))
+    };
+    let enum_type = |r#enum: Option<&String>, size| match r#enum {
+        Some(e) if matches!(objects.get(e), Some(ObjectType::Enum(_))) => name(e),
+        _ => cpp::int_type(size).into(),
+    };
+    cpp::type_spelling(prop, &name, &enum_type)
+}
+
+pub fn short_name(path: &str) -> &str {
+    path.rsplit(['/', '.', ':']).next().unwrap()
+}
+
+pub fn package_name(path: &str) -> &str {
+    path.split_once('.').map_or(path, |(package, _)| package)
+}
+
+/// Replace any characters which are not valid in an identifier
+pub fn sanitize_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn align_up(value: usize, alignment: usize) -> usize {
+    value.next_multiple_of(alignment.max(1))
+}
+
+/// Package and the types declared in it, each in dependency order
+#[derive(Debug, Default)]
+pub struct Package<'a> {
+    pub name: &'a str,
+    pub enums: Vec<&'a str>,
+    pub structs: Vec<&'a str>,
+    pub classes: Vec<&'a str>,
+    /// Packages declaring structs this package's types derive from or contain by value
+    pub dependencies: BTreeSet<&'a str>,
+}
+
+#[derive(Debug)]
+pub enum Member<'a> {
+    Property(&'a Property),
+    /// One byte of bitfield bools as (bit index, property)
+    BitField {
+        offset: usize,
+        bits: Vec<(u32, &'a Property)>,
+    },
+    Padding {
+        offset: usize,
+        size: usize,
+    },
+}
+
+#[derive(Debug)]
+pub struct Layout<'a> {
+    /// Size of the super struct (start of this struct's members)
+    pub base_size: usize,
+    pub size: usize,
+    pub members: Vec<Member<'a>>,
+    /// Properties which could not be placed because they overlap a previous member
+    pub skipped: Vec<&'a Property>,
+}
+
+/// Sizes of engine types which are emitted as opaque blobs, taken from the dump where possible
+pub struct OpaqueSizes {
+    pub name: usize,
+    pub text: usize,
+    pub field_path: usize,
+    pub delegate: usize,
+    pub multicast_inline_delegate: usize,
+    pub multicast_sparse_delegate: usize,
+    pub multicast_delegate: usize,
+    pub weak_object: usize,
+    pub soft_object: usize,
+    pub soft_class: usize,
+    pub lazy_object: usize,
+    pub interface: usize,
+    pub map: usize,
+    pub set: usize,
+}
+
+impl OpaqueSizes {
+    fn new(sdk: &Sdk) -> Self {
+        let mut sizes = Self {
+            name: 8,
+            text: 0x18,
+            field_path: 0x20,
+            delegate: 0x10,
+            multicast_inline_delegate: 0x10,
+            multicast_sparse_delegate: 1,
+            multicast_delegate: 0x10,
+            weak_object: 8,
+            soft_object: 0x28,
+            soft_class: 0x28,
+            lazy_object: 0x1c,
+            interface: 0x10,
+            map: 0x50,
+            set: 0x50,
+        };
+        for obj in sdk.jmap.objects.values() {
+            let Some(s) = obj.get_struct() else {
+                continue;
+            };
+            for prop in &s.properties {
+                visit_props(prop, &mut |prop| {
+                    let size = match &prop.r#type {
+                        PropertyType::Name => &mut sizes.name,
+                        PropertyType::Text => &mut sizes.text,
+                        PropertyType::FieldPath => &mut sizes.field_path,
+                        PropertyType::Delegate { .. } => &mut sizes.delegate,
+                        PropertyType::MulticastInlineDelegate { .. } => {
+                            &mut sizes.multicast_inline_delegate
+                        }
+                        PropertyType::MulticastSparseDelegate { .. } => {
+                            &mut sizes.multicast_sparse_delegate
+                        }
+                        PropertyType::MulticastDelegate { .. } => &mut sizes.multicast_delegate,
+                        PropertyType::WeakObject { .. } => &mut sizes.weak_object,
+                        PropertyType::SoftObject { .. } => &mut sizes.soft_object,
+                        PropertyType::SoftClass { .. } => &mut sizes.soft_class,
+                        PropertyType::LazyObject { .. } => &mut sizes.lazy_object,
+                        PropertyType::Interface { .. } => &mut sizes.interface,
+                        PropertyType::Map { .. } => &mut sizes.map,
+                        PropertyType::Set { .. } => &mut sizes.set,
+                        _ => return,
+                    };
+                    *size = prop.size;
+                });
+            }
+        }
+        sizes
+    }
+}
+
+/// Underlying integer type of an enum
+#[derive(Debug, Clone, Copy)]
+pub struct EnumRepr {
+    pub size: usize,
+    pub signed: bool,
+}
+
+impl EnumRepr {
+    /// Range of values representable by the type
+    pub fn range(&self) -> (i64, i64) {
+        match (self.size, self.signed) {
+            (1, _) => (0, u8::MAX as i64),
+            (2, _) => (0, u16::MAX as i64),
+            (4, true) => (i32::MIN as i64, i32::MAX as i64),
+            (4, false) => (0, u32::MAX as i64),
+            _ => (i64::MIN, i64::MAX),
+        }
+    }
+}
+
+pub struct Sdk<'a> {
+    pub jmap: &'a Jmap,
+    names: HashMap<&'a str, String>,
+    children_of: HashMap<&'a str, Vec<&'a str>>,
+    enum_sizes: HashMap<&'a str, usize>,
+    /// Packages in dependency order
+    pub packages: Vec<Package<'a>>,
+}
+
+impl<'a> Sdk<'a> {
+    pub fn new(jmap: &'a Jmap) -> Self {
+        let objects = &jmap.objects;
+
+        let mut names = HashMap::new();
+        let mut counts = HashMap::<String, usize>::new();
+        for (path, obj) in objects {
+            if let Some(name) = type_base_name(path, obj) {
+                *counts.entry(name.clone()).or_default() += 1;
+                names.insert(path.as_str(), name);
+            }
+        }
+        for (path, name) in &mut names {
+            if counts[name.as_str()] > 1 {
+                *name = format!("{}_{name}", sanitize_ident(short_name(package_name(path))));
+            }
+        }
+
+        let mut children_of = HashMap::<&str, Vec<&str>>::new();
+        let mut enum_sizes = HashMap::new();
+        for (path, obj) in objects {
+            if let Some(s) = obj.get_struct() {
+                if let Some(super_struct) = &s.super_struct {
+                    children_of
+                        .entry(super_struct.as_str())
+                        .or_default()
+                        .push(path.as_str());
+                }
+                for prop in &s.properties {
+                    visit_props(prop, &mut |prop| match &prop.r#type {
+                        PropertyType::Byte { r#enum: Some(e) } => {
+                            enum_sizes.entry(e.as_str()).or_insert(1);
+                        }
+                        PropertyType::Enum {
+                            container,
+                            r#enum: Some(e),
+                        } => {
+                            enum_sizes.entry(e.as_str()).or_insert(container.size);
+                        }
+                        _ => {}
+                    });
+                }
+            }
+        }
+
+        let mut sdk = Self {
+            jmap,
+            names,
+            children_of,
+            enum_sizes,
+            packages: vec![],
+        };
+        sdk.packages = sdk.sort_packages();
+        sdk
+    }
+
+    pub fn object(&self, path: &str) -> Option<&'a ObjectType> {
+        self.jmap.objects.get(path)
+    }
+
+    /// Unique type name of an enum, struct or class
+    pub fn type_name(&self, path: &str) -> String {
+        self.names
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| sanitize_ident(short_name(path)))
+    }
+
+    /// Size of the underlying integer type of an enum
+    pub fn enum_size(&self, path: &str) -> usize {
+        if let Some(size) = self.enum_sizes.get(path) {
+            return *size;
+        }
+        let Some(ObjectType::Enum(e)) = self.object(path) else {
+            return 1;
+        };
+        if e.names.iter().all(|(_, v)| (0..=0xff).contains(v)) {
+            1
+        } else if e.names.iter().all(|(_, v)| i32::try_from(*v).is_ok()) {
+            4
+        } else {
+            8
+        }
+    }
+
+    pub fn enum_repr(&self, path: &str) -> EnumRepr {
+        let size = self.enum_size(path);
+        let signed = match self.object(path) {
+            Some(ObjectType::Enum(e)) if size == 4 => {
+                e.names.iter().all(|(_, v)| i32::try_from(*v).is_ok())
+            }
+            _ => size == 8,
+        };
+        EnumRepr { size, signed }
+    }
+
+    pub fn opaque_sizes(&self) -> OpaqueSizes {
+        OpaqueSizes::new(self)
+    }
+
+    /// Names of types referenced by the dump which are not defined by it
+    pub fn undefined_types(&self) -> BTreeSet<String> {
+        let mut undefined = BTreeSet::new();
+        let mut check = |path: &str| {
+            if self.object(path).is_none_or(|o| o.get_struct().is_none()) {
+                undefined.insert(self.type_name(path));
+            }
+        };
+        for obj in self.jmap.objects.values() {
+            let Some(s) = obj.get_struct() else {
+                continue;
+            };
+            for prop in &s.properties {
+                visit_props(prop, &mut |prop| match &prop.r#type {
+                    PropertyType::Struct { r#struct } => check(r#struct),
+                    PropertyType::Object { property_class }
+                    | PropertyType::WeakObject { property_class }
+                    | PropertyType::SoftObject { property_class }
+                    | PropertyType::LazyObject { property_class } => check(property_class),
+                    PropertyType::Class {
+                        property_class,
+                        meta_class,
+                    }
+                    | PropertyType::SoftClass {
+                        property_class,
+                        meta_class,
+                    } => {
+                        check(property_class);
+                        check(meta_class);
+                    }
+                    PropertyType::Interface { interface_class } => check(interface_class),
+                    _ => {}
+                });
+            }
+        }
+        undefined
+    }
+
+    /// Size of a struct or class as emitted. Structs are padded to their alignment unless a
+    /// derived struct places members in the tail padding.
+    pub fn struct_size(&self, path: &str) -> usize {
+        let Some(s) = self.object(path).and_then(|o| o.get_struct()) else {
+            return 0;
+        };
+        let aligned = align_up(s.properties_size, s.min_alignment);
+        let tail_reused = self.children_of.get(path).is_some_and(|children| {
+            children.iter().any(|child| {
+                self.object(child)
+                    .and_then(|o| o.get_struct())
+                    .and_then(|c| c.properties.iter().map(|p| p.offset).min())
+                    .is_some_and(|offset| offset < aligned)
+            })
+        });
+        if tail_reused {
+            s.properties_size
+        } else {
+            aligned
+        }
+    }
+
+    /// Lay out the members declared by a struct or class after its super struct, inserting
+    /// padding for gaps and grouping bitfield bools
+    pub fn layout(&self, path: &str) -> Layout<'a> {
+        let s = self
+            .object(path)
+            .and_then(|o| o.get_struct())
+            .expect("layout of struct");
+        let base_size = s
+            .super_struct
+            .as_deref()
+            .map(|s| self.struct_size(s))
+            .unwrap_or_default();
+        let size = self.struct_size(path).max(base_size);
+        solve_layout(s.properties.iter().collect(), base_size, size)
+    }
+
+    /// Lay out all members of a struct or class including those inherited from super structs.
+    /// The size is always padded to the alignment as there is no base to share tail padding with.
+    pub fn flat_layout(&self, path: &str) -> Layout<'a> {
+        let s = self
+            .object(path)
+            .and_then(|o| o.get_struct())
+            .expect("layout of struct");
+        let size = align_up(s.properties_size, s.min_alignment);
+
+        let mut props = vec![];
+        let mut next = Some(s);
+        while let Some(s) = next {
+            props.extend(&s.properties);
+            next = s
+                .super_struct
+                .as_deref()
+                .and_then(|s| self.object(s))
+                .and_then(|o| o.get_struct());
+        }
+        solve_layout(props, 0, size)
+    }
+
+    /// Functions declared directly on a class (excluding delegate signatures)
+    pub fn functions(&self, path: &str) -> Vec<(&'a str, &'a Function)> {
+        let Some(s) = self.object(path).and_then(|o| o.get_struct()) else {
+            return vec![];
+        };
+        s.object
+            .children
+            .iter()
+            .filter_map(|child| match self.object(child) {
+                Some(ObjectType::Function(func))
+                    if !func.function_flags.contains(EFunctionFlags::FUNC_Delegate) =>
+                {
+                    Some((short_name(child), func))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Structs which must be fully defined before the given struct (super struct and by-value
+    /// members)
+    fn value_dependencies(&self, path: &str) -> Vec<&'a str> {
+        let Some(s) = self.object(path).and_then(|o| o.get_struct()) else {
+            return vec![];
+        };
+        let mut deps = vec![];
+        if let Some(super_struct) = &s.super_struct {
+            deps.push(super_struct.as_str());
+        }
+        for prop in &s.properties {
+            if let PropertyType::Struct { r#struct } = &prop.r#type {
+                deps.push(r#struct.as_str());
+            }
+        }
+        deps.retain(|d| self.names.contains_key(d));
+        deps
+    }
+
+    fn sort_packages(&self) -> Vec<Package<'a>> {
+        let jmap = self.jmap;
+        let mut packages = BTreeMap::<&str, Package>::new();
+        for (path, obj) in &jmap.objects {
+            if !matches!(
+                obj,
+                ObjectType::Enum(_) | ObjectType::ScriptStruct(_) | ObjectType::Class(_)
+            ) {
+                continue;
+            }
+            let package_name = package_name(path);
+            let package = packages.entry(package_name).or_insert_with(|| Package {
+                name: package_name,
+                ..Default::default()
+            });
+            match obj {
+                ObjectType::Enum(_) => package.enums.push(path),
+                ObjectType::ScriptStruct(_) => package.structs.push(path),
+                _ => package.classes.push(path),
+            }
+        }
+
+        // order types within each package
+        for package in packages.values_mut() {
+            package.structs = self.sort_types(&package.structs);
+            package.classes = self.sort_types(&package.classes);
+        }
+
+        // order packages by the packages their types depend on
+        let mut package_deps = BTreeMap::<&str, BTreeSet<&str>>::new();
+        for package in packages.values() {
+            let deps = package_deps.entry(package.name).or_default();
+            for path in package.structs.iter().chain(&package.classes) {
+                for dep in self.value_dependencies(path) {
+                    let dep = package_name(dep);
+                    if dep != package.name {
+                        deps.insert(dep);
+                    }
+                }
+            }
+        }
+        let order = topological_sort(&package_deps.keys().copied().collect::<Vec<_>>(), |p| {
+            package_deps.get(p).into_iter().flatten().copied().collect()
+        });
+        for (name, deps) in package_deps {
+            if let Some(package) = packages.get_mut(name) {
+                package.dependencies = deps;
+            }
+        }
+        order
+            .into_iter()
+            .filter_map(|name| packages.remove(name))
+            .collect()
+    }
+
+    fn sort_types(&self, paths: &[&'a str]) -> Vec<&'a str> {
+        let set: HashSet<&str> = paths.iter().copied().collect();
+        topological_sort(paths, |path| {
+            self.value_dependencies(path)
+                .into_iter()
+                .filter(|d| set.contains(d))
+                .collect()
+        })
+    }
+}
+
+fn solve_layout(mut props: Vec<&Property>, base_size: usize, size: usize) -> Layout<'_> {
+    props.sort_by_key(|p| {
+        let (byte_offset, bit) = bool_bit(p).unwrap_or_default();
+        (p.offset + byte_offset, bit)
+    });
+
+    let mut members = vec![];
+    let mut skipped = vec![];
+    let mut cursor = base_size;
+    for prop in props {
+        if let Some((byte_offset, bit)) = bool_bit(prop) {
+            let offset = prop.offset + byte_offset;
+            if let Some(Member::BitField { offset: o, bits }) = members.last_mut()
+                && *o == offset
+            {
+                bits.push((bit, prop));
+                continue;
+            }
+            if offset < cursor {
+                skipped.push(prop);
+                continue;
+            }
+            if offset > cursor {
+                members.push(Member::Padding {
+                    offset: cursor,
+                    size: offset - cursor,
+                });
+            }
+            members.push(Member::BitField {
+                offset,
+                bits: vec![(bit, prop)],
+            });
+            cursor = offset + 1;
+            continue;
+        }
+        if prop.offset < cursor {
+            skipped.push(prop);
+            continue;
+        }
+        if prop.offset > cursor {
+            members.push(Member::Padding {
+                offset: cursor,
+                size: prop.offset - cursor,
+            });
+        }
+        members.push(Member::Property(prop));
+        cursor = prop.offset + prop.size * prop.array_dim;
+    }
+    if size > cursor {
+        members.push(Member::Padding {
+            offset: cursor,
+            size: size - cursor,
+        });
+    }
+
+    Layout {
+        base_size,
+        size: size.max(cursor),
+        members,
+        skipped,
+    }
+}
+
+/// Depth first topological sort. Cycles are broken arbitrarily.
+fn topological_sort<'a, F>(nodes: &[&'a str], deps: F) -> Vec<&'a str>
+where
+    F: Fn(&'a str) -> Vec<&'a str>,
+{
+    fn visit<'a, F: Fn(&'a str) -> Vec<&'a str>>(
+        node: &'a str,
+        deps: &F,
+        visited: &mut HashSet<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) {
+        if !visited.insert(node) {
+            return;
+        }
+        for dep in deps(node) {
+            visit(dep, deps, visited, order);
+        }
+        order.push(node);
+    }
+
+    let mut visited = HashSet::new();
+    let mut order = vec![];
+    for node in nodes {
+        visit(node, &deps, &mut visited, &mut order);
+    }
+    order
+}
+
+fn type_base_name(path: &str, obj: &ObjectType) -> Option<String> {
+    let name = sanitize_ident(short_name(path));
+    Some(match obj {
+        ObjectType::Enum(_) => name,
+        ObjectType::ScriptStruct(_) => format!("F{name}"),
+        ObjectType::Class(class) => {
+            if class.r#struct.super_struct.as_deref() == Some("/Script/CoreUObject.Interface") {
+                format!("I{name}")
+            } else if class
+                .class_cast_flags
+                .contains(EClassCastFlags::CASTCLASS_AActor)
+            {
+                format!("A{name}")
+            } else {
+                format!("U{name}")
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Byte offset (relative to the property) and bit index of bitfield bool properties
+fn bool_bit(prop: &Property) -> Option<(usize, u32)> {
+    match prop.r#type {
+        PropertyType::Bool {
+            byte_offset,
+            byte_mask,
+            field_mask,
+            ..
+        } if field_mask != 0xff => Some((byte_offset as usize, byte_mask.trailing_zeros())),
+        _ => None,
+    }
+}
+
+/// Visit a property and all of its inner properties
+pub fn visit_props<'a>(prop: &'a Property, f: &mut impl FnMut(&'a Property)) {
+    f(prop);
+    match &prop.r#type {
+        PropertyType::Array { inner } | PropertyType::Optional { inner } => visit_props(inner, f),
+        PropertyType::Enum { container, .. } => visit_props(container, f),
+        PropertyType::Map {
+            key_prop,
+            value_prop,
+        } => {
+            visit_props(key_prop, f);
+            visit_props(value_prop, f);
+        }
+        PropertyType::Set { key_prop } => visit_props(key_prop, f),
+        _ => {}
+    }
+}
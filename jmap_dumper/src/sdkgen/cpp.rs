@@ -0,0 +1,582 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use jmap::{EFunctionFlags, EPropertyFlags, Function, ObjectType, Property, PropertyType};
+
+use super::{EnumRepr, Member, Package, Sdk, sanitize_ident, short_name};
+
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break",
+    "case", "catch", "char", "char8_t", "char16_t", "char32_t", "class", "compl", "concept",
+    "const", "consteval", "constexpr", "constinit", "const_cast", "continue", "co_await",
+    "co_return", "co_yield", "decltype", "default", "delete", "do", "double", "dynamic_cast",
+    "else", "enum", "explicit", "export", "extern", "false", "float", "for", "friend", "goto",
+    "if", "inline", "int", "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq",
+    "nullptr", "operator", "or", "or_eq", "private", "protected", "public", "register",
+    "reinterpret_cast", "requires", "return", "short", "signed", "sizeof", "static",
+    "static_assert", "static_cast", "struct", "switch", "template", "this", "thread_local",
+    "throw", "true", "try", "typedef", "typeid", "typename", "union", "unsigned", "using",
+    "virtual", "void", "volatile", "wchar_t", "while", "xor", "xor_eq",
+];
+
+fn ident(name: &str) -> String {
+    let ident = sanitize_ident(name);
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("{ident}_")
+    } else {
+        ident
+    }
+}
+
+/// Underlying integer type of an enum
+fn enum_underlying_type(repr: EnumRepr) -> &'static str {
+    match (repr.size, repr.signed) {
+        (1, _) => "uint8_t",
+        (2, _) => "uint16_t",
+        (4, true) => "int32_t",
+        (4, false) => "uint32_t",
+        _ => "int64_t",
+    }
+}
+
+pub(super) fn int_type(size: usize) -> &'static str {
+    match size {
+        1 => "uint8_t",
+        2 => "uint16_t",
+        4 => "uint32_t",
+        _ => "uint64_t",
+    }
+}
+
+/// C++ type of a property, with types named by `name` and enums `size` bytes wide by `enum_type`
+pub(super) fn type_spelling(
+    prop: &Property,
+    name: &impl Fn(&str) -> String,
+    enum_type: &impl Fn(Option<&String>, usize) -> String,
+) -> String {
+    let inner = |prop: &Property| type_spelling(prop, name, enum_type);
+    match &prop.r#type {
+        PropertyType::Struct { r#struct } => name(r#struct),
+        PropertyType::Str => "FString".into(),
+        PropertyType::Name => "FName".into(),
+        PropertyType::Text => "FText".into(),
+        PropertyType::FieldPath => "FFieldPath".into(),
+        PropertyType::MulticastInlineDelegate { .. } => "FMulticastInlineDelegate".into(),
+        PropertyType::MulticastSparseDelegate { .. } => "FMulticastSparseDelegate".into(),
+        PropertyType::MulticastDelegate { .. } => "FMulticastDelegate".into(),
+        PropertyType::Delegate { .. } => "FDelegate".into(),
+        PropertyType::Bool { .. } => "bool".into(),
+        PropertyType::Array { inner: prop } => format!("TArray<{}>", inner(prop)),
+        PropertyType::Enum { container, r#enum } => enum_type(r#enum.as_ref(), container.size),
+        PropertyType::Map {
+            key_prop,
+            value_prop,
+        } => format!("TMap<{}, {}>", inner(key_prop), inner(value_prop)),
+        PropertyType::Set { key_prop } => format!("TSet<{}>", inner(key_prop)),
+        PropertyType::Float => "float".into(),
+        PropertyType::Double => "double".into(),
+        PropertyType::Byte { r#enum } => enum_type(r#enum.as_ref(), 1),
+        PropertyType::UInt16 => "uint16_t".into(),
+        PropertyType::UInt32 => "uint32_t".into(),
+        PropertyType::UInt64 => "uint64_t".into(),
+        PropertyType::Int8 => "int8_t".into(),
+        PropertyType::Int16 => "int16_t".into(),
+        PropertyType::Int => "int32_t".into(),
+        PropertyType::Int64 => "int64_t".into(),
+        PropertyType::Object { property_class } => format!("{}*", name(property_class)),
+        PropertyType::Class { property_class, .. } => format!("{}*", name(property_class)),
+        PropertyType::WeakObject { property_class } => {
+            format!("TWeakObjectPtr<{}>", name(property_class))
+        }
+        PropertyType::SoftObject { property_class } => {
+            format!("TSoftObjectPtr<{}>", name(property_class))
+        }
+        PropertyType::SoftClass { meta_class, .. } => {
+            format!("TSoftClassPtr<{}>", name(meta_class))
+        }
+        PropertyType::LazyObject { property_class } => {
+            format!("TLazyObjectPtr<{}>", name(property_class))
+        }
+        PropertyType::Interface { interface_class } => {
+            format!("TScriptInterface<{}>", name(interface_class))
+        }
+        PropertyType::Optional { inner: prop } => format!("TOptional<{}>", inner(prop)),
+        PropertyType::Utf8Str => "FUtf8String".into(),
+        PropertyType::AnsiStr => "FAnsiString".into(),
+    }
+}
+
+struct Generator<'a> {
+    sdk: &'a Sdk<'a>,
+}
+
+impl Generator<'_> {
+    fn is_defined_struct(&self, path: &str) -> bool {
+        matches!(self.sdk.object(path), Some(ObjectType::ScriptStruct(_)))
+    }
+
+    fn enum_type(&self, r#enum: Option<&String>, size: usize) -> String {
+        match r#enum {
+            Some(e)
+                if matches!(self.sdk.object(e), Some(ObjectType::Enum(_)))
+                    && self.sdk.enum_size(e) == size =>
+            {
+                self.sdk.type_name(e)
+            }
+            _ => int_type(size).into(),
+        }
+    }
+
+    /// C++ type of a property. Returns None if it can only be represented as raw bytes.
+    fn property_type(&self, prop: &Property) -> Option<String> {
+        match &prop.r#type {
+            PropertyType::Struct { r#struct } if !self.is_defined_struct(r#struct) => None,
+            // layout depends on the inner type so only usable as an incomplete type
+            PropertyType::Optional { .. } => None,
+            _ => Some(self.template_arg(prop)),
+        }
+    }
+
+    /// Type used as a template argument or parameter where an incomplete type is acceptable
+    fn template_arg(&self, prop: &Property) -> String {
+        type_spelling(prop, &|path| self.sdk.type_name(path), &|r#enum, size| {
+            self.enum_type(r#enum, size)
+        })
+    }
+
+    fn basic(&self) -> String {
+        let sdk = self.sdk;
+        let sizes = sdk.opaque_sizes();
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "#pragma once").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "#include <cstddef>").unwrap();
+        writeln!(b, "#include <cstdint>").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "#pragma pack(push, 1)").unwrap();
+        writeln!(b).unwrap();
+
+        writeln!(b, "template<typename T> struct TArray {{").unwrap();
+        writeln!(b, "    T* Data;").unwrap();
+        writeln!(b, "    int32_t Num;").unwrap();
+        writeln!(b, "    int32_t Max;").unwrap();
+        writeln!(b, "}};").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "struct FString : TArray<char16_t> {{}};").unwrap();
+        writeln!(b, "struct FUtf8String : TArray<char> {{}};").unwrap();
+        writeln!(b, "struct FAnsiString : TArray<char> {{}};").unwrap();
+        writeln!(b).unwrap();
+
+        match sizes.name {
+            8 => {
+                writeln!(b, "struct FName {{").unwrap();
+                writeln!(b, "    int32_t ComparisonIndex;").unwrap();
+                writeln!(b, "    int32_t Number;").unwrap();
+                writeln!(b, "}};").unwrap();
+            }
+            0xc => {
+                writeln!(b, "struct FName {{").unwrap();
+                writeln!(b, "    int32_t ComparisonIndex;").unwrap();
+                writeln!(b, "    int32_t DisplayIndex;").unwrap();
+                writeln!(b, "    int32_t Number;").unwrap();
+                writeln!(b, "}};").unwrap();
+            }
+            size => writeln!(b, "struct FName {{ uint8_t Data[0x{size:x}]; }};").unwrap(),
+        }
+
+        let opaque = [
+            ("struct FText", sizes.text),
+            ("struct FFieldPath", sizes.field_path),
+            ("struct FDelegate", sizes.delegate),
+            (
+                "struct FMulticastInlineDelegate",
+                sizes.multicast_inline_delegate,
+            ),
+            (
+                "struct FMulticastSparseDelegate",
+                sizes.multicast_sparse_delegate,
+            ),
+            ("struct FMulticastDelegate", sizes.multicast_delegate),
+            (
+                "template<typename T> struct TWeakObjectPtr",
+                sizes.weak_object,
+            ),
+            (
+                "template<typename T> struct TSoftObjectPtr",
+                sizes.soft_object,
+            ),
+            (
+                "template<typename T> struct TSoftClassPtr",
+                sizes.soft_class,
+            ),
+            (
+                "template<typename T> struct TLazyObjectPtr",
+                sizes.lazy_object,
+            ),
+            (
+                "template<typename T> struct TScriptInterface",
+                sizes.interface,
+            ),
+            ("template<typename K, typename V> struct TMap", sizes.map),
+            ("template<typename T> struct TSet", sizes.set),
+        ];
+        for (decl, size) in opaque {
+            writeln!(b, "{decl} {{ uint8_t Data[0x{size:x}]; }};").unwrap();
+        }
+        writeln!(b, "template<typename T> struct TOptional;").unwrap();
+        writeln!(b).unwrap();
+
+        writeln!(b, "// forward declarations").unwrap();
+        for package in &sdk.packages {
+            for path in &package.enums {
+                let underlying = enum_underlying_type(sdk.enum_repr(path));
+                writeln!(b, "enum class {} : {underlying};", sdk.type_name(path)).unwrap();
+            }
+            for path in package.structs.iter().chain(&package.classes) {
+                writeln!(b, "struct {};", sdk.type_name(path)).unwrap();
+            }
+        }
+        for name in sdk.undefined_types() {
+            writeln!(b, "struct {name};").unwrap();
+        }
+        writeln!(b).unwrap();
+        writeln!(b, "#pragma pack(pop)").unwrap();
+
+        buffer
+    }
+
+    fn enums(&self, package: &Package) -> String {
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "#pragma once").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "#include \"Basic.hpp\"").unwrap();
+        writeln!(b).unwrap();
+        self.generate_enums(b, package);
+
+        buffer
+    }
+
+    fn generate_enums(&self, b: &mut String, package: &Package) {
+        let sdk = self.sdk;
+        for path in &package.enums {
+            let Some(ObjectType::Enum(e)) = sdk.object(path) else {
+                continue;
+            };
+            let repr = sdk.enum_repr(path);
+            let underlying = enum_underlying_type(repr);
+            let (min, max) = repr.range();
+            writeln!(b, "// {path}").unwrap();
+            writeln!(b, "enum class {} : {underlying} {{", sdk.type_name(path)).unwrap();
+            let mut seen = HashSet::new();
+            for (name, value) in &e.names {
+                let name = ident(name.rsplit("::").next().unwrap_or(name));
+                if !seen.insert(name.clone()) {
+                    continue;
+                }
+                if (min..=max).contains(value) {
+                    writeln!(b, "    {name} = {value},").unwrap();
+                } else {
+                    writeln!(b, "    // {name} = {value} (out of range)").unwrap();
+                }
+            }
+            writeln!(b, "}};").unwrap();
+            writeln!(b).unwrap();
+        }
+    }
+
+    fn types(&self, paths: &[&str], include: &str) -> String {
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "#pragma once").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "#include \"{include}\"").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "#pragma pack(push, 1)").unwrap();
+        writeln!(b).unwrap();
+        for path in paths {
+            self.generate_struct(b, path);
+        }
+        writeln!(b, "#pragma pack(pop)").unwrap();
+
+        buffer
+    }
+
+    fn generate_struct(&self, b: &mut String, path: &str) {
+        let sdk = self.sdk;
+        let s = sdk.object(path).and_then(|o| o.get_struct()).unwrap();
+        let name = sdk.type_name(path);
+        let layout = sdk.layout(path);
+
+        // avoid collisions between members of the same struct
+        let mut idents = HashSet::new();
+        let mut unique = |name: &str| {
+            let base = ident(name);
+            let mut ident = base.clone();
+            let mut i = 1;
+            while !idents.insert(ident.clone()) {
+                ident = format!("{base}_{i}");
+                i += 1;
+            }
+            ident
+        };
+
+        writeln!(b, "// {path}").unwrap();
+        writeln!(b, "// Size: 0x{:x}", layout.size).unwrap();
+        write!(b, "struct {name}").unwrap();
+        if let Some(super_struct) = &s.super_struct
+            && sdk.object(super_struct).is_some()
+        {
+            write!(b, " : public {}", sdk.type_name(super_struct)).unwrap();
+        }
+        writeln!(b, " {{").unwrap();
+
+        for member in &layout.members {
+            match member {
+                Member::Property(prop) => {
+                    let name = unique(&prop.name);
+                    let dim = if prop.array_dim > 1 {
+                        format!("[{}]", prop.array_dim)
+                    } else {
+                        String::new()
+                    };
+                    match self.property_type(prop) {
+                        Some(ty) => {
+                            writeln!(b, "    /* 0x{:04x} */ {ty} {name}{dim};", prop.offset)
+                                .unwrap()
+                        }
+                        None => writeln!(
+                            b,
+                            "    /* 0x{:04x} */ uint8_t {name}[0x{:x}];",
+                            prop.offset,
+                            prop.size * prop.array_dim
+                        )
+                        .unwrap(),
+                    }
+                }
+                Member::BitField { offset, bits } => {
+                    let mut next = 0;
+                    for (bit, prop) in bits {
+                        if *bit < next {
+                            continue;
+                        }
+                        if *bit > next {
+                            writeln!(b, "    /* 0x{offset:04x} */ uint8_t : {};", bit - next)
+                                .unwrap();
+                        }
+                        let name = unique(&prop.name);
+                        writeln!(b, "    /* 0x{offset:04x} */ uint8_t {name} : 1;").unwrap();
+                        next = bit + 1;
+                    }
+                    if next < 8 {
+                        writeln!(b, "    /* 0x{offset:04x} */ uint8_t : {};", 8 - next).unwrap();
+                    }
+                }
+                Member::Padding { offset, size } => {
+                    let name = unique(&format!("Pad_{offset:x}"));
+                    writeln!(b, "    /* 0x{offset:04x} */ uint8_t {name}[0x{size:x}];").unwrap();
+                }
+            }
+        }
+        for prop in &layout.skipped {
+            writeln!(
+                b,
+                "    // 0x{:04x} {} overlaps previous member",
+                prop.offset, prop.name
+            )
+            .unwrap();
+        }
+
+        let functions = sdk.functions(path);
+        if !functions.is_empty() {
+            writeln!(b).unwrap();
+            for (func_name, func) in functions {
+                let func_name = unique(func_name);
+                self.generate_function(b, &func_name, func);
+            }
+        }
+
+        writeln!(b, "}};").unwrap();
+        if layout.size > 0 {
+            writeln!(
+                b,
+                "static_assert(sizeof({name}) == 0x{:x}, \"{name}\");",
+                layout.size
+            )
+            .unwrap();
+        }
+        writeln!(b).unwrap();
+    }
+
+    fn generate_function(&self, b: &mut String, name: &str, func: &Function) {
+        let props = &func.r#struct.properties;
+        let return_type = props
+            .iter()
+            .find(|p| p.flags.contains(EPropertyFlags::CPF_ReturnParm))
+            .map(|p| self.template_arg(p))
+            .unwrap_or_else(|| "void".into());
+
+        let mut seen = HashSet::new();
+        let params = props
+            .iter()
+            .filter(|p| {
+                p.flags.contains(EPropertyFlags::CPF_Parm)
+                    && !p.flags.contains(EPropertyFlags::CPF_ReturnParm)
+            })
+            .map(|p| {
+                let mut param = String::new();
+                if p.flags.contains(EPropertyFlags::CPF_ConstParm) {
+                    param.push_str("const ");
+                }
+                param.push_str(&self.template_arg(p));
+                if p.flags.contains(EPropertyFlags::CPF_OutParm)
+                    || p.flags.contains(EPropertyFlags::CPF_ReferenceParm)
+                {
+                    param.push('&');
+                }
+                let mut name = ident(&p.name);
+                while !seen.insert(name.clone()) {
+                    name.push('_');
+                }
+                param.push(' ');
+                param.push_str(&name);
+                param
+            })
+            .collect::<Vec<_>>();
+
+        let is_static = func.function_flags.contains(EFunctionFlags::FUNC_Static);
+        let is_const = func.function_flags.contains(EFunctionFlags::FUNC_Const);
+
+        write!(b, "    ").unwrap();
+        if is_static {
+            write!(b, "static ").unwrap();
+        }
+        write!(b, "{return_type} {name}({})", params.join(", ")).unwrap();
+        if is_const && !is_static {
+            write!(b, " const").unwrap();
+        }
+        writeln!(b, ";").unwrap();
+    }
+}
+
+/// Everything in a single header, declared in the order of the SDK headers
+pub fn single_header(sdk: &Sdk) -> String {
+    let generator = Generator { sdk };
+    let mut buffer = generator.basic();
+    let b = &mut buffer;
+
+    writeln!(b).unwrap();
+    for package in &sdk.packages {
+        generator.generate_enums(b, package);
+    }
+    writeln!(b, "#pragma pack(push, 1)").unwrap();
+    writeln!(b).unwrap();
+    for package in &sdk.packages {
+        for path in &package.structs {
+            generator.generate_struct(b, path);
+        }
+    }
+    for package in &sdk.packages {
+        for path in &package.classes {
+            generator.generate_struct(b, path);
+        }
+    }
+    writeln!(b, "#pragma pack(pop)").unwrap();
+
+    buffer
+}
+
+/// Header per package with everything declared in it, named after the package path
+pub fn package_headers(sdk: &Sdk) -> BTreeMap<String, String> {
+    let generator = Generator { sdk };
+    let mut files = BTreeMap::new();
+
+    files.insert("Basic.hpp".to_string(), generator.basic());
+
+    let file_name = |package: &str| format!("{}.hpp", package.trim_start_matches('/'));
+    let mut index = String::new();
+    writeln!(index, "#pragma once").unwrap();
+    writeln!(index).unwrap();
+    writeln!(index, "#include \"Basic.hpp\"").unwrap();
+    for package in &sdk.packages {
+        let file = file_name(package.name);
+        writeln!(index, "#include \"{file}\"").unwrap();
+
+        // includes are relative to the directory of the package header
+        let root = "../".repeat(file.matches('/').count());
+        let mut buffer = String::new();
+        let b = &mut buffer;
+        writeln!(b, "#pragma once").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "#include \"{root}Basic.hpp\"").unwrap();
+        for dep in &package.dependencies {
+            writeln!(b, "#include \"{root}{}\"", file_name(dep)).unwrap();
+        }
+        writeln!(b).unwrap();
+        generator.generate_enums(b, package);
+        writeln!(b, "#pragma pack(push, 1)").unwrap();
+        writeln!(b).unwrap();
+        for path in package.structs.iter().chain(&package.classes) {
+            generator.generate_struct(b, path);
+        }
+        writeln!(b, "#pragma pack(pop)").unwrap();
+        files.insert(file, buffer);
+    }
+    files.insert("index.hpp".to_string(), index);
+
+    files
+}
+
+pub fn generate(sdk: &Sdk) -> BTreeMap<String, String> {
+    let generator = Generator { sdk };
+    let mut files = BTreeMap::new();
+
+    files.insert("Basic.hpp".to_string(), generator.basic());
+
+    let mut sdk_header = String::new();
+    writeln!(sdk_header, "#pragma once").unwrap();
+    writeln!(sdk_header).unwrap();
+    writeln!(sdk_header, "#include \"Basic.hpp\"").unwrap();
+
+    let package_files: Vec<String> = sdk
+        .packages
+        .iter()
+        .map(|p| sanitize_ident(short_name(p.name)))
+        .collect();
+
+    // enums are forward declared in Basic.hpp so they can be included in any order, structs
+    // must precede classes as classes may contain any struct by value
+    let mut includes = vec![];
+    for (package, file) in sdk.packages.iter().zip(&package_files) {
+        if !package.enums.is_empty() {
+            let name = format!("{file}_enums.hpp");
+            files.insert(name.clone(), generator.enums(package));
+            includes.push(name);
+        }
+    }
+    for (package, file) in sdk.packages.iter().zip(&package_files) {
+        if !package.structs.is_empty() {
+            let name = format!("{file}_structs.hpp");
+            files.insert(name.clone(), generator.types(&package.structs, "Basic.hpp"));
+            includes.push(name);
+        }
+    }
+    for (package, file) in sdk.packages.iter().zip(&package_files) {
+        if !package.classes.is_empty() {
+            let name = format!("{file}_classes.hpp");
+            files.insert(name.clone(), generator.types(&package.classes, "Basic.hpp"));
+            includes.push(name);
+        }
+    }
+    for include in includes {
+        writeln!(sdk_header, "#include \"{include}\"").unwrap();
+    }
+    files.insert("SDK.hpp".to_string(), sdk_header);
+
+    files
+}
@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use jmap::{EEnumFlags, ObjectType, Property, PropertyType};
+
+use super::{EnumRepr, Member, Package, Sdk, sanitize_ident, short_name};
+
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "base", "bool", "break", "byte", "case", "catch", "char", "checked",
+    "class", "const", "continue", "decimal", "default", "delegate", "do", "double", "else",
+    "enum", "event", "explicit", "extern", "false", "finally", "fixed", "float", "for",
+    "foreach", "goto", "if", "implicit", "in", "int", "interface", "internal", "is", "lock",
+    "long", "namespace", "new", "null", "object", "operator", "out", "override", "params",
+    "private", "protected", "public", "readonly", "ref", "return", "sbyte", "sealed", "short",
+    "sizeof", "stackalloc", "static", "string", "struct", "switch", "this", "throw", "true",
+    "try", "typeof", "uint", "ulong", "unchecked", "unsafe", "ushort", "using", "virtual", "void",
+    "volatile", "while",
+];
+
+fn ident(name: &str) -> String {
+    let ident = sanitize_ident(name);
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("@{ident}")
+    } else {
+        ident
+    }
+}
+
+fn int_type(size: usize) -> &'static str {
+    match size {
+        1 => "byte",
+        2 => "ushort",
+        4 => "uint",
+        _ => "ulong",
+    }
+}
+
+fn enum_underlying_type(repr: EnumRepr) -> &'static str {
+    match (repr.size, repr.signed) {
+        (1, _) => "byte",
+        (2, _) => "ushort",
+        (4, true) => "int",
+        (4, false) => "uint",
+        _ => "long",
+    }
+}
+
+/// Whether a type may be used as a fixed size buffer element
+fn is_primitive(ty: &str) -> bool {
+    matches!(
+        ty,
+        "bool"
+            | "byte"
+            | "sbyte"
+            | "short"
+            | "ushort"
+            | "int"
+            | "uint"
+            | "long"
+            | "ulong"
+            | "float"
+            | "double"
+    )
+}
+
+struct Generator<'a> {
+    sdk: &'a Sdk<'a>,
+}
+
+impl Generator<'_> {
+    fn is_defined_struct(&self, path: &str) -> bool {
+        matches!(self.sdk.object(path), Some(ObjectType::ScriptStruct(_)))
+    }
+
+    fn enum_type(&self, r#enum: Option<&String>, size: usize) -> String {
+        match r#enum {
+            Some(e)
+                if matches!(self.sdk.object(e), Some(ObjectType::Enum(_)))
+                    && self.sdk.enum_size(e) == size =>
+            {
+                self.sdk.type_name(e)
+            }
+            _ => int_type(size).into(),
+        }
+    }
+
+    /// C# type of a property. Returns None if it can only be represented as raw bytes.
+    fn property_type(&self, prop: &Property) -> Option<String> {
+        let sdk = self.sdk;
+        Some(match &prop.r#type {
+            PropertyType::Struct { r#struct } => {
+                if !self.is_defined_struct(r#struct) {
+                    return None;
+                }
+                sdk.type_name(r#struct)
+            }
+            PropertyType::Str => "FString".into(),
+            PropertyType::Name => "FName".into(),
+            PropertyType::Text => "FText".into(),
+            PropertyType::FieldPath => "FFieldPath".into(),
+            PropertyType::MulticastInlineDelegate { .. } => "FMulticastInlineDelegate".into(),
+            PropertyType::MulticastSparseDelegate { .. } => "FMulticastSparseDelegate".into(),
+            PropertyType::MulticastDelegate { .. } => "FMulticastDelegate".into(),
+            PropertyType::Delegate { .. } => "FDelegate".into(),
+            PropertyType::Bool { .. } => "bool".into(),
+            PropertyType::Array { inner } => format!("TArray<{}>", self.type_arg(inner)),
+            PropertyType::Enum { container, r#enum } => {
+                self.enum_type(r#enum.as_ref(), container.size)
+            }
+            PropertyType::Map {
+                key_prop,
+                value_prop,
+            } => format!(
+                "TMap<{}, {}>",
+                self.type_arg(key_prop),
+                self.type_arg(value_prop)
+            ),
+            PropertyType::Set { key_prop } => format!("TSet<{}>", self.type_arg(key_prop)),
+            PropertyType::Float => "float".into(),
+            PropertyType::Double => "double".into(),
+            PropertyType::Byte { r#enum } => self.enum_type(r#enum.as_ref(), 1),
+            PropertyType::UInt16 => "ushort".into(),
+            PropertyType::UInt32 => "uint".into(),
+            PropertyType::UInt64 => "ulong".into(),
+            PropertyType::Int8 => "sbyte".into(),
+            PropertyType::Int16 => "short".into(),
+            PropertyType::Int => "int".into(),
+            PropertyType::Int64 => "long".into(),
+            PropertyType::Object { property_class } => {
+                format!("{}*", sdk.type_name(property_class))
+            }
+            PropertyType::Class { property_class, .. } => {
+                format!("{}*", sdk.type_name(property_class))
+            }
+            PropertyType::WeakObject { property_class } => {
+                format!("TWeakObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::SoftObject { property_class } => {
+                format!("TSoftObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::SoftClass { meta_class, .. } => {
+                format!("TSoftClassPtr<{}>", sdk.type_name(meta_class))
+            }
+            PropertyType::LazyObject { property_class } => {
+                format!("TLazyObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::Interface { interface_class } => {
+                format!("TScriptInterface<{}>", sdk.type_name(interface_class))
+            }
+            // layout depends on the inner type so only usable behind a pointer
+            PropertyType::Optional { .. } => return None,
+            PropertyType::Utf8Str => "FUtf8String".into(),
+            PropertyType::AnsiStr => "FAnsiString".into(),
+        })
+    }
+
+    /// Type used as a generic argument where only a pointer to the type is stored
+    fn type_arg(&self, prop: &Property) -> String {
+        match &prop.r#type {
+            PropertyType::Struct { r#struct } => self.sdk.type_name(r#struct),
+            // pointers cannot be used as type arguments
+            PropertyType::Object { .. } | PropertyType::Class { .. } => "nint".into(),
+            PropertyType::Optional { inner } => format!("TOptional<{}>", self.type_arg(inner)),
+            _ => self.property_type(prop).unwrap(),
+        }
+    }
+
+    fn header(b: &mut String) {
+        writeln!(b, "// <auto-generated/>").unwrap();
+        writeln!(b, "using System;").unwrap();
+        writeln!(b, "using System.Runtime.InteropServices;").unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "namespace SDK;").unwrap();
+        writeln!(b).unwrap();
+    }
+
+    fn basic(&self) -> String {
+        let sdk = self.sdk;
+        let sizes = sdk.opaque_sizes();
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        Self::header(b);
+
+        writeln!(b, "[StructLayout(LayoutKind.Sequential)]").unwrap();
+        writeln!(b, "public unsafe struct TArray<T> where T : unmanaged").unwrap();
+        writeln!(b, "{{").unwrap();
+        writeln!(b, "    public T* Data;").unwrap();
+        writeln!(b, "    public int Num;").unwrap();
+        writeln!(b, "    public int Max;").unwrap();
+        writeln!(b, "}}").unwrap();
+        writeln!(b).unwrap();
+        for (name, ty) in [
+            ("FString", "char"),
+            ("FUtf8String", "byte"),
+            ("FAnsiString", "byte"),
+        ] {
+            writeln!(b, "[StructLayout(LayoutKind.Sequential)]").unwrap();
+            writeln!(b, "public struct {name}").unwrap();
+            writeln!(b, "{{").unwrap();
+            writeln!(b, "    public TArray<{ty}> Data;").unwrap();
+            writeln!(b, "}}").unwrap();
+            writeln!(b).unwrap();
+        }
+
+        writeln!(b, "[StructLayout(LayoutKind.Sequential)]").unwrap();
+        writeln!(b, "public unsafe struct FName").unwrap();
+        writeln!(b, "{{").unwrap();
+        match sizes.name {
+            8 => {
+                writeln!(b, "    public int ComparisonIndex;").unwrap();
+                writeln!(b, "    public int Number;").unwrap();
+            }
+            0xc => {
+                writeln!(b, "    public int ComparisonIndex;").unwrap();
+                writeln!(b, "    public int DisplayIndex;").unwrap();
+                writeln!(b, "    public int Number;").unwrap();
+            }
+            size => writeln!(b, "    public fixed byte Data[0x{size:x}];").unwrap(),
+        }
+        writeln!(b, "}}").unwrap();
+        writeln!(b).unwrap();
+
+        for (name, size) in [
+            ("FText", sizes.text),
+            ("FFieldPath", sizes.field_path),
+            ("FDelegate", sizes.delegate),
+            ("FMulticastInlineDelegate", sizes.multicast_inline_delegate),
+            ("FMulticastSparseDelegate", sizes.multicast_sparse_delegate),
+            ("FMulticastDelegate", sizes.multicast_delegate),
+            ("TWeakObjectPtr<T>", sizes.weak_object),
+            ("TSoftObjectPtr<T>", sizes.soft_object),
+            ("TSoftClassPtr<T>", sizes.soft_class),
+            ("TLazyObjectPtr<T>", sizes.lazy_object),
+            ("TScriptInterface<T>", sizes.interface),
+            ("TMap<K, V>", sizes.map),
+            ("TSet<T>", sizes.set),
+        ] {
+            writeln!(b, "[StructLayout(LayoutKind.Sequential)]").unwrap();
+            writeln!(b, "public unsafe struct {name}").unwrap();
+            writeln!(b, "{{").unwrap();
+            writeln!(b, "    public fixed byte Data[0x{size:x}];").unwrap();
+            writeln!(b, "}}").unwrap();
+            writeln!(b).unwrap();
+        }
+        writeln!(
+            b,
+            "/// <summary>Layout depends on T so only usable behind a pointer</summary>"
+        )
+        .unwrap();
+        writeln!(b, "public struct TOptional<T> {{ }}").unwrap();
+        writeln!(b).unwrap();
+
+        writeln!(b, "// types referenced by the dump but not defined by it").unwrap();
+        for name in sdk.undefined_types() {
+            writeln!(b, "public struct {name} {{ }}").unwrap();
+        }
+
+        buffer
+    }
+
+    fn package(&self, package: &Package) -> String {
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "// {}", package.name).unwrap();
+        Self::header(b);
+
+        for path in &package.enums {
+            self.generate_enum(b, path);
+        }
+        for path in package.structs.iter().chain(&package.classes) {
+            self.generate_struct(b, path);
+        }
+
+        buffer
+    }
+
+    fn generate_enum(&self, b: &mut String, path: &str) {
+        let sdk = self.sdk;
+        let Some(ObjectType::Enum(e)) = sdk.object(path) else {
+            return;
+        };
+        let repr = sdk.enum_repr(path);
+        let (min, max) = repr.range();
+
+        writeln!(b, "// {path}").unwrap();
+        if e.enum_flags.is_some_and(|f| f.contains(EEnumFlags::Flags)) {
+            writeln!(b, "[Flags]").unwrap();
+        }
+        writeln!(
+            b,
+            "public enum {} : {}",
+            sdk.type_name(path),
+            enum_underlying_type(repr)
+        )
+        .unwrap();
+        writeln!(b, "{{").unwrap();
+        let mut seen = HashSet::new();
+        for (name, value) in &e.names {
+            let name = ident(name.rsplit("::").next().unwrap_or(name));
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            if (min..=max).contains(value) {
+                writeln!(b, "    {name} = {value},").unwrap();
+            } else {
+                writeln!(b, "    // {name} = {value} (out of range)").unwrap();
+            }
+        }
+        writeln!(b, "}}").unwrap();
+        writeln!(b).unwrap();
+    }
+
+    fn generate_struct(&self, b: &mut String, path: &str) {
+        let sdk = self.sdk;
+        let s = sdk.object(path).and_then(|o| o.get_struct()).unwrap();
+        let name = sdk.type_name(path);
+        // inherited members are flattened into each struct as C# structs have no inheritance
+        let layout = sdk.flat_layout(path);
+
+        // members may not share the name of the enclosing type
+        let mut idents = HashSet::from([name.clone()]);
+        let mut unique = |name: &str| {
+            let base = ident(name);
+            let mut ident = base.clone();
+            let mut i = 1;
+            while !idents.insert(ident.clone()) {
+                ident = format!("{base}_{i}");
+                i += 1;
+            }
+            ident
+        };
+
+        writeln!(b, "// {path}").unwrap();
+        if let Some(super_struct) = &s.super_struct {
+            writeln!(b, "// Super: {super_struct}").unwrap();
+        }
+        if layout.size > 0 {
+            writeln!(
+                b,
+                "[StructLayout(LayoutKind.Explicit, Size = 0x{:x})]",
+                layout.size
+            )
+            .unwrap();
+        } else {
+            writeln!(b, "[StructLayout(LayoutKind.Explicit)]").unwrap();
+        }
+        writeln!(b, "public unsafe struct {name}").unwrap();
+        writeln!(b, "{{").unwrap();
+        for member in &layout.members {
+            match member {
+                Member::Property(prop) => {
+                    let field = unique(&prop.name);
+                    match self.property_type(prop) {
+                        Some(ty) if prop.array_dim == 1 => {
+                            writeln!(
+                                b,
+                                "    [FieldOffset(0x{:x})] public {ty} {field};",
+                                prop.offset
+                            )
+                            .unwrap();
+                        }
+                        Some(ty) if is_primitive(&ty) => {
+                            writeln!(
+                                b,
+                                "    [FieldOffset(0x{:x})] public fixed {ty} {field}[{}];",
+                                prop.offset, prop.array_dim
+                            )
+                            .unwrap();
+                        }
+                        // fixed size buffers only support primitive element types
+                        Some(ty) => {
+                            for i in 0..prop.array_dim {
+                                writeln!(
+                                    b,
+                                    "    [FieldOffset(0x{:x})] public {ty} {field}_{i};",
+                                    prop.offset + i * prop.size
+                                )
+                                .unwrap();
+                            }
+                        }
+                        None => {
+                            writeln!(
+                                b,
+                                "    [FieldOffset(0x{:x})] public fixed byte {field}[0x{:x}];",
+                                prop.offset,
+                                prop.size * prop.array_dim
+                            )
+                            .unwrap();
+                        }
+                    }
+                }
+                Member::BitField { offset, bits } => {
+                    let field = unique(&format!("BitField_{offset:x}"));
+                    writeln!(b, "    [FieldOffset(0x{offset:x})] public byte {field};").unwrap();
+                    for (bit, prop) in bits {
+                        let mask = 1u8 << bit;
+                        writeln!(
+                            b,
+                            "    public bool {} {{ get => ({field} & 0x{mask:02x}) != 0; set => {field} = (byte)(value ? {field} | 0x{mask:02x} : {field} & ~0x{mask:02x}); }}",
+                            unique(&prop.name)
+                        )
+                        .unwrap();
+                    }
+                }
+                // explicit offsets make padding members unnecessary
+                Member::Padding { .. } => {}
+            }
+        }
+        for prop in &layout.skipped {
+            writeln!(
+                b,
+                "    // 0x{:04x} {} overlaps previous member",
+                prop.offset, prop.name
+            )
+            .unwrap();
+        }
+        writeln!(b, "}}").unwrap();
+        writeln!(b).unwrap();
+    }
+}
+
+pub fn generate(sdk: &Sdk) -> BTreeMap<String, String> {
+    let generator = Generator { sdk };
+    let mut files = BTreeMap::new();
+
+    files.insert("Basic.cs".to_string(), generator.basic());
+    for package in &sdk.packages {
+        files.insert(
+            format!("{}.cs", sanitize_ident(short_name(package.name))),
+            generator.package(package),
+        );
+    }
+
+    files
+}
@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use jmap::{EFunctionFlags, EPropertyFlags, Function, ObjectType, Property, PropertyType};
+
+use super::{Package, Sdk, sanitize_ident, short_name};
+
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+fn ident(name: &str) -> String {
+    let ident = sanitize_ident(name);
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("{ident}_")
+    } else {
+        ident
+    }
+}
+
+struct Generator<'a> {
+    sdk: &'a Sdk<'a>,
+}
+
+impl Generator<'_> {
+    fn enum_type(&self, r#enum: Option<&String>) -> String {
+        match r#enum {
+            Some(e) if matches!(self.sdk.object(e), Some(ObjectType::Enum(_))) => {
+                self.sdk.type_name(e)
+            }
+            _ => "integer".into(),
+        }
+    }
+
+    /// EmmyLua type annotation of a property
+    fn property_type(&self, prop: &Property) -> String {
+        let sdk = self.sdk;
+        let ty = match &prop.r#type {
+            PropertyType::Struct { r#struct } => sdk.type_name(r#struct),
+            PropertyType::Str => "FString".into(),
+            PropertyType::Name => "FName".into(),
+            PropertyType::Text => "FText".into(),
+            PropertyType::FieldPath => "FFieldPath".into(),
+            PropertyType::MulticastInlineDelegate { .. }
+            | PropertyType::MulticastSparseDelegate { .. }
+            | PropertyType::MulticastDelegate { .. } => "FMulticastDelegate".into(),
+            PropertyType::Delegate { .. } => "FDelegate".into(),
+            PropertyType::Bool { .. } => "boolean".into(),
+            PropertyType::Array { inner } => format!("TArray<{}>", self.property_type(inner)),
+            PropertyType::Enum { r#enum, .. } => self.enum_type(r#enum.as_ref()),
+            PropertyType::Map {
+                key_prop,
+                value_prop,
+            } => format!(
+                "TMap<{}, {}>",
+                self.property_type(key_prop),
+                self.property_type(value_prop)
+            ),
+            PropertyType::Set { key_prop } => format!("TSet<{}>", self.property_type(key_prop)),
+            PropertyType::Float | PropertyType::Double => "number".into(),
+            PropertyType::Byte { r#enum } => self.enum_type(r#enum.as_ref()),
+            PropertyType::UInt16
+            | PropertyType::UInt32
+            | PropertyType::UInt64
+            | PropertyType::Int8
+            | PropertyType::Int16
+            | PropertyType::Int
+            | PropertyType::Int64 => "integer".into(),
+            PropertyType::Object { property_class } => sdk.type_name(property_class),
+            PropertyType::Class { meta_class, .. } => {
+                format!("TSubclassOf<{}>", sdk.type_name(meta_class))
+            }
+            PropertyType::WeakObject { property_class } => {
+                format!("TWeakObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::SoftObject { property_class } => {
+                format!("TSoftObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::SoftClass { meta_class, .. } => {
+                format!("TSoftClassPtr<{}>", sdk.type_name(meta_class))
+            }
+            PropertyType::LazyObject { property_class } => {
+                format!("TLazyObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::Interface { interface_class } => {
+                format!("TScriptInterface<{}>", sdk.type_name(interface_class))
+            }
+            PropertyType::Optional { inner } => format!("{}?", self.property_type(inner)),
+            PropertyType::Utf8Str | PropertyType::AnsiStr => "string".into(),
+        };
+        if prop.array_dim > 1 {
+            format!("{ty}[]")
+        } else {
+            ty
+        }
+    }
+
+    fn basic(&self) -> String {
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "---@meta").unwrap();
+        writeln!(b).unwrap();
+        for (class, generic) in [
+            ("FString", ""),
+            ("FName", ""),
+            ("FText", ""),
+            ("FFieldPath", ""),
+            ("FDelegate", ""),
+            ("FMulticastDelegate", ""),
+            ("TArray", "<T>"),
+            ("TMap", "<K, V>"),
+            ("TSet", "<T>"),
+            ("TSubclassOf", "<T>"),
+            ("TWeakObjectPtr", "<T>"),
+            ("TSoftObjectPtr", "<T>"),
+            ("TSoftClassPtr", "<T>"),
+            ("TLazyObjectPtr", "<T>"),
+            ("TScriptInterface", "<T>"),
+        ] {
+            writeln!(b, "---@class {class}{generic}").unwrap();
+        }
+        writeln!(b).unwrap();
+        writeln!(b, "-- types referenced by the dump but not defined by it").unwrap();
+        for name in self.sdk.undefined_types() {
+            writeln!(b, "---@class {name}").unwrap();
+        }
+
+        buffer
+    }
+
+    fn package(&self, package: &Package) -> String {
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "---@meta").unwrap();
+        writeln!(b, "-- {}", package.name).unwrap();
+        writeln!(b).unwrap();
+
+        for path in &package.enums {
+            self.generate_enum(b, path);
+        }
+        for path in package.structs.iter().chain(&package.classes) {
+            self.generate_struct(b, path);
+        }
+
+        buffer
+    }
+
+    fn generate_enum(&self, b: &mut String, path: &str) {
+        let sdk = self.sdk;
+        let Some(ObjectType::Enum(e)) = sdk.object(path) else {
+            return;
+        };
+        let name = sdk.type_name(path);
+        writeln!(b, "-- {path}").unwrap();
+        writeln!(b, "---@enum {name}").unwrap();
+        writeln!(b, "{name} = {{").unwrap();
+        let mut seen = HashSet::new();
+        for (variant, value) in &e.names {
+            let variant = ident(variant.rsplit("::").next().unwrap_or(variant));
+            if seen.insert(variant.clone()) {
+                writeln!(b, "    {variant} = {value},").unwrap();
+            }
+        }
+        writeln!(b, "}}").unwrap();
+        writeln!(b).unwrap();
+    }
+
+    fn generate_struct(&self, b: &mut String, path: &str) {
+        let sdk = self.sdk;
+        let s = sdk.object(path).and_then(|o| o.get_struct()).unwrap();
+        let name = sdk.type_name(path);
+
+        writeln!(b, "-- {path}").unwrap();
+        write!(b, "---@class {name}").unwrap();
+        if let Some(super_struct) = &s.super_struct {
+            write!(b, " : {}", sdk.type_name(super_struct)).unwrap();
+        }
+        writeln!(b).unwrap();
+        for prop in &s.properties {
+            writeln!(b, "---@field {} {}", prop.name, self.property_type(prop)).unwrap();
+        }
+        writeln!(b, "{name} = {{}}").unwrap();
+        writeln!(b).unwrap();
+
+        for (func_name, func) in sdk.functions(path) {
+            if func
+                .function_flags
+                .contains(EFunctionFlags::FUNC_BlueprintCallable)
+            {
+                self.generate_function(b, &name, func_name, func);
+            }
+        }
+    }
+
+    fn generate_function(&self, b: &mut String, class: &str, name: &str, func: &Function) {
+        let mut seen = HashSet::new();
+        let mut params = vec![];
+        let mut returns = vec![];
+        for prop in &func.r#struct.properties {
+            if prop.flags.contains(EPropertyFlags::CPF_ReturnParm) {
+                returns.push(self.property_type(prop));
+                continue;
+            }
+            if !prop.flags.contains(EPropertyFlags::CPF_Parm) {
+                continue;
+            }
+            let mut param = ident(&prop.name);
+            while !seen.insert(param.clone()) {
+                param.push('_');
+            }
+            writeln!(b, "---@param {param} {}", self.property_type(prop)).unwrap();
+            params.push(param);
+        }
+        for ty in returns {
+            writeln!(b, "---@return {ty}").unwrap();
+        }
+        let separator = if func.function_flags.contains(EFunctionFlags::FUNC_Static) {
+            '.'
+        } else {
+            ':'
+        };
+        writeln!(
+            b,
+            "function {class}{separator}{}({}) end",
+            ident(name),
+            params.join(", ")
+        )
+        .unwrap();
+        writeln!(b).unwrap();
+    }
+}
+
+pub fn generate(sdk: &Sdk) -> BTreeMap<String, String> {
+    let generator = Generator { sdk };
+    let mut files = BTreeMap::new();
+
+    files.insert("Basic.lua".to_string(), generator.basic());
+    for package in &sdk.packages {
+        files.insert(
+            format!("{}.lua", sanitize_ident(short_name(package.name))),
+            generator.package(package),
+        );
+    }
+
+    files
+}
@@ -0,0 +1,484 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use jmap::{EEnumFlags, ObjectType, Property, PropertyType};
+
+use super::{EnumRepr, Member, Package, Sdk, sanitize_ident, short_name};
+
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+fn ident(name: &str) -> String {
+    let ident = sanitize_ident(name);
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("{ident}_")
+    } else {
+        ident
+    }
+}
+
+/// Module name of a package e.g. /Script/CoreUObject -> core_uobject
+fn module_name(package: &str) -> String {
+    let name: Vec<char> = sanitize_ident(short_name(package)).chars().collect();
+    let mut module = String::new();
+    for (i, c) in name.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = name[i - 1];
+            let next_lower = name.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase() || (prev.is_ascii_uppercase() && next_lower) {
+                module.push('_');
+            }
+        }
+        module.push(c.to_ascii_lowercase());
+    }
+    ident(&module)
+}
+
+fn int_type(size: usize) -> &'static str {
+    match size {
+        1 => "u8",
+        2 => "u16",
+        4 => "u32",
+        _ => "u64",
+    }
+}
+
+fn enum_underlying_type(repr: EnumRepr) -> &'static str {
+    match (repr.size, repr.signed) {
+        (1, _) => "u8",
+        (2, _) => "u16",
+        (4, true) => "i32",
+        (4, false) => "u32",
+        _ => "i64",
+    }
+}
+
+struct Generator<'a> {
+    sdk: &'a Sdk<'a>,
+}
+
+impl Generator<'_> {
+    fn is_defined_struct(&self, path: &str) -> bool {
+        matches!(self.sdk.object(path), Some(ObjectType::ScriptStruct(_)))
+    }
+
+    fn enum_type(&self, r#enum: Option<&String>, size: usize) -> String {
+        match r#enum {
+            Some(e)
+                if matches!(self.sdk.object(e), Some(ObjectType::Enum(_)))
+                    && self.sdk.enum_size(e) == size =>
+            {
+                self.sdk.type_name(e)
+            }
+            _ => int_type(size).into(),
+        }
+    }
+
+    /// Rust type of a property. Returns None if it can only be represented as raw bytes.
+    fn property_type(&self, prop: &Property) -> Option<String> {
+        let sdk = self.sdk;
+        Some(match &prop.r#type {
+            PropertyType::Struct { r#struct } => {
+                if !self.is_defined_struct(r#struct) {
+                    return None;
+                }
+                sdk.type_name(r#struct)
+            }
+            PropertyType::Str => "FString".into(),
+            PropertyType::Name => "FName".into(),
+            PropertyType::Text => "FText".into(),
+            PropertyType::FieldPath => "FFieldPath".into(),
+            PropertyType::MulticastInlineDelegate { .. } => "FMulticastInlineDelegate".into(),
+            PropertyType::MulticastSparseDelegate { .. } => "FMulticastSparseDelegate".into(),
+            PropertyType::MulticastDelegate { .. } => "FMulticastDelegate".into(),
+            PropertyType::Delegate { .. } => "FDelegate".into(),
+            PropertyType::Bool { .. } => "bool".into(),
+            PropertyType::Array { inner } => format!("TArray<{}>", self.type_arg(inner)),
+            PropertyType::Enum { container, r#enum } => {
+                self.enum_type(r#enum.as_ref(), container.size)
+            }
+            PropertyType::Map {
+                key_prop,
+                value_prop,
+            } => format!(
+                "TMap<{}, {}>",
+                self.type_arg(key_prop),
+                self.type_arg(value_prop)
+            ),
+            PropertyType::Set { key_prop } => format!("TSet<{}>", self.type_arg(key_prop)),
+            PropertyType::Float => "f32".into(),
+            PropertyType::Double => "f64".into(),
+            PropertyType::Byte { r#enum } => self.enum_type(r#enum.as_ref(), 1),
+            PropertyType::UInt16 => "u16".into(),
+            PropertyType::UInt32 => "u32".into(),
+            PropertyType::UInt64 => "u64".into(),
+            PropertyType::Int8 => "i8".into(),
+            PropertyType::Int16 => "i16".into(),
+            PropertyType::Int => "i32".into(),
+            PropertyType::Int64 => "i64".into(),
+            PropertyType::Object { property_class } => {
+                format!("*mut {}", sdk.type_name(property_class))
+            }
+            PropertyType::Class { property_class, .. } => {
+                format!("*mut {}", sdk.type_name(property_class))
+            }
+            PropertyType::WeakObject { property_class } => {
+                format!("TWeakObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::SoftObject { property_class } => {
+                format!("TSoftObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::SoftClass { meta_class, .. } => {
+                format!("TSoftClassPtr<{}>", sdk.type_name(meta_class))
+            }
+            PropertyType::LazyObject { property_class } => {
+                format!("TLazyObjectPtr<{}>", sdk.type_name(property_class))
+            }
+            PropertyType::Interface { interface_class } => {
+                format!("TScriptInterface<{}>", sdk.type_name(interface_class))
+            }
+            // layout depends on the inner type so only usable behind a pointer
+            PropertyType::Optional { .. } => return None,
+            PropertyType::Utf8Str => "FUtf8String".into(),
+            PropertyType::AnsiStr => "FAnsiString".into(),
+        })
+    }
+
+    /// Type used as a generic argument where only a pointer to the type is stored
+    fn type_arg(&self, prop: &Property) -> String {
+        match &prop.r#type {
+            PropertyType::Struct { r#struct } => self.sdk.type_name(r#struct),
+            PropertyType::Optional { inner } => format!("TOptional<{}>", self.type_arg(inner)),
+            _ => self.property_type(prop).unwrap(),
+        }
+    }
+
+    fn basic(&self) -> String {
+        let sdk = self.sdk;
+        let sizes = sdk.opaque_sizes();
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "//! Engine types shared by all packages").unwrap();
+        writeln!(
+            b,
+            "#![allow(non_snake_case, non_camel_case_types, dead_code)]"
+        )
+        .unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "use std::marker::PhantomData;").unwrap();
+        writeln!(b).unwrap();
+
+        writeln!(b, "#[repr(C)]").unwrap();
+        writeln!(b, "pub struct TArray<T> {{").unwrap();
+        writeln!(b, "    pub Data: *mut T,").unwrap();
+        writeln!(b, "    pub Num: i32,").unwrap();
+        writeln!(b, "    pub Max: i32,").unwrap();
+        writeln!(b, "}}").unwrap();
+        writeln!(b).unwrap();
+        for name in ["FString", "FUtf8String", "FAnsiString"] {
+            let ty = if name == "FString" { "u16" } else { "u8" };
+            writeln!(b, "#[repr(transparent)]").unwrap();
+            writeln!(b, "pub struct {name}(pub TArray<{ty}>);").unwrap();
+        }
+        writeln!(b).unwrap();
+
+        writeln!(b, "#[repr(C)]").unwrap();
+        writeln!(b, "pub struct FName {{").unwrap();
+        match sizes.name {
+            8 => {
+                writeln!(b, "    pub ComparisonIndex: i32,").unwrap();
+                writeln!(b, "    pub Number: i32,").unwrap();
+            }
+            0xc => {
+                writeln!(b, "    pub ComparisonIndex: i32,").unwrap();
+                writeln!(b, "    pub DisplayIndex: i32,").unwrap();
+                writeln!(b, "    pub Number: i32,").unwrap();
+            }
+            size => writeln!(b, "    pub Data: [u8; 0x{size:x}],").unwrap(),
+        }
+        writeln!(b, "}}").unwrap();
+        writeln!(b).unwrap();
+
+        for (name, size) in [
+            ("FText", sizes.text),
+            ("FFieldPath", sizes.field_path),
+            ("FDelegate", sizes.delegate),
+            ("FMulticastInlineDelegate", sizes.multicast_inline_delegate),
+            ("FMulticastSparseDelegate", sizes.multicast_sparse_delegate),
+            ("FMulticastDelegate", sizes.multicast_delegate),
+        ] {
+            writeln!(b, "#[repr(C)]").unwrap();
+            writeln!(b, "pub struct {name} {{").unwrap();
+            writeln!(b, "    pub Data: [u8; 0x{size:x}],").unwrap();
+            writeln!(b, "}}").unwrap();
+            writeln!(b).unwrap();
+        }
+        for (name, params, size) in [
+            ("TWeakObjectPtr", "T", sizes.weak_object),
+            ("TSoftObjectPtr", "T", sizes.soft_object),
+            ("TSoftClassPtr", "T", sizes.soft_class),
+            ("TLazyObjectPtr", "T", sizes.lazy_object),
+            ("TScriptInterface", "T", sizes.interface),
+            ("TMap", "K, V", sizes.map),
+            ("TSet", "T", sizes.set),
+        ] {
+            writeln!(b, "#[repr(C)]").unwrap();
+            writeln!(b, "pub struct {name}<{params}> {{").unwrap();
+            writeln!(b, "    pub Data: [u8; 0x{size:x}],").unwrap();
+            writeln!(b, "    _marker: PhantomData<*const ({params})>,").unwrap();
+            writeln!(b, "}}").unwrap();
+            writeln!(b).unwrap();
+        }
+        writeln!(b, "/// Layout depends on T so only usable behind a pointer").unwrap();
+        writeln!(b, "pub struct TOptional<T>(PhantomData<T>);").unwrap();
+        writeln!(b).unwrap();
+
+        writeln!(b, "// types referenced by the dump but not defined by it").unwrap();
+        for name in sdk.undefined_types() {
+            writeln!(b, "pub struct {name} {{").unwrap();
+            writeln!(b, "    _private: [u8; 0],").unwrap();
+            writeln!(b, "}}").unwrap();
+        }
+
+        buffer
+    }
+
+    fn package(&self, package: &Package) -> String {
+        let mut buffer = String::new();
+        let b = &mut buffer;
+
+        writeln!(b, "//! {}", package.name).unwrap();
+        writeln!(
+            b,
+            "#![allow(non_snake_case, non_camel_case_types, non_upper_case_globals, dead_code)]"
+        )
+        .unwrap();
+        writeln!(b).unwrap();
+        writeln!(b, "use super::*;").unwrap();
+        writeln!(b).unwrap();
+
+        for path in &package.enums {
+            self.generate_enum(b, path);
+        }
+        for path in package.structs.iter().chain(&package.classes) {
+            self.generate_struct(b, path);
+        }
+
+        buffer
+    }
+
+    fn generate_enum(&self, b: &mut String, path: &str) {
+        let sdk = self.sdk;
+        let Some(ObjectType::Enum(e)) = sdk.object(path) else {
+            return;
+        };
+        let name = sdk.type_name(path);
+        let repr = sdk.enum_repr(path);
+        let underlying = enum_underlying_type(repr);
+        let (min, max) = repr.range();
+
+        let mut seen = HashSet::new();
+        let mut variants = vec![];
+        for (variant, value) in &e.names {
+            let variant = ident(variant.rsplit("::").next().unwrap_or(variant));
+            if seen.insert(variant.clone()) {
+                variants.push((variant, *value, (min..=max).contains(value)));
+            }
+        }
+
+        let is_flags = e.enum_flags.is_some_and(|f| f.contains(EEnumFlags::Flags));
+
+        writeln!(b, "// {path}").unwrap();
+        if is_flags {
+            writeln!(b, "bitflags::bitflags! {{").unwrap();
+            writeln!(b, "    #[repr(transparent)]").unwrap();
+            writeln!(b, "    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+            writeln!(b, "    pub struct {name}: {underlying} {{").unwrap();
+            for (variant, value, in_range) in variants {
+                if in_range {
+                    writeln!(b, "        const {variant} = {value};").unwrap();
+                } else {
+                    writeln!(b, "        // {variant} = {value} (out of range)").unwrap();
+                }
+            }
+            writeln!(b, "    }}").unwrap();
+            writeln!(b, "}}").unwrap();
+        } else {
+            // newtype rather than a Rust enum as memory may hold values outside of the known set
+            writeln!(b, "#[repr(transparent)]").unwrap();
+            writeln!(b, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+            writeln!(b, "pub struct {name}(pub {underlying});").unwrap();
+            writeln!(b, "impl {name} {{").unwrap();
+            for (variant, value, in_range) in variants {
+                if in_range {
+                    writeln!(b, "    pub const {variant}: Self = Self({value});").unwrap();
+                } else {
+                    writeln!(b, "    // {variant} = {value} (out of range)").unwrap();
+                }
+            }
+            writeln!(b, "}}").unwrap();
+        }
+        writeln!(b).unwrap();
+    }
+
+    fn generate_struct(&self, b: &mut String, path: &str) {
+        let sdk = self.sdk;
+        let s = sdk.object(path).and_then(|o| o.get_struct()).unwrap();
+        let name = sdk.type_name(path);
+        // inherited members are flattened into each struct as Rust has no inheritance
+        let layout = sdk.flat_layout(path);
+
+        let mut idents = HashSet::new();
+        let mut unique = |name: &str| {
+            let base = ident(name);
+            let mut ident = base.clone();
+            let mut i = 1;
+            while !idents.insert(ident.clone()) {
+                ident = format!("{base}_{i}");
+                i += 1;
+            }
+            ident
+        };
+
+        let mut offsets = vec![];
+        let mut bits = vec![];
+        let mut fields = vec![];
+
+        writeln!(b, "// {path}").unwrap();
+        if let Some(super_struct) = &s.super_struct {
+            writeln!(b, "// Super: {super_struct}").unwrap();
+        }
+        writeln!(b, "// Size: 0x{:x}", layout.size).unwrap();
+        writeln!(b, "#[repr(C)]").unwrap();
+        writeln!(b, "pub struct {name} {{").unwrap();
+        for member in &layout.members {
+            match member {
+                Member::Property(prop) => {
+                    let field = unique(&prop.name);
+                    let ty = match self.property_type(prop) {
+                        Some(ty) if prop.array_dim > 1 => format!("[{ty}; {}]", prop.array_dim),
+                        Some(ty) => ty,
+                        None => format!("[u8; 0x{:x}]", prop.size * prop.array_dim),
+                    };
+                    writeln!(b, "    /* 0x{:04x} */ pub {field}: {ty},", prop.offset).unwrap();
+                    offsets.push((prop.name.as_str(), prop.offset));
+                    fields.push((field, prop.offset));
+                }
+                Member::BitField {
+                    offset,
+                    bits: props,
+                } => {
+                    let field = unique(&format!("BitField_{offset:x}"));
+                    writeln!(b, "    /* 0x{offset:04x} */ pub {field}: u8,").unwrap();
+                    for (bit, prop) in props {
+                        offsets.push((prop.name.as_str(), *offset));
+                        bits.push((prop.name.as_str(), field.clone(), *bit));
+                    }
+                    fields.push((field, *offset));
+                }
+                Member::Padding { offset, size } => {
+                    let field = unique(&format!("Pad_{offset:x}"));
+                    writeln!(b, "    /* 0x{offset:04x} */ pub {field}: [u8; 0x{size:x}],").unwrap();
+                }
+            }
+        }
+        for prop in &layout.skipped {
+            writeln!(
+                b,
+                "    // 0x{:04x} {} overlaps previous member",
+                prop.offset, prop.name
+            )
+            .unwrap();
+        }
+        writeln!(b, "}}").unwrap();
+
+        if !offsets.is_empty() || !bits.is_empty() {
+            writeln!(b, "impl {name} {{").unwrap();
+            let mut consts = HashSet::new();
+            for (prop, offset) in offsets {
+                let konst = format!("OFFSET_{}", sanitize_ident(prop));
+                if consts.insert(konst.clone()) {
+                    writeln!(b, "    pub const {konst}: usize = 0x{offset:x};").unwrap();
+                }
+            }
+            let mut methods = HashSet::new();
+            for (prop, field, bit) in bits {
+                let getter = ident(prop);
+                if !methods.insert(getter.clone()) {
+                    continue;
+                }
+                let mask = 1u8 << bit;
+                writeln!(b, "    pub fn {getter}(&self) -> bool {{").unwrap();
+                writeln!(b, "        self.{field} & 0x{mask:02x} != 0").unwrap();
+                writeln!(b, "    }}").unwrap();
+                writeln!(b, "    pub fn set_{getter}(&mut self, value: bool) {{").unwrap();
+                writeln!(b, "        if value {{").unwrap();
+                writeln!(b, "            self.{field} |= 0x{mask:02x};").unwrap();
+                writeln!(b, "        }} else {{").unwrap();
+                writeln!(b, "            self.{field} &= !0x{mask:02x};").unwrap();
+                writeln!(b, "        }}").unwrap();
+                writeln!(b, "    }}").unwrap();
+            }
+            writeln!(b, "}}").unwrap();
+        }
+
+        if layout.size > 0 {
+            writeln!(
+                b,
+                "const _: () = assert!(std::mem::size_of::<{name}>() == 0x{:x});",
+                layout.size
+            )
+            .unwrap();
+        }
+        for (field, offset) in fields {
+            writeln!(
+                b,
+                "const _: () = assert!(std::mem::offset_of!({name}, {field}) == 0x{offset:x});"
+            )
+            .unwrap();
+        }
+        writeln!(b).unwrap();
+    }
+}
+
+pub fn generate(sdk: &Sdk) -> BTreeMap<String, String> {
+    let generator = Generator { sdk };
+    let mut files = BTreeMap::new();
+
+    files.insert("basic.rs".to_string(), generator.basic());
+
+    let mut root = String::new();
+    writeln!(root, "//! Generated SDK bindings, one module per package").unwrap();
+    writeln!(root, "//!").unwrap();
+    writeln!(
+        root,
+        "//! Flags enums are emitted with the `bitflags` crate which must be a dependency."
+    )
+    .unwrap();
+    writeln!(root).unwrap();
+    writeln!(root, "pub mod basic;").unwrap();
+
+    let mut modules = vec![];
+    for package in &sdk.packages {
+        let module = module_name(package.name);
+        files.insert(format!("{module}.rs"), generator.package(package));
+        writeln!(root, "pub mod {module};").unwrap();
+        modules.push(module);
+    }
+    writeln!(root).unwrap();
+    // type names are unique across packages so everything can be re-exported from the root
+    writeln!(root, "pub use basic::*;").unwrap();
+    for module in modules {
+        writeln!(root, "pub use {module}::*;").unwrap();
+    }
+    files.insert("mod.rs".to_string(), root);
+
+    files
+}
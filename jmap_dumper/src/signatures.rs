@@ -0,0 +1,369 @@
+//! Byte signatures (AOB patterns) of UFunction natives and engine globals, to find them again in
+//! later builds of a game without dumping it. A function's signature starts at its first byte, a
+//! global's at an instruction referencing it; relative branch targets and RIP relative
+//! displacements are wildcarded since they change whenever code moves. Every signature is as
+//! long as it takes to match only once in the image's code, in whole instructions.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Result, bail};
+use iced_x86::{Decoder, DecoderOptions, FlowControl, Instruction, OpKind};
+use jmap::{EFunctionFlags, Jmap, ObjectType, SignatureReference};
+use rayon::prelude::*;
+use tracing::{info, info_span};
+
+use crate::mem::Mem;
+use crate::vtable::SECTION_EXECUTE;
+
+/// Longest signature, shorter ones end at the end of their function
+const MAX_LEN: usize = 64;
+/// Bytes of code scanned per task
+const CHUNK: usize = 0x100000;
+/// Instructions referencing a global to try signatures at
+const MAX_REFERENCES: usize = 32;
+
+/// Executable sections of the main image
+struct Code {
+    sections: Vec<(u64, Vec<u8>)>,
+}
+
+impl Code {
+    fn read<M: Mem>(mem: &M, header: &Jmap) -> Self {
+        let base = header.image_base_address.0;
+        let sections = header
+            .image
+            .iter()
+            .flat_map(|i| &i.sections)
+            .filter(|s| s.characteristics & SECTION_EXECUTE != 0)
+            .map(|s| {
+                let start = base + s.virtual_address as u64;
+                let mut bytes = vec![0; s.virtual_size as usize];
+                // pages missing from the source are left zeroed, which only match wildcards
+                for (i, chunk) in bytes.chunks_mut(CHUNK).enumerate() {
+                    if mem.read_buf(start + (i * CHUNK) as u64, chunk).is_err() {
+                        chunk.fill(0);
+                    }
+                }
+                (start, bytes)
+            })
+            .collect();
+        Self { sections }
+    }
+
+    /// Code from `address` to the end of its section
+    fn at(&self, address: u64) -> Option<&[u8]> {
+        self.sections.iter().find_map(|(start, bytes)| {
+            let offset = usize::try_from(address.checked_sub(*start)?).ok()?;
+            bytes.get(offset..)
+        })
+    }
+
+    /// `(address, chunk)` of every [`CHUNK`], overlapping the next by twice [`MAX_LEN`] (a
+    /// pattern stops at the first instruction past it) so nothing starting near the end of one is
+    /// missed
+    fn chunks(&self) -> Vec<(u64, &[u8])> {
+        self.sections
+            .iter()
+            .flat_map(|(start, bytes)| {
+                (0..bytes.len()).step_by(CHUNK).map(move |offset| {
+                    let end = (offset + CHUNK + 2 * MAX_LEN).min(bytes.len());
+                    (start + offset as u64, &bytes[offset..end])
+                })
+            })
+            .collect()
+    }
+}
+
+struct Candidate {
+    /// Index of the function or global in the targets
+    target: usize,
+    address: u64,
+    pattern: Vec<Option<u8>>,
+    /// End of every instruction in the pattern
+    ends: Vec<usize>,
+    reference: Option<SignatureReference>,
+    /// Offset of the first four fixed bytes, which other matches are looked up by
+    anchor: usize,
+}
+
+impl Candidate {
+    fn new(code: &Code, target: usize, address: u64, reference: bool) -> Option<Self> {
+        let bytes = code.at(address)?;
+        let mut decoder = Decoder::with_ip(64, bytes, address, DecoderOptions::NONE);
+        let mut instruction = Instruction::default();
+        let mut pattern = vec![];
+        let mut ends = vec![];
+        let mut first = None;
+        while decoder.can_decode() && pattern.len() < MAX_LEN {
+            decoder.decode_out(&mut instruction);
+            if instruction.is_invalid() {
+                break;
+            }
+            let start = pattern.len();
+            let len = instruction.len();
+            pattern.extend(bytes[start..start + len].iter().map(|b| Some(*b)));
+
+            let offsets = decoder.get_constant_offsets(&instruction);
+            if instruction.is_ip_rel_memory_operand() && offsets.has_displacement() {
+                let displacement = start + offsets.displacement_offset();
+                pattern[displacement..displacement + offsets.displacement_size()].fill(None);
+                first.get_or_insert(SignatureReference {
+                    displacement,
+                    end: start + len,
+                });
+            }
+            if matches!(
+                instruction.op0_kind(),
+                OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64
+            ) {
+                // the relative target is the end of the instruction, a byte for short branches
+                let rel = if len < 5 { 1 } else { 4 };
+                pattern[start + len - rel..start + len].fill(None);
+            }
+            ends.push(start + len);
+
+            if matches!(
+                instruction.flow_control(),
+                FlowControl::Return | FlowControl::UnconditionalBranch | FlowControl::Interrupt
+            ) {
+                break;
+            }
+        }
+        let reference = if reference {
+            // a reference has to start with the instruction referencing the global
+            Some(first.filter(|r| r.end == ends[0])?)
+        } else {
+            None
+        };
+        let anchor = (0..pattern.len().saturating_sub(3))
+            .find(|i| pattern[*i..*i + 4].iter().all(Option::is_some))?;
+        Some(Self {
+            target,
+            address,
+            pattern,
+            ends,
+            reference,
+            anchor,
+        })
+    }
+
+    fn key(&self) -> u32 {
+        let b = |i: usize| self.pattern[self.anchor + i].unwrap();
+        u32::from_le_bytes([b(0), b(1), b(2), b(3)])
+    }
+
+    /// Bytes of the pattern `bytes` agrees with from the start
+    fn agreement(&self, bytes: &[u8]) -> usize {
+        self.pattern
+            .iter()
+            .zip(bytes)
+            .take_while(|(p, b)| p.is_none_or(|p| p == **b))
+            .count()
+    }
+
+    /// Shortest prefix of whole instructions matching nowhere else, given the longest agreement
+    /// anywhere else
+    fn unique_len(&self, longest_elsewhere: usize) -> Option<usize> {
+        // elsewhere there can be an agreement up to the anchor without the anchor matching
+        let needed = (longest_elsewhere + 1).max(self.anchor + 4);
+        self.ends.iter().copied().find(|end| *end >= needed)
+    }
+}
+
+/// Instructions in the main image's code referencing each of `globals` RIP relatively
+fn find_references(code: &Code, globals: &[u64]) -> HashMap<u64, Vec<u64>> {
+    let chunks = code.chunks();
+    let references = chunks
+        .par_iter()
+        .flat_map_iter(|(address, bytes)| {
+            let mut references = vec![];
+            let mut decoder = Decoder::with_ip(64, bytes, *address, DecoderOptions::NONE);
+            let mut instruction = Instruction::default();
+            // stop at the chunk boundary, the next chunk decodes past its overlap
+            while decoder.can_decode() && decoder.ip() < address + CHUNK as u64 {
+                decoder.decode_out(&mut instruction);
+                if instruction.is_ip_rel_memory_operand()
+                    && globals.contains(&instruction.ip_rel_memory_address())
+                {
+                    references.push((instruction.ip_rel_memory_address(), instruction.ip()));
+                }
+            }
+            references
+        })
+        .collect::<Vec<_>>();
+
+    let mut by_global = HashMap::<u64, Vec<u64>>::new();
+    for (global, instruction) in references {
+        by_global.entry(global).or_default().push(instruction);
+    }
+    for references in by_global.values_mut() {
+        references.sort_unstable();
+        references.truncate(MAX_REFERENCES);
+    }
+    by_global
+}
+
+/// Native of `obj` if it is a native UFunction, script functions all point to the interpreter
+pub(crate) fn native(obj: &ObjectType) -> Option<u64> {
+    match obj {
+        ObjectType::Function(function)
+            if function
+                .function_flags
+                .contains(EFunctionFlags::FUNC_Native)
+                && function.func.0 != 0 =>
+        {
+            Some(function.func.0)
+        }
+        _ => None,
+    }
+}
+
+/// Signatures of the resolved engine globals of `header` and `natives`, the `(path, native)` of
+/// UFunctions
+pub(crate) fn find_signatures<M: Mem>(
+    mem: &M,
+    header: &Jmap,
+    natives: impl IntoIterator<Item = (String, u64)>,
+) -> Vec<jmap::Signature> {
+    let base = header.image_base_address.0;
+    let code = Code::read(mem, header);
+    let is_code = |address: u64| code.at(address).is_some();
+
+    // (name, address) of every target
+    let mut targets = header
+        .resolved_addresses
+        .iter()
+        .map(|(name, address)| (name.clone(), address.0))
+        .collect::<Vec<_>>();
+    let globals = targets.len();
+    targets.extend(natives);
+    let _span = info_span!("signatures", count = targets.len()).entered();
+
+    let data = targets[..globals]
+        .iter()
+        .map(|(_, address)| *address)
+        .filter(|a| !is_code(*a))
+        .collect::<Vec<_>>();
+    let references = find_references(&code, &data);
+
+    let candidates = targets
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(i, (_, address))| {
+            if is_code(*address) {
+                Candidate::new(&code, i, *address, false)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            } else {
+                references
+                    .get(address)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|r| Candidate::new(&code, i, *r, true))
+                    .collect()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut anchors = HashMap::<u32, Vec<usize>>::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        anchors.entry(candidate.key()).or_default().push(i);
+    }
+    let longest = candidates
+        .iter()
+        .map(|_| AtomicUsize::new(0))
+        .collect::<Vec<_>>();
+    // the overlap is scanned twice, so matches starting in one chunk and anchored in the next
+    // are seen in full
+    code.chunks().par_iter().for_each(|(address, bytes)| {
+        for (offset, key) in bytes.windows(4).enumerate() {
+            let Some(matches) = anchors.get(&u32::from_le_bytes(key.try_into().unwrap())) else {
+                continue;
+            };
+            for i in matches {
+                let candidate = &candidates[*i];
+                let Some(start) = offset.checked_sub(candidate.anchor) else {
+                    continue;
+                };
+                if address + start as u64 == candidate.address {
+                    continue;
+                }
+                let agreement = candidate.agreement(&bytes[start..]);
+                longest[*i].fetch_max(agreement, Ordering::Relaxed);
+            }
+        }
+    });
+
+    // shortest unique signature of every target
+    let mut best = BTreeMap::<usize, (usize, &Candidate)>::new();
+    for (candidate, longest) in candidates.iter().zip(&longest) {
+        let Some(len) = candidate.unique_len(longest.load(Ordering::Relaxed)) else {
+            continue;
+        };
+        match best.get(&candidate.target) {
+            Some((best_len, _)) if *best_len <= len => {}
+            _ => {
+                best.insert(candidate.target, (len, candidate));
+            }
+        }
+    }
+    info!(
+        "unique signatures for {} of {} targets",
+        best.len(),
+        targets.len()
+    );
+
+    best.into_iter()
+        .map(|(target, (len, candidate))| jmap::Signature {
+            name: targets[target].0.clone(),
+            rva: targets[target].1.wrapping_sub(base).into(),
+            pattern: format_pattern(&candidate.pattern[..len]),
+            reference: candidate.reference.clone(),
+        })
+        .collect()
+}
+
+fn format_pattern(pattern: &[Option<u8>]) -> String {
+    pattern
+        .iter()
+        .map(|b| match b {
+            Some(b) => format!("{b:02X}"),
+            None => "??".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Tab separated signature list: name, RVA, pattern and for globals where in the pattern the
+/// displacement resolving to it is
+pub fn into_signatures(jmap: &Jmap) -> Result<String> {
+    if jmap.signatures.is_empty() {
+        bail!("dump has no signatures, dump the game with --signatures");
+    }
+    let mut buffer = String::new();
+    writeln!(
+        buffer,
+        "# global: the global is at match + end + the disp32 at match + displacement"
+    )
+    .unwrap();
+    for signature in &jmap.signatures {
+        write!(
+            buffer,
+            "{}\t0x{:x}\t{}",
+            signature.name, signature.rva.0, signature.pattern
+        )
+        .unwrap();
+        if let Some(reference) = &signature.reference {
+            write!(
+                buffer,
+                "\tglobal displacement={} end={}",
+                reference.displacement, reference.end
+            )
+            .unwrap();
+        }
+        writeln!(buffer).unwrap();
+    }
+    Ok(buffer)
+}
@@ -0,0 +1,47 @@
+//! Full memory minidumps of a live process, readable later with `--minidump`
+
+use std::path::Path;
+
+use anyhow::Result;
+
+#[cfg(target_os = "windows")]
+pub fn write_minidump(pid: i32, path: &Path) -> Result<()> {
+    use anyhow::Context as _;
+    use std::os::windows::io::AsRawHandle as _;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Diagnostics::Debug::{MiniDumpWithFullMemory, MiniDumpWriteDump};
+    use windows::Win32::System::Threading::{
+        OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
+    };
+
+    use crate::proc_name::HandleGuard;
+
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    unsafe {
+        let process = OpenProcess(
+            PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
+            false,
+            pid as u32,
+        )
+        .with_context(|| format!("Failed to open process {pid}"))?;
+        let _process = HandleGuard(process);
+
+        MiniDumpWriteDump(
+            process,
+            pid as u32,
+            HANDLE(file.as_raw_handle()),
+            MiniDumpWithFullMemory,
+            None,
+            None,
+            None,
+        )
+        .with_context(|| format!("MiniDumpWriteDump of process {pid} failed"))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn write_minidump(_pid: i32, _path: &Path) -> Result<()> {
+    anyhow::bail!("Writing minidumps is only supported on Windows")
+}
@@ -0,0 +1,1248 @@
+//! Struct layouts computed from Unreal Engine source headers, so the built-in table in
+//! `unreal.gs` can be checked and regenerated against a source checkout of any version instead of
+//! being maintained offset by offset.
+//!
+//! This is not a C++ compiler. Headers are run through a small preprocessor (conditions are
+//! evaluated against shipping build defines, `#include`s are ignored), then class and struct
+//! bodies are scanned for data members, skipping functions, macros and templates. Layout follows
+//! MSVC x64 rules: members are placed at their natural alignment after the base class,
+//! consecutive bit fields of the same type share storage and polymorphic classes get a vtable
+//! pointer. Engine templates and typedefs the scanner can't size come from a table of known
+//! sizes, anything else has to be given with [`SourceOptions::type_sizes`].
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::{Context as _, Result, bail};
+
+use crate::structs::{STRUCT_NAMES, StructInfo, StructMember, Structs};
+
+pub struct SourceOptions {
+    pub version: (u16, u16),
+    pub case_preserving: bool,
+    /// Extra defines for preprocessor conditions
+    pub defines: Vec<(String, i64)>,
+    /// Size and alignment of types the scanner can't size itself
+    pub type_sizes: Vec<(String, u64, u64)>,
+}
+
+/// Result of [`generate`]
+pub struct Generated {
+    pub structs: Structs,
+    /// Table structs without a definition in the headers
+    pub not_found: Vec<String>,
+    /// Table structs whose layout failed, with the reason
+    pub failed: Vec<(String, String)>,
+}
+
+/// Lay out every struct of the built-in table that is defined in the `.h` files found under
+/// `paths` (files or directories, e.g. `Engine/Source/Runtime/CoreUObject`)
+pub fn generate(paths: &[PathBuf], options: &SourceOptions) -> Result<Generated> {
+    let mut files = vec![];
+    for path in paths {
+        collect_headers(path, &mut files)?;
+    }
+    if files.is_empty() {
+        bail!("No .h files found");
+    }
+
+    let mut defines = predefined(options);
+    let mut index = Index::default();
+    for file in &files {
+        let source = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        index.scan(&tokenize(&preprocess(&source, &mut defines)));
+    }
+
+    let mut layouts = Layouts {
+        index: &index,
+        defines: &defines,
+        case_preserving: options.case_preserving,
+        overrides: options
+            .type_sizes
+            .iter()
+            .map(|(name, size, align)| (name.clone(), (*size, *align)))
+            .collect(),
+        cache: HashMap::new(),
+        in_progress: HashSet::new(),
+    };
+
+    let mut generated = Generated {
+        structs: Structs(vec![]),
+        not_found: vec![],
+        failed: vec![],
+    };
+    for table_name in STRUCT_NAMES {
+        let source_name = match table_name.strip_prefix('Z') {
+            Some(rest) if options.version >= (4, 25) => format!("F{rest}"),
+            Some(rest) => format!("U{rest}"),
+            None => table_name.to_string(),
+        };
+        if !index.records.contains_key(&source_name) {
+            generated.not_found.push(table_name.to_string());
+            continue;
+        }
+        match layouts.record(&source_name) {
+            Ok(layout) => generated.structs.0.push(StructInfo {
+                name: table_name.to_string(),
+                size: layout.size,
+                alignment: layout.align,
+                members: layout
+                    .members
+                    .iter()
+                    .map(|(name, offset, size)| StructMember {
+                        name: name.clone(),
+                        offset: *offset,
+                        size: *size,
+                    })
+                    .collect(),
+            }),
+            Err(err) => generated
+                .failed
+                .push((table_name.to_string(), format!("{err:#}"))),
+        }
+    }
+    Ok(generated)
+}
+
+fn collect_headers(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_dir() {
+        let mut entries = std::fs::read_dir(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            collect_headers(&entry, files)?;
+        }
+    } else if path.extension().is_some_and(|e| e == "h") {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+fn predefined(options: &SourceOptions) -> HashMap<String, i64> {
+    let (major, minor) = options.version;
+    let mut defines: HashMap<String, i64> = [
+        ("ENGINE_MAJOR_VERSION", major as i64),
+        ("ENGINE_MINOR_VERSION", minor as i64),
+        ("WITH_CASE_PRESERVING_NAME", options.case_preserving as i64),
+        ("UE_BUILD_SHIPPING", 1),
+        ("PLATFORM_WINDOWS", 1),
+        ("PLATFORM_64BITS", 1),
+        ("PLATFORM_CPU_X86_FAMILY", 1),
+        ("PLATFORM_LITTLE_ENDIAN", 1),
+        ("WITH_EDITOR", 0),
+        ("WITH_EDITORONLY_DATA", 0),
+        ("WITH_ENGINE", 1),
+        ("WITH_METADATA", 0),
+        ("DO_CHECK", 0),
+        ("STATS", 0),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect();
+    defines.extend(options.defines.iter().cloned());
+    defines
+}
+
+/// Strip comments, drop inactive conditional blocks and record object-like `#define`s. Defines
+/// already known win, so the shipping configuration isn't overridden by header defaults.
+fn preprocess(source: &str, defines: &mut HashMap<String, i64>) -> String {
+    struct Frame {
+        parent_active: bool,
+        active: bool,
+        taken: bool,
+    }
+    fn is_active(stack: &[Frame]) -> bool {
+        stack.last().is_none_or(|f| f.parent_active && f.active)
+    }
+
+    let source = strip_comments(source).replace("\\\n", " ");
+    let mut stack: Vec<Frame> = vec![];
+    let mut out = String::new();
+    for line in source.lines() {
+        let Some(directive) = line.trim_start().strip_prefix('#') else {
+            if is_active(&stack) {
+                out.push_str(line);
+            }
+            out.push('\n');
+            continue;
+        };
+        let directive = directive.trim_start();
+        let name = directive
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        let rest = directive[name.len()..].trim();
+        let active = is_active(&stack);
+        match name {
+            "if" | "ifdef" | "ifndef" => {
+                let value = match name {
+                    "ifdef" => defines.contains_key(rest),
+                    "ifndef" => !defines.contains_key(rest),
+                    _ => eval(rest, defines).unwrap_or(0) != 0,
+                };
+                stack.push(Frame {
+                    parent_active: active,
+                    active: value,
+                    taken: value,
+                });
+            }
+            "elif" => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.active = !frame.taken && eval(rest, defines).unwrap_or(0) != 0;
+                    frame.taken |= frame.active;
+                }
+            }
+            "else" => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.active = !frame.taken;
+                    frame.taken = true;
+                }
+            }
+            "endif" => {
+                stack.pop();
+            }
+            "define" if active => {
+                let (name, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                // function-like macros aren't expanded
+                if !name.contains('(') {
+                    let value = eval(value, defines).unwrap_or(1);
+                    defines.entry(name.to_string()).or_insert(value);
+                }
+            }
+            "undef" if active => {
+                defines.remove(rest);
+            }
+            _ => {}
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Remove comments and replace string and character literals with `0` so their contents can't
+/// look like code
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                out.push(' ');
+            }
+            '"' | '\'' => {
+                out.push('0');
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if next == '\\' {
+                        escaped = true;
+                    } else if next == c || next == '\n' {
+                        break;
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Evaluate a preprocessor expression. Like the real preprocessor, unknown identifiers are 0.
+fn eval(expression: &str, defines: &HashMap<String, i64>) -> Option<i64> {
+    let tokens = tokenize(expression);
+    let mut parser = ExprParser {
+        tokens: &tokens,
+        pos: 0,
+        defines,
+    };
+    let value = parser.binary(0)?;
+    (parser.pos == tokens.len()).then_some(value)
+}
+
+struct ExprParser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    defines: &'a HashMap<String, i64>,
+}
+
+/// Binary operators by increasing precedence
+const PRECEDENCE: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        let token = self.tokens.get(self.pos)?;
+        self.pos += 1;
+        Some(token)
+    }
+
+    fn binary(&mut self, level: usize) -> Option<i64> {
+        let Some(ops) = PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(op) = self.peek().filter(|t| ops.contains(t)).map(str::to_string) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = match op.as_str() {
+                "||" => (lhs != 0 || rhs != 0) as i64,
+                "&&" => (lhs != 0 && rhs != 0) as i64,
+                "|" => lhs | rhs,
+                "^" => lhs ^ rhs,
+                "&" => lhs & rhs,
+                "==" => (lhs == rhs) as i64,
+                "!=" => (lhs != rhs) as i64,
+                "<" => (lhs < rhs) as i64,
+                "<=" => (lhs <= rhs) as i64,
+                ">" => (lhs > rhs) as i64,
+                ">=" => (lhs >= rhs) as i64,
+                "<<" => lhs.checked_shl(rhs as u32).unwrap_or(0),
+                ">>" => lhs.checked_shr(rhs as u32).unwrap_or(0),
+                "+" => lhs.wrapping_add(rhs),
+                "-" => lhs.wrapping_sub(rhs),
+                "*" => lhs.wrapping_mul(rhs),
+                "/" => lhs.checked_div(rhs)?,
+                _ => lhs.checked_rem(rhs)?,
+            };
+        }
+        Some(lhs)
+    }
+
+    fn unary(&mut self) -> Option<i64> {
+        let token = self.next()?.to_string();
+        match token.as_str() {
+            "!" => Some((self.unary()? == 0) as i64),
+            "-" => Some(self.unary()?.wrapping_neg()),
+            "~" => Some(!self.unary()?),
+            "(" => {
+                let value = self.binary(0)?;
+                (self.next()? == ")").then_some(value)
+            }
+            "defined" => {
+                let parens = self.peek() == Some("(");
+                if parens {
+                    self.pos += 1;
+                }
+                let name = self.next()?.to_string();
+                if parens && self.next()? != ")" {
+                    return None;
+                }
+                Some(self.defines.contains_key(&name) as i64)
+            }
+            _ if is_ident(&token) => {
+                // calls of function-like macros such as UE_VERSION_NEWER_THAN(5, 1, 0) are 0
+                if self.peek() == Some("(") {
+                    self.pos = skip_group(self.tokens, self.pos);
+                    return Some(0);
+                }
+                Some(self.defines.get(&token).copied().unwrap_or(0))
+            }
+            _ => parse_int(&token),
+        }
+    }
+}
+
+fn parse_int(token: &str) -> Option<i64> {
+    let digits = token.trim_end_matches(['u', 'U', 'l', 'L']);
+    match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => digits.parse().ok(),
+    }
+}
+
+fn is_ident(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+}
+
+/// Split into identifiers, numbers and punctuation. `>>` stays two tokens so nested template
+/// argument lists close properly.
+fn tokenize(source: &str) -> Vec<String> {
+    const PUNCT: &[&str] = &["::", "||", "&&", "==", "!=", "<=", ">=", "<<", "->", "..."];
+
+    let mut tokens = vec![];
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len())
+        } else if let Some(p) = PUNCT.iter().find(|p| rest.starts_with(**p)) {
+            p.len()
+        } else {
+            c.len_utf8()
+        };
+        tokens.push(rest[..len].to_string());
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// Position after the bracket matching the one at `start`
+fn skip_group(tokens: &[String], start: usize) -> usize {
+    let (open, close) = match tokens[start].as_str() {
+        "(" => ("(", ")"),
+        "[" => ("[", "]"),
+        "<" => ("<", ">"),
+        _ => ("{", "}"),
+    };
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        if token == open {
+            depth += 1;
+        } else if token == close {
+            depth -= 1;
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+    }
+    tokens.len()
+}
+
+fn is_api_macro(token: &str) -> bool {
+    token.ends_with("_API") || token == "final"
+}
+
+fn is_macro_name(token: &str) -> bool {
+    is_ident(token)
+        && token
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Class, struct or union definition
+#[derive(Clone)]
+struct Record {
+    is_union: bool,
+    /// First base class, others can only be empty interfaces
+    base: Option<String>,
+    align: Option<u64>,
+    body: Vec<String>,
+}
+
+/// Every type definition found in the headers
+#[derive(Default)]
+struct Index {
+    records: HashMap<String, Record>,
+    /// Underlying type of enums
+    enums: HashMap<String, Vec<String>>,
+    /// `typedef` and `using` aliases
+    aliases: HashMap<String, Vec<String>>,
+}
+
+impl Index {
+    fn scan(&mut self, tokens: &[String]) {
+        let statement_end = |from: usize| {
+            (from..tokens.len())
+                .find(|&j| tokens[j] == ";")
+                .unwrap_or(tokens.len())
+        };
+        let mut i = 0;
+        while i < tokens.len() {
+            match tokens[i].as_str() {
+                "template" if tokens.get(i + 1).is_some_and(|t| t == "<") => {
+                    // templates can't be laid out without their arguments
+                    i = skip_group(tokens, i + 1);
+                    while tokens.get(i).is_some_and(|t| t != "{" && t != ";") {
+                        i += 1;
+                    }
+                    if tokens.get(i).is_some_and(|t| t == "{") {
+                        i = skip_group(tokens, i);
+                    }
+                }
+                "class" | "struct" | "union" => i = self.scan_record(tokens, i),
+                "enum" => i = self.scan_enum(tokens, i),
+                "typedef" => {
+                    let end = statement_end(i);
+                    let decl = &tokens[i + 1..end];
+                    if let Some((name, ty)) = decl.split_last()
+                        && is_ident(name)
+                        && !decl.iter().any(|t| t == "(")
+                    {
+                        self.aliases.insert(name.clone(), ty.to_vec());
+                    }
+                    i = end + 1;
+                }
+                "using" if tokens.get(i + 2).is_some_and(|t| t == "=") => {
+                    let end = statement_end(i);
+                    self.aliases
+                        .insert(tokens[i + 1].clone(), tokens[i + 3..end].to_vec());
+                    i = end + 1;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    /// Record the definition starting at the keyword at `start`, returns where scanning resumes
+    fn scan_record(&mut self, tokens: &[String], start: usize) -> usize {
+        let is_union = tokens[start] == "union";
+        let mut i = start + 1;
+        let mut align = None;
+        loop {
+            match tokens.get(i).map(String::as_str) {
+                Some("alignas" | "MS_ALIGN" | "GCC_ALIGN")
+                    if tokens.get(i + 1).is_some_and(|t| t == "(") =>
+                {
+                    align = tokens
+                        .get(i + 2)
+                        .and_then(|t| parse_int(t))
+                        .map(|a| a as u64);
+                    i = skip_group(tokens, i + 1);
+                }
+                Some(t) if is_api_macro(t) => i += 1,
+                _ => break,
+            }
+        }
+        let Some(name) = tokens.get(i).filter(|t| is_ident(t)).cloned() else {
+            return start + 1;
+        };
+        i += 1;
+        while tokens.get(i).is_some_and(|t| is_api_macro(t)) {
+            i += 1;
+        }
+
+        let mut base = None;
+        if tokens.get(i).is_some_and(|t| t == ":") {
+            i += 1;
+            let mut base_tokens = vec![];
+            while let Some(t) = tokens.get(i) {
+                match t.as_str() {
+                    "{" | ";" | "," => break,
+                    "<" => {
+                        let end = skip_group(tokens, i);
+                        base_tokens.extend_from_slice(&tokens[i..end]);
+                        i = end;
+                        continue;
+                    }
+                    "public" | "protected" | "private" | "virtual" => {}
+                    _ => base_tokens.push(t.clone()),
+                }
+                i += 1;
+            }
+            if !base_tokens.is_empty() {
+                base = Some(base_tokens.concat());
+            }
+            while tokens.get(i).is_some_and(|t| t != "{" && t != ";") {
+                i += 1;
+            }
+        }
+
+        if tokens.get(i).is_none_or(|t| t != "{") {
+            // forward declaration or elaborated type specifier
+            return i;
+        }
+        let end = skip_group(tokens, i);
+        let body = tokens[i + 1..end - 1].to_vec();
+        // nested types are found by scanning the body as well
+        self.scan(&body);
+        self.records.entry(name).or_insert(Record {
+            is_union,
+            base,
+            align,
+            body,
+        });
+        end
+    }
+
+    fn scan_enum(&mut self, tokens: &[String], start: usize) -> usize {
+        let mut i = start + 1;
+        if tokens.get(i).is_some_and(|t| t == "class" || t == "struct") {
+            i += 1;
+        }
+        let Some(name) = tokens.get(i).filter(|t| is_ident(t)).cloned() else {
+            return start + 1;
+        };
+        i += 1;
+        let mut underlying = vec!["int32".to_string()];
+        if tokens.get(i).is_some_and(|t| t == ":") {
+            let end = (i..tokens.len())
+                .find(|&j| tokens[j] == "{" || tokens[j] == ";")
+                .unwrap_or(tokens.len());
+            underlying = tokens[i + 1..end].to_vec();
+            i = end;
+        }
+        self.enums.entry(name).or_insert(underlying);
+        match tokens.get(i).map(String::as_str) {
+            Some("{") => skip_group(tokens, i),
+            _ => i,
+        }
+    }
+}
+
+struct Layout {
+    size: u64,
+    align: u64,
+    has_vtable: bool,
+    /// Name, offset and size of every member including those of base classes
+    members: Vec<(String, u64, u64)>,
+}
+
+/// Data member of a class body
+struct Declaration {
+    ty: DeclaredType,
+    /// Empty for anonymous structs and unions, whose members belong to the outer record
+    name: String,
+    count: u64,
+    bits: Option<u64>,
+}
+
+enum DeclaredType {
+    Named(Vec<String>),
+    Nested(Layout),
+}
+
+fn align_to(value: u64, align: u64) -> u64 {
+    value.div_ceil(align) * align
+}
+
+struct Layouts<'a> {
+    index: &'a Index,
+    defines: &'a HashMap<String, i64>,
+    case_preserving: bool,
+    overrides: HashMap<String, (u64, u64)>,
+    cache: HashMap<String, Rc<Layout>>,
+    in_progress: HashSet<String>,
+}
+
+impl Layouts<'_> {
+    fn record(&mut self, name: &str) -> Result<Rc<Layout>> {
+        if let Some(layout) = self.cache.get(name) {
+            return Ok(layout.clone());
+        }
+        let record = self
+            .index
+            .records
+            .get(name)
+            .with_context(|| format!("{name} is not defined in the headers"))?;
+        if !self.in_progress.insert(name.to_string()) {
+            bail!("{name} contains itself");
+        }
+        let layout = self
+            .compute(record)
+            .with_context(|| format!("Failed to lay out {name}"));
+        self.in_progress.remove(name);
+        let layout = Rc::new(layout?);
+        self.cache.insert(name.to_string(), layout.clone());
+        Ok(layout)
+    }
+
+    fn compute(&mut self, record: &Record) -> Result<Layout> {
+        let mut layout = match &record.base {
+            Some(base) => {
+                let base = self.record(base)?;
+                Layout {
+                    size: base.size,
+                    align: base.align,
+                    has_vtable: base.has_vtable,
+                    members: base.members.clone(),
+                }
+            }
+            None => Layout {
+                size: 0,
+                align: 1,
+                has_vtable: false,
+                members: vec![],
+            },
+        };
+        if !layout.has_vtable && record.body.iter().any(|t| t == "virtual") {
+            // the vtable pointer goes in front of a non-polymorphic base
+            layout.has_vtable = true;
+            for member in &mut layout.members {
+                member.1 += 8;
+            }
+            layout.size = align_to(layout.size, layout.align.max(8)) + 8;
+            layout.align = layout.align.max(8);
+        }
+
+        // offset, size and used bits of the current bit field storage unit
+        let mut bitfield: Option<(u64, u64, u64)> = None;
+        for decl in self.declarations(&record.body)? {
+            let (size, align) = match &decl.ty {
+                DeclaredType::Named(ty) => self.type_size(ty)?,
+                DeclaredType::Nested(nested) => (nested.size, nested.align),
+            };
+            if let (Some(bits), Some((offset, unit, used))) = (decl.bits, bitfield)
+                && unit == size
+                && used + bits <= size * 8
+            {
+                bitfield = Some((offset, unit, used + bits));
+                layout.members.push((decl.name, offset, size));
+                continue;
+            }
+
+            let offset = if record.is_union {
+                0
+            } else {
+                align_to(layout.size, align)
+            };
+            bitfield = decl.bits.map(|bits| (offset, size, bits));
+            layout.align = layout.align.max(align);
+            layout.size = layout.size.max(offset + size * decl.count);
+            match decl.ty {
+                DeclaredType::Nested(nested) if decl.name.is_empty() => layout.members.extend(
+                    nested
+                        .members
+                        .into_iter()
+                        .map(|(n, o, s)| (n, offset + o, s)),
+                ),
+                _ => layout.members.push((decl.name, offset, size * decl.count)),
+            }
+        }
+
+        if let Some(align) = record.align {
+            layout.align = layout.align.max(align);
+        }
+        layout.size = align_to(layout.size.max(1), layout.align);
+        Ok(layout)
+    }
+
+    /// Sizes of engine types declared in ways the scanner doesn't follow (templates, platform
+    /// typedefs, version dependent definitions)
+    fn known_size(&self, name: &str) -> Option<(u64, u64)> {
+        let fname = if self.case_preserving { 12 } else { 8 };
+        Some(match name {
+            "bool" | "char" | "int8" | "uint8" | "ANSICHAR" | "UTF8CHAR" | "int8_t" | "uint8_t" => {
+                (1, 1)
+            }
+            "short" | "int16" | "uint16" | "wchar_t" | "TCHAR" | "WIDECHAR" | "UTF16CHAR" => (2, 2),
+            "int" | "long" | "float" | "int32" | "uint32" | "int32_t" | "uint32_t"
+            | "FThreadSafeCounter" | "FNameEntryId" => (4, 4),
+            "double"
+            | "int64"
+            | "uint64"
+            | "int64_t"
+            | "uint64_t"
+            | "size_t"
+            | "SIZE_T"
+            | "SSIZE_T"
+            | "PTRINT"
+            | "UPTRINT"
+            | "FThreadSafeCounter64"
+            | "FObjectHandle"
+            | "FNativeFuncPtr"
+            | "FRWLock"
+            | "FWindowsRWLock" => (8, 8),
+            "FName" | "FMinimalName" => (fname, 4),
+            "FWeakObjectPtr" => (8, 4),
+            "FScriptDelegate" => (8 + fname, 4),
+            "FGuid" => (16, 4),
+            "FString" | "FScriptArray" | "FMulticastScriptDelegate" => (16, 8),
+            "FText" => (24, 8),
+            "FCriticalSection" | "FWindowsCriticalSection" => (40, 8),
+            "FScriptSparseArray" => (56, 8),
+            "FScriptSet" | "FScriptMap" => (80, 8),
+            _ => return None,
+        })
+    }
+
+    /// Size of an instance of template `name`, `None` for unknown templates
+    fn template_size(&mut self, name: &str, args: &[String]) -> Result<Option<(u64, u64)>> {
+        Ok(Some(match name {
+            "TEnumAsByte" => (1, 1),
+            "TObjectPtr"
+            | "TNonAccessTrackedObjectPtr"
+            | "TSubclassOf"
+            | "TWeakObjectPtr"
+            | "TUniquePtr" => (8, 8),
+            "TArray" | "TScriptArray" | "TSortedMap" | "TSharedPtr" | "TSharedRef"
+            | "TFunctionRef" => (16, 8),
+            "TMap" | "TSet" | "TMultiMap" => (80, 8),
+            "TAtomic" | "atomic" => self.type_size(args)?,
+            "TAlignedBytes" => {
+                let mut args = args.split(|t| t == ",");
+                let mut next = || args.next().and_then(|a| self.eval(a));
+                match (next(), next()) {
+                    (Some(size), Some(align)) => (size, align),
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    fn type_size(&mut self, ty: &[String]) -> Result<(u64, u64)> {
+        let ty = ty
+            .iter()
+            .filter(|t| {
+                !matches!(
+                    t.as_str(),
+                    "const"
+                        | "volatile"
+                        | "mutable"
+                        | "class"
+                        | "struct"
+                        | "enum"
+                        | "typename"
+                        | "signed"
+                        | "unsigned"
+                )
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        match ty.as_slice() {
+            [.., last] if last == "*" || last == "&" => return Ok((8, 8)),
+            // `unsigned` alone
+            [] => return Ok((4, 4)),
+            [a, b] if a == "long" && b == "long" => return Ok((8, 8)),
+            _ => {}
+        }
+
+        // UE::FPackageId -> FPackageId
+        let mut start = 0;
+        while ty.get(start + 1).is_some_and(|t| t == "::") {
+            start += 2;
+        }
+        let (name, args) = (&ty[start], &ty[start + 1..]);
+
+        if let Some(size) = self.overrides.get(name) {
+            return Ok(*size);
+        }
+        if args.first().is_some_and(|t| t == "<") {
+            if let Some(size) = self.template_size(name, &args[1..args.len() - 1])? {
+                return Ok(size);
+            }
+        } else if args.is_empty() {
+            if let Some(size) = self.known_size(name) {
+                return Ok(size);
+            }
+            if let Some(underlying) = self.index.enums.get(name) {
+                return self.type_size(underlying);
+            }
+            if let Some(alias) = self.index.aliases.get(name) {
+                return self.type_size(alias);
+            }
+            if self.index.records.contains_key(name) {
+                let layout = self.record(name)?;
+                return Ok((layout.size, layout.align));
+            }
+        }
+        bail!(
+            "Unknown type {}, give its size with --type-size",
+            ty.concat()
+        )
+    }
+
+    fn eval(&self, tokens: &[String]) -> Option<u64> {
+        eval(&tokens.join(" "), self.defines).map(|v| v as u64)
+    }
+
+    fn declarations(&mut self, body: &[String]) -> Result<Vec<Declaration>> {
+        let mut declarations = vec![];
+        let mut statement: Vec<String> = vec![];
+        let mut i = 0;
+        while i < body.len() {
+            match body[i].as_str() {
+                ";" => {
+                    self.statement(&std::mem::take(&mut statement), &mut declarations)?;
+                    i += 1;
+                }
+                ":" if matches!(
+                    statement.as_slice(),
+                    [access] if matches!(access.as_str(), "public" | "protected" | "private")
+                ) =>
+                {
+                    statement.clear();
+                    i += 1;
+                }
+                "(" => {
+                    let end = skip_group(body, i);
+                    // UPROPERTY(...), GENERATED_BODY(), DECLARE_FUNCTION(...)
+                    if statement.last().is_some_and(|t| is_macro_name(t)) {
+                        statement.pop();
+                    } else {
+                        statement.extend_from_slice(&body[i..end]);
+                    }
+                    i = end;
+                }
+                "<" | "[" => {
+                    let end = skip_group(body, i);
+                    statement.extend_from_slice(&body[i..end]);
+                    i = end;
+                }
+                "{" => {
+                    let end = skip_group(body, i);
+                    let kind = statement.first().map(String::as_str);
+                    if statement.iter().any(|t| t == "(") {
+                        // function body
+                        statement.clear();
+                        i = end;
+                        if body.get(i).is_some_and(|t| t == ";") {
+                            i += 1;
+                        }
+                    } else if matches!(kind, Some("struct" | "class" | "union")) {
+                        // nested type, a member if a declarator follows or it's anonymous
+                        let nested = Record {
+                            is_union: kind == Some("union"),
+                            base: None,
+                            align: None,
+                            body: body[i + 1..end - 1].to_vec(),
+                        };
+                        let decl_end = (end..body.len())
+                            .find(|&j| body[j] == ";")
+                            .unwrap_or(body.len());
+                        let name = body.get(end).filter(|t| is_ident(t)).cloned();
+                        let anonymous = statement.len() == 1;
+                        if name.is_some() || anonymous {
+                            declarations.push(Declaration {
+                                ty: DeclaredType::Nested(self.compute(&nested)?),
+                                name: name.unwrap_or_default(),
+                                count: 1,
+                                bits: None,
+                            });
+                        }
+                        statement.clear();
+                        i = decl_end + 1;
+                    } else {
+                        // brace initializer or enum body
+                        i = end;
+                    }
+                }
+                _ => {
+                    statement.push(body[i].clone());
+                    i += 1;
+                }
+            }
+        }
+        Ok(declarations)
+    }
+
+    /// Declarations of one statement of a class body: `int32 A, *B, C[2], D : 1;`
+    fn statement(&self, tokens: &[String], out: &mut Vec<Declaration>) -> Result<()> {
+        const SKIP: &[&str] = &[
+            "static",
+            "friend",
+            "using",
+            "typedef",
+            "enum",
+            "template",
+            "virtual",
+            "constexpr",
+            "inline",
+            "explicit",
+            "operator",
+        ];
+        if tokens.is_empty()
+            || tokens.iter().any(|t| t == "(" || SKIP.contains(&t.as_str()))
+            // forward declaration
+            || matches!(tokens, [keyword, _] if keyword == "class" || keyword == "struct")
+        {
+            return Ok(());
+        }
+
+        let mut depth = 0;
+        let mut declarators = vec![vec![]];
+        for token in tokens {
+            match token.as_str() {
+                "<" | "[" | "{" => depth += 1,
+                ">" | "]" | "}" => depth -= 1,
+                "," if depth == 0 => {
+                    declarators.push(vec![]);
+                    continue;
+                }
+                _ => {}
+            }
+            declarators.last_mut().unwrap().push(token.clone());
+        }
+
+        let mut base_type = vec![];
+        for (n, mut declarator) in declarators.into_iter().enumerate() {
+            if let Some(eq) = declarator.iter().position(|t| t == "=") {
+                declarator.truncate(eq);
+            }
+            let mut bits = None;
+            if let Some(colon) = declarator.iter().position(|t| t == ":") {
+                bits = self.eval(&declarator[colon + 1..]);
+                declarator.truncate(colon);
+            }
+            let mut count = 1;
+            while let Some(open) = declarator.iter().position(|t| t == "[") {
+                let close = skip_group(&declarator, open);
+                count *= self
+                    .eval(&declarator[open + 1..close - 1])
+                    .with_context(|| format!("Bad array size in {}", declarator.concat()))?;
+                declarator.drain(open..close);
+            }
+            let Some((name, ty)) = declarator.split_last() else {
+                continue;
+            };
+            if !is_ident(name) {
+                continue;
+            }
+            let ty = if n == 0 {
+                // pointers belong to the declarator, not the shared type
+                base_type = ty
+                    .iter()
+                    .filter(|t| *t != "*" && *t != "&")
+                    .cloned()
+                    .collect();
+                ty.to_vec()
+            } else {
+                [base_type.as_slice(), ty].concat()
+            };
+            out.push(Declaration {
+                ty: DeclaredType::Named(ty),
+                name: name.clone(),
+                count,
+                bits,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(version: (u16, u16)) -> SourceOptions {
+        SourceOptions {
+            version,
+            case_preserving: false,
+            defines: vec![],
+            type_sizes: vec![],
+        }
+    }
+
+    /// Layout of `name` as defined in `source`
+    fn lay_out(source: &str, name: &str, options: &SourceOptions) -> Result<Rc<Layout>> {
+        let mut defines = predefined(options);
+        let mut index = Index::default();
+        index.scan(&tokenize(&preprocess(source, &mut defines)));
+        let mut layouts = Layouts {
+            index: &index,
+            defines: &defines,
+            case_preserving: options.case_preserving,
+            overrides: options
+                .type_sizes
+                .iter()
+                .map(|(name, size, align)| (name.clone(), (*size, *align)))
+                .collect(),
+            cache: HashMap::new(),
+            in_progress: HashSet::new(),
+        };
+        layouts.record(name)
+    }
+
+    fn members(members: &[(&str, u64, u64)]) -> Vec<(String, u64, u64)> {
+        members
+            .iter()
+            .map(|(name, offset, size)| (name.to_string(), *offset, *size))
+            .collect()
+    }
+
+    const UOBJECT: &str = r#"
+        // UObjectBase.h
+        class COREUOBJECT_API UObjectBase
+        {
+        public:
+            UObjectBase(EObjectFlags InFlags);
+            virtual ~UObjectBase();
+            FORCEINLINE UClass* GetClass() const { return ClassPrivate; }
+        private:
+            EObjectFlags ObjectFlags;
+            int32 InternalIndex;
+            UClass* ClassPrivate;
+            FName NamePrivate;
+            UObject* OuterPrivate;
+        #if WITH_EDITORONLY_DATA
+            UObject* EditorOnly;
+        #endif
+        };
+        enum EObjectFlags { RF_NoFlags = 0x00000000, };
+        class UObjectBaseUtility : public UObjectBase {};
+        class UObject : public UObjectBaseUtility
+        {
+            DECLARE_CLASS(UObject, UObject, CLASS_Abstract, CASTCLASS_None, TEXT("/Script/CoreUObject"), NO_API)
+        };
+    "#;
+
+    #[test]
+    fn test_virtual_and_bases() -> Result<()> {
+        let layout = lay_out(UOBJECT, "UObject", &options((5, 3)))?;
+        assert_eq!((layout.size, layout.align), (0x28, 8));
+        assert_eq!(
+            layout.members,
+            members(&[
+                ("ObjectFlags", 0x8, 4),
+                ("InternalIndex", 0xc, 4),
+                ("ClassPrivate", 0x10, 8),
+                ("NamePrivate", 0x18, 8),
+                ("OuterPrivate", 0x20, 8),
+            ])
+        );
+
+        let mut case_preserving = options((5, 3));
+        case_preserving.case_preserving = true;
+        let layout = lay_out(UOBJECT, "UObject", &case_preserving)?;
+        assert_eq!(layout.size, 0x30);
+        assert_eq!(layout.members[3], ("NamePrivate".to_string(), 0x18, 12));
+        assert_eq!(layout.members[4], ("OuterPrivate".to_string(), 0x28, 8));
+        Ok(())
+    }
+
+    #[test]
+    fn test_preprocessor() -> Result<()> {
+        let source = r#"
+            #define UE_WITH_EXTRA 0
+            struct FItem
+            {
+                UObject* Object;
+            #if ENGINE_MAJOR_VERSION >= 5 && ENGINE_MINOR_VERSION >= 2
+                int32 NewFlags;
+            #elif ENGINE_MAJOR_VERSION == 4
+                int64 OldFlags;
+            #else
+                int16 Flags;
+            #endif
+            #ifdef UE_WITH_EXTRA
+                int32 Defined;
+            #endif
+            #if UE_WITH_EXTRA
+                int32 Extra;
+            #endif
+                /* int32 Commented; */
+                const char* Text = "int32 NotAMember;";
+            };
+        "#;
+        let names = |version| -> Result<Vec<String>> {
+            let layout = lay_out(source, "FItem", &options(version))?;
+            Ok(layout
+                .members
+                .iter()
+                .map(|(name, _, _)| name.clone())
+                .collect())
+        };
+        assert_eq!(names((5, 3))?, ["Object", "NewFlags", "Defined", "Text"]);
+        assert_eq!(names((5, 1))?, ["Object", "Flags", "Defined", "Text"]);
+        assert_eq!(names((4, 27))?, ["Object", "OldFlags", "Defined", "Text"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_members() -> Result<()> {
+        let source = r#"
+            struct FChunk
+            {
+                uint8 bFirst : 1;
+                uint8 bSecond : 1;
+                uint32 bWide : 1;
+                int32 A, *B, C[2];
+                union
+                {
+                    int64 Whole;
+                    int32 Half;
+                };
+                struct { int16 X; int16 Y; } Point;
+                TArray<UObject*> Objects;
+                TMap<FName, int32> Lookup;
+                static int32 Count;
+                void Tick(float DeltaTime);
+                UPROPERTY(EditAnywhere)
+                FString Label;
+            };
+        "#;
+        let layout = lay_out(source, "FChunk", &options((5, 3)))?;
+        assert_eq!(
+            layout.members,
+            members(&[
+                ("bFirst", 0, 1),
+                ("bSecond", 0, 1),
+                ("bWide", 4, 4),
+                ("A", 8, 4),
+                ("B", 0x10, 8),
+                ("C", 0x18, 8),
+                ("Whole", 0x20, 8),
+                ("Half", 0x20, 4),
+                ("Point", 0x28, 4),
+                ("Objects", 0x30, 16),
+                ("Lookup", 0x40, 80),
+                ("Label", 0x90, 16),
+            ])
+        );
+        assert_eq!((layout.size, layout.align), (0xa0, 8));
+        Ok(())
+    }
+
+    #[test]
+    fn test_types() -> Result<()> {
+        let source = r#"
+            namespace UE { struct FPackageId { uint64 Id; }; }
+            enum class EKind : uint8 { A, B };
+            typedef int32 FIndex;
+            using FHandle = void*;
+            template<typename T> struct TWrapper { T Value; };
+            struct alignas(16) FAligned
+            {
+                EKind Kind;
+                FIndex Index;
+                FHandle Handle;
+                UE::FPackageId Package;
+                FCustom Custom;
+            };
+        "#;
+        let err = format!(
+            "{:#}",
+            lay_out(source, "FAligned", &options((5, 3)))
+                .map(|_| ())
+                .unwrap_err()
+        );
+        assert!(err.contains("Unknown type FCustom"), "{err}");
+
+        let mut options = options((5, 3));
+        options.type_sizes.push(("FCustom".into(), 2, 2));
+        let layout = lay_out(source, "FAligned", &options)?;
+        assert_eq!(
+            layout.members,
+            members(&[
+                ("Kind", 0, 1),
+                ("Index", 4, 4),
+                ("Handle", 8, 8),
+                ("Package", 0x10, 8),
+                ("Custom", 0x18, 2),
+            ])
+        );
+        assert_eq!((layout.size, layout.align), (0x20, 16));
+        assert!(lay_out(source, "TWrapper", &options).is_err());
+        Ok(())
+    }
+}
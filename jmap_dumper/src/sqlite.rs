@@ -0,0 +1,303 @@
+//! SQLite export. Objects, properties, enums, functions and vtables are written to indexed
+//! tables so large dumps can be explored with SQL instead of grepping JSON.
+
+use std::path::Path;
+
+use anyhow::Result;
+use jmap::{Jmap, ObjectType, Property, PropertyType};
+use rusqlite::{Connection, params, types::Value};
+
+const SCHEMA: &str = "
+CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT);
+CREATE TABLE objects (
+    path TEXT PRIMARY KEY,
+    type TEXT NOT NULL,
+    class TEXT NOT NULL,
+    outer TEXT,
+    address INTEGER NOT NULL,
+    vtable INTEGER NOT NULL,
+    object_flags INTEGER NOT NULL,
+    internal_index INTEGER NOT NULL,
+    property_values TEXT
+);
+CREATE TABLE structs (
+    path TEXT PRIMARY KEY,
+    super_struct TEXT,
+    properties_size INTEGER NOT NULL,
+    min_alignment INTEGER NOT NULL,
+    -- EStructFlags or EClassFlags
+    flags INTEGER
+);
+CREATE TABLE properties (
+    owner TEXT NOT NULL,
+    idx INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    type TEXT NOT NULL,
+    -- struct, class or enum referenced by the property type
+    type_object TEXT,
+    offset INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    array_dim INTEGER NOT NULL,
+    flags INTEGER NOT NULL,
+    type_json TEXT NOT NULL,
+    PRIMARY KEY (owner, idx)
+);
+CREATE TABLE enum_values (
+    enum TEXT NOT NULL,
+    idx INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    PRIMARY KEY (enum, idx)
+);
+CREATE TABLE functions (
+    path TEXT PRIMARY KEY,
+    owner TEXT,
+    function_flags INTEGER NOT NULL,
+    func INTEGER NOT NULL
+);
+CREATE TABLE vtables (
+    address INTEGER NOT NULL,
+    idx INTEGER NOT NULL,
+    function INTEGER NOT NULL,
+    PRIMARY KEY (address, idx)
+);
+CREATE INDEX objects_class ON objects (class);
+CREATE INDEX objects_outer ON objects (outer);
+CREATE INDEX objects_address ON objects (address);
+CREATE INDEX structs_super ON structs (super_struct);
+CREATE INDEX properties_name ON properties (name);
+CREATE INDEX properties_type_object ON properties (type_object);
+CREATE INDEX enum_values_name ON enum_values (name);
+CREATE INDEX functions_owner ON functions (owner);
+CREATE INDEX functions_func ON functions (func);
+CREATE INDEX vtables_function ON vtables (function);
+";
+
+pub(crate) fn type_name(obj: &ObjectType) -> &'static str {
+    match obj {
+        ObjectType::Object(_) => "Object",
+        ObjectType::Package(_) => "Package",
+        ObjectType::Enum(_) => "Enum",
+        ObjectType::ScriptStruct(_) => "ScriptStruct",
+        ObjectType::Class(_) => "Class",
+        ObjectType::Function(_) => "Function",
+    }
+}
+
+/// Object referenced by a property type, used to join properties with their types
+fn type_object(prop: &Property) -> Option<&str> {
+    match &prop.r#type {
+        PropertyType::Struct { r#struct } => Some(r#struct),
+        PropertyType::Enum { r#enum, .. } | PropertyType::Byte { r#enum } => r#enum.as_deref(),
+        PropertyType::Object { property_class }
+        | PropertyType::WeakObject { property_class }
+        | PropertyType::SoftObject { property_class }
+        | PropertyType::LazyObject { property_class } => Some(property_class),
+        PropertyType::Class { meta_class, .. } | PropertyType::SoftClass { meta_class, .. } => {
+            Some(meta_class)
+        }
+        PropertyType::Interface { interface_class } => Some(interface_class),
+        PropertyType::MulticastInlineDelegate { signature_function }
+        | PropertyType::MulticastSparseDelegate { signature_function }
+        | PropertyType::MulticastDelegate { signature_function }
+        | PropertyType::Delegate { signature_function } => signature_function.as_deref(),
+        PropertyType::Array { inner } | PropertyType::Optional { inner } => type_object(inner),
+        PropertyType::Set { key_prop } => type_object(key_prop),
+        PropertyType::Map { value_prop, .. } => type_object(value_prop),
+        _ => None,
+    }
+}
+
+/// Write a dump to a new SQLite database, replacing any existing file
+pub fn write_sqlite(jmap: &Jmap, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let mut conn = Connection::open(path)?;
+    conn.pragma_update(None, "journal_mode", "OFF")?;
+    conn.pragma_update(None, "synchronous", "OFF")?;
+
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+    {
+        let mut metadata = tx.prepare("INSERT INTO metadata VALUES (?1, ?2)")?;
+        metadata.execute(params![
+            "image_base_address",
+            jmap.image_base_address.0.to_string()
+        ])?;
+        if let Some(m) = &jmap.metadata {
+            metadata.execute(params!["tool", m.tool])?;
+            metadata.execute(params!["timestamp", m.timestamp])?;
+            metadata.execute(params!["source", m.source])?;
+            metadata.execute(params![
+                "engine_version",
+                match m.engine_version.patch {
+                    Some(patch) => format!(
+                        "{}.{}.{patch}",
+                        m.engine_version.major, m.engine_version.minor
+                    ),
+                    None => format!("{}.{}", m.engine_version.major, m.engine_version.minor),
+                }
+            ])?;
+            if let Some(branch) = &m.engine_version.branch {
+                metadata.execute(params!["engine_branch", branch])?;
+            }
+            if let Some(cl) = &m.build_change_list {
+                metadata.execute(params!["build_change_list", cl])?;
+            }
+        }
+
+        let mut objects =
+            tx.prepare("INSERT INTO objects VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
+        let mut structs = tx.prepare("INSERT INTO structs VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        let mut properties =
+            tx.prepare("INSERT INTO properties VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")?;
+        let mut enum_values = tx.prepare("INSERT INTO enum_values VALUES (?1, ?2, ?3, ?4)")?;
+        let mut functions = tx.prepare("INSERT INTO functions VALUES (?1, ?2, ?3, ?4)")?;
+        let mut vtables = tx.prepare("INSERT INTO vtables VALUES (?1, ?2, ?3)")?;
+
+        for (path, obj) in &jmap.objects {
+            let o = obj.get_object();
+            objects.execute(params![
+                path,
+                type_name(obj),
+                o.class,
+                o.outer,
+                o.address.0 as i64,
+                o.vtable.0 as i64,
+                o.object_flags.bits(),
+                o.internal_index,
+                serde_json::to_string(&o.property_values)?,
+            ])?;
+
+            if let Some(s) = obj.get_struct() {
+                let flags = match obj {
+                    ObjectType::ScriptStruct(s) => Some(s.struct_flags.bits()),
+                    ObjectType::Class(c) => Some(c.class_flags.bits()),
+                    _ => None,
+                };
+                structs.execute(params![
+                    path,
+                    s.super_struct,
+                    s.properties_size,
+                    s.min_alignment,
+                    flags
+                ])?;
+                for (i, prop) in s.properties.iter().enumerate() {
+                    let type_json = serde_json::to_value(&prop.r#type)?;
+                    properties.execute(params![
+                        path,
+                        i,
+                        prop.name,
+                        type_json["type"].as_str().unwrap_or_default(),
+                        type_object(prop),
+                        prop.offset,
+                        prop.size,
+                        prop.array_dim,
+                        prop.flags.bits() as i64,
+                        type_json.to_string(),
+                    ])?;
+                }
+            }
+
+            match obj {
+                ObjectType::Enum(e) => {
+                    for (i, (name, value)) in e.names.iter().enumerate() {
+                        enum_values.execute(params![path, i, name, value])?;
+                    }
+                }
+                ObjectType::Function(f) => {
+                    functions.execute(params![
+                        path,
+                        o.outer,
+                        f.function_flags.bits(),
+                        f.func.0 as i64
+                    ])?;
+                }
+                _ => {}
+            }
+        }
+
+        for (address, entries) in &jmap.vtables {
+            for (i, function) in entries.iter().enumerate() {
+                vtables.execute(params![address.0 as i64, i, function.0 as i64])?;
+            }
+        }
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// A property row returned by [`Database::properties`] as (owner, name, type, offset, size)
+pub type PropertyRow = (String, String, String, usize, usize);
+
+/// Column names and rows returned by [`Database::query`]
+#[derive(Debug, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Read only handle to a database written by [`write_sqlite`]
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            conn: Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?,
+        })
+    }
+
+    /// Run an arbitrary SQL query
+    pub fn query(&self, sql: &str) -> Result<QueryResult> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let count = columns.len();
+        let rows = stmt
+            .query_map([], |row| (0..count).map(|i| row.get(i)).collect())?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(QueryResult { columns, rows })
+    }
+
+    /// Paths of objects matching a SQL LIKE pattern, optionally restricted to a class
+    pub fn find_objects(&self, pattern: &str, class: Option<&str>) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT path FROM objects WHERE path LIKE ?1 AND (?2 IS NULL OR class = ?2) ORDER BY path",
+        )?;
+        let paths = stmt
+            .query_map(params![pattern, class], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(paths)
+    }
+
+    /// Properties of a struct including inherited ones
+    pub fn properties(&self, path: &str) -> Result<Vec<PropertyRow>> {
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE chain(path, depth) AS (
+                SELECT ?1, 0
+                UNION ALL
+                SELECT s.super_struct, c.depth + 1 FROM structs s JOIN chain c ON s.path = c.path
+                WHERE s.super_struct IS NOT NULL
+            )
+            SELECT p.owner, p.name, p.type, p.offset, p.size FROM properties p
+            JOIN chain c ON p.owner = c.path
+            ORDER BY p.offset, c.depth DESC, p.idx",
+        )?;
+        let props = stmt
+            .query_map(params![path], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(props)
+    }
+}
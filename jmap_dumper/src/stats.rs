@@ -0,0 +1,122 @@
+//! Object counts per class and package of a dump, and the memory the reflected properties of
+//! each class's instances take up (`PropertiesSize` × instances).
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use jmap::{Jmap, ObjectType, split::package_of};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassStats {
+    pub class: String,
+    pub instances: usize,
+    /// Absent if the class isn't in the dump
+    pub properties_size: Option<usize>,
+    pub total_size: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageStats {
+    pub package: String,
+    pub objects: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub objects: usize,
+    pub classes: usize,
+    pub packages: usize,
+    /// Most instantiated classes
+    pub top_instances: Vec<ClassStats>,
+    /// Classes whose instances take up the most memory
+    pub top_size: Vec<ClassStats>,
+    /// Packages containing the most objects
+    pub top_packages: Vec<PackageStats>,
+}
+
+/// Stats of `jmap` with the top `top` entries of each list (all if 0)
+pub fn stats(jmap: &Jmap, top: usize) -> Stats {
+    let mut instances = BTreeMap::<&str, usize>::new();
+    let mut packages = BTreeMap::<&str, usize>::new();
+    for (path, obj) in &jmap.objects {
+        *instances.entry(&obj.get_object().class).or_default() += 1;
+        *packages.entry(package_of(path)).or_default() += 1;
+    }
+
+    let mut classes = instances
+        .into_iter()
+        .map(|(class, instances)| {
+            let properties_size = match jmap.objects.get(class) {
+                Some(ObjectType::Class(c)) => Some(c.r#struct.properties_size),
+                _ => None,
+            };
+            ClassStats {
+                class: class.to_string(),
+                instances,
+                properties_size,
+                total_size: properties_size.unwrap_or_default() * instances,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut packages = packages
+        .into_iter()
+        .map(|(package, objects)| PackageStats {
+            package: package.to_string(),
+            objects,
+        })
+        .collect::<Vec<_>>();
+    let (num_classes, num_packages) = (classes.len(), packages.len());
+    let top = if top == 0 { usize::MAX } else { top };
+
+    let mut top_size = classes.clone();
+    top_size.sort_by_key(|c| Reverse(c.total_size));
+    top_size.truncate(top);
+    classes.sort_by_key(|c| Reverse(c.instances));
+    classes.truncate(top);
+    packages.sort_by_key(|p| Reverse(p.objects));
+    packages.truncate(top);
+
+    Stats {
+        objects: jmap.objects.len(),
+        classes: num_classes,
+        packages: num_packages,
+        top_instances: classes,
+        top_size,
+        top_packages: packages,
+    }
+}
+
+fn write_classes(f: &mut fmt::Formatter<'_>, title: &str, classes: &[ClassStats]) -> fmt::Result {
+    writeln!(f, "\n{title}:")?;
+    writeln!(
+        f,
+        "{:>10} {:>10} {:>12}  class",
+        "instances", "size", "total"
+    )?;
+    for c in classes {
+        let size = c.properties_size.map_or("?".into(), |s| format!("0x{s:x}"));
+        let total = format!("0x{:x}", c.total_size);
+        writeln!(f, "{:>10} {size:>10} {total:>12}  {}", c.instances, c.class)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} objects of {} classes in {} packages",
+            self.objects, self.classes, self.packages
+        )?;
+        write_classes(f, "Classes by instances", &self.top_instances)?;
+        write_classes(f, "Classes by total size", &self.top_size)?;
+        writeln!(f, "\nPackages by objects:")?;
+        writeln!(f, "{:>10}  package", "objects")?;
+        for p in &self.top_packages {
+            writeln!(f, "{:>10}  {}", p.objects, p.package)?;
+        }
+        Ok(())
+    }
+}
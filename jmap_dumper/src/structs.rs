@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use anyhow::{Context, Result, anyhow, bail};
 use gospel_compiler::backend::{CompilerInstance, CompilerModuleBuilder, CompilerResultTrait};
@@ -10,10 +11,28 @@ use gospel_vm::vm::{GospelVMOptions, GospelVMRunContext, GospelVMState, GospelVM
 use patternsleuth::resolvers::unreal::engine_version::EngineVersion;
 use serde::{Deserialize, Serialize};
 
+/// Struct layouts the dumper reads memory with. As a `--struct-info` file this is a JSON array of
+/// structs:
+///
+/// ```json
+/// [
+///   {
+///     "name": "UObject",
+///     "size": 40,
+///     "alignment": 8,
+///     "members": [{"name": "ObjectFlags", "offset": 8, "size": 4}, ...]
+///   },
+///   ...
+/// ]
+/// ```
+///
+/// Every struct and member of the built-in layout for the engine version is required, so the
+/// easiest starting point is `struct-info print --json <version>`.
 #[derive(Serialize, Deserialize)]
 pub struct Structs(pub Vec<StructInfo>);
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct StructInfo {
     pub name: String,
     pub size: u64,
@@ -22,6 +41,7 @@ pub struct StructInfo {
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct StructMember {
     pub name: String,
     pub offset: u64,
@@ -29,6 +49,145 @@ pub struct StructMember {
     //pub type_name: String,
 }
 
+impl Structs {
+    /// Load a `--struct-info` file
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read struct info {}", path.display()))?;
+        serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse struct info {}", path.display()))
+    }
+
+    /// Check that every struct and member of `reference` is present and that members fit inside
+    /// their struct, reporting all problems at once
+    pub fn validate(&self, reference: &Structs) -> Result<()> {
+        let mut problems = vec![];
+
+        let mut by_name = HashMap::new();
+        for info in &self.0 {
+            if by_name.insert(info.name.as_str(), info).is_some() {
+                problems.push(format!("{} is defined more than once", info.name));
+            }
+            for member in &info.members {
+                if member.offset + member.size > info.size {
+                    problems.push(format!(
+                        "{}::{} (offset 0x{:x}, size 0x{:x}) extends past the struct size 0x{:x}",
+                        info.name, member.name, member.offset, member.size, info.size
+                    ));
+                }
+            }
+        }
+
+        for expected in &reference.0 {
+            let Some(info) = by_name.get(expected.name.as_str()) else {
+                problems.push(format!("{} is missing", expected.name));
+                continue;
+            };
+            let missing = expected
+                .members
+                .iter()
+                .filter(|m| !info.members.iter().any(|i| i.name == m.name))
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                problems.push(format!(
+                    "{} is missing members: {}",
+                    expected.name,
+                    missing.join(", ")
+                ));
+            }
+        }
+
+        if !problems.is_empty() {
+            bail!("Invalid struct info:\n  {}", problems.join("\n  "));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Structs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for info in &self.0 {
+            writeln!(
+                f,
+                "{} (size 0x{:x}, alignment {})",
+                info.name, info.size, info.alignment
+            )?;
+            for member in &info.members {
+                writeln!(
+                    f,
+                    "  0x{:04x} {:<32} size 0x{:x}",
+                    member.offset, member.name, member.size
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Structs of the built-in layout table, `Z` prefixed ones are `FProperty` types from 4.25 on and
+/// `UProperty` types before
+pub const STRUCT_NAMES: &[&str] = &[
+    "FUObjectArray",
+    "FUObjectArrayOld",
+    "FUObjectArrayOlder",
+    "FUObjectItem",
+    "FFixedUObjectArray",
+    "FChunkedFixedUObjectArray",
+    "UObject",
+    "UField",
+    "UStruct",
+    "UClass",
+    "FImplementedInterface",
+    "UEnum",
+    "UEnumNameTuple",
+    "UFunction",
+    "UScriptStruct",
+    "UPackage",
+    "FPackagePath",
+    "FTopLevelAssetPath",
+    "FSoftObjectPath",
+    "FSoftObjectPtr",
+    "ZField",
+    "ZProperty",
+    "ZStructProperty",
+    "ZArrayProperty",
+    "ZEnumProperty",
+    "ZByteProperty",
+    "ZBoolProperty",
+    "ZSetProperty",
+    "ZMapProperty",
+    "ZDelegateProperty",
+    "ZMulticastDelegateProperty",
+    "ZObjectPropertyBase",
+    "ZObjectProperty",
+    "ZClassProperty",
+    "ZSoftClassProperty",
+    "ZInterfaceProperty",
+    "FOptionalPropertyLayout",
+    "FName",
+    "FField",
+    "FFieldClass",
+];
+
+/// Last minor version of each major version the built-in layouts were checked against, starting
+/// at 4.7. Conditions in `unreal.gs` compare ranges of versions, so any other version gets the
+/// layout of the range it falls into.
+const LAST_MINOR_VERSIONS: &[(u16, u16)] = &[(4, 27), (5, 6)];
+const FIRST_VERSION: (u16, u16) = (4, 7);
+
+/// Engine versions the built-in layouts were checked against, oldest first
+pub fn builtin_versions() -> impl Iterator<Item = EngineVersion> {
+    LAST_MINOR_VERSIONS.iter().flat_map(|&(major, last_minor)| {
+        let first_minor = if major == FIRST_VERSION.0 {
+            FIRST_VERSION.1
+        } else {
+            0
+        };
+        (first_minor..=last_minor).map(move |minor| EngineVersion { major, minor })
+    })
+}
+
 pub fn get_struct_info_for_version(
     version: &EngineVersion,
     case_preserving: bool,
@@ -55,43 +214,6 @@ pub fn get_struct_info_for_version(
     let ue_version = (version.major as u64) * 100 + (version.minor as u64);
     let case_preserving_flag = if case_preserving { 1 } else { 0 };
 
-    let struct_names = vec![
-        "FUObjectArray",
-        "FUObjectArrayOld",
-        "FUObjectArrayOlder",
-        "FUObjectItem",
-        "FFixedUObjectArray",
-        "FChunkedFixedUObjectArray",
-        "UObject",
-        "UField",
-        "UStruct",
-        "UClass",
-        "UEnum",
-        "UEnumNameTuple",
-        "UFunction",
-        "UScriptStruct",
-        "ZField",
-        "ZProperty",
-        "ZStructProperty",
-        "ZArrayProperty",
-        "ZEnumProperty",
-        "ZByteProperty",
-        "ZBoolProperty",
-        "ZSetProperty",
-        "ZMapProperty",
-        "ZDelegateProperty",
-        "ZMulticastDelegateProperty",
-        "ZObjectPropertyBase",
-        "ZObjectProperty",
-        "ZClassProperty",
-        "ZSoftClassProperty",
-        "ZInterfaceProperty",
-        "FOptionalPropertyLayout",
-        "FName",
-        "FField",
-        "FFieldClass",
-    ];
-
     let container = module_writer.compile().to_simple_result()?;
 
     let mounted_container = vm_state.mount_container(container)?;
@@ -103,7 +225,7 @@ pub fn get_struct_info_for_version(
         .with_global("WITH_CASE_PRESERVING_NAME", case_preserving_flag);
     let mut execution_context = GospelVMRunContext::create(vm_options);
 
-    for struct_name in struct_names {
+    for struct_name in STRUCT_NAMES {
         if let Some(struct_info) = eval_struct_layout(
             &mounted_container,
             &mut execution_context,
@@ -0,0 +1,70 @@
+//! Names for the code and data addresses in a dump, shared by the debugger symbol exporters
+
+use std::collections::HashSet;
+
+use jmap::{Jmap, ObjectType};
+
+use crate::sdkgen::{Sdk, sanitize_ident, short_name};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// Native exec thunk of a UFunction
+    Function,
+    Vtable,
+    /// Engine global such as GUObjectArray
+    Global,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub address: u64,
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Path of the function or class the symbol was derived from
+    pub object: Option<String>,
+}
+
+/// Exec functions as `Type::execName`, vtables as ``Type::`vftable'`` and resolved globals, with
+/// duplicate names dropped
+pub fn collect_symbols(jmap: &Jmap) -> Vec<Symbol> {
+    let sdk = Sdk::new(jmap);
+
+    let mut symbols = vec![];
+    for (path, obj) in &jmap.objects {
+        if let ObjectType::Function(func) = obj
+            && let Some(outer) = func.r#struct.object.outer.as_deref()
+        {
+            symbols.push(Symbol {
+                address: func.func.0,
+                name: format!(
+                    "{}::exec{}",
+                    sdk.type_name(outer),
+                    sanitize_ident(short_name(path))
+                ),
+                kind: SymbolKind::Function,
+                object: Some(path.clone()),
+            });
+        }
+        if let Some(vtable) = obj.get_class().and_then(|c| c.instance_vtable) {
+            symbols.push(Symbol {
+                address: vtable.0,
+                name: format!("{}::`vftable'", sdk.type_name(path)),
+                kind: SymbolKind::Vtable,
+                object: Some(path.clone()),
+            });
+        }
+    }
+    let globals = jmap.image.iter().flat_map(|image| &image.globals);
+    for (name, address) in jmap.resolved_addresses.iter().chain(globals) {
+        symbols.push(Symbol {
+            address: address.0,
+            name: name.clone(),
+            kind: SymbolKind::Global,
+            object: None,
+        });
+    }
+
+    let mut seen = HashSet::new();
+    symbols.retain(|s| seen.insert(s.name.clone()));
+    symbols
+}
@@ -0,0 +1,160 @@
+//! Flat property table export. Every property of every struct, class and function becomes one
+//! row so layouts can be analyzed with pandas, duckdb, etc. without walking the JSON.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use jmap::Jmap;
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedFileWriter, SerializedRowGroupWriter};
+use parquet::schema::parser::parse_message_type;
+use serde::Serialize;
+
+use crate::sdkgen::property_type_name;
+use crate::sqlite::type_name;
+
+const ROW_GROUP_SIZE: usize = 1 << 20;
+
+const PARQUET_SCHEMA: &str = "
+message property {
+    required binary owner (STRING);
+    required binary owner_type (STRING);
+    required int64 index;
+    required binary name (STRING);
+    required binary type (STRING);
+    required binary kind (STRING);
+    required int64 offset;
+    required int64 size;
+    required int64 array_dim;
+    required int64 flags;
+    required binary flag_names (STRING);
+}
+";
+
+/// One property of a struct, class or function. Field order matches the CSV and Parquet columns.
+#[derive(Debug, Serialize)]
+pub struct PropertyRow<'a> {
+    /// Path of the struct, class or function declaring the property
+    pub owner: &'a str,
+    /// `ScriptStruct`, `Class` or `Function`
+    pub owner_type: &'static str,
+    /// Position in the owner's property list
+    pub index: usize,
+    pub name: &'a str,
+    /// C++ type, e.g. `TArray<FVector>`
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// Property class without the `Property` suffix, e.g. `Array`
+    pub kind: String,
+    pub offset: usize,
+    pub size: usize,
+    pub array_dim: usize,
+    pub flags: u64,
+    /// `flags` as `CPF_` names joined with `|`
+    pub flag_names: String,
+}
+
+/// Flatten all properties in the dump, ordered by owner path then declaration order
+pub fn property_rows(jmap: &Jmap) -> Result<Vec<PropertyRow<'_>>> {
+    let mut rows = vec![];
+    for (path, obj) in &jmap.objects {
+        let Some(s) = obj.get_struct() else {
+            continue;
+        };
+        for (index, prop) in s.properties.iter().enumerate() {
+            let kind = serde_json::to_value(&prop.r#type)?["type"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            rows.push(PropertyRow {
+                owner: path,
+                owner_type: type_name(obj),
+                index,
+                name: &prop.name,
+                type_name: property_type_name(&jmap.objects, prop),
+                kind,
+                offset: prop.offset,
+                size: prop.size,
+                array_dim: prop.array_dim,
+                flags: prop.flags.bits(),
+                flag_names: prop
+                    .flags
+                    .iter_names()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>()
+                    .join("|"),
+            });
+        }
+    }
+    Ok(rows)
+}
+
+/// Write the property table as CSV with a header row
+pub fn write_csv(jmap: &Jmap, path: impl AsRef<Path>) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for row in property_rows(jmap)? {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the property table as a Snappy compressed Parquet file
+pub fn write_parquet(jmap: &Jmap, path: impl AsRef<Path>) -> Result<()> {
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+    let props = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, props)?;
+
+    for rows in property_rows(jmap)?.chunks(ROW_GROUP_SIZE) {
+        let mut group = writer.next_row_group()?;
+        write_strings(&mut group, rows.iter().map(|r| r.owner))?;
+        write_strings(&mut group, rows.iter().map(|r| r.owner_type))?;
+        write_ints(&mut group, rows.iter().map(|r| r.index as i64))?;
+        write_strings(&mut group, rows.iter().map(|r| r.name))?;
+        write_strings(&mut group, rows.iter().map(|r| r.type_name.as_str()))?;
+        write_strings(&mut group, rows.iter().map(|r| r.kind.as_str()))?;
+        write_ints(&mut group, rows.iter().map(|r| r.offset as i64))?;
+        write_ints(&mut group, rows.iter().map(|r| r.size as i64))?;
+        write_ints(&mut group, rows.iter().map(|r| r.array_dim as i64))?;
+        write_ints(&mut group, rows.iter().map(|r| r.flags as i64))?;
+        write_strings(&mut group, rows.iter().map(|r| r.flag_names.as_str()))?;
+        group.close()?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+fn write_strings<'a, W: Write + Send>(
+    group: &mut SerializedRowGroupWriter<'_, W>,
+    values: impl Iterator<Item = &'a str>,
+) -> Result<()> {
+    let values: Vec<ByteArray> = values.map(ByteArray::from).collect();
+    let mut column = group.next_column()?.context("missing parquet column")?;
+    column
+        .typed::<ByteArrayType>()
+        .write_batch(&values, None, None)?;
+    column.close()?;
+    Ok(())
+}
+
+fn write_ints<W: Write + Send>(
+    group: &mut SerializedRowGroupWriter<'_, W>,
+    values: impl Iterator<Item = i64>,
+) -> Result<()> {
+    let values: Vec<i64> = values.collect();
+    let mut column = group.next_column()?.context("missing parquet column")?;
+    column
+        .typed::<Int64Type>()
+        .write_batch(&values, None, None)?;
+    column.close()?;
+    Ok(())
+}
@@ -0,0 +1,198 @@
+//! Implementations behind the `execFoo` thunks UFunction natives point to. A thunk reads the
+//! parameters off the FFrame with calls to `FFrame::Step` and friends, then calls the C++ function
+//! (directly, or through the vtable for BlueprintNativeEvents and other virtuals) and stores the
+//! result. The implementation is taken to be the last call of the thunk that isn't to a helper,
+//! helpers being the functions many thunks call.
+
+use std::collections::{BTreeMap, HashMap};
+
+use iced_x86::{Decoder, DecoderOptions, FlowControl, Instruction, OpKind, Register};
+use jmap::{Jmap, NativeFunction, ObjectType, VirtualFunction};
+use rayon::prelude::*;
+use tracing::{debug, info_span};
+
+use crate::mem::{Mem, PAGE_SIZE};
+use crate::signatures::native;
+use crate::vtable::native_function;
+
+/// Bytes of a thunk decoded at most
+const MAX_THUNK_LEN: usize = 0x800;
+/// Functions called by more thunks than this are helpers rather than implementations
+const HELPER_CALLERS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Call {
+    Direct(u64),
+    /// Through slot n of the vtable of an object
+    Virtual(usize),
+}
+
+/// What a thunk calls into
+#[derive(Debug, Clone, Copy)]
+pub enum Implementation {
+    Direct(u64),
+    /// Slot of the vtable of the class the function belongs to
+    Virtual(usize),
+}
+
+impl Implementation {
+    /// Function in the modules of `header`, `virtual_functions` being those of the class the
+    /// function belongs to
+    pub fn resolve(
+        self,
+        header: &Jmap,
+        virtual_functions: &[VirtualFunction],
+    ) -> Option<NativeFunction> {
+        match self {
+            Implementation::Direct(address) => Some(native_function(header, address)),
+            Implementation::Virtual(slot) => virtual_functions.get(slot).map(|f| NativeFunction {
+                rva: f.rva,
+                module: f.module.clone(),
+                symbol: None,
+            }),
+        }
+    }
+}
+
+/// Code of the thunk at `address`, cut short at the end of the readable pages
+fn read_thunk<M: Mem>(mem: &M, address: u64) -> Vec<u8> {
+    let mut bytes = vec![0; MAX_THUNK_LEN];
+    if mem.read_buf(address, &mut bytes).is_ok() {
+        return bytes;
+    }
+    let page_end = (address / PAGE_SIZE as u64 + 1) * PAGE_SIZE as u64;
+    bytes.truncate((page_end - address) as usize);
+    if mem.read_buf(address, &mut bytes).is_ok() {
+        bytes
+    } else {
+        vec![]
+    }
+}
+
+/// Calls of the thunk at `address` in order, including a tail jump
+fn calls<M: Mem>(mem: &M, address: u64) -> Vec<Call> {
+    let bytes = read_thunk(mem, address);
+    let mut decoder = Decoder::with_ip(64, &bytes, address, DecoderOptions::NONE);
+    let mut instruction = Instruction::default();
+    let mut calls = vec![];
+    while decoder.can_decode() {
+        decoder.decode_out(&mut instruction);
+        if instruction.is_invalid() {
+            break;
+        }
+        let flow = instruction.flow_control();
+        let call = matches!(flow, FlowControl::Call | FlowControl::IndirectCall);
+        let tail = matches!(
+            flow,
+            FlowControl::UnconditionalBranch | FlowControl::IndirectBranch
+        );
+        if call || tail {
+            match instruction.op0_kind() {
+                OpKind::NearBranch64 => calls.push(Call::Direct(instruction.near_branch_target())),
+                OpKind::Memory
+                    if instruction.memory_base() != Register::RIP
+                        && instruction.memory_base() != Register::None
+                        && instruction.memory_index() == Register::None
+                        && instruction.memory_displacement64() % 8 == 0 =>
+                {
+                    let slot = instruction.memory_displacement64() / 8;
+                    calls.push(Call::Virtual(slot as usize));
+                }
+                _ => {}
+            }
+        }
+        // jumps within the thunk are branches of it, anything else ends it
+        let internal = flow == FlowControl::UnconditionalBranch
+            && instruction.op0_kind() == OpKind::NearBranch64
+            && (address..address + bytes.len() as u64).contains(&instruction.near_branch_target())
+            && instruction.near_branch_target() > instruction.ip();
+        if internal {
+            calls.pop();
+            continue;
+        }
+        if tail || matches!(flow, FlowControl::Return | FlowControl::Interrupt) {
+            break;
+        }
+    }
+    calls
+}
+
+/// Implementations of the thunks at `thunks`, leaving out those where everything called is a
+/// helper
+pub fn resolve_thunks<M: Mem>(
+    mem: &M,
+    thunks: impl IntoIterator<Item = u64>,
+) -> HashMap<u64, Implementation> {
+    let mut thunks = thunks.into_iter().collect::<Vec<_>>();
+    thunks.sort_unstable();
+    thunks.dedup();
+    let _span = info_span!("thunks", count = thunks.len()).entered();
+
+    let calls = thunks
+        .par_iter()
+        .map(|thunk| (*thunk, calls(mem, *thunk)))
+        .collect::<Vec<_>>();
+
+    let mut callers = HashMap::<u64, usize>::new();
+    for (_, calls) in &calls {
+        let mut targets = calls
+            .iter()
+            .filter_map(|c| match c {
+                Call::Direct(target) => Some(*target),
+                Call::Virtual(_) => None,
+            })
+            .collect::<Vec<_>>();
+        targets.sort_unstable();
+        targets.dedup();
+        for target in targets {
+            *callers.entry(target).or_default() += 1;
+        }
+    }
+
+    let implementations = calls
+        .into_iter()
+        .filter_map(|(thunk, calls)| {
+            let implementation = calls.into_iter().rev().find_map(|call| match call {
+                Call::Direct(target) if callers[&target] <= HELPER_CALLERS => {
+                    Some(Implementation::Direct(target))
+                }
+                Call::Direct(_) => None,
+                Call::Virtual(slot) => Some(Implementation::Virtual(slot)),
+            })?;
+            Some((thunk, implementation))
+        })
+        .collect::<HashMap<_, _>>();
+    debug!(
+        "resolved {} of {} thunks",
+        implementations.len(),
+        thunks.len()
+    );
+    implementations
+}
+
+/// Fill in the `implementation` of every native function among `objects`
+pub fn analyze_thunks<M: Mem>(mem: &M, header: &Jmap, objects: &mut BTreeMap<String, ObjectType>) {
+    let implementations = resolve_thunks(mem, objects.values().filter_map(native));
+
+    let resolved = objects
+        .iter()
+        .filter_map(|(path, obj)| {
+            let implementation = implementations.get(&native(obj)?)?;
+            let virtual_functions = obj
+                .get_object()
+                .outer
+                .as_ref()
+                .and_then(|outer| objects.get(outer)?.get_class())
+                .map_or(&[][..], |class| &class.virtual_functions[..]);
+            Some((
+                path.clone(),
+                implementation.resolve(header, virtual_functions)?,
+            ))
+        })
+        .collect::<Vec<_>>();
+    for (path, implementation) in resolved {
+        if let Some(ObjectType::Function(function)) = objects.get_mut(&path) {
+            function.implementation = Some(implementation);
+        }
+    }
+}
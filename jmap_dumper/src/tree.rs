@@ -0,0 +1,126 @@
+//! Indented inheritance tree of the classes (and optionally script structs) of a dump, with the
+//! number of direct children and descendants of every type and its most telling class flags.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::{Result, bail};
+use jmap::{EClassFlags, Jmap, ObjectType};
+
+#[derive(Debug, Default)]
+pub struct TreeOptions {
+    /// Type to start from instead of every type without a super struct in the dump
+    pub root: Option<String>,
+    /// Include script structs in addition to classes
+    pub structs: bool,
+    /// Levels below the roots to print (all if unset)
+    pub depth: Option<usize>,
+    /// Print full paths instead of names
+    pub paths: bool,
+}
+
+const FLAGS: [(EClassFlags, &str); 6] = [
+    (EClassFlags::CLASS_Abstract, "abstract"),
+    (EClassFlags::CLASS_Interface, "interface"),
+    (EClassFlags::CLASS_Native, "native"),
+    (EClassFlags::CLASS_CompiledFromBlueprint, "blueprint"),
+    (EClassFlags::CLASS_Config, "config"),
+    (EClassFlags::CLASS_Deprecated, "deprecated"),
+];
+
+struct Tree<'a> {
+    jmap: &'a Jmap,
+    options: &'a TreeOptions,
+    children: BTreeMap<&'a str, Vec<&'a str>>,
+}
+
+impl Tree<'_> {
+    fn descendants(&self, path: &str) -> usize {
+        self.children
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(|child| 1 + self.descendants(child))
+            .sum()
+    }
+
+    fn write(&self, buffer: &mut String, path: &str, level: usize) {
+        let name = if self.options.paths {
+            path
+        } else {
+            path.rsplit(['/', '.', ':']).next().unwrap()
+        };
+        write!(buffer, "{:indent$}{name}", "", indent = level * 2).unwrap();
+        let children = self.children.get(path).map_or(&[][..], |c| c.as_slice());
+        if !children.is_empty() {
+            write!(
+                buffer,
+                " ({} children, {} total)",
+                children.len(),
+                self.descendants(path)
+            )
+            .unwrap();
+        }
+        if let Some(ObjectType::Class(class)) = self.jmap.objects.get(path) {
+            let flags = FLAGS
+                .iter()
+                .filter(|(flag, _)| class.class_flags.contains(*flag))
+                .map(|(_, name)| *name)
+                .collect::<Vec<_>>();
+            if !flags.is_empty() {
+                write!(buffer, " [{}]", flags.join(", ")).unwrap();
+            }
+        }
+        writeln!(buffer).unwrap();
+
+        if self.options.depth.is_some_and(|depth| level >= depth) {
+            return;
+        }
+        for child in children {
+            self.write(buffer, child, level + 1);
+        }
+    }
+}
+
+pub fn into_tree(jmap: &Jmap, options: &TreeOptions) -> Result<String> {
+    let included = |obj: &ObjectType| match obj {
+        ObjectType::Class(_) => true,
+        ObjectType::ScriptStruct(_) => options.structs,
+        _ => false,
+    };
+
+    let mut children = BTreeMap::<&str, Vec<&str>>::new();
+    let mut roots = vec![];
+    for (path, obj) in &jmap.objects {
+        if !included(obj) {
+            continue;
+        }
+        // types deriving from something outside the dump are roots as well
+        match obj.get_struct().and_then(|s| s.super_struct.as_deref()) {
+            Some(super_struct) if jmap.objects.get(super_struct).is_some_and(included) => {
+                children.entry(super_struct).or_default().push(path)
+            }
+            _ => roots.push(path.as_str()),
+        }
+    }
+    if let Some(root) = &options.root {
+        if !jmap.objects.get(root).is_some_and(included) {
+            bail!(
+                "{root} is not a class{} in the dump",
+                if options.structs { " or struct" } else { "" }
+            );
+        }
+        roots = vec![root.as_str()];
+    }
+
+    let tree = Tree {
+        jmap,
+        options,
+        children,
+    };
+    let mut buffer = String::new();
+    for root in roots {
+        tree.write(&mut buffer, root, 0);
+    }
+    Ok(buffer)
+}
@@ -359,7 +359,9 @@ struct FRepRecord {
     uint32_t Placeholder;
 };
 struct FImplementedInterface {
-    uint64_t Placeholder[2];
+    UClass* Class;
+    int32_t PointerOffset;
+    bool bImplementedByK2;
 };
 struct FGCReferenceTokenStream {
     uint64_t Placeholder1[2]; // TArray Tokens
@@ -829,6 +831,29 @@ struct FPackagePath {
     }
 };
 
+struct FWeakObjectPtr {
+    int32_t ObjectIndex;
+    int32_t ObjectSerialNumber;
+};
+
+struct FTopLevelAssetPath {
+    FName PackageName;
+    FName AssetName;
+};
+
+struct FSoftObjectPath {
+    if (UE_VERSION >= 501) FTopLevelAssetPath AssetPath;
+    else FName AssetPathName;
+    FString SubPathString;
+};
+
+// TPersistentObjectPtr<FSoftObjectPath>
+struct FSoftObjectPtr {
+    FWeakObjectPtr WeakPtr;
+    int32_t TagAtLastTest;
+    FSoftObjectPath ObjectID;
+};
+
 struct FPackageFileVersion {
     int32_t FileVersionUE4;
     int32_t FileVersionUE5;
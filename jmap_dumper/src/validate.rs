@@ -0,0 +1,296 @@
+//! `--validate-layout`: sanity checks of the chosen struct layout against the target before
+//! dumping. Each check exercises one member through invariants every engine build holds (the
+//! `Class` class is its own class, super chains end at `Object`, property chains only contain
+//! property classes, ...) and logs a verdict, so a wrong offset shows up as a named member
+//! instead of as garbage deep in the dump.
+
+use std::collections::HashMap;
+
+use crate::mem::{Ctx, Ptr};
+use crate::objects::{FUObjectArray, UClass, UObject};
+
+/// Objects sampled from the start of GUObjectArray
+const SAMPLES: usize = 1000;
+/// Chains longer than this are taken to be cycles
+const MAX_CHAIN: usize = 10000;
+
+pub enum Verdict {
+    Ok(String),
+    Fail(String),
+    Skipped(String),
+}
+
+pub struct Check {
+    pub member: &'static str,
+    pub verdict: Verdict,
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (status, detail) = match &self.verdict {
+            Verdict::Ok(detail) => ("ok", detail),
+            Verdict::Fail(detail) => ("FAIL", detail),
+            Verdict::Skipped(detail) => ("skipped", detail),
+        };
+        write!(f, "{:<32} {status:<8} {detail}", self.member)
+    }
+}
+
+/// `passed` of `total` checked objects holding up, allowing for a few objects being torn down
+/// while they are read
+fn ratio(passed: usize, total: usize, what: &str) -> Verdict {
+    let detail = format!("{passed}/{total} {what}");
+    if total == 0 {
+        Verdict::Skipped(format!("no {what}"))
+    } else if passed * 100 >= total * 95 {
+        Verdict::Ok(detail)
+    } else {
+        Verdict::Fail(detail)
+    }
+}
+
+fn walk<T>(first: Option<T>, next: impl Fn(&T) -> Option<Option<T>>) -> Option<Vec<T>> {
+    let mut chain = vec![];
+    let mut cur = first;
+    while let Some(item) = cur {
+        cur = next(&item)?;
+        chain.push(item);
+        if chain.len() > MAX_CHAIN {
+            return None;
+        }
+    }
+    Some(chain)
+}
+
+pub fn validate_layout<C: Ctx>(array: &Ptr<FUObjectArray, C>) -> Vec<Check> {
+    let mut checks = vec![];
+    let mut check = |member, verdict| checks.push(Check { member, verdict });
+
+    let count = match array.num_elements() {
+        Ok(count) if count > 0 => count as usize,
+        Ok(count) => {
+            check(
+                "FUObjectArray::NumElements",
+                Verdict::Fail(format!("{count} objects")),
+            );
+            return checks;
+        }
+        Err(err) => {
+            check(
+                "FUObjectArray::NumElements",
+                Verdict::Fail(format!("{err:#}")),
+            );
+            return checks;
+        }
+    };
+    check(
+        "FUObjectArray::NumElements",
+        Verdict::Ok(format!("{count} objects")),
+    );
+    let sampled = count.min(SAMPLES);
+    let objects = (0..sampled)
+        .filter_map(|i| Some((i, array.read_item_ptr(i).ok()??)))
+        .collect::<Vec<_>>();
+    check(
+        "FUObjectArray::ObjObjects",
+        ratio(objects.len(), sampled, "items hold an object"),
+    );
+
+    let names = objects
+        .iter()
+        .map(|(_, o)| o.name_private().read().ok())
+        .collect::<Vec<_>>();
+    check(
+        "UObject::NamePrivate",
+        ratio(
+            names.iter().flatten().filter(|n| !n.is_empty()).count(),
+            objects.len(),
+            "names readable",
+        ),
+    );
+    let by_name = objects
+        .iter()
+        .zip(&names)
+        .filter_map(|((_, o), n)| Some((n.as_deref()?, o.clone())))
+        .collect::<HashMap<_, _>>();
+
+    let read_class = |o: &Ptr<UObject, C>| o.class_private().read().ok();
+    let Some(class) = by_name.get("Class") else {
+        check(
+            "UObject::ClassPrivate",
+            Verdict::Fail("no object named Class".into()),
+        );
+        return checks;
+    };
+    let class_address = class.address();
+    let is_class =
+        |o: &Ptr<UObject, C>| read_class(o).is_some_and(|c| c.address() == class_address);
+    check(
+        "UObject::ClassPrivate",
+        if !is_class(class) {
+            Verdict::Fail("Class is not its own class".into())
+        } else {
+            ratio(
+                objects
+                    .iter()
+                    .filter(|(_, o)| read_class(o).is_some_and(|c| is_class(&c.uobject())))
+                    .count(),
+                objects.len(),
+                "classes are instances of Class",
+            )
+        },
+    );
+
+    check(
+        "UObject::OuterPrivate",
+        match (by_name.get("Object"), by_name.get("CoreUObject")) {
+            (Some(object), Some(package)) => {
+                let outer = object.outer_private().read().ok().flatten();
+                let package_outer = package.outer_private().read();
+                if outer.is_none_or(|o| o.address() != package.address()) {
+                    Verdict::Fail("outer of Object is not CoreUObject".into())
+                } else if !matches!(package_outer, Ok(None)) {
+                    Verdict::Fail("CoreUObject has an outer".into())
+                } else {
+                    Verdict::Ok("Object is in CoreUObject".into())
+                }
+            }
+            _ => Verdict::Fail("no Object class or CoreUObject package".into()),
+        },
+    );
+
+    let ctx = array.ctx();
+    let object_layout = ctx.get_struct("UObject");
+    check(
+        "UObject::InternalIndex",
+        match object_layout
+            .members
+            .iter()
+            .find(|m| m.name == "InternalIndex")
+        {
+            Some(member) => ratio(
+                objects
+                    .iter()
+                    .filter(|(i, o)| {
+                        o.byte_offset(member.offset as usize)
+                            .cast::<i32>()
+                            .read()
+                            .is_ok_and(|index| index as usize == *i)
+                    })
+                    .count(),
+                objects.len(),
+                "indices match",
+            ),
+            None => Verdict::Skipped("not in layout".into()),
+        },
+    );
+
+    let classes = objects
+        .iter()
+        .filter(|(_, o)| is_class(o))
+        .map(|(_, o)| o.cast::<UClass>())
+        .collect::<Vec<_>>();
+    let object_class = by_name.get("Object").map(|o| o.address());
+    check(
+        "UStruct::SuperStruct",
+        ratio(
+            classes
+                .iter()
+                .filter(|c| {
+                    let chain = walk(Some(c.ustruct()), |s| s.super_struct().read().ok());
+                    chain.is_some_and(|chain| chain.last().map(|s| s.address()) == object_class)
+                })
+                .count(),
+            classes.len(),
+            "super chains end at Object",
+        ),
+    );
+
+    check(
+        "UStruct::Children",
+        ratio(
+            classes
+                .iter()
+                .filter(|c| {
+                    let Ok(first) = c.ustruct().children().read() else {
+                        return false;
+                    };
+                    walk(first, |f| f.next().read().ok()).is_some_and(|fields| {
+                        fields.iter().all(|f| {
+                            read_class(&f.uobject()).is_some_and(|c| is_class(&c.uobject()))
+                        })
+                    })
+                })
+                .count(),
+            classes.len(),
+            "child chains hold UFields",
+        ),
+    );
+
+    check(
+        "UStruct::ChildProperties",
+        if ctx.ue_version() < (4, 25) {
+            Verdict::Skipped("properties are UFields before 4.25".into())
+        } else {
+            ratio(
+                classes
+                    .iter()
+                    .filter(|c| {
+                        let Ok(first) = c.ustruct().child_properties().read() else {
+                            return false;
+                        };
+                        walk(first, |f| f.next().read().ok()).is_some_and(|fields| {
+                            fields.iter().all(|f| {
+                                f.name_private().read().is_ok()
+                                    && f.field_class()
+                                        .read()
+                                        .and_then(|class| class.name().read())
+                                        .is_ok_and(|name| name.ends_with("Property"))
+                            })
+                        })
+                    })
+                    .count(),
+                classes.len(),
+                "property chains hold FProperties",
+            )
+        },
+    );
+
+    check(
+        "UStruct::PropertiesSize",
+        ratio(
+            classes
+                .iter()
+                .filter(|c| {
+                    let size = c.ustruct().properties_size().read();
+                    let super_size = match c.ustruct().super_struct().read() {
+                        Ok(Some(s)) => s.properties_size().read(),
+                        Ok(None) => Ok(0),
+                        Err(err) => Err(err),
+                    };
+                    matches!((size, super_size), (Ok(size), Ok(super_size)) if size >= super_size && size < 1 << 24)
+                })
+                .count(),
+            classes.len(),
+            "sizes at least their super's",
+        ),
+    );
+
+    check(
+        "UClass::ClassDefaultObject",
+        ratio(
+            classes
+                .iter()
+                .filter(|c| match c.class_default_object().read() {
+                    Ok(Some(cdo)) => read_class(&cdo).is_some_and(|k| k.address() == c.address()),
+                    Ok(None) => true,
+                    Err(_) => false,
+                })
+                .count(),
+            classes.len(),
+            "defaults are instances of their class",
+        ),
+    );
+
+    checks
+}
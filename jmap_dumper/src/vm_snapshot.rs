@@ -0,0 +1,102 @@
+//! Guest physical memory captured from the hypervisor side, so games inside a VM can be dumped
+//! without anything running in the guest. Supports QEMU `dump-guest-memory` ELF cores and flat
+//! `pmemsave` images; guest virtual addresses are translated through the page tables at a given
+//! CR3.
+
+use std::sync::Arc;
+
+use anyhow::{Result, bail};
+
+use crate::kernel_dump::{PAGE_SIZE, PhysicalMemory, u16_at, u32_at, u64_at};
+use crate::mem::Mem;
+
+const PT_LOAD: u32 = 1;
+
+/// Physical range stored contiguously in the file
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    physical: u64,
+    size: u64,
+    file_offset: u64,
+}
+
+pub struct VmSnapshot {
+    mmap: memmap2::Mmap,
+    /// Sorted by physical address. Empty for flat images where file offset == physical address.
+    segments: Vec<Segment>,
+}
+
+impl VmSnapshot {
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let mmap = unsafe { memmap2::MmapOptions::new().map(&file)? };
+
+        let mut segments = vec![];
+        if mmap.starts_with(b"\x7fELF") {
+            if mmap.len() < 0x40 || mmap[4] != 2 || mmap[5] != 1 {
+                bail!("expected a 64-bit little endian ELF core");
+            }
+            let phoff = u64_at(&mmap, 0x20) as usize;
+            let phentsize = u16_at(&mmap, 0x36) as usize;
+            let phnum = u16_at(&mmap, 0x38) as usize;
+            for i in 0..phnum {
+                let header = phoff + i * phentsize;
+                if mmap.len() < header + 0x38 {
+                    bail!("truncated ELF program headers");
+                }
+                if u32_at(&mmap, header) != PT_LOAD {
+                    continue;
+                }
+                segments.push(Segment {
+                    file_offset: u64_at(&mmap, header + 0x8),
+                    physical: u64_at(&mmap, header + 0x18),
+                    size: u64_at(&mmap, header + 0x20),
+                });
+            }
+            if segments.is_empty() {
+                bail!("ELF core has no PT_LOAD segments");
+            }
+            segments.sort_by_key(|s| s.physical);
+        }
+        Ok(Self { mmap, segments })
+    }
+
+    /// View of the address space whose top level page table is at `cr3`
+    pub fn process_mem(self: &Arc<Self>, cr3: u64) -> VmSnapshotMem {
+        VmSnapshotMem {
+            snapshot: self.clone(),
+            cr3,
+        }
+    }
+}
+
+impl PhysicalMemory for VmSnapshot {
+    fn page(&self, pfn: u64) -> Option<&[u8]> {
+        let address = pfn * PAGE_SIZE;
+        let offset = if self.segments.is_empty() {
+            address
+        } else {
+            let i = self
+                .segments
+                .partition_point(|s| s.physical + s.size <= address);
+            let segment = self.segments.get(i).filter(|s| s.physical <= address)?;
+            if address + PAGE_SIZE > segment.physical + segment.size {
+                return None;
+            }
+            segment.file_offset + (address - segment.physical)
+        } as usize;
+        self.mmap.get(offset..offset + PAGE_SIZE as usize)
+    }
+}
+
+#[derive(Clone)]
+pub struct VmSnapshotMem {
+    snapshot: Arc<VmSnapshot>,
+    cr3: u64,
+}
+
+impl Mem for VmSnapshotMem {
+    fn read_buf(&self, address: u64, buf: &mut [u8]) -> Result<()> {
+        self.snapshot.read_virt(self.cr3, address, buf)
+    }
+}
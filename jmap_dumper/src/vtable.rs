@@ -1,19 +1,333 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
 
+use crate::Cancel;
 use crate::mem::Mem;
 use anyhow::Result;
-use jmap::{Address, ObjectType};
+use jmap::{Address, ImageFormat, Jmap, NativeFunction, ObjectType, VirtualFunction};
+use tracing::warn;
 
+/// IMAGE_SCN_MEM_EXECUTE
+pub(crate) const SECTION_EXECUTE: u32 = 0x20000000;
+
+/// C++ ABI the vtables follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Abi {
+    /// Windows: one slot for the (vector) deleting destructor, the RTTI locator before the
+    /// vtable
+    Msvc,
+    /// Linux: complete and deleting destructors in two slots, the offset to the top of the object
+    /// and the typeinfo (null without RTTI) before the vtable
+    Itanium,
+}
+
+impl Abi {
+    /// ABI of the image `header` was taken from
+    pub fn new(header: &Jmap) -> Self {
+        match header.image.as_ref().map(|i| i.format) {
+            Some(ImageFormat::Elf) => Abi::Itanium,
+            _ => Abi::Msvc,
+        }
+    }
+}
+
+/// Where virtual functions can be: the executable sections of the main image and, without their
+/// section headers, anywhere in the other modules. Lacking any image info every readable address
+/// counts.
+pub struct Code {
+    ranges: Option<Vec<Range<u64>>>,
+}
+
+impl Code {
+    /// Code of the image `header` (a dump without its objects) was taken from
+    pub fn new(header: &Jmap) -> Self {
+        let image = header.image.as_ref().filter(|i| !i.sections.is_empty());
+        let ranges = image.map(|image| {
+            let base = header.image_base_address.0;
+            let sections = image
+                .sections
+                .iter()
+                .filter(|s| s.characteristics & SECTION_EXECUTE != 0)
+                .map(|s| {
+                    let start = base + s.virtual_address as u64;
+                    start..start + s.virtual_size as u64
+                });
+            let modules = image
+                .modules
+                .iter()
+                .filter(|m| m.base != header.image_base_address)
+                .map(|m| m.base.0..m.base.0 + m.size);
+            sections.chain(modules).collect()
+        });
+        Self { ranges }
+    }
+
+    fn contains<M: Mem>(&self, mem: &M, address: u64) -> bool {
+        match &self.ranges {
+            Some(ranges) => ranges.iter().any(|r| r.contains(&address)),
+            None => {
+                let mut buf = [0; 1];
+                mem.read_buf(address, &mut buf).is_ok()
+            }
+        }
+    }
+}
+
+/// Virtuals of UObjectBase, UObjectBaseUtility and UObject in the vtable order of shipping 4.25 -
+/// 4.27 builds, each overload of e.g. `Serialize` taking a slot of its own
+const UOBJECT_SLOTS_4_25: &[&str] = &[
+    "__vecDelDtor",
+    "RegisterDependencies",
+    "DeferredRegister",
+    "CanBeClusterRoot",
+    "CanBeInCluster",
+    "CreateCluster",
+    "OnClusterMarkedAsPendingKill",
+    "GetDetailedInfoInternal",
+    "PostInitProperties",
+    "PostCDOContruct",
+    "PreSaveRoot",
+    "PostSaveRoot",
+    "PreSave",
+    "IsReadyForAsyncPostLoad",
+    "PostLoad",
+    "PostLoadSubobjects",
+    "BeginDestroy",
+    "IsReadyForFinishDestroy",
+    "FinishDestroy",
+    "Serialize",
+    "Serialize",
+    "ShutdownAfterError",
+    "PostInterpChange",
+    "PostRename",
+    "PostDuplicate",
+    "PostDuplicate",
+    "NeedsLoadForClient",
+    "NeedsLoadForServer",
+    "NeedsLoadForTargetPlatform",
+    "NeedsLoadForEditorGame",
+    "IsEditorOnly",
+    "IsPostLoadThreadSafe",
+    "IsDestructionThreadSafe",
+    "GetPreloadDependencies",
+    "GetPrestreamPackages",
+    "ExportCustomProperties",
+    "ImportCustomProperties",
+    "PostEditImport",
+    "PostReloadConfig",
+    "Rename",
+    "GetDesc",
+    "GetSparseClassDataStruct",
+    "GetWorld",
+    "GetNativePropertyValues",
+    "GetResourceSizeEx",
+    "GetExporterName",
+    "GetRestoreForUObjectOverwrite",
+    "AreNativePropertiesIdenticalTo",
+    "GetAssetRegistryTags",
+    "IsAsset",
+    "GetPrimaryAssetId",
+    "IsLocalizedResource",
+    "IsSafeForRootSet",
+    "TagSubobjects",
+    "GetLifetimeReplicatedProps",
+    "IsNameStableForNetworking",
+    "IsFullNameStableForNetworking",
+    "IsSupportedForNetworking",
+    "GetSubobjectsWithStableNamesForNetworking",
+    "PreNetReceive",
+    "PostNetReceive",
+    "PostRepNotifies",
+    "PreDestroyFromReplication",
+    "BuildSubobjectMapping",
+    "GetConfigOverridePlatform",
+    "OverrideConfigSection",
+    "OverridePerObjectConfigSection",
+    "ProcessEvent",
+    "GetFunctionCallspace",
+    "CallRemoteFunction",
+    "ProcessConsoleExec",
+    "RegenerateClass",
+    "MarkAsEditorOnlySubobject",
+    "CheckDefaultSubobjectsInternal",
+    "ValidateGeneratedRepEnums",
+    "SetNetPushIdDynamic",
+    "GetNetPushIdDynamic",
+];
+
+/// Names of the well known slots of UObject's vtable by index. The table for the engine version
+/// is only trusted if the resolved `UObject::ProcessEvent` is in the slot it expects (or there is
+/// nothing to check it against), since editor builds and other versions have more virtuals.
+pub fn slot_names(
+    header: &Jmap,
+    class_vtables: &HashMap<String, Address>,
+    vtables: &BTreeMap<Address, Vec<Address>>,
+) -> Vec<Option<&'static str>> {
+    let version = header
+        .metadata
+        .as_ref()
+        .map(|m| (m.engine_version.major, m.engine_version.minor));
+    let table = match version {
+        Some(version) if ((4, 25)..=(4, 27)).contains(&version) => UOBJECT_SLOTS_4_25,
+        _ => &[],
+    };
+    let table = match Abi::new(header) {
+        Abi::Msvc => table.to_vec(),
+        // the destructor is the first virtual of UObjectBase
+        Abi::Itanium if !table.is_empty() => ["~UObject", "~UObject"]
+            .into_iter()
+            .chain(table[1..].iter().copied())
+            .collect(),
+        Abi::Itanium => vec![],
+    };
+    let mut names: Vec<_> = table.iter().map(|name| Some(*name)).collect();
+
+    let process_event = header
+        .resolved_addresses
+        .get("UObject::ProcessEvent")
+        .and_then(|process_event| {
+            let vtable = vtables.get(class_vtables.get("/Script/CoreUObject.Object")?)?;
+            vtable.iter().position(|f| f == process_event)
+        });
+    if let Some(slot) = process_event {
+        if let Some(expected) = table.iter().position(|name| *name == "ProcessEvent")
+            && expected != slot
+        {
+            warn!(
+                "UObject::ProcessEvent is in vtable slot {slot} rather than {expected}, \
+                 naming only that slot"
+            );
+            names.clear();
+        }
+        if names.len() <= slot {
+            names.resize(slot + 1, None);
+        }
+        names[slot] = Some("ProcessEvent");
+    }
+    names
+}
+
+/// Function at `address` relative to the module of `header` it is in, the main image unless
+/// another module contains it
+pub(crate) fn native_function(header: &Jmap, address: u64) -> NativeFunction {
+    let modules = header.image.as_ref().map_or(&[][..], |i| &i.modules[..]);
+    let module = modules
+        .iter()
+        .filter(|m| m.base != header.image_base_address)
+        .find(|m| (m.base.0..m.base.0 + m.size).contains(&address));
+    match module {
+        Some(module) => NativeFunction {
+            rva: (address - module.base.0).into(),
+            module: Some(module.name.clone()),
+            symbol: None,
+        },
+        None => NativeFunction {
+            rva: address.wrapping_sub(header.image_base_address.0).into(),
+            module: None,
+            symbol: None,
+        },
+    }
+}
+
+/// Slots of `vtable` as found by [`find_vtables`], relative to the modules of `header` and named
+/// after [`slot_names`]
+pub fn virtual_functions(
+    header: &Jmap,
+    vtables: &BTreeMap<Address, Vec<Address>>,
+    vtable: Address,
+    names: &[Option<&str>],
+) -> Vec<VirtualFunction> {
+    let Some(functions) = vtables.get(&vtable) else {
+        return vec![];
+    };
+    functions
+        .iter()
+        .enumerate()
+        .map(|(slot, function)| {
+            let NativeFunction { rva, module, .. } = native_function(header, function.0);
+            VirtualFunction {
+                rva,
+                module,
+                name: names.get(slot).copied().flatten().map(str::to_string),
+                symbol: None,
+            }
+        })
+        .collect()
+}
+
+/// Find the vtables of the dumped objects, filling in `instance_vtable` and `virtual_functions`
+/// of their classes. `header` is the dump the objects go in.
 pub fn analyze_vtables<M: Mem>(
     mem: &M,
+    header: &Jmap,
     objects: &mut BTreeMap<String, ObjectType>,
+    cancel: &Cancel,
 ) -> BTreeMap<Address, Vec<Address>> {
+    let (class_vtables, vtables) = find_vtables(
+        mem,
+        &Code::new(header),
+        Abi::new(header),
+        cancel,
+        objects.values().map(|obj| {
+            let object = obj.get_object();
+            (object.class.as_str(), object.vtable)
+        }),
+        // classes of dumped objects can be filtered out
+        |class| objects.get(class)?.get_struct()?.super_struct.as_deref(),
+    );
+
+    // update UClass::instance_vtable
+    let names = slot_names(header, &class_vtables, &vtables);
+    for (class, vtable) in class_vtables {
+        match objects.get_mut(&class).unwrap() {
+            ObjectType::Class(class) => {
+                class.instance_vtable = Some(vtable);
+                class.virtual_functions = virtual_functions(header, &vtables, vtable, &names);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    vtables
+}
+
+fn read_ptr<M: Mem>(mem: &M, addr: u64) -> Result<u64> {
+    let mut buf = [0; 8];
+    mem.read_buf(addr, &mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Whether `vtable` looks like the primary Itanium vtable of a class: an offset to the top of 0,
+/// and if the image has RTTI a typeinfo whose type name (its second member) can be read
+fn is_itanium_vtable<M: Mem>(mem: &M, vtable: u64) -> bool {
+    let (Ok(offset_to_top), Ok(typeinfo)) = (
+        read_ptr(mem, vtable.wrapping_sub(16)),
+        read_ptr(mem, vtable.wrapping_sub(8)),
+    ) else {
+        return false;
+    };
+    if offset_to_top != 0 {
+        return false;
+    }
+    typeinfo == 0
+        || read_ptr(mem, typeinfo + 8).is_ok_and(|name| mem.read_buf(name, &mut [0; 1]).is_ok())
+}
+
+/// Find vtables from the `(class, vtable)` of every object. Returns the instance vtable of each
+/// class and the functions of each vtable, which end at the first pointer outside `code`. Stops
+/// reading vtables once `cancel` is set.
+#[tracing::instrument(skip_all)]
+pub fn find_vtables<'a, M: Mem>(
+    mem: &M,
+    code: &Code,
+    abi: Abi,
+    cancel: &Cancel,
+    instances: impl IntoIterator<Item = (&'a str, Address)>,
+    super_struct: impl Fn(&str) -> Option<&'a str>,
+) -> (HashMap<String, Address>, BTreeMap<Address, Vec<Address>>) {
     let mut class_vtables: HashMap<String, Address> = HashMap::new();
     let mut grouped: BTreeMap<Address, HashSet<&str>> = Default::default();
-    for obj in objects.values() {
-        let object = obj.get_object();
-        let vtable = object.vtable;
-        let class = object.class.as_str();
+    for (class, vtable) in instances {
         class_vtables
             .insert(class.to_string(), vtable)
             .inspect(|existing| assert_eq!(*existing, vtable, "found conflicting vtable"));
@@ -24,22 +338,19 @@ pub fn analyze_vtables<M: Mem>(
     //     println!("{i} {vtable:08x} {classes:?}");
     // }
 
-    fn read_ptr<M: Mem>(mem: &M, addr: u64) -> Result<u64> {
-        let mut buf = [0; 8];
-        mem.read_buf(addr, &mut buf)?;
-        Ok(u64::from_le_bytes(buf))
-    }
-    fn is_valid<M: Mem>(mem: &M, addr: u64) -> bool {
-        // TODO check for executable bit, not just valid memory
-        let mut buf = [0; 1];
-        mem.read_buf(addr, &mut buf).is_ok()
-    }
-
     let mut vtables: BTreeMap<Address, Vec<Address>> = Default::default();
+    let mut rejected = HashSet::new();
 
     let mut vtable_iter = grouped.iter().peekable();
     while let Some((vtable, _classes)) = vtable_iter.next() {
+        if cancel.is_cancelled() {
+            break;
+        }
         let next = vtable_iter.peek();
+        if abi == Abi::Itanium && !is_itanium_vtable(mem, vtable.0) {
+            rejected.insert(*vtable);
+            continue;
+        }
 
         let mut addr = *vtable;
         let mut funcs = vec![];
@@ -53,7 +364,9 @@ pub fn analyze_vtables<M: Mem>(
             }
 
             if let Ok(ptr) = read_ptr(mem, addr.0) {
-                if is_valid(mem, ptr) {
+                // MSVC puts the RTTI locator of a vtable right before it and Itanium the offset
+                // to top and typeinfo, so this also stops at the start of the next one
+                if code.contains(mem, ptr) {
                     funcs.push(ptr.into());
                 } else {
                     // println!("BREAK BAD FUNC PTR n={}", funcs.len());
@@ -70,50 +383,41 @@ pub fn analyze_vtables<M: Mem>(
 
         assert!(vtables.insert(*vtable, funcs).is_none());
     }
+    if !rejected.is_empty() {
+        warn!(
+            "{} instance vtables don't look like Itanium vtables, skipping them",
+            rejected.len()
+        );
+        class_vtables.retain(|_, vtable| !rejected.contains(vtable));
+    }
 
     // trim vtables as they must be bounded by size of child vtable
-    for (path, obj) in &*objects {
-        if obj.get_class().is_some() {
-            let mut class = path.as_str();
+    for (path, vtable_ptr) in &class_vtables {
+        let mut class = path.as_str();
+        // not read if interrupted
+        let Some(mut vtable_len) = vtables.get(vtable_ptr).map(Vec::len) else {
+            continue;
+        };
+
+        while let Some(parent) = super_struct(class) {
+            class = parent;
             let Some(vtable_ptr) = class_vtables.get(class) else {
                 // println!("no vtable found for class {class}");
                 continue;
             };
-            let mut vtable_len = vtables.get(vtable_ptr).unwrap().len();
-
-            while let Some(parent) = objects
-                .get(class)
-                .unwrap()
-                .get_struct()
-                .unwrap()
-                .super_struct
-                .as_deref()
-            {
-                class = parent;
-                let Some(vtable_ptr) = class_vtables.get(class) else {
-                    // println!("no vtable found for class {class}");
-                    continue;
-                };
-                let vtable = vtables.get_mut(vtable_ptr).unwrap();
-                if vtable.len() > vtable_len {
-                    // println!(
-                    //     "trimming vtable {} -> {} ({}) for {class}",
-                    //     vtable.len(),
-                    //     vtable_len,
-                    //     vtable.len() - vtable_len
-                    // );
-                    vtable.truncate(vtable_len);
-                }
-                vtable_len = vtable.len();
+            let Some(vtable) = vtables.get_mut(vtable_ptr) else {
+                continue;
+            };
+            if vtable.len() > vtable_len {
+                // println!(
+                //     "trimming vtable {} -> {} ({}) for {class}",
+                //     vtable.len(),
+                //     vtable_len,
+                //     vtable.len() - vtable_len
+                // );
+                vtable.truncate(vtable_len);
             }
-        }
-    }
-
-    // update UClass::instance_vtable
-    for (class, vtable) in class_vtables {
-        match objects.get_mut(&class).unwrap() {
-            ObjectType::Class(class) => class.instance_vtable = Some(vtable),
-            _ => unreachable!(),
+            vtable_len = vtable.len();
         }
     }
 
@@ -160,5 +464,5 @@ pub fn analyze_vtables<M: Mem>(
     //     }
     // }
 
-    vtables
+    (class_vtables, vtables)
 }
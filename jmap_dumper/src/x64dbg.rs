@@ -0,0 +1,45 @@
+//! x64dbg script labelling exec functions, vtables and engine globals. Addresses are relative to
+//! `mod.main()` so the script works regardless of where ASLR placed the image.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use jmap::Jmap;
+
+use crate::symbols::collect_symbols;
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Generate an x64dbg script setting a label and a comment with the object path at every symbol
+pub fn into_x64dbg_script(jmap: &Jmap) -> String {
+    let base = jmap.image_base_address.0;
+
+    let mut buffer = String::new();
+    writeln!(buffer, "guiupdatedisable").unwrap();
+
+    // an address holds a single label, keep the first
+    let mut seen = HashSet::new();
+    for symbol in collect_symbols(jmap) {
+        // symbols in other modules of multi-module games aren't relative to mod.main()
+        let Some(rva) = jmap
+            .module_of(symbol.address)
+            .map(|(_, rva)| rva)
+            .filter(|rva| symbol.address != 0 && symbol.address - rva == base)
+        else {
+            continue;
+        };
+        if !seen.insert(rva) {
+            continue;
+        }
+        writeln!(buffer, "lbl mod.main()+0x{rva:x}, {}", quote(&symbol.name)).unwrap();
+        if let Some(object) = &symbol.object {
+            writeln!(buffer, "cmt mod.main()+0x{rva:x}, {}", quote(object)).unwrap();
+        }
+    }
+
+    writeln!(buffer, "guiupdateenable").unwrap();
+    writeln!(buffer, "ret").unwrap();
+    buffer
+}
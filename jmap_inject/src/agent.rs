@@ -0,0 +1,132 @@
+use std::ffi::c_void;
+use std::fs::File;
+use std::os::windows::io::FromRawHandle as _;
+
+use anyhow::{Context as _, Result, bail};
+use jmap_remote::{RemoteInfo, pipe_name};
+use windows::Win32::System::Memory::{
+    MEM_COMMIT, MEMORY_BASIC_INFORMATION, PAGE_GUARD, PAGE_NOACCESS, VirtualQuery,
+};
+use windows::core::HSTRING;
+
+/// Read the current process' own memory, checked against `VirtualQuery` so bad pointers fail
+/// instead of crashing the game
+fn read_local(address: u64, buf: &mut [u8]) -> Result<()> {
+    let end = address
+        .checked_add(buf.len() as u64)
+        .context("read wraps the address space")?;
+    let mut cur = address;
+    while cur < end {
+        let mut info = MEMORY_BASIC_INFORMATION::default();
+        let written = unsafe {
+            VirtualQuery(
+                Some(cur as *const c_void),
+                &mut info,
+                size_of::<MEMORY_BASIC_INFORMATION>(),
+            )
+        };
+        if written == 0
+            || info.State != MEM_COMMIT
+            || info.Protect.contains(PAGE_NOACCESS)
+            || info.Protect.contains(PAGE_GUARD)
+        {
+            bail!(
+                "reading {} bytes at 0x{:x}: 0x{cur:x} is not readable",
+                buf.len(),
+                address
+            );
+        }
+        cur = info.BaseAddress as u64 + info.RegionSize as u64;
+    }
+    unsafe {
+        std::ptr::copy_nonoverlapping(address as *const u8, buf.as_mut_ptr(), buf.len());
+    }
+    Ok(())
+}
+
+/// `FString` as laid out by the engine
+#[repr(C)]
+struct FString {
+    data: *mut u16,
+    num: i32,
+    max: i32,
+}
+
+type GetPathName = unsafe extern "system" fn(*const c_void, *const c_void, *mut FString);
+
+/// Entry point of the injected agent: serve the game to one dumper connection at a time over
+/// the pipe named after the current PID
+pub fn agent_main() -> Result<()> {
+    use patternsleuth::resolvers::resolve;
+    use patternsleuth::resolvers::unreal::UObjectBaseUtilityGetPathName;
+    use windows::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+    use windows::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_TYPE_BYTE, PIPE_WAIT,
+    };
+
+    let image = patternsleuth::process::internal::read_image()?;
+    let name = std::env::current_exe()?
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let info = RemoteInfo {
+        image_base: image.base_address as u64,
+        name,
+    };
+
+    let get_path_name = resolve(&image, UObjectBaseUtilityGetPathName::resolver())
+        .ok()
+        .map(|f| unsafe { std::mem::transmute::<usize, GetPathName>(f.0) });
+    // the FString buffer comes from the engine allocator and is leaked, this is only used to
+    // spot check a handful of objects
+    let path_name = |object: u64| -> Result<String> {
+        let Some(get_path_name) = get_path_name else {
+            bail!("UObjectBaseUtility::GetPathName not found");
+        };
+        // don't hand the engine a pointer that would fault
+        read_local(object, &mut [0; 0x30])?;
+
+        let mut result = FString {
+            data: std::ptr::null_mut(),
+            num: 0,
+            max: 0,
+        };
+        unsafe { get_path_name(object as *const c_void, std::ptr::null(), &mut result) };
+        if result.data.is_null() || result.num <= 0 {
+            return Ok(String::new());
+        }
+        let chars = unsafe { std::slice::from_raw_parts(result.data, result.num as usize - 1) };
+        Ok(String::from_utf16_lossy(chars))
+    };
+
+    let name = HSTRING::from(pipe_name(std::process::id()));
+    loop {
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                &name,
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+                1,
+                1 << 20,
+                1 << 20,
+                0,
+                None,
+            )
+        };
+        if pipe.is_invalid() {
+            return Err(windows::core::Error::from_win32().into());
+        }
+        // a client connecting between create and connect already counts as connected
+        let _ = unsafe { ConnectNamedPipe(pipe, None) };
+
+        let pipe = unsafe { File::from_raw_handle(pipe.0) };
+        let _ = jmap_remote::handle_client(
+            pipe.try_clone()?,
+            pipe,
+            &read_local,
+            &info,
+            Some(&path_name),
+        );
+    }
+}
@@ -0,0 +1,25 @@
+//! Agent DLL loaded into the game by `jmap_dumper --inject`, see `jmap_dumper::inject`. Serves
+//! the `jmap_remote` protocol over a named pipe reading the game's memory directly, and only
+//! depends on what it needs for that so the DLL stays small.
+
+#[cfg(target_os = "windows")]
+mod agent;
+
+#[cfg(target_os = "windows")]
+#[unsafe(no_mangle)]
+extern "system" fn DllMain(
+    _module: *mut std::ffi::c_void,
+    reason: u32,
+    _reserved: *mut std::ffi::c_void,
+) -> i32 {
+    const DLL_PROCESS_ATTACH: u32 = 1;
+    if reason == DLL_PROCESS_ATTACH {
+        // runs once the loader lock is released
+        std::thread::spawn(|| {
+            if let Err(err) = agent::agent_main() {
+                eprintln!("jmap_inject: {err:#}");
+            }
+        });
+    }
+    1
+}
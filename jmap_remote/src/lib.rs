@@ -0,0 +1,200 @@
+//! Minimal read-memory protocol between `jmap_agent`, running next to the game, and
+//! `jmap_dumper::remote::RemoteMem` so a dump can be made from another machine. The injected
+//! `jmap_inject` agent speaks the same protocol over a named pipe. Kept free of the dumper's
+//! dependencies so the agent DLL only links what it serves with.
+//!
+//! All integers are little endian. The client opens with [`MAGIC`] and [`VERSION`] and then
+//! sends requests, each answered with a status byte (0 ok, 1 error followed by a length prefixed
+//! message) and the payload:
+//!
+//! - `INFO` -> image base `u64`, length prefixed main module name
+//! - `READ address: u64, len: u32` -> `len` bytes
+//! - `PATH_NAME address: u64` -> length prefixed path of the UObject at `address` as reported by
+//!   the engine (only served in process)
+
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use anyhow::{Result, bail};
+
+pub const MAGIC: &[u8; 4] = b"JMPA";
+pub const VERSION: u32 = 2;
+pub const DEFAULT_PORT: u16 = 7878;
+
+pub const INFO: u8 = 1;
+pub const READ: u8 = 2;
+pub const PATH_NAME: u8 = 3;
+
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_ERROR: u8 = 1;
+
+/// Largest single read the agent serves
+pub const MAX_READ: u32 = 16 << 20;
+
+/// What the agent reports about the process it serves
+#[derive(Debug, Clone)]
+pub struct RemoteInfo {
+    pub image_base: u64,
+    pub name: String,
+}
+
+/// Named pipe the injected agent of `pid` listens on
+pub fn pipe_name(pid: u32) -> String {
+    format!(r"\\.\pipe\jmap_inject_{pid}")
+}
+
+pub fn read_u32(r: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+pub fn read_u64(r: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+pub fn read_string(r: &mut impl Read) -> std::io::Result<String> {
+    let mut buf = vec![0; read_u32(r)? as usize];
+    r.read_exact(&mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+pub fn write_string(w: &mut impl Write, s: &str) -> std::io::Result<()> {
+    w.write_all(&(s.len() as u32).to_le_bytes())?;
+    w.write_all(s.as_bytes())
+}
+
+/// Memory read backing `READ`
+pub type ReadFn<'a> = &'a dyn Fn(u64, &mut [u8]) -> Result<()>;
+
+/// Engine side path lookup backing `PATH_NAME`
+pub type PathNameFn<'a> = &'a dyn Fn(u64) -> Result<String>;
+
+/// Answer requests from one client until it disconnects
+pub fn handle_client(
+    reader: impl Read,
+    writer: impl Write,
+    read: ReadFn<'_>,
+    info: &RemoteInfo,
+    path_name: Option<PathNameFn<'_>>,
+) -> Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut writer = BufWriter::new(writer);
+
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    let version = read_u32(&mut reader)?;
+    if &magic != MAGIC || version != VERSION {
+        bail!("client speaks an unsupported protocol (version {version})");
+    }
+
+    let mut buf = vec![];
+    loop {
+        let mut op = [0];
+        if reader.read(&mut op)? == 0 {
+            return Ok(());
+        }
+        match op[0] {
+            INFO => {
+                writer.write_all(&[STATUS_OK])?;
+                writer.write_all(&info.image_base.to_le_bytes())?;
+                write_string(&mut writer, &info.name)?;
+            }
+            READ => {
+                let address = read_u64(&mut reader)?;
+                let len = read_u32(&mut reader)?;
+                if len > MAX_READ {
+                    writer.write_all(&[STATUS_ERROR])?;
+                    write_string(&mut writer, &format!("read of {len} bytes exceeds limit"))?;
+                } else {
+                    buf.resize(len as usize, 0);
+                    match read(address, &mut buf) {
+                        Ok(()) => {
+                            writer.write_all(&[STATUS_OK])?;
+                            writer.write_all(&buf)?;
+                        }
+                        Err(err) => {
+                            writer.write_all(&[STATUS_ERROR])?;
+                            write_string(&mut writer, &format!("{err:#}"))?;
+                        }
+                    }
+                }
+            }
+            PATH_NAME => {
+                let address = read_u64(&mut reader)?;
+                match path_name.map(|f| f(address)) {
+                    Some(Ok(path)) => {
+                        writer.write_all(&[STATUS_OK])?;
+                        write_string(&mut writer, &path)?;
+                    }
+                    Some(Err(err)) => {
+                        writer.write_all(&[STATUS_ERROR])?;
+                        write_string(&mut writer, &format!("{err:#}"))?;
+                    }
+                    None => {
+                        writer.write_all(&[STATUS_ERROR])?;
+                        write_string(&mut writer, "path names are only available in process")?;
+                    }
+                }
+            }
+            other => bail!("unknown request {other}"),
+        }
+        writer.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_handle_client() -> Result<()> {
+        let mut request = vec![];
+        request.extend(MAGIC);
+        request.extend(VERSION.to_le_bytes());
+        request.push(INFO);
+        request.push(READ);
+        request.extend(0x1000u64.to_le_bytes());
+        request.extend(4u32.to_le_bytes());
+        request.push(READ);
+        request.extend(0u64.to_le_bytes());
+        request.extend(1u32.to_le_bytes());
+        request.push(PATH_NAME);
+        request.extend(0x1000u64.to_le_bytes());
+
+        let read = |address: u64, buf: &mut [u8]| -> Result<()> {
+            if address != 0x1000 {
+                bail!("unmapped");
+            }
+            buf.copy_from_slice(&[1, 2, 3, 4][..buf.len()]);
+            Ok(())
+        };
+        let info = RemoteInfo {
+            image_base: 0x140000000,
+            name: "Game.exe".into(),
+        };
+        let mut response = vec![];
+        handle_client(request.as_slice(), &mut response, &read, &info, None)?;
+
+        let r = &mut response.as_slice();
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        assert_eq!(byte[0], STATUS_OK);
+        assert_eq!(read_u64(r)?, 0x140000000);
+        assert_eq!(read_string(r)?, "Game.exe");
+
+        let mut data = [0; 4];
+        r.read_exact(&mut byte)?;
+        assert_eq!(byte[0], STATUS_OK);
+        r.read_exact(&mut data)?;
+        assert_eq!(data, [1, 2, 3, 4]);
+
+        r.read_exact(&mut byte)?;
+        assert_eq!(byte[0], STATUS_ERROR);
+        assert_eq!(read_string(r)?, "unmapped");
+
+        r.read_exact(&mut byte)?;
+        assert_eq!(byte[0], STATUS_ERROR);
+        assert_eq!(read_string(r)?, "path names are only available in process");
+        assert!(r.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! UEnums as Binary Ninja enumerations. A UEnum doesn't know its underlying type, so it is as wide
+//! as the properties holding it (the widest if they differ, narrower ones stay plain integers), or
+//! as its values need for enums no dumped property uses.
+
+use std::collections::HashMap;
+use std::num::NonZero;
+
+use binaryninja::binary_view::BinaryViewExt;
+use binaryninja::types::{EnumerationBuilder, Type};
+
+use jmap::{Jmap, Property, PropertyType};
+
+use crate::{Ctx, obj_name};
+
+/// Width of the properties holding each enum
+pub(crate) fn enum_widths(ref_data: &Jmap) -> HashMap<&str, usize> {
+    fn visit<'a>(prop: &'a Property, widths: &mut HashMap<&'a str, usize>) {
+        let (r#enum, size) = match &prop.r#type {
+            PropertyType::Enum {
+                container,
+                r#enum: Some(r#enum),
+            } => (r#enum, container.size),
+            PropertyType::Byte {
+                r#enum: Some(r#enum),
+            } => (r#enum, prop.size),
+            PropertyType::Array { inner } | PropertyType::Optional { inner } => {
+                return visit(inner, widths);
+            }
+            PropertyType::Set { key_prop } => return visit(key_prop, widths),
+            PropertyType::Map {
+                key_prop,
+                value_prop,
+            } => {
+                visit(key_prop, widths);
+                return visit(value_prop, widths);
+            }
+            _ => return,
+        };
+        let width = widths.entry(r#enum.as_str()).or_default();
+        *width = (*width).max(size);
+    }
+
+    let mut widths = HashMap::new();
+    for obj in ref_data.objects.values() {
+        if let Some(struct_) = obj.get_struct() {
+            for prop in &struct_.properties {
+                visit(prop, &mut widths);
+            }
+        }
+    }
+    widths
+}
+
+impl Ctx<'_, '_, '_> {
+    pub(crate) fn enum_width(&self, path: &str) -> usize {
+        if let Some(width) = self.enum_widths.get(path).filter(|w| **w != 0) {
+            return *width;
+        }
+        let enum_ = self.ref_data.objects[path].get_enum().unwrap();
+        let min = enum_
+            .names
+            .iter()
+            .map(|(_, v)| *v)
+            .min()
+            .unwrap_or_default();
+        let max = enum_
+            .names
+            .iter()
+            .map(|(_, v)| *v)
+            .max()
+            .unwrap_or_default();
+        if min < i8::MIN as i64 || max > u8::MAX as i64 {
+            4
+        } else {
+            1
+        }
+    }
+
+    pub(crate) fn decl_enum(&mut self, path: &str) {
+        let enum_ = self.ref_data.objects[path].get_enum().unwrap();
+        let width = self.enum_width(path);
+        if !self
+            .applied
+            .changed(&format!("enum:{path}"), &(enum_, width))
+        {
+            return;
+        }
+
+        let mut builder = EnumerationBuilder::new();
+        for (name, value) in &enum_.names {
+            builder.insert(name, *value as u64);
+        }
+        let is_signed = enum_.names.iter().any(|(_, v)| *v < 0);
+        let width = NonZero::new(width).unwrap();
+
+        self.bv.define_user_type(
+            obj_name(self.ref_data, path),
+            &Type::enumeration(&builder.finalize(), width, is_signed),
+        );
+    }
+}
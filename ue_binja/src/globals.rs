@@ -0,0 +1,176 @@
+//! Types of the engine globals the dumper resolved. The engine structs behind them follow the
+//! versioned layouts the dumper reads memory with (`unreal.gs`), where only the leading members of
+//! FUObjectArray and FNamePool are described.
+
+use binaryninja::architecture::CoreArchitecture;
+use binaryninja::binary_view::BinaryViewExt;
+use binaryninja::rc::Ref;
+use binaryninja::symbol::{Symbol, SymbolType};
+use binaryninja::types::{
+    FunctionParameter, NamedTypeReference, NamedTypeReferenceClass, Structure, Type,
+};
+use log::{info, warn};
+
+use crate::{Ctx, StructureBuilderExt};
+
+fn named_struct(name: &str) -> Ref<Type> {
+    Type::named_type(&NamedTypeReference::new(
+        NamedTypeReferenceClass::StructNamedTypeClass,
+        name,
+    ))
+}
+
+fn ptr(ty: &Type) -> Ref<Type> {
+    Type::pointer(&CoreArchitecture::by_name("x86_64").unwrap(), ty)
+}
+
+/// `FNativeFuncPtr[256]`, the bytecode handlers indexed by opcode
+fn gnatives_type() -> Ref<Type> {
+    let void_ptr = ptr(&Type::void());
+    let params = ["Context", "Stack", "Result"]
+        .into_iter()
+        .map(|name| FunctionParameter::new(void_ptr.clone(), name.to_string(), None))
+        .collect();
+    let native = Type::function(&Type::void(), params, false);
+    Type::array(&ptr(&native), 256)
+}
+
+impl Ctx<'_, '_, '_> {
+    fn decl_fuobjectarray(&self, version: (u16, u16)) {
+        let int = Type::int(4, true);
+        let object = ptr(&named_struct("UObject"));
+
+        let mut item = Structure::builder();
+        item.m(&object, "Object", 0);
+        if version < (4, 13) {
+            item.m(&int, "ClusterAndFlags", 8);
+            item.m(&int, "SerialNumber", 12);
+        } else {
+            item.m(&int, "Flags", 8);
+            let cluster = if version < (4, 16) {
+                "ClusterIndex"
+            } else {
+                "ClusterRootIndex"
+            };
+            item.m(&int, cluster, 12);
+            item.m(&int, "SerialNumber", 16);
+            if version >= (5, 5) {
+                item.m(&int, "RefCount", 20);
+            }
+            item.width(24);
+        }
+        self.bv
+            .define_user_type("FUObjectItem", &Type::structure(&item.finalize()));
+        let item = ptr(&named_struct("FUObjectItem"));
+
+        let mut array = Structure::builder();
+        array.m(&int, "ObjFirstGCIndex", 0);
+        array.m(&int, "ObjLastNonGCIndex", 4);
+        if version < (4, 11) {
+            // TStaticIndirectArrayThreadSafeRead and TArray<UObject*> aren't worth describing
+            array.m(&int, "OpenForDisregardForGC", 8);
+        } else {
+            array.m(&int, "MaxObjectsNotConsideredByGC", 8);
+            array.m(&Type::bool(), "OpenForDisregardForGC", 12);
+
+            let (name, objects) = if version < (4, 20) {
+                let mut objects = Structure::builder();
+                objects
+                    .m(&item, "Objects", 0)
+                    .m(&int, "MaxElements", 8)
+                    .m(&int, "NumElements", 12);
+                ("FFixedUObjectArray", objects.finalize())
+            } else {
+                let mut objects = Structure::builder();
+                objects
+                    .m(&ptr(&item), "Objects", 0)
+                    .m(&item, "PreAllocatedObjects", 8)
+                    .m(&int, "MaxElements", 16)
+                    .m(&int, "NumElements", 20)
+                    .m(&int, "MaxChunks", 24)
+                    .m(&int, "NumChunks", 28);
+                ("FChunkedFixedUObjectArray", objects.finalize())
+            };
+            self.bv.define_user_type(name, &Type::structure(&objects));
+            array.m(&named_struct(name), "ObjObjects", 16);
+        }
+        self.bv
+            .define_user_type("FUObjectArray", &Type::structure(&array.finalize()));
+    }
+
+    /// `FNamePool` from 4.23, before that the global is `GNames`, a `TNameEntryArray*`
+    fn decl_fnamepool(&self, version: (u16, u16)) -> Ref<Type> {
+        let uint = Type::int(4, false);
+        let void_ptr = ptr(&Type::void());
+        if version < (4, 23) {
+            let mut array = Structure::builder();
+            // 2M names in chunks of 16K
+            array
+                .m(&Type::array(&ptr(&void_ptr), 128), "Chunks", 0)
+                .m(&Type::int(4, true), "NumElements", 128 * 8)
+                .m(&Type::int(4, true), "NumChunks", 128 * 8 + 4);
+            self.bv
+                .define_user_type("TNameEntryArray", &Type::structure(&array.finalize()));
+            return ptr(&named_struct("TNameEntryArray"));
+        }
+
+        let mut allocator = Structure::builder();
+        allocator
+            .m(&void_ptr, "Lock", 0)
+            .m(&uint, "CurrentBlock", 8)
+            .m(&uint, "CurrentByteCursor", 12)
+            .m(&Type::array(&ptr(&Type::int(1, false)), 8192), "Blocks", 16);
+        self.bv.define_user_type(
+            "FNameEntryAllocator",
+            &Type::structure(&allocator.finalize()),
+        );
+
+        let mut pool = Structure::builder();
+        pool.m(&named_struct("FNameEntryAllocator"), "Entries", 0);
+        self.bv
+            .define_user_type("FNamePool", &Type::structure(&pool.finalize()));
+        named_struct("FNamePool")
+    }
+
+    /// Name the engine globals resolved by the dumper and type the ones in data
+    pub(crate) fn decl_globals(&mut self) {
+        let Some(metadata) = &self.ref_data.metadata else {
+            warn!("dump has no engine version, not applying engine globals");
+            return;
+        };
+        let version = (metadata.engine_version.major, metadata.engine_version.minor);
+        let record = (&self.ref_data.resolved_addresses, version);
+        if !self.applied.changed("globals", &record) {
+            return;
+        }
+
+        let image_base = self.bv.original_image_base();
+        let og_base = self.ref_data.image_base_address;
+
+        for (name, address) in &self.ref_data.resolved_addresses {
+            let addr = address.0 - og_base.0 + image_base;
+            let ty = match name.as_str() {
+                "GUObjectArray" => {
+                    self.decl_fuobjectarray(version);
+                    named_struct("FUObjectArray")
+                }
+                "FNamePool" => self.decl_fnamepool(version),
+                "GWorld" => ptr(&named_struct("UWorld")),
+                "GEngine" => ptr(&named_struct("UEngine")),
+                "GNatives" => gnatives_type(),
+                _ => {
+                    let sym = Symbol::builder(SymbolType::Function, name, addr).create();
+                    self.bv.define_user_symbol(&sym);
+                    continue;
+                }
+            };
+            let sym = Symbol::builder(SymbolType::Data, name, addr).create();
+            self.bv.define_user_symbol(&sym);
+            self.bv.define_user_data_var(addr, &ty);
+        }
+        info!(
+            "applied {} engine globals",
+            self.ref_data.resolved_addresses.len()
+        );
+    }
+}
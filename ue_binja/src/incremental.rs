@@ -0,0 +1,95 @@
+//! Incremental re-apply. Every type, symbol and function the plugin defines is recorded in the
+//! view's metadata as a hash of its key and one of what it was made from, so applying a newer
+//! dump to the same database only redefines what changed. Types and symbols of objects missing
+//! from the newer dump are left as they are.
+
+use std::collections::HashMap;
+
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use log::{info, warn};
+use serde::Serialize;
+
+const METADATA_KEY: &str = "ue_binja.applied";
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a, stable across builds unlike the std hasher
+fn fnv(seed: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(seed, |hash, b| (hash ^ *b as u64).wrapping_mul(FNV_PRIME))
+}
+
+#[derive(Default)]
+pub(crate) struct Applied {
+    /// Mixed into every record, so rebasing either side applies everything again
+    seed: u64,
+    /// Records of the last apply, `None` to apply everything
+    previous: Option<HashMap<u64, u64>>,
+    current: HashMap<u64, u64>,
+}
+
+impl Applied {
+    /// Records of the last apply to `bv` if `incremental`, apply everything otherwise
+    pub(crate) fn new(bv: &BinaryView, bases: (u64, u64), incremental: bool) -> Self {
+        let previous = incremental.then(|| read(bv)).flatten();
+        if incremental && previous.is_none() {
+            warn!("nothing applied to this view before, applying everything");
+        }
+        let seed = fnv(
+            FNV_OFFSET,
+            &[bases.0.to_le_bytes(), bases.1.to_le_bytes()].concat(),
+        );
+        Self {
+            seed,
+            previous,
+            current: HashMap::new(),
+        }
+    }
+
+    /// Record what `key` is made from, returning whether it has to be (re)defined
+    pub(crate) fn changed(&mut self, key: &str, value: &impl Serialize) -> bool {
+        let key = fnv(self.seed, key.as_bytes());
+        let value = fnv(self.seed, &serde_json::to_vec(value).unwrap());
+        self.current.insert(key, value);
+        self.previous
+            .as_ref()
+            .is_none_or(|previous| previous.get(&key) != Some(&value))
+    }
+
+    /// Save the records for the next apply
+    pub(crate) fn write(self, bv: &BinaryView) {
+        let changed = self
+            .previous
+            .as_ref()
+            .map_or(self.current.len(), |previous| {
+                self.current
+                    .iter()
+                    .filter(|(key, value)| previous.get(key) != Some(value))
+                    .count()
+            });
+        info!(
+            "defined {changed} of {} types, symbols and functions",
+            self.current.len()
+        );
+        let records = self.current.into_iter().collect::<Vec<_>>();
+        bv.store_metadata(
+            METADATA_KEY,
+            serde_json::to_string(&records).unwrap().as_str(),
+            false,
+        );
+    }
+}
+
+fn read(bv: &BinaryView) -> Option<HashMap<u64, u64>> {
+    let metadata = bv.query_metadata(METADATA_KEY)?;
+    let records = metadata.get_string()?;
+    match serde_json::from_str::<Vec<(u64, u64)>>(&records.to_string()) {
+        Ok(records) => Some(records.into_iter().collect()),
+        Err(err) => {
+            warn!("failed to read what was applied before: {err}");
+            None
+        }
+    }
+}
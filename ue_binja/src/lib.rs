@@ -1,14 +1,19 @@
+mod enums;
+mod globals;
+mod incremental;
+mod natives;
+
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::num::NonZero;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use binaryninja::architecture::CoreArchitecture;
 use binaryninja::binary_view::BinaryView;
 use binaryninja::rc::Ref;
 use binaryninja::symbol::{Symbol, SymbolType};
 use binaryninja::types::{
-    BaseStructure, NamedTypeReference, NamedTypeReferenceClass, StructureBuilder,
+    BaseStructure, FunctionParameter, NamedTypeReference, NamedTypeReferenceClass, StructureBuilder,
 };
 use binaryninja::{
     binary_view::BinaryViewExt,
@@ -20,7 +25,9 @@ use log::{error, info};
 
 use jmap::{Class, EClassCastFlags, Jmap, ObjectType, Property, PropertyType, Struct};
 
-struct ImportCommand {}
+struct ImportCommand {
+    incremental: bool,
+}
 impl Command for ImportCommand {
     fn action(&self, bv: &binaryninja::binary_view::BinaryView) {
         info!("do the stuff");
@@ -40,13 +47,9 @@ impl Command for ImportCommand {
             }
         };
 
-        let action = bv.file().begin_undo_actions(false);
-
-        info!("loaded {} objects", ref_data.objects.len());
-
-        into_header(&ref_data, bv, |_path, _obj| true);
-
-        bv.file().commit_undo_actions(&action);
+        if let Err(e) = apply(&ref_data, bv, self.incremental) {
+            error!("failed to apply objects: {e}");
+        }
     }
 
     fn valid(&self, _view: &binaryninja::binary_view::BinaryView) -> bool {
@@ -54,10 +57,21 @@ impl Command for ImportCommand {
     }
 }
 
-fn load(path: std::path::PathBuf) -> Result<Jmap> {
-    Ok(serde_json::from_reader(std::io::BufReader::new(
-        std::fs::File::open(path)?,
-    ))?)
+pub fn load(path: std::path::PathBuf) -> Result<Jmap> {
+    Ok(jmap::io::read(path)?)
+}
+
+/// Apply a dump to `bv` as one undo action, with `incremental` only what changed since the last
+/// one applied to it
+pub fn apply(ref_data: &Jmap, bv: &BinaryView, incremental: bool) -> Result<()> {
+    let action = bv.file().begin_undo_actions(false);
+
+    info!("loaded {} objects", ref_data.objects.len());
+
+    let result = into_header(ref_data, bv, incremental, |_path, _obj| true);
+
+    bv.file().commit_undo_actions(&action);
+    result
 }
 
 #[unsafe(no_mangle)]
@@ -69,7 +83,12 @@ pub extern "C" fn CorePluginInit() -> bool {
     command::register_command(
         "ue_binja - import reflection data",
         "Import Unreal Engine reflection data (.jmap)",
-        ImportCommand {},
+        ImportCommand { incremental: false },
+    );
+    command::register_command(
+        "ue_binja - re-import changed reflection data",
+        "Import a newer dump of the game, only updating what changed since the last import",
+        ImportCommand { incremental: true },
     );
 
     true
@@ -80,6 +99,8 @@ struct Ctx<'ref_data, 'types, 'bv> {
 
     ref_data: &'ref_data Jmap,
     store: &'types mut TypeStore<'ref_data>,
+    enum_widths: HashMap<&'ref_data str, usize>,
+    applied: incremental::Applied,
 }
 
 struct TypeStore<'a> {
@@ -147,14 +168,25 @@ fn obj_name(ref_data: &Jmap, path: &str) -> String {
     }
 }
 
+/// With `incremental` only what changed since the last dump applied to `bv` is defined
 #[allow(unused)]
-pub fn into_header(ref_data: &Jmap, bv: &BinaryView, filter: impl Fn(&str, &ObjectType) -> bool) {
-    Ctx {
+pub fn into_header(
+    ref_data: &Jmap,
+    bv: &BinaryView,
+    incremental: bool,
+    filter: impl Fn(&str, &ObjectType) -> bool,
+) -> Result<()> {
+    let bases = (ref_data.image_base_address.0, bv.original_image_base());
+    let mut ctx = Ctx {
         bv,
         ref_data,
         store: &mut TypeStore::default(),
-    }
-    .generate(filter)
+        enum_widths: enums::enum_widths(ref_data),
+        applied: incremental::Applied::new(bv, bases, incremental),
+    };
+    ctx.generate(filter)?;
+    ctx.applied.write(bv);
+    Ok(())
 }
 
 fn format_template(name: &str, params: impl IntoIterator<Item = impl AsRef<str>>) -> String {
@@ -207,10 +239,18 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
                 CType::BoolBit(inner, index)
             }
             PropertyType::Array { inner } => CType::TArray(self.prop_ctype(inner)),
-            PropertyType::Enum {
-                container: _,
-                r#enum,
-            } => CType::UEEnum(r#enum.as_ref().expect("TODO unknown enum name")),
+            PropertyType::Enum { container, r#enum } => match r#enum {
+                Some(r#enum)
+                    if self.ref_data.objects.contains_key(r#enum)
+                        && self.enum_width(r#enum) == container.size =>
+                {
+                    CType::UEEnum(r#enum)
+                }
+                _ => {
+                    let container = self.prop_ctype(container);
+                    self.store[container]
+                }
+            },
             PropertyType::Map {
                 key_prop,
                 value_prop,
@@ -222,6 +262,13 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
             PropertyType::Set { key_prop } => CType::TSet(self.prop_ctype(key_prop)),
             PropertyType::Float => CType::Float,
             PropertyType::Double => CType::Double,
+            PropertyType::Byte {
+                r#enum: Some(r#enum),
+            } if self.ref_data.objects.contains_key(r#enum)
+                && self.enum_width(r#enum) == prop.size =>
+            {
+                CType::UEEnum(r#enum)
+            }
             PropertyType::Byte { r#enum: _ } => CType::UInt8,
             PropertyType::UInt16 => CType::UInt16,
             PropertyType::UInt32 => CType::UInt32,
@@ -447,7 +494,10 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
 
             CType::Array(type_id, size) => Type::array(&self.bn_type(type_id), size as u64),
 
-            CType::UEEnum(path) => struct_(&obj_name(self.ref_data, path)), // TODO type enum
+            CType::UEEnum(path) => Type::named_type(&NamedTypeReference::new(
+                NamedTypeReferenceClass::EnumNamedTypeClass,
+                obj_name(self.ref_data, path),
+            )),
             CType::UEStruct(path) => struct_(&obj_name(self.ref_data, path)),
             CType::UEClass(path) => struct_(&obj_name(self.ref_data, path)), // TODO type class
         }
@@ -620,15 +670,8 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
                 (size * inner_size, alignment)
             }
             CType::UEEnum(path) => {
-                // TODO unknown...
-                let enum_ = &self.ref_data.objects[path].get_enum().unwrap();
-                let min = enum_.names.iter().map(|(_, v)| v).min().unwrap();
-                let max = enum_.names.iter().map(|(_, v)| v).max().unwrap();
-                if *min < i8::MIN as i64 || *max > u8::MAX as i64 {
-                    (4, 4)
-                } else {
-                    (1, 1)
-                }
+                let width = self.enum_width(path);
+                (width, width)
             }
             CType::UEClass(path) | CType::UEStruct(path) => {
                 let struct_ = &self.ref_data.objects[path].get_struct().unwrap();
@@ -640,6 +683,27 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
     fn decl_ctype(&mut self, buffer: &mut String, id: TypeId) {
         let ctype = self.store[id];
         let this = self.type_to_string(id, false);
+        let changed = match ctype {
+            CType::UEStruct(path) | CType::UEClass(path) => {
+                // the parent's size is the width of the base structure
+                let struct_ = self.ref_data.objects[path].get_struct().unwrap();
+                let parent_size = struct_
+                    .super_struct
+                    .as_deref()
+                    .and_then(|parent| self.ref_data.objects.get(parent)?.get_struct())
+                    .map(|parent| parent.properties_size);
+                let record = (&self.ref_data.objects[path], parent_size);
+                self.applied.changed(&format!("type:{path}"), &record)
+            }
+            // everything else is made from what its name says, laid out after its inner types
+            _ => {
+                let size = self.get_type_size(id);
+                self.applied.changed(&format!("type:{this}"), &size)
+            }
+        };
+        if !changed {
+            return;
+        }
         match ctype {
             CType::Float => {}
             CType::Double => {}
@@ -841,28 +905,8 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
 
             CType::Array(_, _) => {}
 
-            CType::UEEnum(path) => {
-                // TODO unknown...
-                let enum_ = &self.ref_data.objects[path].get_enum().unwrap();
-                let min = enum_.names.iter().map(|(_, v)| v).min().unwrap();
-                let max = enum_.names.iter().map(|(_, v)| v).max().unwrap();
-                let type_ = if *min < i8::MIN as i64 || *max > u8::MAX as i64 {
-                    "uint32_t"
-                } else {
-                    "uint8_t"
-                };
-                let enum_name = obj_name(self.ref_data, path);
-                let prefix = format!("{enum_name}::");
-                writeln!(buffer, "enum {this} : {type_} {{").unwrap();
-                if let Some((last, rest)) = enum_.names.split_last() {
-                    let iter = rest.iter().map(|e| (e, ",")).chain([(last, "")]);
-                    for ((name, value), comma) in iter {
-                        //let name = name.strip_prefix(&prefix).unwrap_or(&name);
-                        writeln!(buffer, "    `{name}` = {value}{comma}",).unwrap();
-                    }
-                }
-                writeln!(buffer, "}};").unwrap();
-            }
+            // every enum is defined up front, used or not
+            CType::UEEnum(_) => {}
             CType::UEStruct(path) | CType::UEClass(path) => {
                 let struct_ = &self.ref_data.objects[path].get_struct().unwrap();
                 let name = obj_name(self.ref_data, path);
@@ -885,7 +929,7 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
                 }
 
                 if let Some(_class) = self.ref_data.objects.get(path).unwrap().get_class() {
-                    let vtable_name = format!("{name}::VTable");
+                    let vtable_name = format!("VTable_{name}");
                     let vtable = Type::named_type(&NamedTypeReference::new(
                         NamedTypeReferenceClass::StructNamedTypeClass,
                         vtable_name,
@@ -926,7 +970,7 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
         }
     }
 
-    fn generate(&mut self, filter: impl Fn(&str, &ObjectType) -> bool) {
+    fn generate(&mut self, filter: impl Fn(&str, &ObjectType) -> bool) -> Result<()> {
         let mut buffer = String::new();
 
         // create vtable structs
@@ -937,6 +981,8 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
         let image_base = self.bv.original_image_base();
         let og_base = self.ref_data.image_base_address;
 
+        self.decl_globals();
+
         fn get_class<'a>(ref_data: &'a Jmap, class: &str) -> &'a Class {
             ref_data.objects.get(class).unwrap().get_class().unwrap()
         }
@@ -961,16 +1007,28 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
         let mut vtable_func_map: HashMap<u64, HashMap<usize, HashSet<&str>>> = Default::default();
 
         {
-            fn vtable_len(ref_data: &Jmap, class: &str) -> usize {
+            /// nearest class from `class` up that has an instance vtable
+            fn vtable_class<'a>(ref_data: &'a Jmap, class: &str) -> Option<&'a Class> {
                 let mut class = Some(class);
                 while let Some(next) = class {
                     let obj = ref_data.objects.get(next).unwrap().get_class().unwrap();
-                    if let Some(vtable) = obj.instance_vtable {
-                        return ref_data.vtables.get(&vtable).unwrap().len();
+                    if obj.instance_vtable.is_some() {
+                        return Some(obj);
                     }
                     class = obj.r#struct.super_struct.as_deref();
                 }
-                0
+                None
+            }
+            fn vtable_len(ref_data: &Jmap, class: &str) -> usize {
+                vtable_class(ref_data, class)
+                    .and_then(|class| ref_data.vtables.get(&class.instance_vtable?))
+                    .map_or(0, |vtable| vtable.len())
+            }
+            /// the UObject virtual the slot holds if the dumper knows it
+            fn slot_name(class: Option<&Class>, index: usize) -> String {
+                class
+                    .and_then(|class| class.virtual_functions.get(index)?.name.clone())
+                    .unwrap_or_else(|| format!("vfunc_0x{:x}", 8 * index))
             }
 
             for (path, obj) in &self.ref_data.objects {
@@ -980,47 +1038,57 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
 
                 let name = obj_name(self.ref_data, path);
 
-                {
+                let len = vtable_len(self.ref_data, path);
+                let parent_len = class
+                    .r#struct
+                    .super_struct
+                    .as_deref()
+                    .map_or(0, |parent| vtable_len(self.ref_data, parent));
+                let slots = vtable_class(self.ref_data, path);
+                let record = (len, parent_len, slots.map(|c| &c.virtual_functions));
+                if self.applied.changed(&format!("vtable:{path}"), &record) {
                     let mut builder = Structure::builder();
                     builder.propagates_data_var_refs(true);
 
-                    let len = vtable_len(self.ref_data, path);
-                    let parent_len = if let Some(parent) = &class.r#struct.super_struct {
+                    if let Some(parent) = &class.r#struct.super_struct {
                         let parent_name = obj_name(self.ref_data, parent);
                         let parent_type = NamedTypeReference::new(
                             NamedTypeReferenceClass::StructNamedTypeClass,
-                            format!("{parent_name}::VTable"),
+                            format!("VTable_{parent_name}"),
                         );
-                        let parent_len = vtable_len(self.ref_data, parent);
                         builder.base_structures(&[BaseStructure {
                             ty: parent_type,
                             offset: 0,
                             width: 8 * parent_len as u64,
                         }]);
-                        parent_len
-                    } else {
-                        0
-                    };
+                    }
 
                     builder.width(8 * len as u64);
 
-                    //let vtable_name = format!("{name}::VTable");
-                    //let vtable = Type::named_type(&NamedTypeReference::new(
-                    //    NamedTypeReferenceClass::StructNamedTypeClass,
-                    //    vtable_name,
-                    //));
+                    let arch = CoreArchitecture::by_name("x86_64").unwrap();
+                    let this = Type::pointer(
+                        &arch,
+                        &Type::named_type(&NamedTypeReference::new(
+                            NamedTypeReferenceClass::StructNamedTypeClass,
+                            name.as_str(),
+                        )),
+                    );
+                    let mut names = HashSet::new();
 
                     for i in parent_len..len {
                         let offset = i as u64 * 8;
-                        let func = Type::function(&Type::void(), vec![], false);
-                        let func_ptr =
-                            Type::pointer(&CoreArchitecture::by_name("x86_64").unwrap(), &func);
-                        //let func_name = format!("{outer_name}::exec{func_name}");
-                        //let sym = Symbol::builder(SymbolType::Function, &func_name, addr).create();
-                        //self.bv.define_user_symbol(&sym);
+                        let params =
+                            vec![FunctionParameter::new(this.clone(), "this".into(), None)];
+                        let func = Type::function(&Type::void(), params, false);
+                        let func_ptr = Type::pointer(&arch, &func);
+                        // both Itanium destructors are ~UObject
+                        let mut slot = slot_name(slots, i);
+                        if !names.insert(slot.clone()) {
+                            slot = format!("{slot}_0x{offset:x}");
+                        }
                         builder.insert(
                             &func_ptr,
-                            &format!("vfunc_0x{offset:x}"),
+                            &slot,
                             offset,
                             false,
                             MemberAccess::PublicAccess,
@@ -1029,25 +1097,30 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
                     }
 
                     self.bv.define_user_type(
-                        format!("{name}::VTable"),
+                        format!("VTable_{name}"),
                         &Type::structure(&builder.finalize()),
                     );
                 }
 
                 if let Some(vtable) = class.instance_vtable {
                     let vtable_addr = vtable.0 - og_base.0 + image_base;
-                    let sym =
-                        Symbol::builder(SymbolType::Data, &format!("{name}::vtable"), vtable_addr)
-                            .create();
-                    self.bv.define_user_symbol(&sym);
+                    let sym_name = format!("{name}::vtable");
+                    if self
+                        .applied
+                        .changed(&format!("vtable_var:{path}"), &(vtable, &sym_name))
+                    {
+                        let sym =
+                            Symbol::builder(SymbolType::Data, &sym_name, vtable_addr).create();
+                        self.bv.define_user_symbol(&sym);
 
-                    let vtable_type = NamedTypeReference::new(
-                        NamedTypeReferenceClass::StructNamedTypeClass,
-                        format!("{name}::VTable"),
-                    );
+                        let vtable_type = NamedTypeReference::new(
+                            NamedTypeReferenceClass::StructNamedTypeClass,
+                            format!("VTable_{name}"),
+                        );
 
-                    self.bv
-                        .define_user_data_var(vtable_addr, &Type::named_type(&vtable_type));
+                        self.bv
+                            .define_user_data_var(vtable_addr, &Type::named_type(&vtable_type));
+                    }
 
                     for (i, func) in self
                         .ref_data
@@ -1083,13 +1156,22 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
                 let owner = roots.iter().next().unwrap();
 
                 let owner_name = obj_name(self.ref_data, owner);
-                let func_name = format!("{owner_name}::vfunc_0x{:x}", 8 * index);
+                let slot = slot_name(vtable_class(self.ref_data, owner), *index);
+                let func_name = format!("{owner_name}::{slot}");
+                if !self.applied.changed(&format!("vfunc:{func:x}"), &func_name) {
+                    continue;
+                }
                 let func_addr = func - og_base.0 + image_base;
                 let sym = Symbol::builder(SymbolType::Function, &func_name, func_addr).create();
                 self.bv.define_user_symbol(&sym);
             }
         }
 
+        let native_tag = self
+            .bv
+            .tag_type_by_name(natives::TAG_TYPE)
+            .unwrap_or_else(|| self.bv.create_tag_type(natives::TAG_TYPE, "U"));
+
         let mut to_visit = HashSet::new();
         let mut dep_graph = HashMap::new();
         // get dependencies of initial top level classes
@@ -1097,23 +1179,11 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
             match obj {
                 ObjectType::Object(object) => {}
                 ObjectType::Package(package) => {}
-                ObjectType::Enum(_) => {}
+                ObjectType::Enum(_) => self.decl_enum(path),
                 ObjectType::ScriptStruct(script_struct) => {}
                 ObjectType::Class(class) => {}
                 ObjectType::Function(function) => {
-                    let addr = function.func.0 - og_base.0 + image_base;
-
-                    let Some(outer) = function.r#struct.object.outer.as_deref() else {
-                        continue;
-                    };
-
-                    let outer_name = obj_name(self.ref_data, outer);
-                    let func_name = obj_name(self.ref_data, path);
-                    let func_name = format!("{outer_name}::exec{func_name}");
-
-                    let sym = Symbol::builder(SymbolType::Function, &func_name, addr).create();
-
-                    self.bv.define_user_symbol(&sym);
+                    self.decl_native(path, function, &native_tag);
                 }
             }
             if filter(path, obj) && obj.get_class().is_some() {
@@ -1177,8 +1247,14 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
         //    }
         //}
 
-        let sorted = topological_sort(&dep_graph).unwrap();
-        dbg!(&sorted);
+        let sorted = topological_sort(&dep_graph).map_err(|cycle| {
+            let names: Vec<_> = cycle
+                .iter()
+                .rev()
+                .map(|(_, id)| self.type_to_string(*id, false))
+                .collect();
+            anyhow!("types depend on each other: {}", names.join(" -> "))
+        })?;
 
         // full declarations
         for (dep_type, type_id) in &sorted {
@@ -1187,7 +1263,7 @@ impl<'ref_data> Ctx<'ref_data, '_, '_> {
             }
         }
 
-        println!("{buffer}");
+        Ok(())
     }
 }
 
@@ -1342,10 +1418,13 @@ fn type_futf8string_data(s: &mut TypeStore<'_>) -> TypeId {
 
 trait GraphKey: Clone + Copy + PartialEq + Eq + std::hash::Hash + std::fmt::Debug {}
 impl<T> GraphKey for T where T: Clone + Copy + PartialEq + Eq + std::hash::Hash + std::fmt::Debug {}
-fn topological_sort<T: GraphKey>(graph: &HashMap<T, Vec<T>>) -> Option<Vec<T>> {
+/// Nodes ordered after their dependencies, or the nodes of a cycle from a dependency to its
+/// dependent
+fn topological_sort<T: GraphKey>(graph: &HashMap<T, Vec<T>>) -> Result<Vec<T>, Vec<T>> {
     let mut result: Vec<T> = Vec::new();
     let mut visited = HashSet::new();
     let mut temp_visited = HashSet::new();
+    let mut cycle = Vec::new();
 
     // Function for DFS
     fn dfs<T: GraphKey>(
@@ -1354,10 +1433,11 @@ fn topological_sort<T: GraphKey>(graph: &HashMap<T, Vec<T>>) -> Option<Vec<T>> {
         visited: &mut HashSet<T>,
         temp_visited: &mut HashSet<T>,
         result: &mut Vec<T>,
+        cycle: &mut Vec<T>,
     ) -> bool {
         // If node is temporarily visited, we have a cycle
         if temp_visited.contains(&node) {
-            println!("CYCLE {node:?}");
+            cycle.push(node);
             return false;
         }
 
@@ -1372,8 +1452,11 @@ fn topological_sort<T: GraphKey>(graph: &HashMap<T, Vec<T>>) -> Option<Vec<T>> {
         // Visit all neighbors
         if let Some(neighbors) = graph.get(&node) {
             for &neighbor in neighbors {
-                if !dfs(neighbor, graph, visited, temp_visited, result) {
-                    println!("  {node:?}");
+                if !dfs(neighbor, graph, visited, temp_visited, result, cycle) {
+                    // stop once back at the node the cycle started from
+                    if cycle.len() == 1 || cycle.first() != cycle.last() {
+                        cycle.push(node);
+                    }
                     return false;
                 }
             }
@@ -1390,13 +1473,20 @@ fn topological_sort<T: GraphKey>(graph: &HashMap<T, Vec<T>>) -> Option<Vec<T>> {
     // Run DFS for each node
     for node in graph.keys() {
         if !visited.contains(node)
-            && !dfs(*node, graph, &mut visited, &mut temp_visited, &mut result)
+            && !dfs(
+                *node,
+                graph,
+                &mut visited,
+                &mut temp_visited,
+                &mut result,
+                &mut cycle,
+            )
         {
-            return None; // Graph has a cycle
+            return Err(cycle); // Graph has a cycle
         }
     }
 
-    Some(result)
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -1423,4 +1513,19 @@ mod test {
         assert_eq!(0xe, get_bitfield_bit_index(1, 0b0100_0000));
         assert_eq!(0xf, get_bitfield_bit_index(1, 0b1000_0000));
     }
+
+    #[test]
+    fn test_topological_sort() {
+        let graph = HashMap::from([(1, vec![2, 3]), (2, vec![3]), (3, vec![]), (4, vec![1])]);
+        assert_eq!(Ok(vec![3, 2, 1, 4]), topological_sort(&graph));
+
+        // 4 only leads into the cycle, so it isn't part of it
+        let graph = HashMap::from([(1, vec![2]), (2, vec![3]), (3, vec![1]), (4, vec![1])]);
+        let cycle = topological_sort(&graph).unwrap_err();
+        assert_eq!(4, cycle.len());
+        assert_eq!(cycle.first(), cycle.last());
+        for pair in cycle.windows(2) {
+            assert!(graph[&pair[1]].contains(&pair[0]), "{pair:?} isn't an edge");
+        }
+    }
 }
@@ -0,0 +1,53 @@
+//! Headless counterpart of the plugin command: open a binary or database, apply a dump to it and
+//! save it as a database, for scripts and CI.
+
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result, bail};
+use binaryninja::binary_view::BinaryViewExt;
+use binaryninja::headless::Session;
+use binaryninja::logger::Logger;
+use clap::Parser;
+use log::info;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Binary or .bndb to apply the dump to
+    input: PathBuf,
+    /// Dump to apply (.jmap or JSON, optionally zstd compressed)
+    dump: PathBuf,
+    /// Database to save to, defaults to the input database or the binary with a .bndb extension
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Only apply what changed since the last dump applied to the input database
+    #[arg(long)]
+    incremental: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    Logger::new("ue_binja").init();
+
+    let ref_data = ue_binja::load(cli.dump.clone())
+        .with_context(|| format!("Failed to load {}", cli.dump.display()))?;
+
+    let session = Session::new().context("Failed to start Binary Ninja")?;
+    let Some(bv) = session.load(&cli.input) else {
+        bail!("Failed to open {}", cli.input.display());
+    };
+    bv.update_analysis_and_wait();
+
+    ue_binja::apply(&ref_data, &bv, cli.incremental)
+        .with_context(|| format!("Failed to apply {}", cli.dump.display()))?;
+    bv.update_analysis_and_wait();
+
+    let output = cli
+        .output
+        .unwrap_or_else(|| cli.input.with_extension("bndb"));
+    if !bv.file().create_database(&output) {
+        bail!("Failed to save {}", output.display());
+    }
+    info!("saved {}", output.display());
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Natives of UFunctions. `Func` is the generated `execFoo` thunk, so it is typed as one: the
+//! object the function is called on, the script stack frame and where to put the return value,
+//! typed after the return property. The C++ declaration of the function goes in a comment.
+
+use binaryninja::architecture::CoreArchitecture;
+use binaryninja::binary_view::BinaryViewExt;
+use binaryninja::rc::Ref;
+use binaryninja::symbol::{Symbol, SymbolType};
+use binaryninja::tags::TagType;
+use binaryninja::types::{FunctionParameter, NamedTypeReference, NamedTypeReferenceClass, Type};
+use log::warn;
+
+use jmap::{EFunctionFlags, EPropertyFlags, Function};
+
+use crate::{Ctx, obj_name};
+
+pub(crate) const TAG_TYPE: &str = "UE Native";
+
+impl<'ref_data> Ctx<'ref_data, '_, '_> {
+    /// `Package::Class::Function`, the package being the last segment of its path
+    fn native_name(&self, path: &str, outer: &str) -> String {
+        let name = obj_name(self.ref_data, path);
+        let class = obj_name(self.ref_data, outer);
+        let package = self.ref_data.objects[outer].get_object().outer.as_deref();
+        match package.and_then(|p| p.rsplit('/').next()) {
+            Some(package) => format!("{package}::{class}::{name}"),
+            None => format!("{class}::{name}"),
+        }
+    }
+
+    /// C++ declaration as in the generated headers
+    fn native_declaration(&mut self, name: &str, function: &'ref_data Function) -> String {
+        let mut return_type = "void".to_string();
+        let mut params = vec![];
+        for prop in &function.r#struct.properties {
+            if !prop.flags.contains(EPropertyFlags::CPF_Parm) {
+                continue;
+            }
+            let ctype = self.prop_ctype(prop);
+            let type_name = self.type_to_string(ctype, false);
+            if prop.flags.contains(EPropertyFlags::CPF_ReturnParm) {
+                return_type = type_name;
+                continue;
+            }
+            let mut param = String::new();
+            if prop.flags.contains(EPropertyFlags::CPF_ConstParm) {
+                param.push_str("const ");
+            }
+            param.push_str(&type_name);
+            if prop.flags.contains(EPropertyFlags::CPF_OutParm) {
+                param.push('&');
+            }
+            param.push(' ');
+            param.push_str(&prop.name);
+            params.push(param);
+        }
+        let is_static = function
+            .function_flags
+            .contains(EFunctionFlags::FUNC_Static);
+        let is_const = function.function_flags.contains(EFunctionFlags::FUNC_Const);
+        format!(
+            "{}{return_type} {name}({}){}",
+            if is_static { "static " } else { "" },
+            params.join(", "),
+            if is_const && !is_static { " const" } else { "" },
+        )
+    }
+
+    /// `void execFoo(UObject* Context, FFrame& Stack, RESULT_DECL)`
+    fn native_type(&mut self, outer: &str, function: &'ref_data Function) -> Ref<Type> {
+        let arch = CoreArchitecture::by_name("x86_64").unwrap();
+        let named = |name: String| {
+            Type::named_type(&NamedTypeReference::new(
+                NamedTypeReferenceClass::StructNamedTypeClass,
+                name,
+            ))
+        };
+
+        let result = function
+            .r#struct
+            .properties
+            .iter()
+            .find(|p| p.flags.contains(EPropertyFlags::CPF_ReturnParm))
+            .map(|prop| {
+                let ctype = self.prop_ctype(prop);
+                self.bn_type(ctype)
+            })
+            .unwrap_or_else(Type::void);
+
+        let params = [
+            ("Context", named(obj_name(self.ref_data, outer))),
+            ("Stack", named("FFrame".to_string())),
+            ("Result", result),
+        ]
+        .into_iter()
+        .map(|(name, ty)| FunctionParameter::new(Type::pointer(&arch, &ty), name.into(), None))
+        .collect::<Vec<_>>();
+
+        let calling_convention = self
+            .bv
+            .default_platform()
+            .and_then(|p| p.get_default_calling_convention());
+        match calling_convention {
+            Some(cc) => Type::function_with_opts(&Type::void(), &params, false, cc, 0.into()),
+            None => Type::function(&Type::void(), params, false),
+        }
+    }
+
+    /// Name, type, comment and tag the native of `function`, if it is a native function
+    pub(crate) fn decl_native(&mut self, path: &str, function: &'ref_data Function, tag: &TagType) {
+        if !function
+            .function_flags
+            .contains(EFunctionFlags::FUNC_Native)
+            || function.func.0 == 0
+        {
+            // script functions all point to UObject::ProcessInternal
+            return;
+        }
+        let Some(outer) = function.r#struct.object.outer.as_deref() else {
+            return;
+        };
+        let addr =
+            function.func.0 - self.ref_data.image_base_address.0 + self.bv.original_image_base();
+
+        let name = self.native_name(path, outer);
+        if !self
+            .applied
+            .changed(&format!("native:{path}"), &(&name, function))
+        {
+            return;
+        }
+        let sym = Symbol::builder(SymbolType::Function, &name, addr).create();
+        self.bv.define_user_symbol(&sym);
+
+        let Some(bn_function) = self.bv.add_user_function(addr) else {
+            warn!("failed to create function {name} at 0x{addr:x}");
+            return;
+        };
+        bn_function.set_user_type(&self.native_type(outer, function));
+        let declaration = self.native_declaration(&obj_name(self.ref_data, path), function);
+        bn_function.set_comment(&declaration);
+        bn_function.add_tag(tag, &declaration, None, true, None);
+    }
+}
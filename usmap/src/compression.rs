@@ -1,5 +1,7 @@
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
+use std::ffi::OsStr;
 use std::io::Write;
+use std::sync::OnceLock;
 
 use crate::CompressionMethod;
 
@@ -14,16 +16,13 @@ pub fn compress<S: Write>(
             output.write_all(&buf)?;
         }
         CompressionMethod::Oodle => {
-            todo!()
-            //let buffer = oodle_loader::oodle()?.compress(
-            //    input,
-            //    oodle_loader::Compressor::Mermaid,
-            //    oodle_loader::CompressionLevel::Normal,
-            //)?;
-            //output.write_all(&buffer)?;
+            let buffer = oodle()?.compress(input)?;
+            output.write_all(&buffer)?;
         }
         CompressionMethod::Brotli => {
-            todo!()
+            let mut writer = brotli::CompressorWriter::new(&mut output, 4096, 11, 22);
+            writer.write_all(input)?;
+            writer.into_inner();
         }
     }
     Ok(())
@@ -35,19 +34,146 @@ pub fn decompress(compression: CompressionMethod, input: &[u8], output: &mut [u8
             zstd::bulk::decompress_to_buffer(input, output)?;
         }
         CompressionMethod::Oodle => {
-            todo!()
-            //let status = oodle_loader::oodle()?.decompress(input, output);
-            //if status < 0 || status as usize != output.len() {
-            //    bail!(
-            //        "Oodle decompression failed: expected {} output bytes, got {}",
-            //        output.len(),
-            //        status,
-            //    );
-            //}
+            oodle()?.decompress(input, output)?;
         }
         CompressionMethod::Brotli => {
-            todo!()
+            let mut buffer = vec![];
+            brotli::BrotliDecompress(&mut std::io::Cursor::new(input), &mut buffer)?;
+            if buffer.len() != output.len() {
+                bail!(
+                    "Brotli decompression failed: expected {} output bytes, got {}",
+                    output.len(),
+                    buffer.len(),
+                );
+            }
+            output.copy_from_slice(&buffer);
         }
     }
     Ok(())
 }
+
+static OODLE: OnceLock<Oodle> = OnceLock::new();
+
+/// Load Oodle from a shared library (e.g. oo2core_9_win64.dll or liboo2corelinux64.so.9) to
+/// enable [`CompressionMethod::Oodle`]. Oodle is not redistributable so must be user provided.
+pub fn load_oodle<P: AsRef<OsStr>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    let oodle = Oodle::load(path).with_context(|| format!("failed to load Oodle from {path:?}"))?;
+    // ignore if already loaded
+    let _ = OODLE.set(oodle);
+    Ok(())
+}
+
+fn oodle() -> Result<&'static Oodle> {
+    OODLE
+        .get()
+        .context("Oodle compression requires an Oodle library to be loaded via load_oodle")
+}
+
+type OodleLzCompress = unsafe extern "C" fn(
+    compressor: i32,
+    raw_buf: *const u8,
+    raw_len: isize,
+    comp_buf: *mut u8,
+    level: i32,
+    options: *const (),
+    dictionary_base: *const (),
+    lrm: *const (),
+    scratch_mem: *mut (),
+    scratch_size: isize,
+) -> isize;
+
+type OodleLzDecompress = unsafe extern "C" fn(
+    comp_buf: *const u8,
+    comp_buf_size: isize,
+    raw_buf: *mut u8,
+    raw_len: isize,
+    fuzz_safe: i32,
+    check_crc: i32,
+    verbosity: i32,
+    dec_buf_base: *mut u8,
+    dec_buf_size: isize,
+    fp_callback: *const (),
+    callback_user_data: *const (),
+    decoder_memory: *mut u8,
+    decoder_memory_size: isize,
+    thread_phase: i32,
+) -> isize;
+
+struct Oodle {
+    compress: OodleLzCompress,
+    decompress: OodleLzDecompress,
+    // keep library loaded for the lifetime of the function pointers
+    _library: libloading::Library,
+}
+
+impl Oodle {
+    const COMPRESSOR_MERMAID: i32 = 9;
+    const COMPRESSION_LEVEL_NORMAL: i32 = 4;
+
+    fn load(path: &OsStr) -> Result<Self> {
+        unsafe {
+            let library = libloading::Library::new(path)?;
+            let compress = *library.get::<OodleLzCompress>(b"OodleLZ_Compress\0")?;
+            let decompress = *library.get::<OodleLzDecompress>(b"OodleLZ_Decompress\0")?;
+            Ok(Self {
+                compress,
+                decompress,
+                _library: library,
+            })
+        }
+    }
+
+    fn compress(&self, input: &[u8]) -> Result<Vec<u8>> {
+        // OodleLZ_GetCompressedBufferSizeNeeded
+        let mut buffer = vec![0; input.len() + 274 * input.len().div_ceil(0x40000)];
+        let len = unsafe {
+            (self.compress)(
+                Self::COMPRESSOR_MERMAID,
+                input.as_ptr(),
+                input.len() as isize,
+                buffer.as_mut_ptr(),
+                Self::COMPRESSION_LEVEL_NORMAL,
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if len <= 0 {
+            bail!("Oodle compression failed: {len}");
+        }
+        buffer.truncate(len as usize);
+        Ok(buffer)
+    }
+
+    fn decompress(&self, input: &[u8], output: &mut [u8]) -> Result<()> {
+        let status = unsafe {
+            (self.decompress)(
+                input.as_ptr(),
+                input.len() as isize,
+                output.as_mut_ptr(),
+                output.len() as isize,
+                1,
+                0,
+                0,
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+                3,
+            )
+        };
+        if status < 0 || status as usize != output.len() {
+            bail!(
+                "Oodle decompression failed: expected {} output bytes, got {}",
+                output.len(),
+                status,
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 mod compression;
 
+pub use compression::load_oodle;
+
 use std::{
     collections::{BTreeMap, HashMap},
     io::{Read, Seek, Write},
@@ -100,9 +102,14 @@ impl<S: Write> SerCtx<'_, S> {
         let (s, names) = (&mut self.inner, &self.names);
         for name in &names.names {
             if self.header.version >= UsmapVersion::LongFName {
-                s.write_u16::<LE>(name.len().try_into().expect("name too long"))?;
+                s.write_u16::<LE>(name.len().try_into().context("name too long")?)?;
             } else {
-                s.write_u8(name.len().try_into().expect("name too long"))?;
+                s.write_u8(name.len().try_into().with_context(|| {
+                    format!(
+                        "name {name:?} too long for usmap version {:?}",
+                        self.header.version
+                    )
+                })?)?;
             };
             s.write_all(name.as_bytes())?;
         }
@@ -340,6 +347,22 @@ impl Ser for Option<CompressionMethod> {
     }
 }
 
+/// Layout to write a [`Usmap`] with
+#[derive(Debug, Clone, Copy)]
+pub struct WriteOptions {
+    pub version: UsmapVersion,
+    pub compression_method: Option<CompressionMethod>,
+}
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            version: UsmapVersion::ExplicitEnumValues,
+            // no compression by default because FModel/UAssetAPI parsers seem to be broken
+            compression_method: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Header {
     pub version: UsmapVersion,
@@ -464,13 +487,15 @@ impl Usmap {
             envp,
         })
     }
-    #[instrument(skip_all, name = "Usmap::write")]
     pub fn write<S: Write>(&self, s: &mut S) -> Result<()> {
+        self.write_with_options(s, WriteOptions::default())
+    }
+    #[instrument(skip_all, name = "Usmap::write")]
+    pub fn write_with_options<S: Write>(&self, s: &mut S, options: WriteOptions) -> Result<()> {
         let mut names = Names::new();
         let mut header = Header {
-            version: UsmapVersion::ExplicitEnumValues,
-            //compression_method: Some(CompressionMethod::Zstd),
-            compression_method: None, // disable compression because FModel/UAssetAPI parsers seem to be broken
+            version: options.version,
+            compression_method: options.compression_method,
             compressed_size: 0,
             decompressed_size: 0,
         };
@@ -615,9 +640,28 @@ fn write_enums<S: Write>(s: &mut SerCtx<S>, enums: &[Enum]) -> Result<()> {
     for e in enums {
         s.write_name(e.name.clone())?;
         if s.header.version >= UsmapVersion::LargeEnums {
-            s.write_u16::<LE>(e.entries.len().try_into().expect("enum entries too large"))?;
+            s.write_u16::<LE>(
+                e.entries
+                    .len()
+                    .try_into()
+                    .context("enum entries too large")?,
+            )?;
         } else {
-            s.write_u8(e.entries.len().try_into().expect("enum entries too large"))?;
+            s.write_u8(e.entries.len().try_into().with_context(|| {
+                format!(
+                    "enum {} has too many entries for usmap version {:?}",
+                    e.name, s.header.version
+                )
+            })?)?;
+        }
+        if s.header.version < UsmapVersion::ExplicitEnumValues
+            && !e.entries.keys().enumerate().all(|(i, v)| *v == i as i64)
+        {
+            bail!(
+                "enum {} has explicit values which require usmap version {:?}",
+                e.name,
+                UsmapVersion::ExplicitEnumValues
+            );
         }
         for (value, name) in &e.entries {
             if s.header.version >= UsmapVersion::ExplicitEnumValues {
@@ -956,4 +1000,25 @@ mod test {
     fn test_explicit_enum_values() -> Result<()> {
         test_usmap("tests/ExplicitEnumValuesExample.usmap")
     }
+    #[test]
+    fn test_write_options() -> Result<()> {
+        let res = Usmap::read(&mut std::io::Cursor::new(std::fs::read("tests/drg.usmap")?))?;
+        for compression_method in [
+            None,
+            Some(CompressionMethod::Zstd),
+            Some(CompressionMethod::Brotli),
+        ] {
+            let mut buffer = vec![];
+            res.write_with_options(
+                &mut buffer,
+                WriteOptions {
+                    version: UsmapVersion::ExplicitEnumValues,
+                    compression_method,
+                },
+            )?;
+            let res2 = Usmap::read(&mut std::io::Cursor::new(buffer))?;
+            assert_eq!(res, res2);
+        }
+        Ok(())
+    }
 }